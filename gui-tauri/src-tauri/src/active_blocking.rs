@@ -0,0 +1,514 @@
+use crate::blocklist::Blocklist;
+use crate::error::AppError;
+use crate::history::ScanHistory;
+use crate::notifications::notify;
+use crate::presence;
+use crate::protection::is_paused;
+use crate::settings::{self, Settings};
+use crate::AppHandle;
+use crate::ScanResult;
+use serde::Serialize;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{Manager, State};
+
+/// Marker lines around the hosts file entries this app manages; nothing
+/// outside them is ever changed
+const SECTION_BEGIN: &str = "# BEGIN Phishing Guard blocked hosts";
+const SECTION_END: &str = "# END Phishing Guard blocked hosts";
+const SECTION_NOTE: &str = "# Managed by Phishing Guard; unblock hosts from the app";
+/// Address blocked hosts resolve to
+const SINK: &str = "0.0.0.0";
+/// Copy of the hosts file as it was before the last change, next to it
+const BACKUP_SUFFIX: &str = ".phishguard.bak";
+/// The new hosts file is written here first, then renamed over the old one
+const NEW_SUFFIX: &str = ".phishguard.new";
+/// New contents, in the app data dir, for an elevated helper to copy in
+const STAGED_FILE: &str = "hosts.staged";
+
+/// Hosts to block that arrive this soon after the first are written with
+/// it, so a batch of phishing verdicts asks for permission once
+const BLOCK_BATCH_WINDOW: Duration = Duration::from_secs(5);
+
+/// Serializes hosts file changes, so one prompt is answered before the next
+#[derive(Default)]
+pub struct ActiveBlocking {
+    lock: tokio::sync::Mutex<()>,
+    /// Hosts waiting for the next write; non-empty while one is scheduled
+    pending: Mutex<Vec<String>>,
+}
+
+impl ActiveBlocking {
+    /// Add `host` to the next write; true if no write was scheduled yet
+    fn queue(&self, host: String) -> bool {
+        let Ok(mut pending) = self.pending.lock() else {
+            return false;
+        };
+        let first = pending.is_empty();
+        if !pending.contains(&host) {
+            pending.push(host);
+        }
+        first
+    }
+
+    fn take_pending(&self) -> Vec<String> {
+        self.pending
+            .lock()
+            .map(|mut pending| std::mem::take(&mut *pending))
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ActiveBlockingStatus {
+    enabled: bool,
+    min_risk_score: i32,
+    hosts_file: String,
+    blocked: Vec<String>,
+}
+
+fn hosts_path() -> PathBuf {
+    #[cfg(windows)]
+    {
+        let root = std::env::var_os("SystemRoot").unwrap_or_else(|| "C:\\Windows".into());
+        PathBuf::from(root).join(r"System32\drivers\etc\hosts")
+    }
+    #[cfg(not(windows))]
+    {
+        PathBuf::from("/etc/hosts")
+    }
+}
+
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// A host name the hosts file can block: a plain DNS name, never an IP
+/// address, `localhost`, or anything that could break the line format
+pub fn blockable_host(raw: &str) -> Option<String> {
+    let host = raw.trim().trim_end_matches('.').to_ascii_lowercase();
+    let valid = host.len() <= 253
+        && host.contains('.')
+        && !host.starts_with(['.', '-'])
+        && host
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'.');
+    match url::Host::parse(&host) {
+        Ok(url::Host::Domain(_)) if valid && host != "localhost" => Some(host),
+        _ => None,
+    }
+}
+
+/// Byte range of the managed section, marker lines included. A section
+/// with a missing, repeated or misplaced marker is refused rather than
+/// guessed at.
+fn section(contents: &str) -> Result<Option<Range<usize>>, AppError> {
+    let (mut begin, mut end) = (Vec::new(), Vec::new());
+    let mut offset = 0;
+    for line in contents.split_inclusive('\n') {
+        match line.trim_end() {
+            SECTION_BEGIN => begin.push(offset),
+            SECTION_END => end.push(offset + line.len()),
+            _ => {}
+        }
+        offset += line.len();
+    }
+    match (begin.as_slice(), end.as_slice()) {
+        ([], []) => Ok(None),
+        ([start], [stop]) if start < stop => Ok(Some(*start..*stop)),
+        _ => Err(AppError::State(
+            "the Phishing Guard section of the hosts file is damaged; \
+             fix or remove it by hand before blocking hosts"
+                .into(),
+        )),
+    }
+}
+
+/// Hosts blocked in the managed section
+fn blocked_in(contents: &str) -> Result<Vec<String>, AppError> {
+    let Some(range) = section(contents)? else {
+        return Ok(Vec::new());
+    };
+    let mut hosts: Vec<String> = contents[range]
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            match (fields.next(), fields.next()) {
+                (Some(SINK), Some(host)) => blockable_host(host),
+                _ => None,
+            }
+        })
+        .collect();
+    hosts.sort();
+    hosts.dedup();
+    Ok(hosts)
+}
+
+/// `contents` with the managed section holding exactly `hosts`; everything
+/// else is kept byte for byte, and with no hosts the section is removed
+fn with_hosts(contents: &str, hosts: &[String]) -> Result<String, AppError> {
+    let newline = if contents.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let mut block = String::new();
+    if !hosts.is_empty() {
+        for line in [SECTION_BEGIN, SECTION_NOTE] {
+            block.push_str(line);
+            block.push_str(newline);
+        }
+        for host in hosts {
+            block.push_str(&format!("{} {}{}", SINK, host, newline));
+        }
+        block.push_str(SECTION_END);
+        block.push_str(newline);
+    }
+
+    Ok(match section(contents)? {
+        Some(range) => format!(
+            "{}{}{}",
+            &contents[..range.start],
+            block,
+            &contents[range.end..]
+        ),
+        None if block.is_empty() => contents.to_string(),
+        None => {
+            let mut updated = contents.to_string();
+            if !updated.is_empty() && !updated.ends_with('\n') {
+                updated.push_str(newline);
+            }
+            updated.push_str(&block);
+            updated
+        }
+    })
+}
+
+/// Replace the hosts file without help, when the app already may
+fn replace_directly(hosts: &Path, contents: &str) -> std::io::Result<()> {
+    std::fs::copy(hosts, sibling(hosts, BACKUP_SUFFIX))?;
+    let new = sibling(hosts, NEW_SUFFIX);
+    std::fs::write(&new, contents)?;
+    std::fs::rename(&new, hosts)
+}
+
+/// Quote for a POSIX shell
+#[cfg(target_os = "macos")]
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// Same steps as `replace_directly`, run by an elevated shell: back up,
+/// write beside the hosts file, then rename over it
+#[cfg(unix)]
+const ELEVATED_SCRIPT: &str = "set -e; \
+    cp -p \"$1\" \"$1.phishguard.bak\"; \
+    cp \"$2\" \"$1.phishguard.new\"; \
+    chmod 644 \"$1.phishguard.new\"; \
+    mv -f \"$1.phishguard.new\" \"$1\"";
+
+#[cfg(target_os = "macos")]
+async fn elevate(hosts: &Path, staged: &Path, reason: &str) -> Result<bool, AppError> {
+    let command = format!(
+        "/bin/sh -c {} sh {} {}",
+        shell_quote(ELEVATED_SCRIPT),
+        shell_quote(&hosts.to_string_lossy()),
+        shell_quote(&staged.to_string_lossy())
+    );
+    let applescript =
+        |text: &str| format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""));
+    let script = format!(
+        "do shell script {} with prompt {} with administrator privileges",
+        applescript(&command),
+        applescript(reason)
+    );
+    let status = tokio::process::Command::new("osascript")
+        .arg("-e")
+        .arg(script)
+        .status()
+        .await?;
+    Ok(status.success())
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+async fn elevate(hosts: &Path, staged: &Path, _reason: &str) -> Result<bool, AppError> {
+    // pkexec shows the desktop's own authentication dialog
+    let status = tokio::process::Command::new("pkexec")
+        .arg("/bin/sh")
+        .arg("-c")
+        .arg(ELEVATED_SCRIPT)
+        .arg("sh")
+        .arg(hosts)
+        .arg(staged)
+        .status()
+        .await?;
+    Ok(status.success())
+}
+
+#[cfg(windows)]
+async fn elevate(hosts: &Path, staged: &Path, _reason: &str) -> Result<bool, AppError> {
+    let quote = |path: &Path| format!("'{}'", path.to_string_lossy().replace('\'', "''"));
+    let script = format!(
+        "$ErrorActionPreference = 'Stop'\r\n\
+         Copy-Item -LiteralPath {hosts} -Destination {backup} -Force\r\n\
+         Copy-Item -LiteralPath {staged} -Destination {new} -Force\r\n\
+         Move-Item -LiteralPath {new} -Destination {hosts} -Force\r\n",
+        hosts = quote(hosts),
+        backup = quote(&sibling(hosts, BACKUP_SUFFIX)),
+        staged = quote(staged),
+        new = quote(&sibling(hosts, NEW_SUFFIX)),
+    );
+    let script_path = sibling(staged, ".ps1");
+    tokio::fs::write(&script_path, script).await?;
+    // UAC asks for consent; declining makes Start-Process fail
+    let launcher = format!(
+        "$p = Start-Process -FilePath powershell -Verb RunAs -Wait -PassThru \
+         -ArgumentList '-NoProfile','-ExecutionPolicy','Bypass','-File','\"{}\"'; \
+         exit $p.ExitCode",
+        script_path.to_string_lossy().replace('\'', "''")
+    );
+    let status = tokio::process::Command::new("powershell")
+        .args(["-NoProfile", "-Command", &launcher])
+        .status()
+        .await?;
+    let _ = tokio::fs::remove_file(&script_path).await;
+    Ok(status.success())
+}
+
+/// Put `contents` in place of the hosts file, asking the OS for permission
+/// when the app can't write it itself, then check what actually landed
+async fn write_hosts(
+    app: &AppHandle,
+    hosts: &Path,
+    contents: &str,
+    reason: &str,
+) -> Result<(), AppError> {
+    match replace_directly(hosts, contents) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => {
+            let dir = app
+                .path()
+                .app_data_dir()
+                .map_err(|e| AppError::State(e.to_string()))?;
+            tokio::fs::create_dir_all(&dir).await?;
+            let staged = dir.join(STAGED_FILE);
+            tokio::fs::write(&staged, contents).await?;
+            let granted = elevate(hosts, &staged, reason).await;
+            let _ = tokio::fs::remove_file(&staged).await;
+            if !granted? {
+                return Err(AppError::State(
+                    "permission to change the hosts file was not granted".into(),
+                ));
+            }
+        }
+        Err(e) => return Err(e.into()),
+    }
+
+    if tokio::fs::read_to_string(hosts).await? != contents {
+        return Err(AppError::State(format!(
+            "the hosts file did not take the change; the previous version is in {}",
+            sibling(hosts, BACKUP_SUFFIX).display()
+        )));
+    }
+    Ok(())
+}
+
+/// Apply `edit` to the blocked hosts. Nothing is written, and no prompt
+/// shown, when the list comes out the same.
+async fn change(
+    app: &AppHandle,
+    reason: &str,
+    edit: impl FnOnce(&mut Vec<String>),
+) -> Result<Vec<String>, AppError> {
+    let blocking = app.state::<ActiveBlocking>();
+    let _changing = blocking.lock.lock().await;
+    let hosts = hosts_path();
+    // A hosts file that isn't UTF-8 is left alone
+    let contents = tokio::fs::read_to_string(&hosts).await?;
+    let before = blocked_in(&contents)?;
+    let mut after = before.clone();
+    edit(&mut after);
+    after.sort();
+    after.dedup();
+    if after == before {
+        return Ok(after);
+    }
+    write_hosts(app, &hosts, &with_hosts(&contents, &after)?, reason).await?;
+    tracing::info!(blocked = after.len(), "hosts file updated");
+    Ok(after)
+}
+
+/// Write every host queued to be blocked in one change
+async fn flush_pending(app: AppHandle) {
+    tokio::time::sleep(BLOCK_BATCH_WINDOW).await;
+    let blocking = app.state::<ActiveBlocking>();
+    // Hosts queued while an earlier change is still being approved join this one
+    let waiting = blocking.lock.lock().await;
+    let hosts = blocking.take_pending();
+    drop(waiting);
+    let (reason, title) = match hosts.as_slice() {
+        [] => return,
+        [host] => (
+            format!("Phishing Guard wants to block the phishing site {}.", host),
+            format!("Could not block {}", host),
+        ),
+        _ => (
+            format!(
+                "Phishing Guard wants to block {} phishing sites: {}.",
+                hosts.len(),
+                hosts.join(", ")
+            ),
+            format!("Could not block {} phishing sites", hosts.len()),
+        ),
+    };
+    let blocked = hosts.clone();
+    if let Err(e) = change(&app, &reason, move |list| list.extend(blocked)).await {
+        tracing::warn!(hosts = ?hosts, error = %e, "could not block hosts");
+        notify(&app, &title, &e.to_string());
+    }
+}
+
+/// Block `host` with the next batched write
+fn block(app: &AppHandle, host: String) {
+    if app.state::<ActiveBlocking>().queue(host) {
+        tauri::async_runtime::spawn(flush_pending(app.clone()));
+    }
+}
+
+/// Block the host of a phishing verdict that reaches the configured risk score
+pub fn on_verdict(app: &AppHandle, result: &ScanResult) {
+    let settings = app.state::<Settings>().get().active_blocking;
+    if !settings.enabled
+        || !result.is_phishing()
+        || result.risk_score < settings.min_risk_score
+        || is_paused(app)
+    {
+        return;
+    }
+    let host = url::Url::parse(&result.url)
+        .ok()
+        .and_then(|url| url.host_str().and_then(blockable_host));
+    if let Some(host) = host {
+        block(app, host);
+    }
+}
+
+/// Block a host just added to the blocklist. The hosts file has no
+/// wildcards, so only exact host rules are blocked.
+pub fn on_blocklisted(app: &AppHandle, pattern: &str) {
+    if !app.state::<Settings>().get().active_blocking.enabled || pattern.starts_with("*.") {
+        return;
+    }
+    if let Some(host) = blockable_host(pattern) {
+        block(app, host);
+    }
+}
+
+fn status(app: &AppHandle, blocked: Vec<String>) -> ActiveBlockingStatus {
+    let settings = app.state::<Settings>().get().active_blocking;
+    ActiveBlockingStatus {
+        enabled: settings.enabled,
+        min_risk_score: settings.min_risk_score,
+        hosts_file: hosts_path().display().to_string(),
+        blocked,
+    }
+}
+
+/// Turn active blocking on, blocking the exact hosts already on the
+/// blocklist, or off, removing every host it blocked. This is the only way
+/// `active_blocking.enabled` changes: update_settings refuses to, and
+/// reset_settings and import_config keep it.
+#[tauri::command]
+pub async fn enable_active_blocking(
+    enabled: bool,
+    min_risk_score: Option<i32>,
+    app: AppHandle,
+    blocklist: State<'_, Blocklist>,
+    history: State<'_, ScanHistory>,
+) -> Result<ActiveBlockingStatus, AppError> {
+    if min_risk_score.is_some_and(|score| !(0..=100).contains(&score)) {
+        return Err(AppError::InvalidInput(
+            "active_blocking.min_risk_score must be between 0 and 100".into(),
+        ));
+    }
+    if !enabled {
+        presence::require(&app, "turn off active blocking")?;
+    }
+    let blocked = if enabled {
+        let listed: Vec<String> = blocklist
+            .0
+            .entries(&history)
+            .await?
+            .into_iter()
+            .filter(|entry| !entry.pattern.starts_with("*."))
+            .filter_map(|entry| blockable_host(&entry.pattern))
+            .collect();
+        let reason = "Phishing Guard wants to block the hosts on its blocklist.";
+        change(&app, reason, move |hosts| hosts.extend(listed)).await?
+    } else {
+        let reason = "Phishing Guard wants to remove the hosts it blocked.";
+        change(&app, reason, |hosts| hosts.clear()).await?
+    };
+    // Saved only once the hosts file matches; nothing else sets the flag,
+    // so the two never disagree
+    settings::update(&app, |s| {
+        s.active_blocking.enabled = enabled;
+        if let Some(score) = min_risk_score {
+            s.active_blocking.min_risk_score = score;
+        }
+    })?;
+    Ok(status(&app, blocked))
+}
+
+/// What the hosts file currently blocks, read from the file itself
+#[tauri::command]
+pub async fn get_blocked_hosts(app: AppHandle) -> Result<ActiveBlockingStatus, AppError> {
+    let contents = tokio::fs::read_to_string(hosts_path()).await?;
+    Ok(status(&app, blocked_in(&contents)?))
+}
+
+#[tauri::command]
+pub async fn unblock_host(
+    domain: String,
+    app: AppHandle,
+) -> Result<ActiveBlockingStatus, AppError> {
+    let host = blockable_host(&domain)
+        .ok_or_else(|| AppError::InvalidInput(format!("'{}' is not a host name", domain)))?;
+    presence::require(&app, "unblock a host")?;
+    let reason = format!("Phishing Guard wants to unblock {}.", host);
+    let blocked = change(&app, &reason, |hosts| hosts.retain(|h| *h != host)).await?;
+    Ok(status(&app, blocked))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_burst_of_hosts_schedules_one_write() {
+        let blocking = ActiveBlocking::default();
+        assert!(blocking.queue("one.example".into()));
+        assert!(!blocking.queue("two.example".into()));
+        assert!(!blocking.queue("one.example".into()));
+        assert_eq!(blocking.take_pending(), vec!["one.example", "two.example"]);
+
+        // Hosts after the write starts need a write of their own
+        assert!(blocking.queue("three.example".into()));
+        assert_eq!(blocking.take_pending(), vec!["three.example"]);
+        assert!(blocking.take_pending().is_empty());
+    }
+
+    #[test]
+    fn the_batch_is_written_as_one_section() {
+        let contents = "127.0.0.1 localhost\n";
+        let hosts = vec!["one.example".to_string(), "two.example".to_string()];
+        let updated = with_hosts(contents, &hosts).unwrap();
+        assert!(updated.starts_with(contents));
+        assert_eq!(blocked_in(&updated).unwrap(), hosts);
+        assert_eq!(with_hosts(&updated, &[]).unwrap(), contents);
+    }
+}
@@ -0,0 +1,116 @@
+use crate::cache::ResultCache;
+use crate::error::AppError;
+use crate::features::FeatureSet;
+use crate::history::{now_secs, ScanHistory};
+use crate::host_rules::{RuleEntry, RuleList};
+use crate::ScanResult;
+use serde::Serialize;
+use tauri::State;
+
+/// Known-good hosts and URL prefixes that are never sent to the detector
+pub struct Allowlist(pub RuleList);
+
+impl Allowlist {
+    pub async fn load(history: &ScanHistory) -> Result<Self, AppError> {
+        Ok(Self(RuleList::load(history, "allowlist", true).await?))
+    }
+
+    /// A synthetic result for `url` if an allowlist rule covers it
+    pub fn verdict(&self, url: &str) -> Option<ScanResult> {
+        let pattern = self.0.matches(url)?;
+        Some(ScanResult {
+            url: url.to_string(),
+            classification: "allowlisted".to_string(),
+            confidence: 1.0,
+            risk_score: 0,
+            explanation: format!("Not scanned: matches allowlist rule {}", pattern),
+            analysis_mode: Some("allowlist".to_string()),
+            features: FeatureSet::default(),
+            cached: false,
+            scanned_at: Some(now_secs()),
+            red_flags: None,
+            degraded: false,
+            redirects: None,
+            duration_ms: None,
+        })
+    }
+}
+
+/// Outcome of importing a shared allowlist file
+#[derive(Serialize, Debug, Clone)]
+pub struct AllowlistImport {
+    added: Vec<String>,
+    /// Entries that were not valid patterns
+    rejected: Vec<String>,
+}
+
+/// Add an exact host, `*.wildcard` subdomain rule, or full URL prefix
+#[tauri::command]
+pub async fn add_to_allowlist(
+    pattern: String,
+    allowlist: State<'_, Allowlist>,
+    history: State<'_, ScanHistory>,
+    cache: State<'_, ResultCache>,
+) -> Result<String, AppError> {
+    let stored = allowlist.0.add(&history, &pattern).await?;
+    cache.invalidate(|url| allowlist.0.matches(url).is_some())?;
+    Ok(stored)
+}
+
+#[tauri::command]
+pub async fn remove_from_allowlist(
+    pattern: String,
+    allowlist: State<'_, Allowlist>,
+    history: State<'_, ScanHistory>,
+) -> Result<bool, AppError> {
+    allowlist.0.remove(&history, &pattern).await
+}
+
+#[tauri::command]
+pub async fn get_allowlist(
+    allowlist: State<'_, Allowlist>,
+    history: State<'_, ScanHistory>,
+) -> Result<Vec<RuleEntry>, AppError> {
+    allowlist.0.entries(&history).await
+}
+
+/// Write the allowlist to `path` as a JSON array of patterns
+#[tauri::command]
+pub async fn export_allowlist(
+    path: String,
+    allowlist: State<'_, Allowlist>,
+    history: State<'_, ScanHistory>,
+) -> Result<usize, AppError> {
+    let patterns: Vec<String> = allowlist
+        .0
+        .entries(&history)
+        .await?
+        .into_iter()
+        .map(|entry| entry.pattern)
+        .collect();
+    tokio::fs::write(&path, serde_json::to_vec_pretty(&patterns)?).await?;
+    Ok(patterns.len())
+}
+
+/// Merge a JSON array of patterns from `path` into the allowlist
+#[tauri::command]
+pub async fn import_allowlist(
+    path: String,
+    allowlist: State<'_, Allowlist>,
+    history: State<'_, ScanHistory>,
+) -> Result<AllowlistImport, AppError> {
+    let patterns: Vec<String> = serde_json::from_slice(&tokio::fs::read(&path).await?)?;
+
+    let mut report = AllowlistImport {
+        added: Vec::new(),
+        rejected: Vec::new(),
+    };
+    for pattern in patterns {
+        match allowlist.0.add(&history, &pattern).await {
+            Ok(stored) => report.added.push(stored),
+            Err(AppError::InvalidInput(_)) => report.rejected.push(pattern),
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(report)
+}
@@ -0,0 +1,107 @@
+use crate::error::AppError;
+use crate::history::{normalize_tag, ScanHistory};
+use rusqlite::{Connection, OptionalExtension};
+use tauri::State;
+
+/// Row id of the newest history entry for `scan_id`
+fn scan_row(conn: &Connection, scan_id: &str) -> Result<i64, AppError> {
+    conn.query_row(
+        "SELECT id FROM scans WHERE scan_id = ?1 ORDER BY id DESC LIMIT 1",
+        [scan_id],
+        |row| row.get(0),
+    )
+    .optional()?
+    .ok_or_else(|| AppError::NotFound(scan_id.to_string()))
+}
+
+fn tags_of(conn: &Connection, row: i64) -> Result<Vec<String>, AppError> {
+    let mut statement =
+        conn.prepare("SELECT tag FROM scan_tags WHERE scan_row = ?1 ORDER BY tag")?;
+    let tags = statement.query_map([row], |r| r.get(0))?;
+    Ok(tags.collect::<Result<Vec<_>, _>>()?)
+}
+
+fn tag_arg(raw: &str) -> Result<String, AppError> {
+    normalize_tag(raw).ok_or_else(|| AppError::InvalidInput("tag is empty".to_string()))
+}
+
+/// Attach free-text notes to a saved scan; an empty note removes it
+#[tauri::command]
+pub async fn set_scan_note(
+    scan_id: String,
+    note: String,
+    history: State<'_, ScanHistory>,
+) -> Result<(), AppError> {
+    let note = Some(note.trim().to_string()).filter(|n| !n.is_empty());
+    history
+        .with_conn(move |conn| {
+            let row = scan_row(conn, &scan_id)?;
+            conn.execute(
+                "UPDATE scans SET note = ?1 WHERE id = ?2",
+                rusqlite::params![note, row],
+            )?;
+            Ok(())
+        })
+        .await
+}
+
+/// Tag a saved scan, returning its tags afterwards
+#[tauri::command]
+pub async fn add_scan_tag(
+    scan_id: String,
+    tag: String,
+    history: State<'_, ScanHistory>,
+) -> Result<Vec<String>, AppError> {
+    tag_scan(&history, scan_id, tag_arg(&tag)?).await
+}
+
+/// Add an already normalized tag to the newest history entry for `scan_id`
+pub async fn tag_scan(
+    history: &ScanHistory,
+    scan_id: String,
+    tag: String,
+) -> Result<Vec<String>, AppError> {
+    history
+        .with_conn(move |conn| {
+            let row = scan_row(conn, &scan_id)?;
+            conn.execute(
+                "INSERT OR IGNORE INTO scan_tags (scan_row, tag) VALUES (?1, ?2)",
+                rusqlite::params![row, tag],
+            )?;
+            tags_of(conn, row)
+        })
+        .await
+}
+
+/// Remove a tag from a saved scan, returning its tags afterwards
+#[tauri::command]
+pub async fn remove_scan_tag(
+    scan_id: String,
+    tag: String,
+    history: State<'_, ScanHistory>,
+) -> Result<Vec<String>, AppError> {
+    let tag = tag_arg(&tag)?;
+    history
+        .with_conn(move |conn| {
+            let row = scan_row(conn, &scan_id)?;
+            conn.execute(
+                "DELETE FROM scan_tags WHERE scan_row = ?1 AND tag = ?2",
+                rusqlite::params![row, tag],
+            )?;
+            tags_of(conn, row)
+        })
+        .await
+}
+
+/// Every tag in use, most used first, for autocomplete
+#[tauri::command]
+pub async fn get_all_tags(history: State<'_, ScanHistory>) -> Result<Vec<String>, AppError> {
+    history
+        .with_conn(|conn| {
+            let mut statement =
+                conn.prepare("SELECT tag FROM scan_tags GROUP BY tag ORDER BY COUNT(*) DESC, tag")?;
+            let tags = statement.query_map([], |row| row.get(0))?;
+            Ok(tags.collect::<Result<Vec<_>, _>>()?)
+        })
+        .await
+}
@@ -0,0 +1,56 @@
+use crate::error::AppError;
+use crate::{AppHandle, AppRuntime};
+use tauri::plugin::TauriPlugin;
+use tauri_plugin_autostart::{MacosLauncher, ManagerExt};
+
+/// Argument the login item launches the app with, so it starts in the tray
+pub const MINIMIZED_ARG: &str = "--minimized";
+/// Alternative spelling of `MINIMIZED_ARG`
+const TRAY_ARG: &str = "--tray";
+
+/// True when the main window should stay hidden at launch, via `--minimized`,
+/// `--tray` or PHISHING_GUARD_START_MINIMIZED=1
+pub fn start_minimized(mut args: impl Iterator<Item = String>) -> bool {
+    let requested = std::env::var("PHISHING_GUARD_START_MINIMIZED")
+        .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+        .unwrap_or(false);
+    requested || args.any(|arg| arg == MINIMIZED_ARG || arg == TRAY_ARG)
+}
+
+/// Registers launch-at-login through the platform's own mechanism: a Run
+/// registry value on Windows, a LaunchAgent plist on macOS and an XDG
+/// autostart .desktop file on Linux
+pub fn plugin() -> TauriPlugin<AppRuntime> {
+    tauri_plugin_autostart::init(MacosLauncher::LaunchAgent, Some(vec![MINIMIZED_ARG]))
+}
+
+fn autostart_error(e: tauri_plugin_autostart::Error) -> AppError {
+    AppError::Autostart(e.to_string())
+}
+
+/// Whether the OS will start the app at login, read from the OS itself
+#[tauri::command]
+pub fn get_autostart(app: AppHandle) -> Result<bool, AppError> {
+    app.autolaunch().is_enabled().map_err(autostart_error)
+}
+
+/// Start the app minimized at login, or remove the login item again
+#[tauri::command]
+pub fn set_autostart(enabled: bool, app: AppHandle) -> Result<bool, AppError> {
+    let launcher = app.autolaunch();
+    if enabled {
+        launcher.enable().map_err(autostart_error)?;
+    } else {
+        launcher.disable().map_err(autostart_error)?;
+    }
+
+    // Sandboxed installs can accept the change without it taking effect
+    let now = launcher.is_enabled().map_err(autostart_error)?;
+    if now != enabled {
+        return Err(AppError::Autostart(format!(
+            "the login item could not be {}",
+            if enabled { "created" } else { "removed" }
+        )));
+    }
+    Ok(now)
+}
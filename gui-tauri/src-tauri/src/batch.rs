@@ -0,0 +1,129 @@
+use crate::error::AppError;
+use crate::inflight::{new_scan_id, InFlightScans};
+use crate::lifecycle::{ScanJob, ScanSource};
+use crate::notifications::notify_batch_done;
+use crate::queue::ScanQueue;
+use crate::AppHandle;
+use crate::ScanResult;
+use serde::Serialize;
+use std::collections::HashMap;
+use tauri::{Emitter, Manager, State};
+use tokio::task::JoinSet;
+
+/// Outcome of one URL within a batch
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum BatchScanItem {
+    Completed {
+        url: String,
+        result: Box<ScanResult>,
+    },
+    Failed {
+        url: String,
+        error: AppError,
+    },
+}
+
+#[derive(Serialize, Clone)]
+struct BatchProgress {
+    completed: usize,
+    total: usize,
+}
+
+/// Scan `urls` through the scan queue, emitting `batch-progress` as items finish.
+///
+/// Each URL is normalized like typed input first. URLs that are the same
+/// once normalized are scanned once and the outcome is repeated for every
+/// occurrence, so the result has one item per input, under the URL as
+/// given, in the original order.
+pub async fn run_batch(
+    app: &AppHandle,
+    urls: Vec<String>,
+    source: ScanSource,
+) -> Vec<BatchScanItem> {
+    let jobs: Vec<Result<ScanJob, AppError>> = urls
+        .iter()
+        .map(|url| ScanJob::from_input(url, source))
+        .collect();
+    let mut unique: Vec<ScanJob> = Vec::new();
+    for job in jobs.iter().flatten() {
+        if !unique.iter().any(|u| u.url == job.url) {
+            unique.push(job.clone());
+        }
+    }
+
+    let total = unique.len();
+    let queue = app.state::<ScanQueue>();
+    let mut tasks = JoinSet::new();
+
+    for job in unique {
+        let url = job.url.clone();
+        let outcome = queue.enqueue(app, job);
+        tasks.spawn(async move {
+            let outcome = match outcome {
+                Ok(reply) => reply.await.unwrap_or(Err(AppError::Cancelled)),
+                Err(e) => Err(e),
+            };
+            (url, outcome)
+        });
+    }
+
+    let mut outcomes: HashMap<String, Result<ScanResult, AppError>> = HashMap::new();
+    let mut phishing = 0;
+    while let Some(joined) = tasks.join_next().await {
+        if let Ok((url, outcome)) = joined {
+            if matches!(&outcome, Ok(result) if result.is_phishing()) {
+                phishing += 1;
+            }
+            outcomes.insert(url, outcome);
+        }
+
+        let _ = app.emit(
+            "batch-progress",
+            BatchProgress {
+                completed: total - tasks.len(),
+                total,
+            },
+        );
+    }
+
+    notify_batch_done(app, source, total, phishing);
+
+    urls.into_iter()
+        .zip(jobs)
+        .map(|(url, job)| {
+            let outcome = job.and_then(|job| {
+                outcomes
+                    .get(&job.url)
+                    .cloned()
+                    .unwrap_or_else(|| Err(AppError::State("scan task aborted".to_string())))
+            });
+            match outcome {
+                Ok(result) => BatchScanItem::Completed {
+                    url,
+                    result: Box::new(result),
+                },
+                Err(error) => BatchScanItem::Failed { url, error },
+            }
+        })
+        .collect()
+}
+
+/// Scan a list of URLs concurrently, reporting a result or error per URL
+///
+/// Pass a `batch_id` to be able to abort the whole batch with `cancel_scan`;
+/// its scans that have not started yet are dropped from the queue.
+#[tauri::command]
+pub async fn scan_urls(
+    urls: Vec<String>,
+    batch_id: Option<String>,
+    app: AppHandle,
+    inflight: State<'_, InFlightScans>,
+) -> Result<Vec<BatchScanItem>, AppError> {
+    let batch_app = app.clone();
+    inflight
+        .run(&app, batch_id.unwrap_or_else(new_scan_id), async move {
+            Ok(run_batch(&batch_app, urls, ScanSource::Batch).await)
+        })
+        .await
+}
@@ -0,0 +1,71 @@
+use crate::active_blocking;
+use crate::cache::ResultCache;
+use crate::error::AppError;
+use crate::features::FeatureSet;
+use crate::history::{now_secs, ScanHistory};
+use crate::host_rules::{RuleEntry, RuleList};
+use crate::AppHandle;
+use crate::ScanResult;
+use tauri::State;
+
+/// Confirmed campaign hosts that get an instant phishing verdict, offline
+pub struct Blocklist(pub RuleList);
+
+impl Blocklist {
+    pub async fn load(history: &ScanHistory) -> Result<Self, AppError> {
+        Ok(Self(RuleList::load(history, "blocklist", false).await?))
+    }
+
+    /// A synthetic high-risk result for `url` if a blocklist rule covers it
+    pub fn verdict(&self, url: &str) -> Option<ScanResult> {
+        let pattern = self.0.matches(url)?;
+        Some(ScanResult {
+            url: url.to_string(),
+            classification: "phishing".to_string(),
+            confidence: 1.0,
+            risk_score: 100,
+            explanation: format!("Matches local blocklist rule {}", pattern),
+            analysis_mode: Some("blocklist".to_string()),
+            features: FeatureSet::default(),
+            cached: false,
+            scanned_at: Some(now_secs()),
+            red_flags: None,
+            degraded: false,
+            redirects: None,
+            duration_ms: None,
+        })
+    }
+}
+
+/// Block an exact host or every subdomain of one (`*.example.com`)
+#[tauri::command]
+pub async fn add_to_blocklist(
+    pattern: String,
+    app: AppHandle,
+    blocklist: State<'_, Blocklist>,
+    history: State<'_, ScanHistory>,
+    cache: State<'_, ResultCache>,
+) -> Result<String, AppError> {
+    let stored = blocklist.0.add(&history, &pattern).await?;
+    // A cached verdict from before the rule existed must not outlive it
+    cache.invalidate(|url| blocklist.0.matches(url).is_some())?;
+    active_blocking::on_blocklisted(&app, &stored);
+    Ok(stored)
+}
+
+#[tauri::command]
+pub async fn remove_from_blocklist(
+    pattern: String,
+    blocklist: State<'_, Blocklist>,
+    history: State<'_, ScanHistory>,
+) -> Result<bool, AppError> {
+    blocklist.0.remove(&history, &pattern).await
+}
+
+#[tauri::command]
+pub async fn get_blocklist(
+    blocklist: State<'_, Blocklist>,
+    history: State<'_, ScanHistory>,
+) -> Result<Vec<RuleEntry>, AppError> {
+    blocklist.0.entries(&history).await
+}
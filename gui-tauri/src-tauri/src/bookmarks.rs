@@ -0,0 +1,314 @@
+use crate::batch::{run_batch, BatchScanItem};
+use crate::error::AppError;
+use crate::history::normalize_url;
+use crate::lifecycle::ScanSource;
+use crate::links::is_scannable_url;
+use crate::AppHandle;
+use rusqlite::{Connection, OpenFlags};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Browser {
+    Chrome,
+    Edge,
+    Firefox,
+}
+
+const ALL: [Browser; 3] = [Browser::Chrome, Browser::Edge, Browser::Firefox];
+
+/// A bookmarked http(s) URL
+#[derive(Serialize, Debug, Clone)]
+pub struct Bookmark {
+    url: String,
+    title: String,
+    /// Folders from the root down, e.g. `Bookmarks bar/Work`
+    folder: String,
+    browser: Browser,
+}
+
+/// What was found for one browser; a missing or unreadable browser is
+/// reported here instead of failing the import
+#[derive(Serialize, Debug, Clone)]
+pub struct BrowserReport {
+    browser: Browser,
+    /// Profiles with a bookmarks file
+    profiles: usize,
+    bookmarks: usize,
+    error: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct BookmarkImport {
+    /// Deduplicated across profiles and browsers, first occurrence kept
+    bookmarks: Vec<Bookmark>,
+    browsers: Vec<BrowserReport>,
+    /// Scan outcomes; absent for a preview
+    items: Option<Vec<BatchScanItem>>,
+}
+
+/// Directory holding the browser's profiles
+fn profiles_root(browser: Browser) -> Option<PathBuf> {
+    #[cfg(windows)]
+    let root = match browser {
+        Browser::Chrome => dirs::data_local_dir()?.join(r"Google\Chrome\User Data"),
+        Browser::Edge => dirs::data_local_dir()?.join(r"Microsoft\Edge\User Data"),
+        Browser::Firefox => dirs::config_dir()?.join(r"Mozilla\Firefox\Profiles"),
+    };
+    #[cfg(target_os = "macos")]
+    let root = dirs::home_dir()?
+        .join("Library/Application Support")
+        .join(match browser {
+            Browser::Chrome => "Google/Chrome",
+            Browser::Edge => "Microsoft Edge",
+            Browser::Firefox => "Firefox/Profiles",
+        });
+    #[cfg(not(any(windows, target_os = "macos")))]
+    let root = match browser {
+        Browser::Chrome => dirs::config_dir()?.join("google-chrome"),
+        Browser::Edge => dirs::config_dir()?.join("microsoft-edge"),
+        Browser::Firefox => dirs::home_dir()?.join(".mozilla/firefox"),
+    };
+    Some(root)
+}
+
+/// The bookmark file of every profile under the browser's root
+fn bookmark_files(browser: Browser, root: &Path) -> Result<Vec<PathBuf>, AppError> {
+    let file = match browser {
+        Browser::Firefox => "places.sqlite",
+        Browser::Chrome | Browser::Edge => "Bookmarks",
+    };
+    let mut files: Vec<PathBuf> = std::fs::read_dir(root)?
+        .filter_map(|entry| Some(entry.ok()?.path().join(file)))
+        .filter(|path| path.is_file())
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// Walk a Chromium bookmark node, collecting URLs under `folder`
+fn walk_chromium(node: &Value, folder: &str, browser: Browser, out: &mut Vec<Bookmark>) {
+    let name = node["name"].as_str().unwrap_or_default();
+    match node["type"].as_str() {
+        Some("url") => out.push(Bookmark {
+            url: node["url"].as_str().unwrap_or_default().to_string(),
+            title: name.to_string(),
+            folder: folder.to_string(),
+            browser,
+        }),
+        Some("folder") => {
+            let path = if folder.is_empty() {
+                name.to_string()
+            } else {
+                format!("{}/{}", folder, name)
+            };
+            for child in node["children"].as_array().into_iter().flatten() {
+                walk_chromium(child, &path, browser, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Bookmarks from a Chrome or Edge `Bookmarks` JSON file
+fn read_chromium(path: &Path, browser: Browser) -> Result<Vec<Bookmark>, AppError> {
+    let document: Value = serde_json::from_slice(&std::fs::read(path)?)?;
+    let mut bookmarks = Vec::new();
+    if let Some(roots) = document["roots"].as_object() {
+        for root in roots.values() {
+            walk_chromium(root, "", browser, &mut bookmarks);
+        }
+    }
+    Ok(bookmarks)
+}
+
+/// Names Firefox shows for its built-in folders, by guid
+fn firefox_root_name(guid: &str) -> Option<&'static str> {
+    match guid {
+        "menu________" => Some("Bookmarks Menu"),
+        "toolbar_____" => Some("Bookmarks Toolbar"),
+        "unfiled_____" => Some("Other Bookmarks"),
+        "mobile______" => Some("Mobile Bookmarks"),
+        _ => None,
+    }
+}
+
+fn query_firefox(conn: &Connection) -> Result<Vec<Bookmark>, AppError> {
+    // type 1 is a bookmark and 2 a folder
+    let mut statement = conn.prepare(
+        "SELECT b.id, b.type, b.parent, coalesce(b.title, ''), b.guid, p.url
+         FROM moz_bookmarks b LEFT JOIN moz_places p ON p.id = b.fk
+         WHERE b.type IN (1, 2)",
+    )?;
+    let rows = statement
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, Option<i64>>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, String>(4)?,
+                row.get::<_, Option<String>>(5)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let folders: HashMap<i64, (Option<i64>, String)> = rows
+        .iter()
+        .filter(|row| row.1 == 2)
+        .map(|(id, _, parent, title, guid, _)| {
+            let name = firefox_root_name(guid).map_or_else(|| title.clone(), str::to_string);
+            (*id, (*parent, name))
+        })
+        .collect();
+    let folder_path = |mut parent: Option<i64>| {
+        let mut names = Vec::new();
+        // Bounded in case a damaged database has a cycle
+        for _ in 0..64 {
+            let Some((grandparent, name)) = parent.and_then(|id| folders.get(&id)) else {
+                break;
+            };
+            if !name.is_empty() {
+                names.push(name.as_str());
+            }
+            parent = *grandparent;
+        }
+        names.reverse();
+        names.join("/")
+    };
+
+    Ok(rows
+        .iter()
+        .filter(|row| row.1 == 1)
+        .filter_map(|(_, _, parent, title, _, url)| {
+            Some(Bookmark {
+                url: url.clone()?,
+                title: title.clone(),
+                folder: folder_path(*parent),
+                browser: Browser::Firefox,
+            })
+        })
+        .collect())
+}
+
+/// Bookmarks from a Firefox `places.sqlite`. Firefox keeps the database
+/// locked while running, so it is read from a copy, along with its
+/// write-ahead log so recent bookmarks aren't missed.
+fn read_firefox(path: &Path) -> Result<Vec<Bookmark>, AppError> {
+    let copy = std::env::temp_dir().join(format!(
+        "phishing-guard-places-{}.sqlite",
+        uuid::Uuid::new_v4().simple()
+    ));
+    let sibling = |db: &Path, suffix: &str| {
+        let mut path = db.as_os_str().to_owned();
+        path.push(suffix);
+        PathBuf::from(path)
+    };
+    let read = || {
+        std::fs::copy(path, &copy)?;
+        if sibling(path, "-wal").is_file() {
+            std::fs::copy(sibling(path, "-wal"), sibling(&copy, "-wal"))?;
+        }
+        // Writable so SQLite can fold the copied log into the copy
+        let conn = Connection::open_with_flags(&copy, OpenFlags::SQLITE_OPEN_READ_WRITE)?;
+        query_firefox(&conn)
+    };
+    let bookmarks = read();
+    for suffix in ["-wal", "-shm", ""] {
+        let _ = std::fs::remove_file(sibling(&copy, suffix));
+    }
+    bookmarks
+}
+
+/// Every bookmark of every profile of `browser`, and how that went
+fn read_browser(browser: Browser) -> (Vec<Bookmark>, BrowserReport) {
+    let mut report = BrowserReport {
+        browser,
+        profiles: 0,
+        bookmarks: 0,
+        error: None,
+    };
+    let root = match profiles_root(browser) {
+        Some(root) if root.is_dir() => root,
+        _ => {
+            report.error = Some("not installed".into());
+            return (Vec::new(), report);
+        }
+    };
+    let files = match bookmark_files(browser, &root) {
+        Ok(files) if !files.is_empty() => files,
+        Ok(_) => {
+            report.error = Some("no profile with bookmarks".into());
+            return (Vec::new(), report);
+        }
+        Err(e) => {
+            report.error = Some(e.to_string());
+            return (Vec::new(), report);
+        }
+    };
+
+    let mut bookmarks = Vec::new();
+    let mut errors = Vec::new();
+    for file in files {
+        let read = match browser {
+            Browser::Firefox => read_firefox(&file),
+            Browser::Chrome | Browser::Edge => read_chromium(&file, browser),
+        };
+        match read {
+            Ok(found) => {
+                report.profiles += 1;
+                bookmarks.extend(found);
+            }
+            Err(e) => errors.push(format!("{}: {}", file.display(), e)),
+        }
+    }
+    report.bookmarks = bookmarks.len();
+    if !errors.is_empty() {
+        report.error = Some(errors.join("; "));
+    }
+    (bookmarks, report)
+}
+
+/// Collect the http(s) bookmarks of `browser`, or of every supported
+/// browser, with their folders. Returns a preview unless `scan` is set, in
+/// which case they are also scanned as a batch.
+#[tauri::command]
+pub async fn import_bookmarks(
+    browser: Option<Browser>,
+    scan: Option<bool>,
+    app: AppHandle,
+) -> Result<BookmarkImport, AppError> {
+    let browsers: Vec<Browser> = browser.map_or_else(|| ALL.to_vec(), |b| vec![b]);
+    let (bookmarks, browsers) = tokio::task::spawn_blocking(move || {
+        let mut seen = HashSet::new();
+        let mut bookmarks = Vec::new();
+        let mut reports = Vec::new();
+        for browser in browsers {
+            let (found, report) = read_browser(browser);
+            bookmarks.extend(
+                found
+                    .into_iter()
+                    .filter(|b| is_scannable_url(&b.url) && seen.insert(normalize_url(&b.url))),
+            );
+            reports.push(report);
+        }
+        (bookmarks, reports)
+    })
+    .await
+    .map_err(|e| AppError::State(format!("bookmark import failed: {}", e)))?;
+
+    let items = if scan.unwrap_or(false) {
+        let urls = bookmarks.iter().map(|b| b.url.clone()).collect();
+        Some(run_batch(&app, urls, ScanSource::Import).await)
+    } else {
+        None
+    };
+    Ok(BookmarkImport {
+        bookmarks,
+        browsers,
+        items,
+    })
+}
@@ -0,0 +1,392 @@
+use crate::error::AppError;
+use crate::history::{normalize_url, ScanHistory};
+use crate::tray;
+use crate::AppHandle;
+use crate::ScanResult;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{Emitter, State};
+
+/// How long a result is reused before the URL is scanned again
+const DEFAULT_TTL_SECS: u64 = 15 * 60;
+/// Results kept before the least recently used one is evicted
+const DEFAULT_MAX_ENTRIES: usize = 500;
+
+struct CacheEntry {
+    result: ScanResult,
+    stored: Instant,
+    /// Value of `CacheState::clock` when the entry was last read or written
+    last_used: u64,
+}
+
+struct CacheState {
+    entries: HashMap<String, CacheEntry>,
+    ttl: Duration,
+    max_entries: usize,
+    clock: u64,
+    hits: u64,
+    misses: u64,
+    /// Bumped by every clear so results from scans started before it are not stored
+    generation: u64,
+}
+
+/// Recent scan results keyed by normalized URL, so repeat scans are instant
+pub struct ResultCache {
+    state: Mutex<CacheState>,
+}
+
+fn env_number<T: std::str::FromStr>(var: &str) -> Option<T> {
+    std::env::var(var).ok().and_then(|v| v.parse().ok())
+}
+
+impl Default for ResultCache {
+    fn default() -> Self {
+        Self {
+            state: Mutex::new(CacheState {
+                entries: HashMap::new(),
+                ttl: Duration::from_secs(
+                    env_number("PHISHING_GUARD_CACHE_TTL_SECS").unwrap_or(DEFAULT_TTL_SECS),
+                ),
+                max_entries: env_number("PHISHING_GUARD_CACHE_MAX_ENTRIES")
+                    .unwrap_or(DEFAULT_MAX_ENTRIES),
+                clock: 0,
+                hits: 0,
+                misses: 0,
+                generation: 0,
+            }),
+        }
+    }
+}
+
+impl ResultCache {
+    /// A still-fresh result for `url`, marked `cached`
+    pub fn get(&self, url: &str) -> Option<ScanResult> {
+        let mut state = self.state.lock().ok()?;
+        let key = normalize_url(url);
+        state.clock += 1;
+        let (clock, ttl) = (state.clock, state.ttl);
+
+        let found = match state.entries.get_mut(&key) {
+            Some(entry) if entry.stored.elapsed() < ttl => {
+                entry.last_used = clock;
+                let mut result = entry.result.clone();
+                result.cached = true;
+                Some(result)
+            }
+            Some(_) => {
+                state.entries.remove(&key);
+                None
+            }
+            None => None,
+        };
+
+        match found {
+            Some(_) => state.hits += 1,
+            None => state.misses += 1,
+        }
+        found
+    }
+
+    /// Token to pass to `insert` for a scan starting now
+    pub fn generation(&self) -> u64 {
+        self.state.lock().map(|s| s.generation).unwrap_or(0)
+    }
+
+    /// Remember a fresh detector result, evicting the least recently used entry
+    /// when full. Results from scans that started before the last clear are dropped.
+    pub fn insert(&self, result: &ScanResult, generation: u64) {
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        if state.generation != generation || state.max_entries == 0 || state.ttl.is_zero() {
+            return;
+        }
+        state.clock += 1;
+        let key = normalize_url(&result.url);
+
+        if !state.entries.contains_key(&key) && state.entries.len() >= state.max_entries {
+            let oldest = state
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                state.entries.remove(&oldest);
+            }
+        }
+
+        let entry = CacheEntry {
+            result: result.clone(),
+            stored: Instant::now(),
+            last_used: state.clock,
+        };
+        state.entries.insert(key, entry);
+    }
+
+    pub fn stats(&self) -> Result<CacheStats, AppError> {
+        let state = self.state.lock()?;
+        let approx_bytes = state
+            .entries
+            .iter()
+            .map(|(key, entry)| {
+                let result = &entry.result;
+                std::mem::size_of::<CacheEntry>()
+                    + key.len()
+                    + result.url.len()
+                    + result.classification.len()
+                    + result.explanation.len()
+                    + serde_json::to_string(&result.features).map_or(0, |f| f.len())
+            })
+            .sum();
+        Ok(CacheStats {
+            entries: state.entries.len(),
+            max_entries: state.max_entries,
+            ttl_secs: state.ttl.as_secs(),
+            hits: state.hits,
+            misses: state.misses,
+            approx_bytes,
+        })
+    }
+
+    /// Drop entries whose URL satisfies `stale`
+    pub fn invalidate(&self, stale: impl Fn(&str) -> bool) -> Result<(), AppError> {
+        self.state
+            .lock()?
+            .entries
+            .retain(|_, entry| !stale(&entry.result.url));
+        Ok(())
+    }
+
+    /// Drop every entry; scans already running still return but are not cached
+    pub fn clear(&self) -> Result<(), AppError> {
+        let mut state = self.state.lock()?;
+        state.entries.clear();
+        state.generation += 1;
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct CacheStats {
+    entries: usize,
+    max_entries: usize,
+    ttl_secs: u64,
+    /// Lookups answered from the cache since launch
+    hits: u64,
+    misses: u64,
+    /// Rough heap footprint of the cached results
+    approx_bytes: usize,
+}
+
+/// What `clear_cache` wipes
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ClearScope {
+    /// The in-memory result cache only
+    Memory,
+    /// The saved scan history
+    History,
+    All,
+}
+
+/// Entry count, hit rate and size of the result cache
+#[tauri::command]
+pub fn get_cache_stats(cache: State<'_, ResultCache>) -> Result<CacheStats, AppError> {
+    cache.stats()
+}
+
+/// Clear the result cache and/or the scan history.
+///
+/// Deleting history cannot be undone, so those scopes need `confirm: true`.
+#[tauri::command]
+pub async fn clear_cache(
+    scope: ClearScope,
+    confirm: Option<bool>,
+    app: AppHandle,
+    cache: State<'_, ResultCache>,
+    history: State<'_, ScanHistory>,
+) -> Result<(), AppError> {
+    if scope != ClearScope::Memory && !confirm.unwrap_or(false) {
+        return Err(AppError::InvalidInput(
+            "clearing history requires confirm: true".to_string(),
+        ));
+    }
+
+    if matches!(scope, ClearScope::Memory | ClearScope::All) {
+        cache.clear()?;
+    }
+    if matches!(scope, ClearScope::History | ClearScope::All) {
+        history.clear().await?;
+        tray::refresh_menu(&app).await;
+        tray::refresh_stats(&app).await;
+    }
+
+    let _ = app.emit("cache-cleared", scope);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::FeatureSet;
+    use crate::lifecycle::{ScanJob, ScanSource};
+    use crate::testing;
+    use serde_json::json;
+    use std::sync::Arc;
+    use tauri::async_runtime::block_on;
+    use tauri::Manager;
+
+    fn cache(ttl: Duration, max_entries: usize) -> ResultCache {
+        let cache = ResultCache::default();
+        {
+            let mut state = cache.state.lock().unwrap();
+            state.ttl = ttl;
+            state.max_entries = max_entries;
+        }
+        cache
+    }
+
+    fn result(url: &str) -> ScanResult {
+        ScanResult {
+            url: url.to_string(),
+            classification: "legitimate".to_string(),
+            confidence: 0.9,
+            risk_score: 5,
+            explanation: String::new(),
+            analysis_mode: None,
+            features: FeatureSet::default(),
+            cached: false,
+            scanned_at: None,
+            red_flags: None,
+            degraded: false,
+            redirects: None,
+            duration_ms: None,
+        }
+    }
+
+    #[test]
+    fn results_expire_after_the_ttl() {
+        let cache = cache(Duration::from_millis(50), 10);
+        cache.insert(&result("https://a.example/"), cache.generation());
+        let hit = cache.get("https://A.example").unwrap();
+        assert!(hit.cached);
+
+        std::thread::sleep(Duration::from_millis(80));
+        assert!(cache.get("https://a.example/").is_none());
+        let stats = cache.stats().unwrap();
+        assert_eq!((stats.entries, stats.hits, stats.misses), (0, 1, 1));
+    }
+
+    #[test]
+    fn concurrent_lookups_and_inserts_across_expiry_stay_consistent() {
+        let cache = Arc::new(cache(Duration::from_millis(5), 20));
+        let threads: Vec<_> = (0..8)
+            .map(|thread| {
+                let cache = cache.clone();
+                std::thread::spawn(move || {
+                    for i in 0..300 {
+                        let url = format!("https://site{}.example/", (thread * 7 + i) % 40);
+                        if cache.get(&url).is_none() {
+                            cache.insert(&result(&url), cache.generation());
+                        }
+                        if i % 50 == 0 {
+                            std::thread::sleep(Duration::from_millis(6));
+                        }
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        let stats = cache.stats().unwrap();
+        assert_eq!(stats.hits + stats.misses, 8 * 300);
+        assert!(stats.hits > 0 && stats.misses > 0);
+        assert!(stats.entries <= 20);
+        // Whatever is still cached is only served while fresh
+        std::thread::sleep(Duration::from_millis(10));
+        assert!((0..40).all(|i| cache.get(&format!("https://site{}.example/", i)).is_none()));
+    }
+
+    #[test]
+    fn a_full_cache_evicts_the_least_recently_used_result() {
+        let cache = cache(Duration::from_secs(60), 3);
+        let generation = cache.generation();
+        for url in [
+            "https://a.example/",
+            "https://b.example/",
+            "https://c.example/",
+        ] {
+            cache.insert(&result(url), generation);
+        }
+        // Reading `a` makes `b` the least recently used
+        assert!(cache.get("https://a.example/").is_some());
+        cache.insert(&result("https://d.example/"), generation);
+
+        assert!(cache.get("https://b.example/").is_none());
+        for url in [
+            "https://a.example/",
+            "https://c.example/",
+            "https://d.example/",
+        ] {
+            assert!(cache.get(url).is_some(), "{} was evicted", url);
+        }
+        // Storing a URL again replaces it rather than evicting another
+        cache.insert(&result("https://c.example/"), generation);
+        assert_eq!(cache.stats().unwrap().entries, 3);
+    }
+
+    #[test]
+    fn a_scan_started_before_a_clear_is_not_cached() {
+        let cache = cache(Duration::from_secs(60), 10);
+        let before = cache.generation();
+        cache.insert(&result("https://old.example/"), before);
+        cache.clear().unwrap();
+        assert!(cache.get("https://old.example/").is_none());
+
+        // The scan was already running when the cache was cleared
+        cache.insert(&result("https://late.example/"), before);
+        assert!(cache.get("https://late.example/").is_none());
+
+        cache.insert(&result("https://late.example/"), cache.generation());
+        assert!(cache.get("https://late.example/").is_some());
+    }
+
+    #[test]
+    fn clearing_history_needs_confirmation() {
+        let app = testing::app(tauri::generate_handler![clear_cache]);
+        let history = app.window.state::<ScanHistory>();
+        let url = "https://kept.example/";
+        let job = ScanJob::from_input(url, ScanSource::Manual).unwrap();
+        let stored = |history: &ScanHistory| block_on(history.recent(10)).unwrap().len();
+        block_on(history.record(&result(url), &job)).unwrap();
+
+        for scope in ["history", "all"] {
+            let error = app
+                .invoke("clear_cache", json!({ "scope": scope }))
+                .unwrap_err();
+            assert_eq!(error["kind"], "invalid_input");
+            let error = app
+                .invoke("clear_cache", json!({ "scope": scope, "confirm": false }))
+                .unwrap_err();
+            assert_eq!(error["kind"], "invalid_input");
+        }
+        assert!(app
+            .invoke("clear_cache", json!({"scope": "memory"}))
+            .is_ok());
+        assert_eq!(stored(&history), 1);
+        assert!(app
+            .invoke("clear_cache", json!({"scope": "all", "confirm": true}))
+            .is_ok());
+        assert_eq!(stored(&history), 0);
+        assert!(app
+            .invoke(
+                "clear_cache",
+                json!({"scope": "everything", "confirm": true})
+            )
+            .is_err());
+    }
+}
@@ -0,0 +1,252 @@
+use crate::error::AppError;
+use crate::heuristics;
+use crate::links::{defang, normalize_input};
+use crate::{scan_url_internal, AppState, ScanResult};
+use serde_json::json;
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+/// Exit codes scripts can branch on
+const EXIT_LEGITIMATE: i32 = 0;
+const EXIT_PHISHING: i32 = 1;
+const EXIT_ERROR: i32 = 2;
+
+const USAGE: &str = "\
+Usage: phishguard scan <url> [--json] [--force]
+       phishguard scan --stdin [--force]
+       phishguard scan - [--force]
+
+  --json    print the full result as JSON
+  --force   run the full MLLM analysis
+  --stdin   scan newline-separated URLs from stdin, one JSON result per line;
+            `-` in place of the URL does the same
+
+Exit status: 0 legitimate, 1 phishing, 2 error (for --stdin, the worst of all lines)";
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Invocation {
+    Help,
+    Scan(ScanCommand),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ScanCommand {
+    /// The URL to scan, or `None` to read URLs from stdin
+    url: Option<String>,
+    json: bool,
+    force: bool,
+}
+
+/// A command-line invocation, or `None` when the app should start normally
+pub fn parse(args: impl Iterator<Item = String>) -> Option<Result<Invocation, String>> {
+    let mut args = args.peekable();
+    if args.peek().map(String::as_str) != Some("scan") {
+        return None;
+    }
+    args.next();
+    Some(parse_scan(args))
+}
+
+fn parse_scan(args: impl Iterator<Item = String>) -> Result<Invocation, String> {
+    let (mut url, mut json, mut force, mut stdin) = (None, false, false, false);
+    for arg in args {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--force" => force = true,
+            "--stdin" | "-" => stdin = true,
+            "-h" | "--help" => return Ok(Invocation::Help),
+            option if option.starts_with('-') => return Err(format!("unknown option {}", option)),
+            _ if url.is_some() => return Err("scan takes one URL; use --stdin for more".into()),
+            _ => url = Some(arg),
+        }
+    }
+    match (stdin, &url) {
+        (true, Some(_)) => Err("--stdin takes no URL argument".into()),
+        (false, None) => Err("no URL to scan".into()),
+        _ => Ok(Invocation::Scan(ScanCommand { url, json, force })),
+    }
+}
+
+fn exit_code(outcome: &Result<ScanResult, AppError>) -> i32 {
+    match outcome {
+        Ok(result) if result.is_phishing() => EXIT_PHISHING,
+        Ok(_) => EXIT_LEGITIMATE,
+        Err(_) => EXIT_ERROR,
+    }
+}
+
+async fn scan(state: &AppState, input: &str, force: bool) -> Result<ScanResult, AppError> {
+    let url = normalize_input(input)?;
+    let mut result =
+        scan_url_internal(&url, &state.project_root, state.scan_timeout_secs, force).await?;
+    result.red_flags = Some(heuristics::check(&url, &[]));
+    Ok(result)
+}
+
+/// One line of `--stdin` output
+fn json_line(url: &str, outcome: &Result<ScanResult, AppError>) -> String {
+    match outcome {
+        Ok(result) => json!(result),
+        Err(e) => json!({ "url": url, "error": e }),
+    }
+    .to_string()
+}
+
+fn print_verdict(result: &ScanResult) {
+    println!(
+        "{}  {}",
+        result.classification.replace('_', " ").to_uppercase(),
+        defang(&result.url)
+    );
+    println!(
+        "Risk score {}/100, confidence {:.0}%",
+        result.risk_score,
+        result.confidence * 100.0
+    );
+    if !result.explanation.is_empty() {
+        println!("{}", result.explanation);
+    }
+}
+
+async fn scan_stdin(state: &AppState, force: bool) -> i32 {
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    let mut worst = EXIT_LEGITIMATE;
+    loop {
+        let line = match lines.next_line().await {
+            Ok(Some(line)) => line,
+            Ok(None) => return worst,
+            Err(e) => {
+                eprintln!("error: could not read stdin: {}", e);
+                return EXIT_ERROR;
+            }
+        };
+        let url = line.trim();
+        if url.is_empty() {
+            continue;
+        }
+        let outcome = scan(state, url, force).await;
+        println!("{}", json_line(url, &outcome));
+        worst = worst.max(exit_code(&outcome));
+    }
+}
+
+async fn scan_one(state: &AppState, url: &str, json: bool, force: bool) -> i32 {
+    let outcome = scan(state, url, force).await;
+    match &outcome {
+        Ok(result) if json => match serde_json::to_string_pretty(result) {
+            Ok(text) => println!("{}", text),
+            Err(e) => {
+                eprintln!("error: {}", e);
+                return EXIT_ERROR;
+            }
+        },
+        Ok(result) => print_verdict(result),
+        Err(_) if json => println!("{}", json_line(url, &outcome)),
+        Err(e) => eprintln!("error: {}", e),
+    }
+    exit_code(&outcome)
+}
+
+/// Run a command-line invocation without any windows or tray; returns the exit code
+pub fn run(invocation: Result<Invocation, String>) -> i32 {
+    let command = match invocation {
+        Ok(Invocation::Scan(command)) => command,
+        Ok(Invocation::Help) => {
+            println!("{}", USAGE);
+            return EXIT_LEGITIMATE;
+        }
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, USAGE);
+            return EXIT_ERROR;
+        }
+    };
+    let runtime = match tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+    {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return EXIT_ERROR;
+        }
+    };
+
+    // Timeouts come from the same environment variables the app reads
+    let state = AppState::new();
+    runtime.block_on(async {
+        match &command.url {
+            Some(url) => scan_one(&state, url, command.json, command.force).await,
+            None => scan_stdin(&state, command.force).await,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parsed(args: &[&str]) -> Option<Result<Invocation, String>> {
+        parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    fn scan(url: Option<&str>, json: bool, force: bool) -> Result<Invocation, String> {
+        Ok(Invocation::Scan(ScanCommand {
+            url: url.map(str::to_string),
+            json,
+            force,
+        }))
+    }
+
+    #[test]
+    fn scan_arguments() {
+        let url = "https://example.com";
+        let cases: &[(&[&str], Result<Invocation, String>)] = &[
+            (&["scan", url], scan(Some(url), false, false)),
+            (&["scan", url, "--json"], scan(Some(url), true, false)),
+            (
+                &["scan", "--json", "--force", url],
+                scan(Some(url), true, true),
+            ),
+            (&["scan", "--stdin"], scan(None, false, false)),
+            (&["scan", "-"], scan(None, false, false)),
+            (&["scan", "-", "--force", "--json"], scan(None, true, true)),
+            (&["scan", "--help"], Ok(Invocation::Help)),
+            (&["scan", url, "-h"], Ok(Invocation::Help)),
+            // Help wins over whatever else is wrong
+            (&["scan", "--help", "--bogus"], Ok(Invocation::Help)),
+            (
+                &["scan", "--bogus", url],
+                Err("unknown option --bogus".into()),
+            ),
+            (&["scan", url, "-x"], Err("unknown option -x".into())),
+            (&["scan"], Err("no URL to scan".into())),
+            (&["scan", "--json"], Err("no URL to scan".into())),
+            (
+                &["scan", url, "https://other.example"],
+                Err("scan takes one URL; use --stdin for more".into()),
+            ),
+            (
+                &["scan", "-", url],
+                Err("--stdin takes no URL argument".into()),
+            ),
+            (
+                &["scan", "--stdin", url],
+                Err("--stdin takes no URL argument".into()),
+            ),
+        ];
+        for (args, expected) in cases {
+            assert_eq!(parsed(args).as_ref(), Some(expected), "{:?}", args);
+        }
+    }
+
+    #[test]
+    fn other_arguments_start_the_app() {
+        for args in [
+            &[][..],
+            &["--minimized"],
+            &["phishguard://scan?url=x"],
+            &["--scan"],
+        ] {
+            assert!(parsed(args).is_none(), "{:?}", args);
+        }
+    }
+}
@@ -0,0 +1,187 @@
+use crate::allowlist::Allowlist;
+use crate::blocklist::Blocklist;
+use crate::crash;
+use crate::error::AppError;
+use crate::lifecycle::{ScanJob, ScanSource};
+use crate::links::{defang, is_scannable_url};
+use crate::notifications::{notify, notify_phishing};
+use crate::protection::is_paused;
+use crate::queue::ScanQueue;
+use crate::tray;
+use crate::AppHandle;
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::async_runtime::JoinHandle;
+use tauri::{Emitter, Manager};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+/// How often the clipboard is sampled
+const POLL_INTERVAL: Duration = Duration::from_millis(750);
+/// Clipboard content must stay unchanged this long before it is acted on
+const DEBOUNCE: Duration = Duration::from_millis(1500);
+/// Anything longer than this is not treated as a candidate URL
+const MAX_URL_LEN: usize = 4096;
+
+/// Handle to the running clipboard watcher task, if any
+#[derive(Default)]
+pub struct ClipboardWatcher {
+    task: Mutex<Option<JoinHandle<()>>>,
+    /// Whether the last start scanned copied URLs, reused when the tray
+    /// turns the watcher back on
+    auto_scan: Mutex<bool>,
+}
+
+impl ClipboardWatcher {
+    pub fn is_running(&self) -> bool {
+        self.task.lock().is_ok_and(|task| task.is_some())
+    }
+}
+
+fn fingerprint(text: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    text.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// React to a copied URL: announce it, then either notify or scan it
+async fn handle_url(app: &AppHandle, url: String, auto_scan: bool) {
+    // Rules and the scan see the URL in the same form as any other scan
+    let Ok(job) = ScanJob::from_input(&url, ScanSource::Clipboard) else {
+        return;
+    };
+    let url = job.url.clone();
+    if is_paused(app) || app.state::<Allowlist>().0.matches(&url).is_some() {
+        return;
+    }
+    let _ = app.emit("clipboard-url-detected", &url);
+
+    if !auto_scan {
+        // Blocklist verdicts are local and instant, so warn even without auto-scan
+        if let Some(result) = app.state::<Blocklist>().verdict(&url) {
+            notify_phishing(app, None, &result);
+            return;
+        }
+        notify(
+            app,
+            "Link copied",
+            &format!("{}\nOpen Phishing Guard to scan it.", defang(&url)),
+        );
+        return;
+    }
+
+    // The queue announces the verdict; the watcher keeps polling meanwhile
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = app.state::<ScanQueue>().submit(&app, job).await {
+            notify(&app, "Copied link could not be scanned", &e.to_string());
+        }
+    });
+}
+
+/// Poll the clipboard, acting on each distinct URL once it has settled.
+///
+/// Only a hash of the previous contents is kept between polls, so non-URL
+/// text is never stored or reported.
+async fn watch(app: AppHandle, auto_scan: bool) {
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+    let mut current: Option<(u64, Instant, bool)> = None;
+    let mut last_url: Option<String> = None;
+
+    loop {
+        interval.tick().await;
+
+        let Ok(text) = app.clipboard().read_text() else {
+            continue;
+        };
+        let hash = fingerprint(&text);
+
+        match current {
+            Some((seen, _, _)) if seen == hash => {}
+            _ => {
+                current = Some((hash, Instant::now(), false));
+                continue;
+            }
+        }
+
+        let Some((_, changed_at, handled)) = current.as_mut() else {
+            continue;
+        };
+        if *handled || changed_at.elapsed() < DEBOUNCE {
+            continue;
+        }
+        *handled = true;
+
+        let candidate = text.trim();
+        if candidate.len() > MAX_URL_LEN || !is_scannable_url(candidate) {
+            continue;
+        }
+        if last_url.as_deref() == Some(candidate) {
+            continue;
+        }
+
+        last_url = Some(candidate.to_string());
+        handle_url(&app, candidate.to_string(), auto_scan).await;
+    }
+}
+
+/// Tell the tray and the windows whether the watcher runs
+fn changed(app: &AppHandle, running: bool) {
+    let _ = app.emit("clipboard-watch-changed", running);
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move { tray::refresh_menu(&app).await });
+}
+
+/// Start the watcher, restarting it if already running; `None` keeps the
+/// last choice of `auto_scan`
+pub fn start(app: &AppHandle, auto_scan: Option<bool>) -> Result<(), AppError> {
+    let watcher = app.state::<ClipboardWatcher>();
+    {
+        let mut task = watcher.task.lock()?;
+        if let Some(running) = task.take() {
+            running.abort();
+        }
+        let mut last = watcher.auto_scan.lock()?;
+        let auto_scan = auto_scan.unwrap_or(*last);
+        *last = auto_scan;
+        *task = Some(crash::supervise(
+            app.clone(),
+            "clipboard watcher",
+            move |app| watch(app, auto_scan),
+        ));
+    }
+    changed(app, true);
+    Ok(())
+}
+
+pub fn stop(app: &AppHandle) -> Result<(), AppError> {
+    let running = app.state::<ClipboardWatcher>().task.lock()?.take();
+    if let Some(running) = running {
+        running.abort();
+        changed(app, false);
+    }
+    Ok(())
+}
+
+/// Start the watcher if it is stopped, or stop it, for the tray item
+pub fn toggle(app: &AppHandle) -> Result<(), AppError> {
+    if app.state::<ClipboardWatcher>().is_running() {
+        stop(app)
+    } else {
+        start(app, None)
+    }
+}
+
+/// Start watching the clipboard for URLs; restarts the watcher if already running
+#[tauri::command]
+pub fn start_clipboard_watch(auto_scan: Option<bool>, app: AppHandle) -> Result<(), AppError> {
+    start(&app, Some(auto_scan.unwrap_or(false)))
+}
+
+/// Stop the clipboard watcher if it is running
+#[tauri::command]
+pub fn stop_clipboard_watch(app: AppHandle) -> Result<(), AppError> {
+    stop(&app)
+}
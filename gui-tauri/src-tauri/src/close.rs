@@ -0,0 +1,86 @@
+use crate::error::AppError;
+use crate::history::ScanHistory;
+use crate::settings::{self, CloseBehavior, Settings};
+use crate::MAIN_WINDOW;
+use crate::{AppHandle, Window};
+use serde::Deserialize;
+use tauri::{CloseRequestApi, Emitter, Manager, State};
+
+/// The user's answer to a `close-requested` prompt
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CloseAction {
+    Tray,
+    Quit,
+}
+
+fn apply(app: &AppHandle, action: CloseAction) {
+    match action {
+        CloseAction::Tray => {
+            if let Some(window) = app.get_webview_window(MAIN_WINDOW) {
+                let _ = window.hide();
+            }
+        }
+        // Exit explicitly so other windows don't keep the app alive
+        CloseAction::Quit => quit(app),
+    }
+}
+
+/// Exit the app once history writes in progress have finished
+pub fn quit(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let _closed = match app.try_state::<ScanHistory>() {
+            Some(history) => Some(history.finish_writes().await),
+            None => None,
+        };
+        app.exit(0);
+    });
+}
+
+/// Handle the main window's close button according to `close_behavior`,
+/// read on every close so a changed setting applies immediately
+pub fn on_close_requested(window: &Window, api: &CloseRequestApi) {
+    let app = window.app_handle();
+    match app.state::<Settings>().get().close_behavior {
+        CloseBehavior::Quit => apply(app, CloseAction::Quit),
+        CloseBehavior::Tray => {
+            api.prevent_close();
+            apply(app, CloseAction::Tray);
+        }
+        CloseBehavior::Ask => {
+            api.prevent_close();
+            let _ = window.emit("close-requested", ());
+        }
+    }
+}
+
+#[tauri::command]
+pub fn get_close_behavior(settings: State<'_, Settings>) -> CloseBehavior {
+    settings.get().close_behavior
+}
+
+#[tauri::command]
+pub fn set_close_behavior(behavior: CloseBehavior, app: AppHandle) -> Result<(), AppError> {
+    settings::update(&app, |s| s.close_behavior = behavior)?;
+    Ok(())
+}
+
+/// Answer a `close-requested` prompt, optionally making the choice the new
+/// close behavior so the prompt is not shown again
+#[tauri::command]
+pub fn resolve_close_request(
+    action: CloseAction,
+    remember: Option<bool>,
+    app: AppHandle,
+) -> Result<(), AppError> {
+    if remember.unwrap_or(false) {
+        let behavior = match action {
+            CloseAction::Tray => CloseBehavior::Tray,
+            CloseAction::Quit => CloseBehavior::Quit,
+        };
+        settings::update(&app, |s| s.close_behavior = behavior)?;
+    }
+    apply(&app, action);
+    Ok(())
+}
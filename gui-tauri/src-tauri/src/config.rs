@@ -0,0 +1,296 @@
+use crate::active_blocking;
+use crate::allowlist::Allowlist;
+use crate::blocklist::Blocklist;
+use crate::cache::ResultCache;
+use crate::error::AppError;
+use crate::history::{normalize_url, ScanHistory};
+use crate::host_rules::RuleList;
+use crate::presence;
+use crate::settings::{self, AppSettings, Settings};
+use crate::watchlist::{self, WatchedUrl, Watchlist};
+use crate::AppHandle;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{Manager, State};
+
+/// Layout of the bundle `export_config` writes
+const BUNDLE_VERSION: u32 = 1;
+
+/// Settings that never leave this machine: window placement and folder
+/// paths mean nothing elsewhere, active blocking mirrors this machine's
+/// hosts file, and anything secret (tokens, credentials) belongs here too
+const LOCAL_ONLY_SETTINGS: &[&str] = &[
+    "mini_scanner_position",
+    "watched_folders",
+    "require_os_auth",
+    "active_blocking",
+];
+
+/// Everything needed to set up another install the same way
+#[derive(Serialize, Deserialize, Debug)]
+struct ConfigBundle {
+    bundle_version: u32,
+    /// App version that wrote the bundle, for the user's information
+    #[serde(default)]
+    app_version: Option<String>,
+    /// Partial settings: only the shareable fields
+    settings: Value,
+    #[serde(default)]
+    allowlist: Vec<String>,
+    #[serde(default)]
+    blocklist: Vec<String>,
+    #[serde(default)]
+    watchlist: Vec<WatchedUrl>,
+}
+
+/// A setting the bundle gives a different value than this install has
+#[derive(Serialize, Debug, Clone)]
+pub struct SettingConflict {
+    /// Dotted path, e.g. `notifications.phishing_risk_score`
+    setting: String,
+    current: Value,
+    imported: Value,
+}
+
+/// A pattern the bundle puts on one list that is on the other list here
+#[derive(Serialize, Debug, Clone)]
+pub struct RuleConflict {
+    pattern: String,
+    /// "allowlist" or "blocklist", the list the bundle adds it to
+    imported_to: &'static str,
+}
+
+/// A URL the bundle watches at a different interval than this install does
+#[derive(Serialize, Debug, Clone)]
+pub struct WatchConflict {
+    url: String,
+    current_interval_secs: u64,
+    /// Applied, like a setting from the bundle
+    imported_interval_secs: u64,
+}
+
+/// What `import_config` changed
+#[derive(Serialize, Debug, Clone)]
+pub struct ConfigImport {
+    merged: bool,
+    /// Settings whose value changed; the bundle's value was applied
+    setting_conflicts: Vec<SettingConflict>,
+    /// Both lists are applied as given; block rules win when scanning
+    rule_conflicts: Vec<RuleConflict>,
+    /// Only reported when merging; a replaced watchlist takes the bundle's intervals
+    watch_conflicts: Vec<WatchConflict>,
+    allowlist: usize,
+    blocklist: usize,
+    watchlist: usize,
+}
+
+/// Settings without the fields that must not be exported
+fn shareable(settings: &AppSettings) -> Result<Value, AppError> {
+    let mut value = serde_json::to_value(settings)?;
+    if let Value::Object(fields) = &mut value {
+        for key in LOCAL_ONLY_SETTINGS {
+            fields.remove(*key);
+        }
+    }
+    Ok(value)
+}
+
+/// Leaf values that differ between `current` and `imported`
+fn differences(path: &str, current: &Value, imported: &Value, out: &mut Vec<SettingConflict>) {
+    match (current, imported) {
+        (Value::Object(current), Value::Object(imported)) => {
+            for (key, value) in imported {
+                let name = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                differences(&name, current.get(key).unwrap_or(&Value::Null), value, out);
+            }
+        }
+        _ if current != imported && path != "schema_version" => out.push(SettingConflict {
+            setting: path.to_string(),
+            current: current.clone(),
+            imported: imported.clone(),
+        }),
+        _ => {}
+    }
+}
+
+/// Canonical form of every pattern, failing on the first invalid one
+fn canonical(list: &RuleList, name: &str, patterns: &[String]) -> Result<Vec<String>, AppError> {
+    patterns
+        .iter()
+        .map(|raw| {
+            list.canonical(raw)
+                .map_err(|e| AppError::InvalidInput(format!("{}: {}", name, e)))
+        })
+        .collect()
+}
+
+/// Write settings, allowlist, blocklist and watchlist to `path` as one JSON bundle.
+/// Machine-specific and secret settings are left out.
+#[tauri::command]
+pub async fn export_config(
+    path: String,
+    app: AppHandle,
+    history: State<'_, ScanHistory>,
+) -> Result<(), AppError> {
+    presence::require(&app, "export the configuration")?;
+    let settings = shareable(&app.state::<Settings>().get())?;
+    let patterns = |entries: Vec<crate::host_rules::RuleEntry>| {
+        entries.into_iter().map(|entry| entry.pattern).collect()
+    };
+    let bundle = ConfigBundle {
+        bundle_version: BUNDLE_VERSION,
+        app_version: Some(app.package_info().version.to_string()),
+        settings,
+        allowlist: patterns(app.state::<Allowlist>().0.entries(&history).await?),
+        blocklist: patterns(app.state::<Blocklist>().0.entries(&history).await?),
+        watchlist: watchlist::entries(&history).await?,
+    };
+    tokio::fs::write(&path, serde_json::to_vec_pretty(&bundle)?).await?;
+    Ok(())
+}
+
+/// Apply a bundle from `export_config`. With `merge` its settings, rules and
+/// watched URLs are added to the current ones; otherwise they replace them (local-only
+/// settings are kept either way). Everything is checked before anything
+/// changes, so a bad or newer bundle leaves the app as it was.
+#[tauri::command]
+pub async fn import_config(
+    path: String,
+    merge: bool,
+    app: AppHandle,
+    history: State<'_, ScanHistory>,
+    cache: State<'_, ResultCache>,
+) -> Result<ConfigImport, AppError> {
+    // An imported allowlist can let phishing through unchecked
+    presence::require(&app, "import a configuration")?;
+    let contents = tokio::fs::read(&path).await?;
+    let raw: Value = serde_json::from_slice(&contents)?;
+    let version = raw.get("bundle_version").and_then(Value::as_u64);
+    if version.is_some_and(|v| v > BUNDLE_VERSION as u64) {
+        return Err(AppError::NewerVersion(path));
+    }
+    let mut bundle: ConfigBundle = serde_json::from_value(raw)
+        .map_err(|e| AppError::InvalidInput(format!("not a configuration bundle: {}", e)))?;
+
+    settings::upgrade(&mut bundle.settings, &path)?;
+    if let Value::Object(fields) = &mut bundle.settings {
+        for key in LOCAL_ONLY_SETTINGS {
+            fields.remove(*key);
+        }
+    }
+    let current = app.state::<Settings>().get();
+    let base = if merge {
+        current.clone()
+    } else {
+        // Keep what is specific to this machine
+        AppSettings {
+            mini_scanner_position: current.mini_scanner_position,
+            watched_folders: current.watched_folders.clone(),
+            require_os_auth: current.require_os_auth,
+            active_blocking: current.active_blocking.clone(),
+            ..AppSettings::default()
+        }
+    };
+    let next = settings::overlay(&base, bundle.settings)?;
+
+    let (allowlist, blocklist) = (app.state::<Allowlist>(), app.state::<Blocklist>());
+    let allowed = canonical(&allowlist.0, "allowlist", &bundle.allowlist)?;
+    let blocked = canonical(&blocklist.0, "blocklist", &bundle.blocklist)?;
+    let watched = bundle
+        .watchlist
+        .iter()
+        .map(|entry| {
+            WatchedUrl::new(&entry.url, Some(entry.interval_secs))
+                .map_err(|e| AppError::InvalidInput(format!("watchlist: {}", e)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut setting_conflicts = Vec::new();
+    differences(
+        "",
+        &serde_json::to_value(&current)?,
+        &serde_json::to_value(&next)?,
+        &mut setting_conflicts,
+    );
+    // Replaced lists can't conflict with what they replace
+    let mut rule_conflicts = Vec::new();
+    let (local_allowed, local_blocked) = if merge {
+        (
+            allowlist.0.entries(&history).await?,
+            blocklist.0.entries(&history).await?,
+        )
+    } else {
+        (Vec::new(), Vec::new())
+    };
+    for pattern in &allowed {
+        if local_blocked.iter().any(|entry| &entry.pattern == pattern) {
+            rule_conflicts.push(RuleConflict {
+                pattern: pattern.clone(),
+                imported_to: "allowlist",
+            });
+        }
+    }
+    for pattern in &blocked {
+        if local_allowed.iter().any(|entry| &entry.pattern == pattern) {
+            rule_conflicts.push(RuleConflict {
+                pattern: pattern.clone(),
+                imported_to: "blocklist",
+            });
+        }
+    }
+
+    let mut watch_conflicts = Vec::new();
+    if merge {
+        let local_watched = watchlist::entries(&history).await?;
+        for entry in &watched {
+            let key = normalize_url(&entry.url);
+            let local = local_watched.iter().find(|w| normalize_url(&w.url) == key);
+            if let Some(local) = local.filter(|w| w.interval_secs != entry.interval_secs) {
+                watch_conflicts.push(WatchConflict {
+                    url: entry.url.clone(),
+                    current_interval_secs: local.interval_secs,
+                    imported_interval_secs: entry.interval_secs,
+                });
+            }
+        }
+    }
+
+    settings::update(&app, |s| *s = next)?;
+    if merge {
+        for pattern in &allowed {
+            allowlist.0.add(&history, pattern).await?;
+        }
+        for pattern in &blocked {
+            blocklist.0.add(&history, pattern).await?;
+        }
+    } else {
+        allowlist.0.replace(&history, &allowed).await?;
+        blocklist.0.replace(&history, &blocked).await?;
+    }
+    if merge {
+        watchlist::add_all(&history, &watched).await?;
+    } else {
+        watchlist::replace(&history, &watched).await?;
+    }
+    app.state::<Watchlist>().changed();
+    // Blocked in one batch, as if each had been added to the blocklist
+    for pattern in &blocked {
+        active_blocking::on_blocklisted(&app, pattern);
+    }
+    // Cached verdicts may predate the new rules
+    cache.clear()?;
+
+    Ok(ConfigImport {
+        merged: merge,
+        setting_conflicts,
+        rule_conflicts,
+        watch_conflicts,
+        allowlist: allowed.len(),
+        blocklist: blocked.len(),
+        watchlist: watched.len(),
+    })
+}
@@ -0,0 +1,164 @@
+use crate::error::AppError;
+use crate::history::now_secs;
+use crate::logging;
+use crate::AppHandle;
+use serde::Serialize;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::async_runtime::JoinHandle;
+use tauri::{Emitter, Manager, State};
+
+/// Crash reports live in this subdirectory of the app data dir
+const CRASH_DIR: &str = "crashes";
+/// Names the report of a crash the next start hasn't announced yet
+const PENDING_FILE: &str = "pending";
+/// Log lines copied into each report
+const REPORT_LOG_LINES: usize = 50;
+/// Reports kept; older ones are deleted when a new one is written
+const MAX_REPORTS: usize = 10;
+/// Pause before restarting a background task that panicked
+const RESTART_DELAY: Duration = Duration::from_secs(5);
+
+/// Where crash reports are written
+pub struct CrashReports {
+    dir: PathBuf,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct CrashReport {
+    file: String,
+    /// Unix seconds
+    created_at: i64,
+    contents: String,
+}
+
+fn reports(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|path| path.extension().is_some_and(|ext| ext == "txt"))
+                .collect()
+        })
+        .unwrap_or_default();
+    // `crash-<unix seconds>.txt` sorts by time
+    files.sort();
+    files
+}
+
+fn read_report(path: &Path) -> Option<CrashReport> {
+    let file = path.file_name()?.to_string_lossy().to_string();
+    let created_at = file
+        .trim_start_matches("crash-")
+        .trim_end_matches(".txt")
+        .parse()
+        .unwrap_or_default();
+    Some(CrashReport {
+        file,
+        created_at,
+        contents: std::fs::read_to_string(path).ok()?,
+    })
+}
+
+fn write_report(dir: &Path, log_dir: Option<&Path>, version: &str, panic: &str) {
+    let mut report = format!(
+        "Phishing Guard {} crashed\nOS: {} {}\nTime: {} (unix)\n\n{}\n\nBacktrace:\n{}\n",
+        version,
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+        now_secs(),
+        panic,
+        std::backtrace::Backtrace::force_capture()
+    );
+    if let Some(log_dir) = log_dir {
+        report.push_str("\nRecent log:\n");
+        for line in logging::tail(log_dir, REPORT_LOG_LINES) {
+            report.push_str(&line);
+            report.push('\n');
+        }
+    }
+
+    if std::fs::create_dir_all(dir).is_err() {
+        return;
+    }
+    let name = format!("crash-{}.txt", now_secs());
+    if std::fs::write(dir.join(&name), report).is_ok() {
+        let _ = std::fs::write(dir.join(PENDING_FILE), &name);
+    }
+    let old = reports(dir);
+    for path in old.iter().take(old.len().saturating_sub(MAX_REPORTS)) {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+/// Write a report for every panic, then let the default hook print it as usual
+pub fn install(data_dir: &Path, log_dir: Option<PathBuf>, version: String) -> CrashReports {
+    let dir = data_dir.join(CRASH_DIR);
+    let report_dir = dir.clone();
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let message = info.to_string();
+        tracing::error!(panic = %message, "panic");
+        write_report(&report_dir, log_dir.as_deref(), &version, &message);
+        default_hook(info);
+    }));
+    CrashReports { dir }
+}
+
+/// Announce a crash from the previous run, once
+pub fn announce_previous(app: &AppHandle, reports: &CrashReports) {
+    let pending = reports.dir.join(PENDING_FILE);
+    let Ok(name) = std::fs::read_to_string(&pending) else {
+        return;
+    };
+    let _ = std::fs::remove_file(&pending);
+    if let Some(report) = read_report(&reports.dir.join(name.trim())) {
+        tracing::warn!(file = %report.file, "previous run crashed");
+        let _ = app.emit("previous-crash-detected", report);
+    }
+}
+
+/// Aborts the task when the supervisor itself is aborted
+struct AbortOnDrop(JoinHandle<()>);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Run a background task, restarting it if it panics. The panic hook has
+/// already written a report; since the app survived, it is not announced
+/// as a crash on the next start.
+pub fn supervise<F, Fut>(app: AppHandle, name: &'static str, task: F) -> JoinHandle<()>
+where
+    F: Fn(AppHandle) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let mut running = AbortOnDrop(tauri::async_runtime::spawn(task(app.clone())));
+            match (&mut running.0).await {
+                Err(e) => {
+                    tracing::error!(task = name, error = %e, "background task failed, restarting");
+                    if let Some(reports) = app.try_state::<CrashReports>() {
+                        let _ = std::fs::remove_file(reports.dir.join(PENDING_FILE));
+                    }
+                    tokio::time::sleep(RESTART_DELAY).await;
+                }
+                Ok(()) => return,
+            }
+        }
+    })
+}
+
+/// The newest crash report, if there is one
+#[tauri::command]
+pub fn get_last_crash_report(
+    crashes: State<'_, CrashReports>,
+) -> Result<Option<CrashReport>, AppError> {
+    Ok(reports(&crashes.dir)
+        .last()
+        .and_then(|path| read_report(path)))
+}
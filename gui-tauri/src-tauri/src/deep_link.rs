@@ -0,0 +1,148 @@
+use crate::error::AppError;
+use crate::lifecycle::{ScanJob, ScanSource};
+use crate::links::is_scannable_url;
+use crate::logging;
+use crate::notifications::{notify, open_scan};
+use crate::queue::ScanQueue;
+use crate::tray::show_main_window;
+use crate::{AppHandle, AppRuntime};
+use tauri::plugin::TauriPlugin;
+use tauri::{Emitter, Manager};
+use tauri_plugin_deep_link::DeepLinkExt;
+use url::Url;
+
+/// Scheme registered with the OS, e.g. `phishguard://scan?url=...`
+pub const SCHEME: &str = "phishguard";
+
+/// What a `phishguard://` link asks for
+#[derive(Debug, PartialEq, Eq)]
+enum Route {
+    /// `scan?url=<percent-encoded http(s) URL>`
+    Scan(String),
+    /// `open-result?id=<scan id>`
+    OpenResult(String),
+    /// `settings`, optionally `?section=<name>`
+    Settings(Option<String>),
+}
+
+fn param(link: &Url, name: &str) -> Option<String> {
+    link.query_pairs()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// Work out what `link` asks for. Query values arrive percent-decoded; a
+/// scan target must still be a plain http or https URL.
+fn route(link: &Url) -> Result<Route, AppError> {
+    if link.scheme() != SCHEME {
+        return Err(AppError::InvalidInput(format!("not a {}:// link", SCHEME)));
+    }
+    // `phishguard://scan` puts the route in the host, `phishguard:scan` in the path
+    let name = link
+        .host_str()
+        .unwrap_or_else(|| link.path())
+        .trim_matches('/')
+        .to_ascii_lowercase();
+    match name.as_str() {
+        "scan" => {
+            let target = param(link, "url")
+                .ok_or_else(|| AppError::InvalidInput("the link has no url to scan".into()))?;
+            if !is_scannable_url(&target) {
+                return Err(AppError::InvalidInput(
+                    "only http and https links can be scanned".into(),
+                ));
+            }
+            Ok(Route::Scan(target))
+        }
+        "open-result" => param(link, "id")
+            .map(Route::OpenResult)
+            .ok_or_else(|| AppError::InvalidInput("the link has no scan id".into())),
+        "settings" => Ok(Route::Settings(param(link, "section"))),
+        other => Err(AppError::InvalidInput(format!(
+            "unknown link action \"{}\"",
+            other
+        ))),
+    }
+}
+
+fn reject(app: &AppHandle, error: AppError) {
+    tracing::warn!(error = %error, "rejected deep link");
+    notify(app, "Link rejected", &error.to_string());
+}
+
+/// Act on a `phishguard://` link. Scan targets only ever go to the queue;
+/// nothing in a link is opened or run.
+pub fn handle(app: &AppHandle, link: &Url) {
+    let route = match route(link) {
+        Ok(route) => route,
+        Err(e) => return reject(app, e),
+    };
+    tracing::info!(?route, "deep link");
+    match route {
+        Route::Scan(url) => {
+            tracing::debug!(url = %logging::url(&url), "deep link scan");
+            show_main_window(app);
+            let job = match ScanJob::from_input(&url, ScanSource::DeepLink) {
+                Ok(job) => job,
+                Err(e) => return reject(app, e),
+            };
+            let scan_id = job.scan_id.clone();
+            // The queue reports progress and the verdict as for any other scan
+            match app.state::<ScanQueue>().enqueue(app, job) {
+                Ok(_) => {
+                    let _ = app.emit("deep-link-scan", scan_id);
+                }
+                Err(e) => notify(app, "Link could not be scanned", &e.to_string()),
+            }
+        }
+        Route::OpenResult(scan_id) => {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move { open_scan(&app, &scan_id).await });
+        }
+        Route::Settings(section) => {
+            show_main_window(app);
+            let _ = app.emit("open-settings", section);
+        }
+    }
+}
+
+/// Links among the launch arguments, which is how Windows and Linux pass a
+/// link that started the app
+pub fn links_in(args: impl Iterator<Item = String>) -> Vec<String> {
+    let prefix = format!("{}:", SCHEME);
+    args.filter(|arg| arg.to_ascii_lowercase().starts_with(&prefix))
+        .collect()
+}
+
+/// Keeps a second launch (e.g. a link clicked while the app is running) from
+/// starting another copy; its link is handed to the running one instead
+pub fn single_instance() -> TauriPlugin<AppRuntime> {
+    tauri_plugin_single_instance::init(|app, _args, _cwd| show_main_window(app))
+}
+
+/// Listen for links while running and handle any the app was launched with
+pub fn listen(app: &AppHandle, launch_links: Vec<String>) {
+    // Installers register the scheme; registering it again at runtime also
+    // covers portable and development builds
+    #[cfg(any(target_os = "linux", windows))]
+    if let Err(e) = app.deep_link().register_all() {
+        tracing::warn!(error = %e, "could not register the {} scheme", SCHEME);
+    }
+
+    let running = app.clone();
+    app.deep_link().on_open_url(move |event| {
+        for link in event.urls() {
+            handle(&running, &link);
+        }
+    });
+    for link in launch_links {
+        match Url::parse(&link) {
+            Ok(link) => handle(app, &link),
+            Err(e) => reject(
+                app,
+                AppError::InvalidInput(format!("malformed link: {}", e)),
+            ),
+        }
+    }
+}
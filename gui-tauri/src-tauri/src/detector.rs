@@ -0,0 +1,228 @@
+use crate::error::AppError;
+use crate::features::{lenient_number, FeatureSet};
+use crate::history::now_secs;
+use crate::logging;
+use crate::ScanResult;
+use serde::{Deserialize, Deserializer};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// How much of unusable output is quoted in the error
+const MAX_DUMP_CHARS: usize = 400;
+
+/// Strings as they are, lists of strings one per line, other values as JSON
+fn lenient_text<'de, D: Deserializer<'de>>(d: D) -> Result<Option<String>, D::Error> {
+    Ok(match Value::deserialize(d)? {
+        Value::Null => None,
+        Value::String(s) => Some(s),
+        Value::Array(items) => Some(
+            items
+                .iter()
+                .map(|item| {
+                    item.as_str()
+                        .map_or_else(|| item.to_string(), str::to_string)
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+        ),
+        other => Some(other.to_string()),
+    })
+}
+
+/// What detect_enhanced.py prints with `--json`. Only the classification is
+/// required; every other field defaults, and numbers may be ints, floats or
+/// numeric strings, so new or retyped fields don't fail the scan.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+struct DetectorOutput {
+    #[serde(deserialize_with = "lenient_text")]
+    url: Option<String>,
+    #[serde(deserialize_with = "lenient_text")]
+    classification: Option<String>,
+    #[serde(deserialize_with = "lenient_number")]
+    confidence: Option<f64>,
+    #[serde(deserialize_with = "lenient_number")]
+    risk_score: Option<f64>,
+    #[serde(deserialize_with = "lenient_text")]
+    explanation: Option<String>,
+    #[serde(deserialize_with = "lenient_text")]
+    analysis_mode: Option<String>,
+    features: Value,
+    /// Fields this version doesn't use
+    #[serde(flatten)]
+    extra: BTreeMap<String, Value>,
+}
+
+/// The start of `stdout` for an error message, with the scanned URL
+/// redacted as it would be in the log
+fn dump(stdout: &str, url: &str) -> String {
+    let trimmed = stdout.trim();
+    let mut quoted: String = trimmed.chars().take(MAX_DUMP_CHARS).collect();
+    if quoted.len() < trimmed.len() {
+        quoted.push('…');
+    }
+    quoted.replace(url, &logging::url(url))
+}
+
+/// The verdict in the detector's output for `url`. Fails only when the
+/// output is not a JSON object or has no classification.
+pub fn parse(stdout: &str, url: &str) -> Result<ScanResult, AppError> {
+    let unusable = |reason: &str| {
+        AppError::Parse(format!("detector output {}: {}", reason, dump(stdout, url)))
+    };
+    let output: DetectorOutput =
+        serde_json::from_str(stdout).map_err(|e| unusable(&format!("is not valid ({})", e)))?;
+    let Some(classification) = output.classification.filter(|c| !c.trim().is_empty()) else {
+        return Err(unusable("has no classification"));
+    };
+    if !output.extra.is_empty() {
+        let fields: Vec<&str> = output.extra.keys().map(String::as_str).collect();
+        tracing::debug!(?fields, "detector output has fields this version ignores");
+    }
+
+    Ok(ScanResult {
+        url: output.url.unwrap_or_else(|| url.to_string()),
+        classification,
+        confidence: output.confidence.unwrap_or(0.0).clamp(0.0, 1.0),
+        risk_score: output.risk_score.unwrap_or(0.0).round().clamp(0.0, 100.0) as i32,
+        explanation: output.explanation.unwrap_or_default(),
+        analysis_mode: output.analysis_mode,
+        features: FeatureSet::from_value(&output.features),
+        cached: false,
+        scanned_at: Some(now_secs()),
+        red_flags: None,
+        degraded: false,
+        redirects: None,
+        duration_ms: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const URL: &str = "https://login.example-bank.test/verify";
+
+    #[test]
+    fn current_output() {
+        let stdout = r#"{
+            "url": "https://login.example-bank.test/verify",
+            "classification": "phishing",
+            "confidence": 0.93,
+            "risk_score": 88,
+            "explanation": "Brand name in a lookalike domain.",
+            "features": {"url_length": 38, "is_https": 1, "brand_in_domain": 1},
+            "recommended_action": "block",
+            "ml_model_used": true,
+            "mllm_used": false,
+            "scraped": false,
+            "scrape_proof": null,
+            "analysis_mode": "online"
+        }"#;
+        let result = parse(stdout, URL).unwrap();
+        assert_eq!(result.classification, "phishing");
+        assert_eq!(result.confidence, 0.93);
+        assert_eq!(result.risk_score, 88);
+        assert_eq!(result.analysis_mode.as_deref(), Some("online"));
+        assert_eq!(result.features.url_length, Some(38.0));
+        assert_eq!(result.features.brand_in_domain, Some(true));
+    }
+
+    #[test]
+    fn older_output_with_strings_and_explanation_lines() {
+        // Earlier detector versions printed numbers as strings, a list of
+        // explanation lines and no analysis mode
+        let stdout = r#"{
+            "classification": "suspicious",
+            "confidence": "0.61",
+            "risk_score": "57.6",
+            "explanation": ["Young domain", "No HTTPS"],
+            "features": {"is_https": "0", "cert_days_remaining": -1}
+        }"#;
+        let result = parse(stdout, URL).unwrap();
+        assert_eq!(result.url, URL);
+        assert_eq!(result.confidence, 0.61);
+        assert_eq!(result.risk_score, 58);
+        assert_eq!(result.explanation, "Young domain\nNo HTTPS");
+        assert_eq!(result.analysis_mode, None);
+        assert_eq!(result.features.is_https, Some(false));
+    }
+
+    #[test]
+    fn whitelist_and_typosquat_shapes() {
+        let whitelisted = r#"{"url": "https://example.com", "classification": "legitimate",
+            "confidence": 1.0, "risk_score": 0, "features": {},
+            "explanation": "Domain 'example.com' is in the trusted whitelist.",
+            "analysis_mode": "whitelist"}"#;
+        let result = parse(whitelisted, "https://example.com").unwrap();
+        assert_eq!(result.risk_score, 0);
+        assert_eq!(result.analysis_mode.as_deref(), Some("whitelist"));
+
+        let typosquat = r#"{"url": "https://paypa1.test", "classification": "phishing",
+            "confidence": 0.95, "risk_score": 90,
+            "explanation": "INVALID DOMAIN: lookalike of paypal",
+            "features": {"typosquatting": {"detection_method": "levenshtein"}},
+            "analysis_mode": "offline"}"#;
+        let result = parse(typosquat, "https://paypa1.test").unwrap();
+        assert!(result.features.typosquatting.is_some());
+    }
+
+    #[test]
+    fn partial_output_defaults_and_clamps() {
+        let result = parse(r#"{"classification": "phishing"}"#, URL).unwrap();
+        assert_eq!(result.confidence, 0.0);
+        assert_eq!(result.risk_score, 0);
+        assert_eq!(result.explanation, "");
+
+        let result = parse(
+            r#"{"classification": "phishing", "confidence": 7, "risk_score": -20}"#,
+            URL,
+        )
+        .unwrap();
+        assert_eq!(result.confidence, 1.0);
+        assert_eq!(result.risk_score, 0);
+    }
+
+    #[test]
+    fn non_finite_numbers_are_dropped() {
+        let stdout = r#"{"classification": "phishing", "confidence": "NaN",
+            "risk_score": "inf", "features": {"entropy": "-inf"}}"#;
+        let result = parse(stdout, URL).unwrap();
+        assert_eq!(result.confidence, 0.0);
+        assert_eq!(result.risk_score, 0);
+        assert_eq!(result.features.entropy, None);
+    }
+
+    #[test]
+    fn unknown_fields_are_ignored() {
+        let stdout = r#"{"classification": "legitimate", "toolkit_signatures": ["x"],
+            "ai_indicators": {"score": 0.2}}"#;
+        assert_eq!(parse(stdout, URL).unwrap().classification, "legitimate");
+    }
+
+    #[test]
+    fn output_without_a_verdict_fails() {
+        for stdout in [
+            r#"{"confidence": 0.9, "risk_score": 80}"#,
+            r#"{"classification": "   "}"#,
+            r#"{"classification": null}"#,
+        ] {
+            let error = parse(stdout, URL).unwrap_err();
+            assert!(
+                matches!(&error, AppError::Parse(message) if message.contains("no classification")),
+                "{:?}",
+                error
+            );
+        }
+    }
+
+    #[test]
+    fn unusable_output_is_quoted_with_the_url_redacted() {
+        let stdout = format!("Traceback: could not fetch {}", URL);
+        let AppError::Parse(message) = parse(&stdout, URL).unwrap_err() else {
+            panic!("expected a parse error");
+        };
+        assert!(message.contains("is not valid"));
+        assert!(!message.contains(URL));
+    }
+}
@@ -0,0 +1,336 @@
+use crate::error::AppError;
+use crate::features::FeatureSet;
+use crate::history::{HistoryEntry, ScanHistory};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use tauri::State;
+
+/// Explanations longer than this many lines are compared as a whole, to
+/// keep the line diff's table small
+const MAX_DIFF_LINES: usize = 500;
+
+#[derive(Serialize, Debug, Clone)]
+pub struct FeatureChange {
+    key: String,
+    before: Value,
+    after: Value,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LineChange {
+    Same,
+    Added,
+    Removed,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct DiffLine {
+    change: LineChange,
+    text: String,
+}
+
+/// What changed between an earlier and a later scan of one URL
+#[derive(Serialize, Debug, Clone)]
+pub struct ScanDiff {
+    url: String,
+    /// Scan ids and times, earlier scan first
+    before: (String, i64),
+    after: (String, i64),
+    /// `(before, after)`, if the classification changed
+    classification: Option<(String, String)>,
+    risk_score_delta: i32,
+    confidence_delta: f64,
+    /// Features only the later scan reported
+    features_added: Vec<FeatureChange>,
+    /// Features only the earlier scan reported
+    features_removed: Vec<FeatureChange>,
+    features_changed: Vec<FeatureChange>,
+    explanation: Vec<DiffLine>,
+}
+
+/// Reported features as a flat object, leaving out ones with no value
+fn feature_map(features: &FeatureSet) -> Map<String, Value> {
+    match serde_json::to_value(features) {
+        Ok(Value::Object(map)) => map.into_iter().filter(|(_, v)| !v.is_null()).collect(),
+        _ => Map::new(),
+    }
+}
+
+/// Features added, removed and changed from `before` to `after`, by key
+fn diff_features(
+    before: &FeatureSet,
+    after: &FeatureSet,
+) -> (Vec<FeatureChange>, Vec<FeatureChange>, Vec<FeatureChange>) {
+    let (before, after) = (feature_map(before), feature_map(after));
+    let change = |key: &String| FeatureChange {
+        key: key.clone(),
+        before: before.get(key).cloned().unwrap_or(Value::Null),
+        after: after.get(key).cloned().unwrap_or(Value::Null),
+    };
+    let added = after
+        .keys()
+        .filter(|key| !before.contains_key(*key))
+        .map(change)
+        .collect();
+    let removed = before
+        .keys()
+        .filter(|key| !after.contains_key(*key))
+        .map(change)
+        .collect();
+    let changed = before
+        .iter()
+        .filter(|(key, value)| after.get(*key).is_some_and(|other| other != *value))
+        .map(|(key, _)| change(key))
+        .collect();
+    (added, removed, changed)
+}
+
+/// Line diff from `before` to `after` along their longest common subsequence
+fn diff_lines(before: &str, after: &str) -> Vec<DiffLine> {
+    let line = |change, text: &str| DiffLine {
+        change,
+        text: text.to_string(),
+    };
+    let (a, b): (Vec<&str>, Vec<&str>) = (before.lines().collect(), after.lines().collect());
+    if a.len() > MAX_DIFF_LINES || b.len() > MAX_DIFF_LINES {
+        if before == after {
+            return a.iter().map(|text| line(LineChange::Same, text)).collect();
+        }
+        let removed = a.iter().map(|text| line(LineChange::Removed, text));
+        return removed
+            .chain(b.iter().map(|text| line(LineChange::Added, text)))
+            .collect();
+    }
+
+    // common[i][j] is the LCS length of a[i..] and b[j..]
+    let mut common = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            common[i][j] = if a[i] == b[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut lines = Vec::new();
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            lines.push(line(LineChange::Same, a[i]));
+            i += 1;
+            j += 1;
+        } else if common[i + 1][j] >= common[i][j + 1] {
+            lines.push(line(LineChange::Removed, a[i]));
+            i += 1;
+        } else {
+            lines.push(line(LineChange::Added, b[j]));
+            j += 1;
+        }
+    }
+    lines.extend(a[i..].iter().map(|text| line(LineChange::Removed, text)));
+    lines.extend(b[j..].iter().map(|text| line(LineChange::Added, text)));
+    lines
+}
+
+/// Compare two stored scans of the same URL, earlier one first
+fn diff(a: &HistoryEntry, b: &HistoryEntry) -> Result<ScanDiff, AppError> {
+    if a.normalized_url != b.normalized_url {
+        return Err(AppError::InvalidInput(format!(
+            "the scans are of different URLs ({} and {})",
+            a.url, b.url
+        )));
+    }
+    let (before, after) = if (b.scanned_at, b.id) < (a.scanned_at, a.id) {
+        (b, a)
+    } else {
+        (a, b)
+    };
+    let (features_added, features_removed, features_changed) =
+        diff_features(&before.features, &after.features);
+    Ok(ScanDiff {
+        url: after.url.clone(),
+        before: (
+            before.scan_id.clone().unwrap_or_default(),
+            before.scanned_at,
+        ),
+        after: (after.scan_id.clone().unwrap_or_default(), after.scanned_at),
+        classification: (before.classification != after.classification)
+            .then(|| (before.classification.clone(), after.classification.clone())),
+        risk_score_delta: after.risk_score - before.risk_score,
+        confidence_delta: after.confidence - before.confidence,
+        features_added,
+        features_removed,
+        features_changed,
+        explanation: diff_lines(&before.explanation, &after.explanation),
+    })
+}
+
+/// What changed between two stored scans of the same URL, in either order
+#[tauri::command]
+pub async fn diff_scans(
+    scan_id_a: String,
+    scan_id_b: String,
+    history: State<'_, ScanHistory>,
+) -> Result<ScanDiff, AppError> {
+    let (a, _) = history
+        .find(&scan_id_a)
+        .await?
+        .ok_or(AppError::NotFound(scan_id_a))?;
+    let (b, _) = history
+        .find(&scan_id_b)
+        .await?
+        .ok_or(AppError::NotFound(scan_id_b))?;
+    diff(&a, &b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// A stored scan of `url` with the given verdict, features and explanation
+    fn entry(
+        id: i64,
+        url: &str,
+        verdict: (&str, i32, f64),
+        features: Value,
+        explanation: &str,
+    ) -> HistoryEntry {
+        HistoryEntry {
+            id,
+            url: url.to_string(),
+            normalized_url: crate::history::normalize_url(url),
+            classification: verdict.0.to_string(),
+            confidence: verdict.2,
+            risk_score: verdict.1,
+            explanation: explanation.to_string(),
+            features: serde_json::from_value(features).unwrap(),
+            scanned_at: 1_700_000_000 + id * 3_600,
+            source: "manual".to_string(),
+            scan_id: Some(format!("scan-{}", id)),
+            note: None,
+            tags: Vec::new(),
+            origin: None,
+            input: None,
+            rescan_of: None,
+            domain_info: None,
+            redirected_from: None,
+            duration_ms: None,
+        }
+    }
+
+    /// The same page scanned before and after it turned into a phishing kit
+    fn fixtures() -> (HistoryEntry, HistoryEntry) {
+        let before = entry(
+            1,
+            "https://shop.example/login",
+            ("legitimate", 12, 0.91),
+            json!({"url_length": 26, "num_dots": 1, "has_favicon": 1}),
+            "Domain is well established\nNo login form found\nCertificate is valid",
+        );
+        let after = entry(
+            2,
+            "https://SHOP.example/login",
+            ("phishing", 87, 0.96),
+            json!({"url_length": 26, "num_dots": 3, "has_login_form": 1, "brand_mismatch": "paypal"}),
+            "Domain is well established\nLogin form posts to another host\nCertificate is valid\nPage imitates PayPal",
+        );
+        (before, after)
+    }
+
+    fn keys(changes: &[FeatureChange]) -> Vec<&str> {
+        changes.iter().map(|change| change.key.as_str()).collect()
+    }
+
+    #[test]
+    fn signals_and_scores_are_compared() {
+        let (before, after) = fixtures();
+        let diff = diff(&before, &after).unwrap();
+
+        assert_eq!(diff.before, ("scan-1".to_string(), before.scanned_at));
+        assert_eq!(diff.after, ("scan-2".to_string(), after.scanned_at));
+        assert_eq!(
+            diff.classification,
+            Some(("legitimate".to_string(), "phishing".to_string()))
+        );
+        assert_eq!(diff.risk_score_delta, 75);
+        assert!((diff.confidence_delta - 0.05).abs() < 1e-9);
+
+        assert_eq!(
+            keys(&diff.features_added),
+            ["brand_mismatch", "has_login_form"]
+        );
+        assert_eq!(diff.features_added[1].before, Value::Null);
+        assert_eq!(diff.features_added[1].after, json!(1));
+        assert_eq!(keys(&diff.features_removed), ["has_favicon"]);
+        assert_eq!(diff.features_removed[0].after, Value::Null);
+        assert_eq!(keys(&diff.features_changed), ["num_dots"]);
+        assert_eq!(diff.features_changed[0].before, json!(1.0));
+        assert_eq!(diff.features_changed[0].after, json!(3.0));
+
+        let changes: Vec<(LineChange, &str)> = diff
+            .explanation
+            .iter()
+            .map(|line| (line.change, line.text.as_str()))
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                (LineChange::Same, "Domain is well established"),
+                (LineChange::Removed, "No login form found"),
+                (LineChange::Added, "Login form posts to another host"),
+                (LineChange::Same, "Certificate is valid"),
+                (LineChange::Added, "Page imitates PayPal"),
+            ]
+        );
+    }
+
+    #[test]
+    fn scans_are_put_in_order_whichever_comes_first() {
+        let (before, after) = fixtures();
+        let reversed = diff(&after, &before).unwrap();
+        assert_eq!(reversed.before.0, "scan-1");
+        assert_eq!(reversed.risk_score_delta, 75);
+        assert_eq!(keys(&reversed.features_removed), ["has_favicon"]);
+
+        // An unchanged rescan has nothing to report
+        let again = entry(
+            3,
+            &before.url,
+            ("legitimate", 12, 0.91),
+            json!({"url_length": 26, "num_dots": 1, "has_favicon": 1}),
+            &before.explanation,
+        );
+        let same = diff(&before, &again).unwrap();
+        assert!(same.classification.is_none());
+        assert_eq!(same.risk_score_delta, 0);
+        assert!(same.features_added.is_empty() && same.features_removed.is_empty());
+        assert!(same.features_changed.is_empty());
+        assert!(same
+            .explanation
+            .iter()
+            .all(|line| line.change == LineChange::Same));
+    }
+
+    #[test]
+    fn scans_of_different_urls_are_not_compared() {
+        let (before, _) = fixtures();
+        let other = entry(
+            4,
+            "https://other.example/login",
+            ("phishing", 90, 0.9),
+            json!({}),
+            "",
+        );
+        let error = diff(&before, &other).unwrap_err();
+        assert!(
+            matches!(&error, AppError::InvalidInput(message) if message.contains("different URLs")),
+            "{:?}",
+            error
+        );
+        assert_eq!(error.kind(), "invalid_input");
+    }
+}
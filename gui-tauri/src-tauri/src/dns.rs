@@ -0,0 +1,272 @@
+use crate::error::AppError;
+use crate::features::FeatureSet;
+use crate::history::now_secs;
+use crate::perf::{self, Operation};
+use crate::AppHandle;
+use crate::ScanResult;
+use hickory_resolver::config::{ResolverConfig, ResolverOpts};
+use hickory_resolver::error::{ResolveError, ResolveErrorKind};
+use hickory_resolver::proto::op::ResponseCode;
+use hickory_resolver::TokioAsyncResolver;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::Manager;
+use tokio::sync::OnceCell;
+use url::{Host, Url};
+
+/// Per query, so a dead resolver doesn't hold a scan up for long
+const QUERY_TIMEOUT: Duration = Duration::from_secs(3);
+/// Answers are reused for this long
+const CACHE_TTL: Duration = Duration::from_secs(300);
+/// Cached answers kept before the oldest are dropped
+const MAX_CACHED: usize = 512;
+
+/// How a lookup went, keeping a missing domain apart from a failed lookup
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LookupStatus {
+    Ok,
+    /// The name exists but has no records of this type
+    NoData,
+    /// The name does not exist at all
+    NxDomain,
+    /// The resolver could not get an answer
+    ServFail,
+    Timeout,
+    /// Refused, unreachable resolver and other failures
+    Failed,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct RecordLookup {
+    status: LookupStatus,
+    records: Vec<String>,
+    error: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct DnsCheck {
+    host: String,
+    /// Whether the host has an A or AAAA record; IP literals always do
+    resolves: bool,
+    /// `ok` when it resolves, otherwise the most telling address failure
+    status: LookupStatus,
+    a: Option<RecordLookup>,
+    aaaa: Option<RecordLookup>,
+    mx: Option<RecordLookup>,
+    checked_at: i64,
+    /// Answered from the last few minutes' lookups
+    cached: bool,
+}
+
+/// Resolver using the system's DNS servers, and recent answers
+#[derive(Default)]
+pub struct Dns {
+    resolver: OnceCell<TokioAsyncResolver>,
+    cache: Mutex<HashMap<String, (Instant, DnsCheck)>>,
+}
+
+fn resolver_config() -> (ResolverConfig, ResolverOpts) {
+    let (config, mut opts) =
+        hickory_resolver::system_conf::read_system_conf().unwrap_or_else(|e| {
+            tracing::warn!(error = %e, "could not read the system DNS settings; using defaults");
+            (ResolverConfig::default(), ResolverOpts::default())
+        });
+    opts.timeout = QUERY_TIMEOUT;
+    opts.attempts = 1;
+    (config, opts)
+}
+
+fn failed(e: &ResolveError) -> RecordLookup {
+    let status = match e.kind() {
+        ResolveErrorKind::NoRecordsFound { response_code, .. } => match *response_code {
+            ResponseCode::NXDomain => LookupStatus::NxDomain,
+            ResponseCode::NoError => LookupStatus::NoData,
+            ResponseCode::ServFail => LookupStatus::ServFail,
+            _ => LookupStatus::Failed,
+        },
+        ResolveErrorKind::Timeout => LookupStatus::Timeout,
+        _ => LookupStatus::Failed,
+    };
+    RecordLookup {
+        status,
+        records: Vec::new(),
+        error: (!matches!(status, LookupStatus::NoData | LookupStatus::NxDomain))
+            .then(|| e.to_string()),
+    }
+}
+
+fn found(records: Vec<String>) -> RecordLookup {
+    RecordLookup {
+        status: LookupStatus::Ok,
+        records,
+        error: None,
+    }
+}
+
+/// The host part of `input`, which may be a bare host or a URL
+fn host_of(input: &str) -> Result<Host<String>, AppError> {
+    let trimmed = input.trim();
+    let as_url = if trimmed.contains("://") {
+        trimmed.to_string()
+    } else {
+        format!("https://{}", trimmed)
+    };
+    Url::parse(&as_url)
+        .ok()
+        .and_then(|url| url.host().map(|host| host.to_owned()))
+        .ok_or_else(|| AppError::InvalidInput(format!("not a host name: {}", trimmed)))
+}
+
+impl Dns {
+    async fn resolver(&self) -> &TokioAsyncResolver {
+        self.resolver
+            .get_or_init(|| async {
+                let (config, opts) = resolver_config();
+                TokioAsyncResolver::tokio(config, opts)
+            })
+            .await
+    }
+
+    async fn lookup(&self, host: String) -> DnsCheck {
+        let resolver = self.resolver().await;
+        // Fully qualified, so search domains aren't tried first
+        let name = format!("{}.", host.trim_end_matches('.'));
+        let (a, aaaa, mx) = tokio::join!(
+            resolver.ipv4_lookup(name.as_str()),
+            resolver.ipv6_lookup(name.as_str()),
+            resolver.mx_lookup(name.as_str()),
+        );
+        let a = a.map_or_else(
+            |e| failed(&e),
+            |found_a| found(found_a.iter().map(|r| r.to_string()).collect()),
+        );
+        let aaaa = aaaa.map_or_else(
+            |e| failed(&e),
+            |found_aaaa| found(found_aaaa.iter().map(|r| r.to_string()).collect()),
+        );
+        let mx = mx.map_or_else(
+            |e| failed(&e),
+            |found_mx| {
+                found(
+                    found_mx
+                        .iter()
+                        .map(|r| r.exchange().to_string().trim_end_matches('.').to_string())
+                        .collect(),
+                )
+            },
+        );
+
+        let resolves = !a.records.is_empty() || !aaaa.records.is_empty();
+        let status = if resolves {
+            LookupStatus::Ok
+        } else {
+            // NXDOMAIN on either family answers the question for both
+            [
+                LookupStatus::NxDomain,
+                LookupStatus::ServFail,
+                LookupStatus::Timeout,
+                LookupStatus::Failed,
+            ]
+            .into_iter()
+            .find(|status| a.status == *status || aaaa.status == *status)
+            .unwrap_or(LookupStatus::NoData)
+        };
+        DnsCheck {
+            host,
+            resolves,
+            status,
+            a: Some(a),
+            aaaa: Some(aaaa),
+            mx: Some(mx),
+            checked_at: now_secs(),
+            cached: false,
+        }
+    }
+
+    /// Look up the host of `input`, reusing an answer from the last few minutes
+    pub async fn check(&self, input: &str) -> Result<DnsCheck, AppError> {
+        let host = match host_of(input)? {
+            Host::Domain(domain) => domain.to_ascii_lowercase(),
+            ip => {
+                return Ok(DnsCheck {
+                    host: ip.to_string(),
+                    resolves: true,
+                    status: LookupStatus::Ok,
+                    a: None,
+                    aaaa: None,
+                    mx: None,
+                    checked_at: now_secs(),
+                    cached: false,
+                })
+            }
+        };
+        if let Some((at, check)) = self.cache.lock()?.get(&host) {
+            if at.elapsed() < CACHE_TTL {
+                return Ok(DnsCheck {
+                    cached: true,
+                    ..check.clone()
+                });
+            }
+        }
+
+        let check = self.lookup(host.clone()).await;
+        let mut cache = self.cache.lock()?;
+        cache.retain(|_, (at, _)| at.elapsed() < CACHE_TTL);
+        if cache.len() >= MAX_CACHED {
+            if let Some(oldest) = cache
+                .iter()
+                .min_by_key(|(_, (at, _))| *at)
+                .map(|(host, _)| host.clone())
+            {
+                cache.remove(&oldest);
+            }
+        }
+        cache.insert(host, (Instant::now(), check.clone()));
+        Ok(check)
+    }
+}
+
+/// `Dns::check`, timing the lookups that weren't answered from the cache
+async fn resolve(app: &AppHandle, input: &str) -> Result<DnsCheck, AppError> {
+    let started = Instant::now();
+    let check = app.state::<Dns>().check(input).await?;
+    if !check.cached {
+        perf::record(app, Operation::Dns, started);
+    }
+    Ok(check)
+}
+
+/// A verdict for a URL whose host does not exist, so the detector isn't run
+/// on a page that can't be fetched. Only NXDOMAIN counts; a lookup that
+/// failed or timed out says nothing about the site.
+pub async fn preflight(app: &AppHandle, url: &str) -> Option<ScanResult> {
+    let check = resolve(app, url).await.ok()?;
+    (check.status == LookupStatus::NxDomain).then(|| ScanResult {
+        url: url.to_string(),
+        classification: "unresolvable".to_string(),
+        confidence: 1.0,
+        risk_score: 0,
+        explanation: format!(
+            "Not scanned: {} does not resolve (likely taken down). Scan with force to run the detector anyway.",
+            check.host
+        ),
+        analysis_mode: Some("dns".to_string()),
+        features: FeatureSet::default(),
+        cached: false,
+        scanned_at: Some(now_secs()),
+        red_flags: None,
+        degraded: false,
+        redirects: None,
+        duration_ms: None,
+    })
+}
+
+/// A and AAAA records of a host or URL's host, whether it resolves at all,
+/// and its MX records, using the system's DNS servers
+#[tauri::command]
+pub async fn check_dns(host: String, app: AppHandle) -> Result<DnsCheck, AppError> {
+    resolve(&app, &host).await
+}
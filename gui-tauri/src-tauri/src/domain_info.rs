@@ -0,0 +1,360 @@
+use crate::error::AppError;
+use crate::history::{now_secs, parse_domain, ScanHistory};
+use crate::perf::{Operation, Timings};
+use crate::settings::Settings;
+use reqwest::StatusCode;
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::State;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// Which RDAP server answers for each TLD, published by IANA
+const RDAP_BOOTSTRAP: &str = "https://data.iana.org/rdap/dns.json";
+/// Refers each TLD to its registry's WHOIS server
+const WHOIS_ROOT: &str = "whois.iana.org";
+/// Cached answers are used for this long before asking again
+const CACHE_SECS: i64 = 24 * 60 * 60;
+const LOOKUP_TIMEOUT: Duration = Duration::from_secs(15);
+/// More WHOIS output than this is not a real answer
+const MAX_WHOIS_BYTES: u64 = 256 * 1024;
+
+/// Registration details of a domain. Registries that redact or withhold a
+/// field, or a lookup cut short by rate limiting, leave it out and name it
+/// in `fields_unavailable`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct DomainInfo {
+    domain: String,
+    /// As the registry reports it; RFC 3339 from RDAP
+    created: Option<String>,
+    registrar: Option<String>,
+    nameservers: Vec<String>,
+    /// "rdap" or "whois"
+    source: String,
+    fetched_at: i64,
+    fields_unavailable: Vec<String>,
+    /// Why the answer is incomplete, e.g. the server was rate limiting
+    note: Option<String>,
+}
+
+impl DomainInfo {
+    fn new(domain: &str, source: &str) -> Self {
+        Self {
+            domain: domain.to_string(),
+            source: source.to_string(),
+            fetched_at: now_secs(),
+            ..Default::default()
+        }
+    }
+
+    /// Fill in `fields_unavailable` from what is still missing
+    fn finish(mut self) -> Self {
+        self.fields_unavailable = [
+            ("created", self.created.is_none()),
+            ("registrar", self.registrar.is_none()),
+            ("nameservers", self.nameservers.is_empty()),
+        ]
+        .iter()
+        .filter(|(_, missing)| *missing)
+        .map(|(field, _)| field.to_string())
+        .collect();
+        self
+    }
+}
+
+/// HTTP client and the RDAP bootstrap table, fetched once per run
+pub struct DomainLookups {
+    client: reqwest::Client,
+    rdap_servers: Mutex<Option<HashMap<String, String>>>,
+}
+
+impl Default for DomainLookups {
+    fn default() -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(LOOKUP_TIMEOUT)
+            .user_agent(concat!("PhishingGuard/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .unwrap_or_default();
+        Self {
+            client,
+            rdap_servers: Mutex::new(None),
+        }
+    }
+}
+
+fn lookup_failed(e: impl std::fmt::Display) -> AppError {
+    AppError::Io(format!("domain lookup failed: {}", e))
+}
+
+/// Placeholder values registries put in place of withheld data
+fn redacted(value: &str) -> bool {
+    let value = value.to_ascii_lowercase();
+    value.is_empty() || value.contains("redacted") || value.contains("not disclosed")
+}
+
+impl DomainLookups {
+    /// RDAP base URL for `tld`, if the registry runs one
+    async fn rdap_server(&self, tld: &str) -> Result<Option<String>, AppError> {
+        if let Some(servers) = self.rdap_servers.lock()?.as_ref() {
+            return Ok(servers.get(tld).cloned());
+        }
+        let bootstrap: Value = self
+            .client
+            .get(RDAP_BOOTSTRAP)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(lookup_failed)?
+            .json()
+            .await
+            .map_err(lookup_failed)?;
+        // `services` is a list of [[tlds...], [base urls...]] pairs
+        let mut servers = HashMap::new();
+        for service in bootstrap["services"].as_array().into_iter().flatten() {
+            let Some(base) = service[1].as_array().and_then(|urls| {
+                let urls = urls.iter().filter_map(Value::as_str);
+                let mut urls: Vec<&str> = urls.collect();
+                urls.sort_by_key(|url| !url.starts_with("https://"));
+                urls.first().map(|url| url.to_string())
+            }) else {
+                continue;
+            };
+            for tld in service[0].as_array().into_iter().flatten() {
+                if let Some(tld) = tld.as_str() {
+                    servers.insert(tld.to_ascii_lowercase(), base.clone());
+                }
+            }
+        }
+        let found = servers.get(tld).cloned();
+        *self.rdap_servers.lock()? = Some(servers);
+        Ok(found)
+    }
+
+    async fn rdap(&self, domain: &str, server: &str) -> Result<DomainInfo, AppError> {
+        let url = format!("{}/domain/{}", server.trim_end_matches('/'), domain);
+        let response = self
+            .client
+            .get(&url)
+            .header(reqwest::header::ACCEPT, "application/rdap+json")
+            .send()
+            .await
+            .map_err(lookup_failed)?;
+        let mut info = DomainInfo::new(domain, "rdap");
+        match response.status() {
+            StatusCode::NOT_FOUND => {
+                return Err(AppError::NotFound(format!(
+                    "no registration record for {}",
+                    domain
+                )))
+            }
+            StatusCode::TOO_MANY_REQUESTS => {
+                info.note = Some("the RDAP server is rate limiting lookups".into());
+                return Ok(info.finish());
+            }
+            status if !status.is_success() => {
+                return Err(lookup_failed(format!("RDAP answered {}", status)))
+            }
+            _ => {}
+        }
+        let record: Value = response.json().await.map_err(lookup_failed)?;
+
+        info.created = record["events"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|event| event["eventAction"] == "registration")
+            .and_then(|event| event["eventDate"].as_str())
+            .map(str::to_string);
+        // The registrar's name is the `fn` property of its vCard
+        info.registrar = record["entities"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|entity| {
+                entity["roles"]
+                    .as_array()
+                    .is_some_and(|roles| roles.iter().any(|role| role == "registrar"))
+            })
+            .and_then(|entity| entity["vcardArray"][1].as_array())
+            .and_then(|properties| properties.iter().find(|p| p[0] == "fn"))
+            .and_then(|property| property[3].as_str())
+            .filter(|name| !redacted(name))
+            .map(str::to_string);
+        info.nameservers = record["nameservers"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|ns| ns["ldhName"].as_str())
+            .map(str::to_ascii_lowercase)
+            .collect();
+        Ok(info.finish())
+    }
+
+    /// One WHOIS query: send `query`, read until the server hangs up
+    async fn whois_query(server: &str, query: &str) -> Result<String, AppError> {
+        let exchange = async {
+            let mut stream = TcpStream::connect((server, 43)).await?;
+            stream
+                .write_all(format!("{}\r\n", query).as_bytes())
+                .await?;
+            let mut answer = Vec::new();
+            stream
+                .take(MAX_WHOIS_BYTES)
+                .read_to_end(&mut answer)
+                .await?;
+            Ok::<_, std::io::Error>(String::from_utf8_lossy(&answer).into_owned())
+        };
+        tokio::time::timeout(LOOKUP_TIMEOUT, exchange)
+            .await
+            .map_err(|_| lookup_failed(format!("{} did not answer", server)))?
+            .map_err(lookup_failed)
+    }
+
+    async fn whois(&self, domain: &str, tld: &str) -> Result<DomainInfo, AppError> {
+        let referral = Self::whois_query(WHOIS_ROOT, tld).await?;
+        let server = referral
+            .lines()
+            .find_map(|line| {
+                let (key, value) = line.split_once(':')?;
+                matches!(key.trim(), "refer" | "whois").then(|| value.trim().to_string())
+            })
+            .filter(|server| !server.is_empty())
+            .ok_or_else(|| lookup_failed(format!("no WHOIS server for .{}", tld)))?;
+        let answer = Self::whois_query(&server, domain).await?;
+
+        let mut info = DomainInfo::new(domain, "whois");
+        if answer.to_ascii_lowercase().contains("limit exceeded") {
+            info.note = Some(format!("{} is rate limiting lookups", server));
+            return Ok(info.finish());
+        }
+        for line in answer.lines() {
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let (key, value) = (key.trim().to_ascii_lowercase(), value.trim());
+            if redacted(value) {
+                continue;
+            }
+            match key.as_str() {
+                "creation date" | "created" | "registered on" | "registration time"
+                    if info.created.is_none() =>
+                {
+                    info.created = Some(value.to_string())
+                }
+                "registrar" | "sponsoring registrar" if info.registrar.is_none() => {
+                    info.registrar = Some(value.to_string())
+                }
+                "name server" | "nserver" | "nameserver" => {
+                    let ns = value
+                        .split_whitespace()
+                        .next()
+                        .unwrap_or_default()
+                        .to_ascii_lowercase();
+                    if !info.nameservers.contains(&ns) {
+                        info.nameservers.push(ns);
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(info.finish())
+    }
+
+    /// RDAP where the registry has it, WHOIS otherwise or when RDAP fails
+    async fn lookup(&self, domain: &str) -> Result<DomainInfo, AppError> {
+        let tld = domain.rsplit('.').next().unwrap_or(domain);
+        let rdap = match self.rdap_server(tld).await {
+            Ok(Some(server)) => Some(self.rdap(domain, &server).await),
+            Ok(None) => None,
+            Err(e) => Some(Err(e)),
+        };
+        match rdap {
+            Some(Ok(info)) => Ok(info),
+            Some(Err(e @ AppError::NotFound(_))) => Err(e),
+            Some(Err(e)) => {
+                tracing::info!(error = %e, "RDAP lookup failed; trying WHOIS");
+                self.whois(domain, tld).await
+            }
+            None => self.whois(domain, tld).await,
+        }
+    }
+}
+
+async fn cached(history: &ScanHistory, domain: String) -> Result<Option<DomainInfo>, AppError> {
+    history
+        .with_conn(move |conn| {
+            let info: Option<String> = conn
+                .query_row(
+                    "SELECT info FROM domain_info WHERE domain = ?1 AND fetched_at >= ?2",
+                    params![domain, now_secs() - CACHE_SECS],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            Ok(info.and_then(|info| serde_json::from_str(&info).ok()))
+        })
+        .await
+}
+
+async fn store(history: &ScanHistory, info: &DomainInfo) -> Result<(), AppError> {
+    let (domain, json) = (info.domain.clone(), serde_json::to_string(info)?);
+    let fetched_at = info.fetched_at;
+    history
+        .with_conn(move |conn| {
+            conn.execute(
+                "INSERT INTO domain_info (domain, info, fetched_at) VALUES (?1, ?2, ?3)
+                 ON CONFLICT (domain) DO UPDATE SET
+                     info = excluded.info, fetched_at = excluded.fetched_at",
+                params![domain, json, fetched_at],
+            )?;
+            Ok(())
+        })
+        .await
+}
+
+/// Registration details of the registrable domain of `domain`, which may be
+/// any host or URL on it. Answers are kept for a day and show up as
+/// `domain_info` on the history entries of that domain. Only ever runs when
+/// called, and not at all with the `domain_lookups` setting off.
+#[tauri::command]
+pub async fn enrich_domain(
+    domain: String,
+    settings: State<'_, Settings>,
+    lookups: State<'_, DomainLookups>,
+    history: State<'_, ScanHistory>,
+    timings: State<'_, Timings>,
+) -> Result<DomainInfo, AppError> {
+    if !settings.get().domain_lookups {
+        return Err(AppError::InvalidInput(
+            "domain lookups are turned off in the settings".into(),
+        ));
+    }
+    let domain = parse_domain(&domain)?;
+    if domain.parse::<std::net::IpAddr>().is_ok()
+        || domain.starts_with('[')
+        || !domain.contains('.')
+    {
+        return Err(AppError::InvalidInput(format!(
+            "{} is not a registered domain",
+            domain
+        )));
+    }
+    if let Some(info) = cached(&history, domain.clone()).await? {
+        return Ok(info);
+    }
+    let started = Instant::now();
+    let info = lookups.lookup(&domain).await;
+    timings.record(
+        Operation::DomainLookup,
+        started.elapsed().as_millis() as u64,
+    );
+    let info = info?;
+    // Rate-limited answers are worth nothing tomorrow either, so retry sooner
+    if info.note.is_none() {
+        store(&history, &info).await?;
+    }
+    Ok(info)
+}
@@ -0,0 +1,127 @@
+use crate::batch::{run_batch, BatchScanItem};
+use crate::error::AppError;
+use crate::lifecycle::ScanSource;
+use crate::links::{dedupe_links, extract_html_links, extract_text_links, ExtractedLink};
+use crate::AppHandle;
+use mail_parser::{Address, MessageParser};
+use serde::Serialize;
+
+/// Larger messages are refused rather than parsed; attachments make up most
+/// of a message this size and carry no links to scan
+const MAX_MESSAGE_BYTES: u64 = 25 * 1024 * 1024;
+
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct EmailHeaders {
+    from: Option<String>,
+    subject: Option<String>,
+    reply_to: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct EmailScanReport {
+    headers: EmailHeaders,
+    links: Vec<ExtractedLink>,
+    items: Vec<BatchScanItem>,
+    /// Problems that were worked around while parsing the message
+    warnings: Vec<String>,
+}
+
+/// Render an address header as `Name <addr>` entries separated by commas
+fn format_address(address: Option<&Address>) -> Option<String> {
+    let formatted: Vec<String> = address?
+        .iter()
+        .map(|addr| match (addr.name(), addr.address()) {
+            (Some(name), Some(email)) => format!("{} <{}>", name, email),
+            (None, Some(email)) => email.to_string(),
+            (Some(name), None) => name.to_string(),
+            (None, None) => String::new(),
+        })
+        .filter(|s| !s.is_empty())
+        .collect();
+    (!formatted.is_empty()).then(|| formatted.join(", "))
+}
+
+/// Parse a raw RFC 5322 message into headers and the links in its text parts.
+///
+/// Unparseable MIME falls back to scanning the raw bytes as text so a broken
+/// message still yields whatever links can be found.
+pub fn parse_email(raw: &[u8]) -> (EmailHeaders, Vec<ExtractedLink>, Vec<String>) {
+    let mut warnings = Vec::new();
+
+    let Some(message) = MessageParser::default().parse(raw) else {
+        warnings.push("message could not be parsed as MIME; scanned raw text".to_string());
+        let text = String::from_utf8_lossy(raw);
+        return (
+            EmailHeaders::default(),
+            dedupe_links(extract_text_links(&text)),
+            warnings,
+        );
+    };
+
+    let headers = EmailHeaders {
+        from: format_address(message.from()),
+        subject: message.subject().map(str::to_string),
+        reply_to: format_address(message.reply_to()),
+    };
+
+    let mut links = Vec::new();
+    let mut text_parts = 0;
+    for part in message.parts.iter().filter(|p| p.is_text()) {
+        let Some(contents) = part.text_contents() else {
+            continue;
+        };
+        text_parts += 1;
+        if part.is_text_html() {
+            links.extend(extract_html_links(contents));
+        } else {
+            links.extend(extract_text_links(contents));
+        }
+    }
+
+    if text_parts == 0 {
+        warnings.push("message has no readable text parts".to_string());
+    }
+
+    (headers, dedupe_links(links), warnings)
+}
+
+fn check_size(bytes: u64) -> Result<(), AppError> {
+    if bytes > MAX_MESSAGE_BYTES {
+        return Err(AppError::InvalidInput(format!(
+            "the message is {} MB; at most {} MB can be scanned",
+            bytes / (1024 * 1024),
+            MAX_MESSAGE_BYTES / (1024 * 1024)
+        )));
+    }
+    Ok(())
+}
+
+async fn scan_message(app: &AppHandle, raw: &[u8]) -> EmailScanReport {
+    let (headers, links, warnings) = parse_email(raw);
+
+    let urls = links.iter().map(|link| link.url.clone()).collect();
+    let items = run_batch(app, urls, ScanSource::Email).await;
+
+    EmailScanReport {
+        headers,
+        links,
+        items,
+        warnings,
+    }
+}
+
+/// Extract every link from a .eml file and scan them
+#[tauri::command]
+pub async fn scan_email_file(path: String, app: AppHandle) -> Result<EmailScanReport, AppError> {
+    check_size(tokio::fs::metadata(&path).await?.len())?;
+    let raw = tokio::fs::read(&path).await?;
+    Ok(scan_message(&app, &raw).await)
+}
+
+/// Extract every link from pasted message source, headers included, and
+/// scan them
+#[tauri::command]
+pub async fn scan_email_content(raw: String, app: AppHandle) -> Result<EmailScanReport, AppError> {
+    check_size(raw.len() as u64)?;
+    Ok(scan_message(&app, raw.as_bytes()).await)
+}
@@ -0,0 +1,291 @@
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+use std::fmt;
+use std::sync::PoisonError;
+
+/// Error returned by every Tauri command.
+///
+/// Serialized to the frontend as `{ kind, message, ... }` so the UI can
+/// branch on `kind` instead of matching on message text.
+#[derive(Debug, Clone)]
+pub enum AppError {
+    /// python3 could not be started (missing interpreter, bad project root)
+    PythonUnavailable(String),
+    /// python3 was started but did not finish within the configured timeout
+    Timeout { seconds: u64 },
+    /// detect_enhanced.py ran but exited unsuccessfully
+    Detector { code: Option<i32>, detail: String },
+    /// The detector output was not the JSON we expected
+    Parse(String),
+    /// The scan was cancelled with cancel_scan before it finished
+    Cancelled,
+    /// An image could not be decoded
+    Image(String),
+    /// An image was decoded but contained no readable QR code
+    NoQrCode,
+    /// A file the command was asked to read or write could not be accessed
+    Io(String),
+    /// Shared application state could not be accessed
+    State(String),
+    /// The local scan history database failed
+    Database(String),
+    /// No stored scan has the requested id
+    NotFound(String),
+    /// A PDF report could not be produced
+    Report(String),
+    /// A command argument was missing or not acceptable
+    InvalidInput(String),
+    /// Text given to scan could not be made into an http(s) URL
+    InvalidUrl(String),
+    /// A global shortcut could not be parsed or registered
+    Shortcut(String),
+    /// Launch at login could not be read or changed
+    Autostart(String),
+    /// A settings file or database was written by a newer app version
+    NewerVersion(String),
+    /// An update could not be checked for, downloaded or installed
+    Update(String),
+    /// The action is gated by `require_os_auth` and verify_user_presence
+    /// has not succeeded recently
+    VerificationRequired(String),
+}
+
+impl AppError {
+    /// Stable identifier for the variant, used as the `kind` field
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AppError::PythonUnavailable(_) => "python_unavailable",
+            AppError::Timeout { .. } => "timeout",
+            AppError::Detector { .. } => "detector",
+            AppError::Parse(_) => "parse",
+            AppError::Cancelled => "cancelled",
+            AppError::Image(_) => "image",
+            AppError::NoQrCode => "no_qr_code",
+            AppError::Io(_) => "io",
+            AppError::State(_) => "state",
+            AppError::Database(_) => "database",
+            AppError::NotFound(_) => "not_found",
+            AppError::Report(_) => "report",
+            AppError::InvalidInput(_) => "invalid_input",
+            AppError::InvalidUrl(_) => "invalid_url",
+            AppError::Shortcut(_) => "shortcut",
+            AppError::Autostart(_) => "autostart",
+            AppError::NewerVersion(_) => "newer_version",
+            AppError::Update(_) => "update",
+            AppError::VerificationRequired(_) => "verification_required",
+        }
+    }
+
+    /// True when the detector could not run at all, as opposed to failing on
+    /// this particular URL, so the scan is worth retrying once it is back
+    pub fn is_offline(&self) -> bool {
+        match self {
+            AppError::PythonUnavailable(_) => true,
+            AppError::Detector { detail, .. } => detail.contains("ModuleNotFoundError"),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::PythonUnavailable(e) => write!(f, "Failed to execute Python: {}", e),
+            AppError::Timeout { seconds } => {
+                write!(f, "Python did not respond within {} seconds", seconds)
+            }
+            AppError::Detector {
+                code: Some(code),
+                detail,
+            } => write!(f, "Python error (exit code {}): {}", code, detail),
+            AppError::Detector { code: None, detail } => write!(f, "Python error: {}", detail),
+            AppError::Parse(e) => write!(f, "Failed to parse result: {}", e),
+            AppError::Cancelled => write!(f, "Scan was cancelled"),
+            AppError::Image(e) => write!(f, "Failed to read image: {}", e),
+            AppError::NoQrCode => write!(f, "No QR code found in image"),
+            AppError::Io(e) => write!(f, "File error: {}", e),
+            AppError::State(e) => write!(f, "Application state unavailable: {}", e),
+            AppError::Database(e) => write!(f, "History database error: {}", e),
+            AppError::NotFound(id) => write!(f, "No saved scan with id {}", id),
+            AppError::Report(e) => write!(f, "Failed to create report: {}", e),
+            AppError::InvalidInput(e) => write!(f, "Invalid input: {}", e),
+            AppError::InvalidUrl(e) => write!(f, "Not a URL that can be scanned: {}", e),
+            AppError::Shortcut(e) => write!(f, "Global shortcut error: {}", e),
+            AppError::Autostart(e) => write!(f, "Failed to update launch at login: {}", e),
+            AppError::NewerVersion(file) => write!(
+                f,
+                "{} was saved by a newer version of Phishing Guard; update the app to use it",
+                file
+            ),
+            AppError::Update(e) => write!(f, "Update failed: {}", e),
+            AppError::VerificationRequired(action) => {
+                write!(f, "Confirm it's you to {}", action)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl AppError {
+    /// This error as failing the scan `scan_id`
+    pub fn for_scan(self, scan_id: &str) -> ScanError {
+        ScanError {
+            error: self,
+            reference: scan_id.to_string(),
+        }
+    }
+
+    fn serialize_fields<S: SerializeStruct>(&self, s: &mut S) -> Result<(), S::Error> {
+        s.serialize_field("kind", self.kind())?;
+        s.serialize_field("message", &self.to_string())?;
+        match self {
+            AppError::Detector { code, .. } => s.serialize_field("code", code),
+            _ => s.skip_field("code"),
+        }
+    }
+}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("AppError", 3)?;
+        self.serialize_fields(&mut s)?;
+        s.end()
+    }
+}
+
+/// Error returned by the commands that scan one URL.
+///
+/// Serialized like `AppError` plus `reference`, the scan id, so a failure can
+/// be matched to its log lines even when the frontend let the id be generated.
+#[derive(Debug, Clone)]
+pub struct ScanError {
+    pub error: AppError,
+    pub reference: String,
+}
+
+impl fmt::Display for ScanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (reference: {})", self.error, self.reference)
+    }
+}
+
+impl std::error::Error for ScanError {}
+
+impl Serialize for ScanError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("ScanError", 4)?;
+        self.error.serialize_fields(&mut s)?;
+        s.serialize_field("reference", &self.reference)?;
+        s.end()
+    }
+}
+
+impl From<serde_json::Error> for AppError {
+    fn from(e: serde_json::Error) -> Self {
+        AppError::Parse(e.to_string())
+    }
+}
+
+impl From<std::io::Error> for AppError {
+    fn from(e: std::io::Error) -> Self {
+        AppError::Io(e.to_string())
+    }
+}
+
+impl From<rusqlite::Error> for AppError {
+    fn from(e: rusqlite::Error) -> Self {
+        AppError::Database(e.to_string())
+    }
+}
+
+impl<T> From<PoisonError<T>> for AppError {
+    fn from(e: PoisonError<T>) -> Self {
+        AppError::State(e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn scan_error_adds_the_reference() {
+        let error = AppError::Detector {
+            code: Some(1),
+            detail: "Traceback".into(),
+        };
+        assert_eq!(
+            serde_json::to_value(error.clone()).unwrap(),
+            json!({"kind": "detector", "message": "Python error (exit code 1): Traceback", "code": 1})
+        );
+        assert_eq!(
+            serde_json::to_value(error.for_scan("7f3a")).unwrap(),
+            json!({
+                "kind": "detector",
+                "message": "Python error (exit code 1): Traceback",
+                "code": 1,
+                "reference": "7f3a"
+            })
+        );
+        let cancelled = serde_json::to_value(AppError::Cancelled.for_scan("7f3a")).unwrap();
+        assert_eq!(cancelled["reference"], "7f3a");
+        assert!(cancelled.get("code").is_none());
+    }
+
+    #[test]
+    fn conversions_keep_the_kind_of_failure() {
+        let io = AppError::from(std::io::Error::new(std::io::ErrorKind::NotFound, "gone"));
+        assert!(matches!(&io, AppError::Io(message) if message == "gone"));
+        assert_eq!(io.kind(), "io");
+
+        let parse = AppError::from(serde_json::from_str::<u32>("{").unwrap_err());
+        assert!(matches!(parse, AppError::Parse(_)));
+        assert_eq!(parse.kind(), "parse");
+
+        let database = AppError::from(rusqlite::Error::QueryReturnedNoRows);
+        assert!(matches!(database, AppError::Database(_)));
+        assert_eq!(database.kind(), "database");
+
+        let lock = std::sync::Mutex::new(());
+        let _ = std::panic::catch_unwind(|| {
+            let _guard = lock.lock().unwrap();
+            panic!("poisoned on purpose");
+        });
+        let state = AppError::from(lock.lock().unwrap_err());
+        assert!(matches!(state, AppError::State(_)));
+        assert_eq!(state.kind(), "state");
+        assert_eq!(serde_json::to_value(state).unwrap()["kind"], "state");
+    }
+
+    #[test]
+    fn scan_url_reports_a_detector_failure_under_its_scan_id() {
+        let app = crate::testing::app(tauri::generate_handler![crate::scan_url]);
+        let error = app
+            .invoke(
+                "scan_url",
+                json!({"url": "https://fail-detector.example/", "scanId": "s-1"}),
+            )
+            .unwrap_err();
+        assert_eq!(error["kind"], "detector");
+        assert_eq!(error["code"], 1);
+        assert_eq!(error["reference"], "s-1");
+    }
+
+    #[test]
+    fn scan_url_rejects_what_is_not_a_url() {
+        let app = crate::testing::app(tauri::generate_handler![crate::scan_url]);
+        let error = app
+            .invoke("scan_url", json!({"url": "not a url", "scanId": "s-2"}))
+            .unwrap_err();
+        assert_eq!(error["kind"], "invalid_url");
+        assert_eq!(error["reference"], "s-2");
+
+        let verdict = app
+            .invoke("scan_url", json!({"url": "https://phish.example/"}))
+            .unwrap();
+        assert_eq!(verdict["classification"], "phishing");
+        assert_eq!(verdict["analysis_mode"], "mock");
+    }
+}
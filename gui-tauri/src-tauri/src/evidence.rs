@@ -0,0 +1,174 @@
+use crate::error::AppError;
+use crate::history::{now_secs, HistoryEntry, ScanHistory};
+use crate::AppHandle;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use tauri::State;
+
+#[derive(Serialize, Debug, Clone)]
+pub struct BundleFile {
+    name: String,
+    bytes: usize,
+    /// Hex SHA-256 of the file as written
+    sha256: String,
+}
+
+/// Something a bundle would hold that this scan doesn't have
+#[derive(Serialize, Debug, Clone)]
+pub struct MissingFile {
+    name: String,
+    reason: String,
+}
+
+/// Written to the bundle as `manifest.json` and returned by the command
+#[derive(Serialize, Debug, Clone)]
+pub struct EvidenceManifest {
+    scan_id: String,
+    url: String,
+    scanned_at: i64,
+    created_at: i64,
+    app_version: String,
+    files: Vec<BundleFile>,
+    unavailable: Vec<MissingFile>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct EvidenceBundle {
+    folder: PathBuf,
+    manifest: EvidenceManifest,
+}
+
+/// `scan-20240131-142501-1a2b3c4d` for a scan stored at that local time
+fn folder_name(scan_id: &str, local_time: &str) -> String {
+    let stamp: String = local_time
+        .chars()
+        .filter(char::is_ascii_digit)
+        .enumerate()
+        .flat_map(|(i, c)| (i == 8).then_some('-').into_iter().chain([c]))
+        .collect();
+    let short_id: String = scan_id
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .take(8)
+        .collect();
+    format!("scan-{}-{}", stamp, short_id)
+}
+
+/// Create `folder`, or check it may be written to: an existing folder must be
+/// empty unless `overwrite` is set
+fn prepare(folder: &Path, overwrite: bool) -> Result<(), AppError> {
+    match std::fs::read_dir(folder) {
+        Ok(mut entries) => {
+            if entries.next().is_some() && !overwrite {
+                return Err(AppError::Io(format!(
+                    "{} is not empty; choose another folder or allow overwriting",
+                    folder.display()
+                )));
+            }
+            Ok(())
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            std::fs::create_dir(folder)?;
+            Ok(())
+        }
+        Err(e) => Err(AppError::Io(format!(
+            "cannot write to {}: {}",
+            folder.display(),
+            e
+        ))),
+    }
+}
+
+fn write(folder: &Path, name: &str, contents: &[u8]) -> Result<BundleFile, AppError> {
+    std::fs::write(folder.join(name), contents)?;
+    let digest = Sha256::digest(contents);
+    Ok(BundleFile {
+        name: name.to_string(),
+        bytes: contents.len(),
+        sha256: digest.iter().map(|byte| format!("{:02x}", byte)).collect(),
+    })
+}
+
+fn write_bundle(
+    entry: &HistoryEntry,
+    folder: &Path,
+    overwrite: bool,
+    app_version: String,
+) -> Result<EvidenceManifest, AppError> {
+    prepare(folder, overwrite)?;
+    let files = vec![
+        write(folder, "result.json", &serde_json::to_vec_pretty(entry)?)?,
+        write(folder, "explanation.txt", entry.explanation.as_bytes())?,
+        write(
+            folder,
+            "features.json",
+            &serde_json::to_vec_pretty(&entry.features)?,
+        )?,
+    ];
+    let missing = |name: &str, reason: &str| MissingFile {
+        name: name.to_string(),
+        reason: reason.to_string(),
+    };
+    let unavailable = vec![
+        missing(
+            "screenshot.png",
+            "the local detector does not capture screenshots",
+        ),
+        missing(
+            "redirects.json",
+            match &entry.redirected_from {
+                Some(_) => "only the URL redirected from is stored, see result.json",
+                None => "the scan did not follow any redirects",
+            },
+        ),
+    ];
+    let manifest = EvidenceManifest {
+        scan_id: entry.scan_id.clone().unwrap_or_default(),
+        url: entry.url.clone(),
+        scanned_at: entry.scanned_at,
+        created_at: now_secs(),
+        app_version,
+        files,
+        unavailable,
+    };
+    std::fs::write(
+        folder.join("manifest.json"),
+        serde_json::to_vec_pretty(&manifest)?,
+    )?;
+    Ok(manifest)
+}
+
+/// Write everything kept for a stored scan into a new folder under `dir`,
+/// named after the scan's time, with a manifest of SHA-256 hashes. Refuses
+/// to write into a folder that already has files in it unless `overwrite`.
+#[tauri::command]
+pub async fn export_evidence_bundle(
+    scan_id: String,
+    dir: String,
+    overwrite: Option<bool>,
+    app: AppHandle,
+    history: State<'_, ScanHistory>,
+) -> Result<EvidenceBundle, AppError> {
+    let dir = PathBuf::from(dir);
+    if !dir.is_dir() {
+        return Err(AppError::Io(format!(
+            "folder {} does not exist",
+            dir.display()
+        )));
+    }
+    let (entry, local_time) = history
+        .find(&scan_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(scan_id.clone()))?;
+    let folder = dir.join(folder_name(&scan_id, &local_time));
+    let app_version = app.package_info().version.to_string();
+
+    let written = folder.clone();
+    let manifest = tokio::task::spawn_blocking(move || {
+        write_bundle(&entry, &written, overwrite.unwrap_or(false), app_version)
+    })
+    .await
+    .map_err(|e| AppError::State(format!("evidence export failed: {}", e)))??;
+    Ok(EvidenceBundle { folder, manifest })
+}
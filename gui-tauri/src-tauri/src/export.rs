@@ -0,0 +1,157 @@
+use crate::error::AppError;
+use crate::history::{HistoryEntry, HistoryFilter, ScanHistory};
+use crate::presence;
+use crate::AppHandle;
+use serde::Deserialize;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, ErrorKind, Write};
+use std::path::Path;
+use tauri::State;
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+const CSV_HEADER: [&str; 12] = [
+    "id",
+    "scanned_at",
+    "url",
+    "normalized_url",
+    "classification",
+    "confidence",
+    "risk_score",
+    "explanation",
+    "source",
+    "features",
+    "note",
+    "tags",
+];
+
+/// Open the destination, turning the common failures into messages a user can act on
+fn create_destination(path: &Path, overwrite: bool) -> Result<File, AppError> {
+    let mut options = OpenOptions::new();
+    options.write(true);
+    if overwrite {
+        options.create(true).truncate(true);
+    } else {
+        options.create_new(true);
+    }
+
+    options.open(path).map_err(|e| {
+        let dir = path
+            .parent()
+            .map(|d| d.display().to_string())
+            .unwrap_or_default();
+        match e.kind() {
+            ErrorKind::AlreadyExists => AppError::Io(format!(
+                "{} already exists; choose another name or allow overwriting",
+                path.display()
+            )),
+            ErrorKind::PermissionDenied => {
+                AppError::Io(format!("cannot write to {}: permission denied", dir))
+            }
+            ErrorKind::NotFound => AppError::Io(format!("folder {} does not exist", dir)),
+            _ => AppError::Io(format!("cannot create {}: {}", path.display(), e)),
+        }
+    })
+}
+
+fn csv_record(entry: &HistoryEntry) -> Result<[String; 12], AppError> {
+    Ok([
+        entry.id.to_string(),
+        entry.scanned_at.to_string(),
+        entry.url.clone(),
+        entry.normalized_url.clone(),
+        entry.classification.clone(),
+        entry.confidence.to_string(),
+        entry.risk_score.to_string(),
+        entry.explanation.clone(),
+        entry.source.clone(),
+        serde_json::to_string(&entry.features)?,
+        entry.note.clone().unwrap_or_default(),
+        entry.tags.join(";"),
+    ])
+}
+
+fn csv_error(e: csv::Error) -> AppError {
+    AppError::Io(e.to_string())
+}
+
+async fn export_csv(
+    history: &ScanHistory,
+    filter: HistoryFilter,
+    file: File,
+) -> Result<usize, AppError> {
+    history
+        .stream(filter, move |cursor| {
+            let mut writer = csv::Writer::from_writer(BufWriter::new(file));
+            writer.write_record(CSV_HEADER).map_err(csv_error)?;
+
+            let mut rows = 0;
+            while let Some(entry) = cursor.next_entry()? {
+                writer
+                    .write_record(csv_record(&entry)?)
+                    .map_err(csv_error)?;
+                rows += 1;
+            }
+            writer.flush()?;
+            Ok(rows)
+        })
+        .await
+}
+
+async fn export_json(
+    history: &ScanHistory,
+    filter: HistoryFilter,
+    file: File,
+) -> Result<usize, AppError> {
+    history
+        .stream(filter, move |cursor| {
+            let mut writer = BufWriter::new(file);
+            writer.write_all(b"[")?;
+
+            let mut rows = 0;
+            while let Some(entry) = cursor.next_entry()? {
+                writer.write_all(if rows == 0 { b"\n" } else { b",\n" })?;
+                // Indent each pretty-printed object one level inside the array
+                let object = serde_json::to_string_pretty(&entry)?;
+                for (index, line) in object.lines().enumerate() {
+                    if index > 0 {
+                        writer.write_all(b"\n")?;
+                    }
+                    write!(writer, "  {}", line)?;
+                }
+                rows += 1;
+            }
+
+            writer.write_all(if rows == 0 { b"]\n" } else { b"\n]\n" })?;
+            writer.flush()?;
+            Ok(rows)
+        })
+        .await
+}
+
+/// Write the history (optionally filtered) to `path` as CSV or a JSON array,
+/// returning the number of rows written
+#[tauri::command]
+pub async fn export_history(
+    format: ExportFormat,
+    path: String,
+    filter: Option<HistoryFilter>,
+    overwrite: Option<bool>,
+    app: AppHandle,
+    history: State<'_, ScanHistory>,
+) -> Result<usize, AppError> {
+    presence::require(&app, "export the scan history")?;
+    let path = Path::new(&path);
+    let file = create_destination(path, overwrite.unwrap_or(false))?;
+    let filter = filter.unwrap_or_default();
+
+    match format {
+        ExportFormat::Csv => export_csv(&history, filter, file).await,
+        ExportFormat::Json => export_json(&history, filter, file).await,
+    }
+}
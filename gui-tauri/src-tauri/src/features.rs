@@ -0,0 +1,225 @@
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
+
+/// Accept ints, floats, bools and numeric strings; anything else, and
+/// strings such as "NaN" or "inf" that aren't a finite number, becomes None
+pub fn lenient_number<'de, D: Deserializer<'de>>(d: D) -> Result<Option<f64>, D::Error> {
+    let number = match serde_json::Value::deserialize(d)? {
+        serde_json::Value::Number(n) => n.as_f64(),
+        serde_json::Value::Bool(b) => Some(if b { 1.0 } else { 0.0 }),
+        serde_json::Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    };
+    Ok(number.filter(|n: &f64| n.is_finite()))
+}
+
+/// Accept 0/1 flags, bools and numeric strings; negative values mean "not checked"
+fn lenient_flag<'de, D: Deserializer<'de>>(d: D) -> Result<Option<bool>, D::Error> {
+    Ok(lenient_number(d)?.filter(|n| *n >= 0.0).map(|n| n != 0.0))
+}
+
+/// URL features reported by the detector.
+///
+/// The commonly displayed features are typed; everything else the Python
+/// extractor emits is kept in `other` so new features pass through untouched.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct FeatureSet {
+    #[serde(deserialize_with = "lenient_number")]
+    pub url_length: Option<f64>,
+    #[serde(deserialize_with = "lenient_number")]
+    pub hostname_length: Option<f64>,
+    #[serde(deserialize_with = "lenient_number")]
+    pub domain_length: Option<f64>,
+    #[serde(deserialize_with = "lenient_number")]
+    pub path_length: Option<f64>,
+    #[serde(deserialize_with = "lenient_number")]
+    pub num_dots: Option<f64>,
+    #[serde(deserialize_with = "lenient_number")]
+    pub num_hyphens: Option<f64>,
+    #[serde(deserialize_with = "lenient_number")]
+    pub num_at: Option<f64>,
+    #[serde(deserialize_with = "lenient_number")]
+    pub num_digits: Option<f64>,
+    #[serde(deserialize_with = "lenient_number")]
+    pub subdomain_count: Option<f64>,
+    #[serde(deserialize_with = "lenient_number")]
+    pub entropy: Option<f64>,
+    #[serde(deserialize_with = "lenient_number")]
+    pub domain_entropy: Option<f64>,
+    #[serde(deserialize_with = "lenient_number")]
+    pub idn_risk_score: Option<f64>,
+    #[serde(deserialize_with = "lenient_number")]
+    pub host_risk_score: Option<f64>,
+    #[serde(deserialize_with = "lenient_number")]
+    pub security_risk_score: Option<f64>,
+    #[serde(deserialize_with = "lenient_number")]
+    pub cert_days_remaining: Option<f64>,
+    #[serde(deserialize_with = "lenient_flag")]
+    pub is_https: Option<bool>,
+    #[serde(deserialize_with = "lenient_flag")]
+    pub has_port: Option<bool>,
+    #[serde(deserialize_with = "lenient_flag")]
+    pub is_ip_address: Option<bool>,
+    #[serde(deserialize_with = "lenient_flag")]
+    pub has_suspicious_words: Option<bool>,
+    #[serde(deserialize_with = "lenient_flag")]
+    pub is_random_domain: Option<bool>,
+    #[serde(deserialize_with = "lenient_flag")]
+    pub has_punycode: Option<bool>,
+    #[serde(deserialize_with = "lenient_flag")]
+    pub mixed_scripts: Option<bool>,
+    #[serde(deserialize_with = "lenient_flag")]
+    pub has_confusables: Option<bool>,
+    #[serde(deserialize_with = "lenient_flag")]
+    pub suspicious_tld: Option<bool>,
+    #[serde(deserialize_with = "lenient_flag")]
+    pub brand_in_domain: Option<bool>,
+    #[serde(deserialize_with = "lenient_flag")]
+    pub cert_valid: Option<bool>,
+    /// Typosquatting check result, passed through as reported
+    pub typosquatting: Option<serde_json::Value>,
+    /// Every feature without a typed field above
+    #[serde(flatten)]
+    pub other: BTreeMap<String, serde_json::Value>,
+}
+
+impl FeatureSet {
+    /// Build from the detector's `features` object, falling back to empty on junk
+    pub fn from_value(value: &serde_json::Value) -> Self {
+        serde_json::from_value(value.clone()).unwrap_or_default()
+    }
+}
+
+/// Human-readable label for a feature key
+#[derive(Serialize, Debug, Clone)]
+pub struct FeatureDescription {
+    key: &'static str,
+    label: &'static str,
+}
+
+const FEATURE_LABELS: &[(&str, &str)] = &[
+    ("url_length", "URL length (characters)"),
+    ("hostname_length", "Hostname length"),
+    ("domain_length", "Domain name length"),
+    ("path_length", "Path length"),
+    ("num_dots", "Number of dots"),
+    ("num_hyphens", "Number of hyphens"),
+    ("num_at", "Number of '@' symbols"),
+    ("num_digits", "Number of digits"),
+    ("subdomain_count", "Number of subdomains"),
+    ("entropy", "URL randomness (entropy)"),
+    ("domain_entropy", "Domain randomness (entropy)"),
+    ("idn_risk_score", "Internationalized domain risk"),
+    ("host_risk_score", "Host risk"),
+    ("security_risk_score", "Overall security risk"),
+    ("cert_days_remaining", "Days until certificate expiry"),
+    ("is_https", "Uses HTTPS"),
+    ("has_port", "Explicit port in URL"),
+    ("is_ip_address", "Host is an IP address"),
+    (
+        "has_suspicious_words",
+        "Contains suspicious words (login, verify, ...)",
+    ),
+    ("is_random_domain", "Domain looks randomly generated"),
+    ("has_punycode", "Punycode (xn--) domain"),
+    ("mixed_scripts", "Mixes alphabets (e.g. Latin and Cyrillic)"),
+    ("has_confusables", "Contains look-alike characters"),
+    ("suspicious_tld", "Suspicious top-level domain"),
+    ("brand_in_domain", "Brand name used in domain"),
+    ("cert_valid", "TLS certificate is valid"),
+    ("typosquatting", "Typosquatting check"),
+];
+
+/// Human-readable label for a feature key, if it is one we know
+pub fn feature_label(key: &str) -> Option<&'static str> {
+    FEATURE_LABELS
+        .iter()
+        .find(|(known, _)| *known == key)
+        .map(|(_, label)| *label)
+}
+
+/// Labels for each feature key so the frontend can render a breakdown table
+#[tauri::command]
+pub fn get_feature_descriptions() -> Vec<FeatureDescription> {
+    FEATURE_LABELS
+        .iter()
+        .map(|(key, label)| FeatureDescription { key, label })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn full_blob() {
+        let features = FeatureSet::from_value(&json!({
+            "url_length": 54, "hostname_length": 22, "domain_length": 14, "path_length": 12,
+            "num_dots": 3, "num_hyphens": 2, "num_at": 0, "num_digits": 4,
+            "subdomain_count": 1, "entropy": 4.12, "domain_entropy": 3.3,
+            "idn_risk_score": 0.0, "host_risk_score": 0.4, "security_risk_score": 0.7,
+            "cert_days_remaining": 30,
+            "is_https": 1, "has_port": 0, "is_ip_address": false, "has_suspicious_words": true,
+            "is_random_domain": 0, "has_punycode": 0, "mixed_scripts": 0, "has_confusables": 0,
+            "suspicious_tld": 1, "brand_in_domain": 1, "cert_valid": 1,
+            "typosquatting": {"target": "paypal.com", "distance": 1}
+        }));
+        assert_eq!(features.url_length, Some(54.0));
+        assert_eq!(features.entropy, Some(4.12));
+        assert_eq!(features.cert_days_remaining, Some(30.0));
+        assert_eq!(features.is_https, Some(true));
+        assert_eq!(features.is_ip_address, Some(false));
+        assert_eq!(features.has_suspicious_words, Some(true));
+        assert_eq!(features.cert_valid, Some(true));
+        assert_eq!(features.typosquatting.unwrap()["distance"], 1);
+        assert!(features.other.is_empty());
+    }
+
+    #[test]
+    fn partial_blob_leaves_missing_fields_empty() {
+        let features = FeatureSet::from_value(&json!({
+            "url_length": "31",
+            "is_https": "0",
+            "cert_valid": -1,
+            "entropy": null,
+            "num_dots": "many"
+        }));
+        assert_eq!(features.url_length, Some(31.0));
+        assert_eq!(features.is_https, Some(false));
+        // -1 is how the extractor says a check didn't run
+        assert_eq!(features.cert_valid, None);
+        assert_eq!(features.entropy, None);
+        assert_eq!(features.num_dots, None);
+        assert_eq!(features.hostname_length, None);
+        assert_eq!(features.brand_in_domain, None);
+        assert!(features.typosquatting.is_none());
+    }
+
+    #[test]
+    fn unknown_keys_land_in_other_and_round_trip() {
+        let features = FeatureSet::from_value(&json!({
+            "url_length": 20,
+            "has_login_form": 1,
+            "asn": {"number": 13335, "org": "Example"}
+        }));
+        assert_eq!(features.url_length, Some(20.0));
+        assert_eq!(features.other["has_login_form"], 1);
+        assert_eq!(features.other["asn"]["number"], 13335);
+        assert!(!features.other.contains_key("url_length"));
+
+        let value = serde_json::to_value(&features).unwrap();
+        assert_eq!(value["has_login_form"], 1);
+        assert_eq!(value["url_length"], 20.0);
+    }
+
+    #[test]
+    fn junk_falls_back_to_empty() {
+        for junk in [json!(null), json!("features"), json!([1, 2])] {
+            let features = FeatureSet::from_value(&junk);
+            assert_eq!(features.url_length, None);
+            assert!(features.other.is_empty());
+        }
+    }
+}
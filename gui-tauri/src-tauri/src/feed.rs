@@ -0,0 +1,444 @@
+use crate::error::AppError;
+use crate::features::FeatureSet;
+use crate::history::{now_secs, ScanHistory};
+use crate::lifecycle::{ScanJob, ScanSource};
+use crate::settings::{FeedSettings, Settings};
+use crate::{tray, AppHandle, ScanResult};
+use reqwest::header::{HeaderName, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, RwLock};
+use std::time::Duration;
+use tauri::{Manager, State};
+use tokio::sync::Notify;
+
+/// The last good copy of the feed, kept in the app data dir
+const FEED_FILE: &str = "feed.json";
+/// Sync bookkeeping, including the validators for conditional requests
+const STATUS_FILE: &str = "feed-status.json";
+/// A bigger download is refused rather than read into memory
+const MAX_FEED_BYTES: usize = 64 * 1024 * 1024;
+const FETCH_TIMEOUT: Duration = Duration::from_secs(60);
+/// Roughly a 1% false positive rate, each of which the exact set then rules out
+const BLOOM_BITS_PER_ENTRY: usize = 10;
+const BLOOM_HASHES: u64 = 7;
+
+/// What the feed server publishes
+#[derive(Deserialize, Serialize)]
+struct FeedDocument {
+    #[serde(default)]
+    version: Option<String>,
+    domains: Vec<String>,
+}
+
+/// Bit set answering "definitely not in the feed" for most lookups without
+/// touching the much larger exact set
+struct Bloom {
+    bits: Vec<u64>,
+}
+
+impl Bloom {
+    fn with_capacity(entries: usize) -> Self {
+        let words = (entries.max(1) * BLOOM_BITS_PER_ENTRY + 63) / 64;
+        Self {
+            bits: vec![0; words],
+        }
+    }
+
+    /// Double hashing: the k positions are `a + i * b`
+    fn positions<'a>(&'a self, item: &str) -> impl Iterator<Item = usize> + 'a {
+        let seeded = |seed: u8| {
+            let mut hasher = DefaultHasher::new();
+            seed.hash(&mut hasher);
+            item.hash(&mut hasher);
+            hasher.finish()
+        };
+        let (a, b) = (seeded(0), seeded(1) | 1);
+        let size = (self.bits.len() * 64) as u64;
+        (0..BLOOM_HASHES).map(move |i| (a.wrapping_add(i.wrapping_mul(b)) % size) as usize)
+    }
+
+    fn insert(&mut self, item: &str) {
+        let positions: Vec<usize> = self.positions(item).collect();
+        for bit in positions {
+            self.bits[bit / 64] |= 1 << (bit % 64);
+        }
+    }
+
+    fn contains(&self, item: &str) -> bool {
+        self.positions(item)
+            .all(|bit| self.bits[bit / 64] & (1 << (bit % 64)) != 0)
+    }
+}
+
+/// A lowercased hostname without a trailing dot, or `None` for anything
+/// that isn't one
+fn normalize(domain: &str) -> Option<String> {
+    let domain = domain.trim().trim_end_matches('.').to_ascii_lowercase();
+    let valid = domain.contains('.')
+        && domain
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.')
+        && domain.split('.').all(|label| !label.is_empty());
+    valid.then_some(domain)
+}
+
+struct FeedIndex {
+    bloom: Bloom,
+    domains: HashSet<String>,
+    version: Option<String>,
+}
+
+impl FeedIndex {
+    /// Index a downloaded or stored feed; a feed without a single usable
+    /// domain is treated as malformed
+    fn parse(body: &[u8]) -> Result<Self, AppError> {
+        let document: FeedDocument = serde_json::from_slice(body)
+            .map_err(|e| AppError::Parse(format!("the feed is not valid: {}", e)))?;
+        let domains: HashSet<String> = document
+            .domains
+            .iter()
+            .filter_map(|d| normalize(d))
+            .collect();
+        if domains.is_empty() {
+            return Err(AppError::Parse("the feed lists no valid domains".into()));
+        }
+        let mut bloom = Bloom::with_capacity(domains.len());
+        for domain in &domains {
+            bloom.insert(domain);
+        }
+        Ok(Self {
+            bloom,
+            domains,
+            version: document.version,
+        })
+    }
+
+    /// The feed domain covering `url`'s host, checking the host itself and
+    /// then each parent domain
+    fn matches(&self, url: &str) -> Option<String> {
+        let parsed = url::Url::parse(url).ok()?;
+        let host = normalize(parsed.host_str()?)?;
+        let mut candidate = host.as_str();
+        loop {
+            if self.bloom.contains(candidate) && self.domains.contains(candidate) {
+                return Some(candidate.to_string());
+            }
+            match candidate.split_once('.') {
+                Some((_, parent)) if parent.contains('.') => candidate = parent,
+                _ => return None,
+            }
+        }
+    }
+}
+
+/// Feed sync state, as returned by get_feed_status
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct FeedStatus {
+    /// The feed URL the stored copy came from
+    url: Option<String>,
+    version: Option<String>,
+    entries: usize,
+    /// When the stored copy last changed
+    synced_at: Option<i64>,
+    /// When the server was last asked, whether or not the feed had changed
+    checked_at: Option<i64>,
+    /// Why the last sync failed; the previous copy stays in use
+    last_error: Option<String>,
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+/// The loaded feed and its sync loop's controls
+pub struct Feed {
+    index: RwLock<Option<FeedIndex>>,
+    status: Mutex<FeedStatus>,
+    /// Settings the sync loop last ran with, so unrelated changes don't resync
+    applied: Mutex<Option<FeedSettings>>,
+    wake: Notify,
+    /// Only one download at a time, between the loop and force_feed_sync
+    syncing: tokio::sync::Mutex<()>,
+    client: reqwest::Client,
+}
+
+impl Default for Feed {
+    fn default() -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(FETCH_TIMEOUT)
+            .user_agent(concat!("PhishingGuard/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .unwrap_or_default();
+        Self {
+            index: RwLock::new(None),
+            status: Mutex::new(FeedStatus::default()),
+            applied: Mutex::new(None),
+            wake: Notify::new(),
+            syncing: tokio::sync::Mutex::new(()),
+            client,
+        }
+    }
+}
+
+impl Feed {
+    /// A synthetic phishing result for `url` if its host is in the feed
+    pub fn verdict(&self, url: &str) -> Option<ScanResult> {
+        let index = self.index.read().ok()?;
+        let index = index.as_ref()?;
+        let domain = index.matches(url)?;
+        let explanation = match &index.version {
+            Some(version) => format!("{} is in the phishing feed (version {})", domain, version),
+            None => format!("{} is in the phishing feed", domain),
+        };
+        Some(ScanResult {
+            url: url.to_string(),
+            classification: "phishing".to_string(),
+            confidence: 1.0,
+            risk_score: 100,
+            explanation,
+            analysis_mode: Some("feed".to_string()),
+            features: FeatureSet::default(),
+            cached: false,
+            scanned_at: Some(now_secs()),
+            red_flags: None,
+            degraded: false,
+            redirects: None,
+            duration_ms: None,
+        })
+    }
+
+    pub fn status(&self) -> FeedStatus {
+        self.status.lock().map(|s| s.clone()).unwrap_or_default()
+    }
+
+    fn use_index(&self, index: Option<FeedIndex>) {
+        if let Ok(mut current) = self.index.write() {
+            *current = index;
+        }
+    }
+}
+
+fn data_dir(app: &AppHandle) -> Result<PathBuf, AppError> {
+    app.path()
+        .app_data_dir()
+        .map_err(|e| AppError::Io(format!("no app data directory: {}", e)))
+}
+
+/// Replace `path` without ever leaving a half-written file behind
+fn write_atomically(path: &Path, contents: &[u8]) -> Result<(), AppError> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let temp = path.with_extension("json.tmp");
+    std::fs::write(&temp, contents)?;
+    std::fs::rename(&temp, path)?;
+    Ok(())
+}
+
+fn save_status(app: &AppHandle, status: &FeedStatus) {
+    let saved = data_dir(app).and_then(|dir| {
+        write_atomically(&dir.join(STATUS_FILE), &serde_json::to_vec_pretty(status)?)
+    });
+    if let Err(e) = saved {
+        tracing::warn!(error = %e, "could not save the feed status");
+    }
+}
+
+/// Load the copy stored by the last successful sync, if it came from the
+/// feed URL configured now
+fn load_stored(app: &AppHandle, url: &str) {
+    let Ok(dir) = data_dir(app) else {
+        return;
+    };
+    let status: FeedStatus = std::fs::read(dir.join(STATUS_FILE))
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_default();
+    if status.url.as_deref() != Some(url) {
+        return;
+    }
+    let feed = app.state::<Feed>();
+    match std::fs::read(dir.join(FEED_FILE))
+        .map_err(AppError::from)
+        .and_then(|body| FeedIndex::parse(&body))
+    {
+        Ok(index) => {
+            tracing::info!(
+                entries = index.domains.len(),
+                "loaded the stored phishing feed"
+            );
+            feed.use_index(Some(index));
+            if let Ok(mut current) = feed.status.lock() {
+                *current = status;
+            }
+        }
+        Err(e) => tracing::warn!(error = %e, "stored phishing feed not loaded"),
+    }
+}
+
+enum Fetched {
+    NotModified,
+    Changed {
+        body: Vec<u8>,
+        etag: Option<String>,
+        last_modified: Option<String>,
+    },
+}
+
+async fn fetch(feed: &Feed, url: &str, status: &FeedStatus) -> Result<Fetched, AppError> {
+    let failed = |e: reqwest::Error| AppError::Io(format!("could not download the feed: {}", e));
+    let mut request = feed.client.get(url);
+    // Validators only mean something to the server they came from
+    if status.url.as_deref() == Some(url) {
+        if let Some(etag) = &status.etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &status.last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified);
+        }
+    }
+    let response = request.send().await.map_err(failed)?;
+    if response.status() == StatusCode::NOT_MODIFIED {
+        return Ok(Fetched::NotModified);
+    }
+    if !response.status().is_success() {
+        return Err(AppError::Io(format!(
+            "the feed server answered {}",
+            response.status()
+        )));
+    }
+    if response
+        .content_length()
+        .is_some_and(|length| length > MAX_FEED_BYTES as u64)
+    {
+        return Err(AppError::Io("the feed is too large".into()));
+    }
+    let header = |name: HeaderName| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+    };
+    let (etag, last_modified) = (header(ETAG), header(LAST_MODIFIED));
+    let body = response.bytes().await.map_err(failed)?;
+    if body.len() > MAX_FEED_BYTES {
+        return Err(AppError::Io("the feed is too large".into()));
+    }
+    Ok(Fetched::Changed {
+        body: body.to_vec(),
+        etag,
+        last_modified,
+    })
+}
+
+/// Check the feed server once, replacing the stored copy only with a feed
+/// that parses; anything else keeps the previous copy in use
+async fn sync(app: &AppHandle) -> Result<FeedStatus, AppError> {
+    let feed = app.state::<Feed>();
+    let _syncing = feed.syncing.lock().await;
+    let Some(url) = app.state::<Settings>().get().feed.url else {
+        return Err(AppError::InvalidInput("no feed URL is configured".into()));
+    };
+    let mut status = feed.status();
+    status.checked_at = Some(now_secs());
+
+    let outcome = match fetch(&feed, &url, &status).await {
+        Ok(Fetched::NotModified) => Ok(()),
+        Ok(Fetched::Changed {
+            body,
+            etag,
+            last_modified,
+        }) => FeedIndex::parse(&body).and_then(|index| {
+            write_atomically(&data_dir(app)?.join(FEED_FILE), &body)?;
+            status.url = Some(url.clone());
+            status.version = index.version.clone();
+            status.entries = index.domains.len();
+            status.synced_at = status.checked_at;
+            status.etag = etag;
+            status.last_modified = last_modified;
+            tracing::info!(entries = status.entries, version = ?status.version, "phishing feed updated");
+            feed.use_index(Some(index));
+            Ok(())
+        }),
+        Err(e) => Err(e),
+    };
+    status.last_error = outcome.as_ref().err().map(|e| e.to_string());
+    save_status(app, &status);
+    if let Ok(mut current) = feed.status.lock() {
+        *current = status.clone();
+    }
+    outcome.map(|_| status)
+}
+
+/// Keep the feed in sync for as long as the app runs, checking every
+/// `FeedSettings::interval_secs` and whenever the feed settings change
+pub async fn watch(app: AppHandle) {
+    let feed = app.state::<Feed>();
+    if let Some(url) = app.state::<Settings>().get().feed.url {
+        load_stored(&app, &url);
+    }
+    loop {
+        let settings = app.state::<Settings>().get().feed;
+        if settings.url.is_some() {
+            if let Err(e) = sync(&app).await {
+                tracing::warn!(error = %e, "phishing feed sync failed");
+            }
+        }
+        let interval = Duration::from_secs(settings.interval_secs);
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => {}
+            _ = feed.wake.notified() => {}
+        }
+    }
+}
+
+/// Resync when the feed settings change; turning the feed off stops its
+/// verdicts straight away
+pub fn apply(app: &AppHandle, settings: &FeedSettings) {
+    let feed = app.state::<Feed>();
+    let Ok(mut applied) = feed.applied.lock() else {
+        return;
+    };
+    if applied.as_ref() == Some(settings) {
+        return;
+    }
+    // The first call is at startup, before the loop has loaded anything
+    let first = applied.is_none();
+    *applied = Some(settings.clone());
+    if settings.url != feed.status().url && !first {
+        feed.use_index(None);
+    }
+    feed.wake.notify_one();
+}
+
+/// Keep a feed verdict in history under the feed's own source
+pub fn record(app: &AppHandle, job: &ScanJob, result: &ScanResult) {
+    let (app, result) = (app.clone(), result.clone());
+    let job = ScanJob {
+        source: ScanSource::Feed,
+        ..job.clone()
+    };
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = app.state::<ScanHistory>().record(&result, &job).await {
+            tracing::warn!(error = %e, "could not record a feed verdict");
+        }
+        tray::refresh_menu(&app).await;
+        tray::refresh_stats(&app).await;
+    });
+}
+
+#[tauri::command]
+pub fn get_feed_status(feed: State<'_, Feed>) -> FeedStatus {
+    feed.status()
+}
+
+/// Check the feed server now instead of waiting for the next interval
+#[tauri::command]
+pub async fn force_feed_sync(app: AppHandle) -> Result<FeedStatus, AppError> {
+    sync(&app).await
+}
@@ -0,0 +1,189 @@
+use crate::batch::{run_batch, BatchScanItem};
+use crate::email::parse_email;
+use crate::error::AppError;
+use crate::import::parse_url_list;
+use crate::lifecycle::ScanSource;
+use crate::links::{dedupe_links, extract_html_links, is_scannable_url};
+use crate::qr::decode_qr_codes;
+use crate::AppHandle;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tauri::Emitter;
+use tokio::io::AsyncReadExt;
+
+/// Larger files are not attachments anyone opens in a browser
+pub const MAX_FILE_BYTES: u64 = 10 * 1024 * 1024;
+
+/// What a dropped file contributed to the scan
+#[derive(Serialize, Debug, Clone)]
+pub struct DroppedFile {
+    path: String,
+    kind: &'static str,
+    urls: usize,
+    warnings: Vec<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+struct UnsupportedDrop {
+    path: String,
+    extension: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+struct DropScanReport {
+    files: Vec<DroppedFile>,
+    items: Vec<BatchScanItem>,
+}
+
+fn extension(path: &Path) -> Option<String> {
+    path.extension()
+        .map(|ext| ext.to_string_lossy().to_ascii_lowercase())
+}
+
+pub fn too_large(bytes: u64) -> AppError {
+    AppError::InvalidInput(format!(
+        "the file is {} MB; at most {} MB can be scanned",
+        bytes / (1024 * 1024),
+        MAX_FILE_BYTES / (1024 * 1024)
+    ))
+}
+
+/// Read `path` whole, refusing files over `MAX_FILE_BYTES`, including one
+/// that grows past it while being read
+pub async fn read_file(path: &Path) -> Result<Vec<u8>, AppError> {
+    let file = tokio::fs::File::open(path).await?;
+    let len = file.metadata().await?.len();
+    if len > MAX_FILE_BYTES {
+        return Err(too_large(len));
+    }
+    let mut bytes = Vec::with_capacity(len as usize);
+    file.take(MAX_FILE_BYTES + 1)
+        .read_to_end(&mut bytes)
+        .await?;
+    if bytes.len() as u64 > MAX_FILE_BYTES {
+        return Err(too_large(bytes.len() as u64));
+    }
+    Ok(bytes)
+}
+
+/// Collect the URLs a single dropped file contains, or None if the type is unsupported
+pub async fn urls_from_file(path: &Path) -> Option<(DroppedFile, Vec<String>)> {
+    let ext = extension(path);
+    let kind = match ext.as_deref() {
+        Some("txt" | "csv") => "url_list",
+        Some("eml") => "email",
+        Some("html" | "htm") => "html",
+        Some("png" | "jpg" | "jpeg") => "qr_image",
+        _ => return None,
+    };
+
+    let mut warnings = Vec::new();
+    let urls = match read_file(path).await {
+        Ok(bytes) => match kind {
+            "url_list" => {
+                let (urls, skipped) = parse_url_list(&String::from_utf8_lossy(&bytes));
+                if !skipped.is_empty() {
+                    warnings.push(format!("{} lines were not URLs", skipped.len()));
+                }
+                urls
+            }
+            "qr_image" => match decode_qr_codes(bytes).await {
+                Ok(payloads) => payloads
+                    .into_iter()
+                    .map(|p| p.trim().to_string())
+                    .filter(|p| is_scannable_url(p))
+                    .collect(),
+                Err(e) => {
+                    warnings.push(e.to_string());
+                    Vec::new()
+                }
+            },
+            "email" => {
+                let (_, links, email_warnings) = parse_email(&bytes);
+                warnings.extend(email_warnings);
+                links.into_iter().map(|link| link.url).collect()
+            }
+            _ => dedupe_links(extract_html_links(&String::from_utf8_lossy(&bytes)))
+                .into_iter()
+                .map(|link| link.url)
+                .collect(),
+        },
+        Err(e) => {
+            warnings.push(e.to_string());
+            Vec::new()
+        }
+    };
+
+    let file = DroppedFile {
+        path: path.to_string_lossy().to_string(),
+        kind,
+        urls: urls.len(),
+        warnings,
+    };
+    Some((file, urls))
+}
+
+/// Scan everything in a set of dropped files as one batch
+pub async fn handle_drop(app: AppHandle, paths: Vec<PathBuf>) {
+    let mut files = Vec::new();
+    let mut urls = Vec::new();
+
+    for path in &paths {
+        match urls_from_file(path).await {
+            Some((file, file_urls)) => {
+                files.push(file);
+                urls.extend(file_urls);
+            }
+            None => {
+                let _ = app.emit(
+                    "unsupported-drop",
+                    UnsupportedDrop {
+                        path: path.to_string_lossy().to_string(),
+                        extension: extension(path),
+                    },
+                );
+            }
+        }
+    }
+
+    if files.is_empty() {
+        return;
+    }
+    let _ = app.emit("drop-scan-started", &files);
+
+    let items = run_batch(&app, urls, ScanSource::Drop).await;
+
+    let _ = app.emit("drop-scan-completed", DropScanReport { files, items });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn files_over_the_limit_are_not_read() {
+        let dir = std::env::temp_dir().join(format!("drop-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let list = dir.join("links.txt");
+        std::fs::write(&list, "https://example.com/a\nnot a url\n").unwrap();
+        let huge = dir.join("huge.txt");
+        std::fs::File::create(&huge)
+            .unwrap()
+            .set_len(MAX_FILE_BYTES + 1)
+            .unwrap();
+
+        let (file, urls) = urls_from_file(&list).await.unwrap();
+        assert_eq!(urls, vec!["https://example.com/a".to_string()]);
+        assert_eq!(file.warnings, vec!["1 lines were not URLs".to_string()]);
+
+        assert!(matches!(
+            read_file(&huge).await,
+            Err(AppError::InvalidInput(_))
+        ));
+        let (file, urls) = urls_from_file(&huge).await.unwrap();
+        assert!(urls.is_empty());
+        assert_eq!(file.warnings.len(), 1);
+        assert!(file.warnings[0].contains("at most 10 MB"));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
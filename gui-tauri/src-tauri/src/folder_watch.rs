@@ -0,0 +1,241 @@
+use crate::error::AppError;
+use crate::file_drop::{urls_from_file, MAX_FILE_BYTES};
+use crate::lifecycle::{ScanJob, ScanSource};
+use crate::protection::is_paused;
+use crate::queue::ScanQueue;
+use crate::settings::{self, Settings};
+use crate::AppHandle;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+use tauri::async_runtime::JoinHandle;
+use tauri::{Emitter, Manager, State};
+use tokio::sync::mpsc;
+
+/// A file is scanned once it has gone this long without another event, so
+/// a download being written, renamed and touched is scanned once
+const DEBOUNCE: Duration = Duration::from_secs(2);
+/// How often settled files are looked for
+const TICK: Duration = Duration::from_millis(500);
+
+/// The watcher for the folders in `AppSettings::watched_folders`
+#[derive(Default)]
+pub struct FolderWatcher {
+    active: Mutex<Option<Watching>>,
+}
+
+struct Watching {
+    folders: Vec<PathBuf>,
+    /// Dropping it stops the OS notifications
+    _watcher: RecommendedWatcher,
+    task: JoinHandle<()>,
+}
+
+impl Drop for Watching {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct WatchedFolders {
+    folders: Vec<String>,
+    /// The user's Downloads folder, the usual one to watch
+    downloads: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+struct WatchedFileScan {
+    path: String,
+    urls: usize,
+}
+
+/// Only the types phishing arrives as; partial downloads (`.crdownload`,
+/// `.part`) never match, and neither do hidden or lock files
+fn is_candidate(path: &Path) -> bool {
+    let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+        return false;
+    };
+    if name.starts_with('.') || name.starts_with("~$") {
+        return false;
+    }
+    let extension = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_ascii_lowercase());
+    matches!(extension.as_deref(), Some("html" | "htm" | "eml" | "txt"))
+}
+
+/// Queue every link in a settled file, recording the file as their origin
+async fn scan_file(app: &AppHandle, path: &Path) {
+    let Some((_, urls)) = urls_from_file(path).await else {
+        return;
+    };
+    let origin = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string());
+    let mut unique: Vec<String> = Vec::new();
+    for url in urls {
+        if !unique.contains(&url) {
+            unique.push(url);
+        }
+    }
+    tracing::info!(file = ?origin, urls = unique.len(), "scanning watched file");
+    let _ = app.emit(
+        "watched-file-scanned",
+        WatchedFileScan {
+            path: path.to_string_lossy().to_string(),
+            urls: unique.len(),
+        },
+    );
+
+    // The queue notifies on phishing verdicts like for any other scan
+    let queue = app.state::<ScanQueue>();
+    for url in unique {
+        let queued = ScanJob::from_input(&url, ScanSource::Download)
+            .and_then(|job| queue.enqueue(app, job.with_origin(origin.clone())));
+        if let Err(e) = queued {
+            tracing::warn!(error = %e, "could not queue a watched file's link");
+        }
+    }
+}
+
+/// Collect file events and scan each file once it has settled
+async fn debounce(app: AppHandle, mut events: mpsc::UnboundedReceiver<PathBuf>) {
+    let mut pending: HashMap<PathBuf, Instant> = HashMap::new();
+    // Modified time each file was scanned at, so touching it doesn't rescan
+    let mut scanned: HashMap<PathBuf, SystemTime> = HashMap::new();
+    let mut tick = tokio::time::interval(TICK);
+    loop {
+        tokio::select! {
+            event = events.recv() => match event {
+                Some(path) => {
+                    pending.insert(path, Instant::now());
+                }
+                None => return,
+            },
+            _ = tick.tick() => {}
+        }
+
+        let settled: Vec<PathBuf> = pending
+            .iter()
+            .filter(|(_, last)| last.elapsed() >= DEBOUNCE)
+            .map(|(path, _)| path.clone())
+            .collect();
+        for path in settled {
+            pending.remove(&path);
+            // Paused protection drops what arrived meanwhile
+            if is_paused(&app) {
+                continue;
+            }
+            let Ok(metadata) = tokio::fs::metadata(&path).await else {
+                continue;
+            };
+            let modified = metadata.modified().ok();
+            if !metadata.is_file()
+                || metadata.len() == 0
+                || metadata.len() > MAX_FILE_BYTES
+                || modified.is_some_and(|m| scanned.get(&path) == Some(&m))
+            {
+                continue;
+            }
+            if let Some(modified) = modified {
+                scanned.insert(path.clone(), modified);
+            }
+            scan_file(&app, &path).await;
+        }
+    }
+}
+
+fn start(app: &AppHandle, folders: &[PathBuf]) -> Result<Watching, AppError> {
+    let (sender, events) = mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        let Ok(event) = event else {
+            return;
+        };
+        // Renames report the new name as a modification
+        if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+            for path in event.paths.into_iter().filter(|p| is_candidate(p)) {
+                let _ = sender.send(path);
+            }
+        }
+    })
+    .map_err(|e| AppError::Io(format!("could not watch folders: {}", e)))?;
+    for folder in folders {
+        watcher
+            .watch(folder, RecursiveMode::NonRecursive)
+            .map_err(|e| AppError::Io(format!("could not watch {}: {}", folder.display(), e)))?;
+    }
+    Ok(Watching {
+        folders: folders.to_vec(),
+        _watcher: watcher,
+        task: tauri::async_runtime::spawn(debounce(app.clone(), events)),
+    })
+}
+
+/// Watch exactly `folders`, stopping the watcher when there are none.
+/// Called whenever the settings are applied; unchanged folders keep the
+/// running watcher.
+pub fn apply(app: &AppHandle, folders: &[PathBuf]) {
+    let watcher = app.state::<FolderWatcher>();
+    let Ok(mut active) = watcher.active.lock() else {
+        return;
+    };
+    if active
+        .as_ref()
+        .map(|w| w.folders.as_slice())
+        .unwrap_or_default()
+        == folders
+    {
+        return;
+    }
+    *active = None;
+    if folders.is_empty() {
+        return;
+    }
+    match start(app, folders) {
+        Ok(watching) => *active = Some(watching),
+        // A folder that has gone away shouldn't stop the app from starting
+        Err(e) => tracing::warn!(error = %e, "folder watcher not started"),
+    }
+}
+
+#[tauri::command]
+pub fn get_watched_folders(settings: State<'_, Settings>) -> WatchedFolders {
+    WatchedFolders {
+        folders: settings
+            .get()
+            .watched_folders
+            .iter()
+            .map(|p| p.display().to_string())
+            .collect(),
+        downloads: dirs::download_dir().map(|p| p.display().to_string()),
+    }
+}
+
+/// Scan new .html, .eml and .txt files that appear in `paths`; an empty
+/// list turns the watcher off
+#[tauri::command]
+pub fn set_watched_folders(paths: Vec<String>, app: AppHandle) -> Result<WatchedFolders, AppError> {
+    let mut folders: Vec<PathBuf> = Vec::new();
+    for path in paths.iter().map(|p| p.trim()).filter(|p| !p.is_empty()) {
+        let folder = PathBuf::from(path);
+        if !folder.is_dir() {
+            return Err(AppError::InvalidInput(format!("{} is not a folder", path)));
+        }
+        if !folders.contains(&folder) {
+            folders.push(folder);
+        }
+    }
+    // Applying the saved settings restarts the watcher
+    settings::update(&app, |s| s.watched_folders = folders.clone())?;
+    let watching = app.state::<FolderWatcher>().active.lock()?.is_some();
+    if !folders.is_empty() && !watching {
+        return Err(AppError::Io(
+            "the folders were saved but could not be watched; see the log".into(),
+        ));
+    }
+    Ok(get_watched_folders(app.state::<Settings>()))
+}
@@ -0,0 +1,170 @@
+use crate::notifications::notify;
+use crate::AppHandle;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::Manager;
+
+/// Span the rate cap is counted over
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// Limits a governor enforces, from the notification settings
+#[derive(Debug, Clone, Copy)]
+pub struct Limits {
+    /// Toasts allowed per minute before the rest are summarized
+    pub per_minute: usize,
+    /// Repeats for the same URL within this span are dropped
+    pub duplicate_window: Duration,
+}
+
+/// What to do with a notification about to be shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    Show,
+    /// The same URL was announced moments ago
+    Duplicate,
+    /// Over the rate cap; it is counted towards the next summary
+    Overflow,
+}
+
+/// Decides whether notifications are shown, kept apart from the OS so the
+/// counting can be exercised on its own
+pub trait NotifyPolicy: Send {
+    /// Whether a notification about `url` may be shown at `now`
+    fn decide(&mut self, url: &str, now: Instant, limits: &Limits) -> Decision;
+    /// When the rate cap next has room, if anything is waiting to be summarized
+    fn summary_due(&self, limits: &Limits) -> Option<Instant>;
+    /// Number of notifications dropped for the cap since the last call
+    fn take_overflow(&mut self) -> usize;
+}
+
+/// Sliding-window rate cap with per-URL deduplication
+#[derive(Default)]
+pub struct RateLimiter {
+    last_seen: HashMap<String, Instant>,
+    shown: VecDeque<Instant>,
+    overflow: usize,
+}
+
+impl NotifyPolicy for RateLimiter {
+    fn decide(&mut self, url: &str, now: Instant, limits: &Limits) -> Decision {
+        self.last_seen
+            .retain(|_, seen| now.duration_since(*seen) < limits.duplicate_window);
+        while self
+            .shown
+            .front()
+            .is_some_and(|shown| now.duration_since(*shown) >= RATE_WINDOW)
+        {
+            self.shown.pop_front();
+        }
+
+        if self.last_seen.contains_key(url) {
+            return Decision::Duplicate;
+        }
+        self.last_seen.insert(url.to_string(), now);
+        if self.shown.len() >= limits.per_minute {
+            self.overflow += 1;
+            return Decision::Overflow;
+        }
+        self.shown.push_back(now);
+        Decision::Show
+    }
+
+    fn summary_due(&self, limits: &Limits) -> Option<Instant> {
+        if self.overflow == 0 {
+            return None;
+        }
+        let oldest = self.shown.len().checked_sub(limits.per_minute)?;
+        self.shown.get(oldest).map(|shown| *shown + RATE_WINDOW)
+    }
+
+    fn take_overflow(&mut self) -> usize {
+        std::mem::take(&mut self.overflow)
+    }
+}
+
+struct GovernorState {
+    policy: Box<dyn NotifyPolicy>,
+    /// A summary task is already waiting for room under the cap
+    summary_scheduled: bool,
+}
+
+/// Keeps bursts of results (a large import, a clipboard manager replaying
+/// history) from flooding the desktop and getting the app muted
+pub struct NotificationGovernor {
+    state: Mutex<GovernorState>,
+}
+
+impl Default for NotificationGovernor {
+    fn default() -> Self {
+        Self::new(Box::<RateLimiter>::default())
+    }
+}
+
+impl NotificationGovernor {
+    pub fn new(policy: Box<dyn NotifyPolicy>) -> Self {
+        Self {
+            state: Mutex::new(GovernorState {
+                policy,
+                summary_scheduled: false,
+            }),
+        }
+    }
+
+    /// Whether a notification about `url` should be shown now. Ones over the
+    /// cap are announced together by a summary once there is room again.
+    pub fn admit(&self, app: &AppHandle, url: &str, limits: Limits) -> bool {
+        let Ok(mut state) = self.state.lock() else {
+            return true;
+        };
+        match state.policy.decide(url, Instant::now(), &limits) {
+            Decision::Show => true,
+            Decision::Duplicate => false,
+            Decision::Overflow => {
+                if !state.summary_scheduled {
+                    state.summary_scheduled = true;
+                    let app = app.clone();
+                    tauri::async_runtime::spawn(summarize(app, limits));
+                }
+                false
+            }
+        }
+    }
+}
+
+/// Wait for room under the cap, then show one notification for everything
+/// dropped meanwhile
+async fn summarize(app: AppHandle, limits: Limits) {
+    loop {
+        let due = {
+            let governor = app.state::<NotificationGovernor>();
+            let Ok(state) = governor.state.lock() else {
+                return;
+            };
+            state.policy.summary_due(&limits)
+        };
+        match due {
+            Some(due) if due > Instant::now() => {
+                tokio::time::sleep_until(due.into()).await;
+            }
+            _ => break,
+        }
+    }
+
+    let dropped = {
+        let governor = app.state::<NotificationGovernor>();
+        let Ok(mut state) = governor.state.lock() else {
+            return;
+        };
+        state.summary_scheduled = false;
+        state.policy.take_overflow()
+    };
+    if dropped > 0 {
+        let noun = if dropped == 1 { "result" } else { "results" };
+        notify(
+            &app,
+            &format!("{} more {}", dropped, noun),
+            "Open Phishing Guard to review them.",
+        );
+    }
+}
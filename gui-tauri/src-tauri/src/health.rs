@@ -0,0 +1,301 @@
+use crate::error::AppError;
+use crate::history::now_secs;
+use crate::mock;
+use crate::perf::{self, Operation};
+use crate::{env_timeout, packages_ready, run_python, AppHandle, AppState};
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+use tauri::{Emitter, Manager, State};
+use tokio::process::Command;
+use tokio::sync::watch;
+
+/// Default time between environment checks while the detector is healthy
+const DEFAULT_INTERVAL_SECS: u64 = 30;
+/// Longest wait between checks while the detector is down
+const MAX_BACKOFF: Duration = Duration::from_secs(120);
+/// Granularity at which a sleeping poller looks for a suspend/resume
+const NAP_SLICE: Duration = Duration::from_secs(5);
+/// Wall-clock time running ahead of the monotonic clock by more than this
+/// means the machine was asleep
+const SUSPEND_GAP: Duration = Duration::from_secs(10);
+
+/// Whether the detector can run
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthState {
+    /// python3 runs and the detector's packages import
+    Up,
+    /// python3 runs but required packages are missing
+    Degraded,
+    /// python3 could not be started
+    Down,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct HealthStatus {
+    pub state: HealthState,
+    python_version: Option<String>,
+    packages_installed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<AppError>,
+    /// Unix seconds
+    checked_at: i64,
+}
+
+#[derive(Serialize, Clone)]
+struct StatusChange<'a> {
+    previous: Option<HealthState>,
+    status: &'a HealthStatus,
+}
+
+/// Latest result of the background environment check
+pub struct HealthMonitor {
+    latest: watch::Sender<Option<HealthStatus>>,
+    /// Seconds between checks while the detector is reachable
+    interval_secs: AtomicU64,
+}
+
+impl Default for HealthMonitor {
+    fn default() -> Self {
+        Self {
+            latest: watch::channel(None).0,
+            interval_secs: AtomicU64::new(default_interval_secs()),
+        }
+    }
+}
+
+fn default_interval_secs() -> u64 {
+    env_timeout("PHISHING_GUARD_HEALTH_INTERVAL_SECS", DEFAULT_INTERVAL_SECS)
+}
+
+impl HealthMonitor {
+    fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_secs.load(Ordering::Relaxed))
+    }
+
+    /// Use `secs` between checks from the next one on, or the default when `None`
+    pub fn set_interval(&self, secs: Option<u64>) {
+        let secs = secs.unwrap_or_else(default_interval_secs);
+        self.interval_secs.store(secs, Ordering::Relaxed);
+    }
+
+    pub fn latest(&self) -> Option<HealthStatus> {
+        self.latest.borrow().clone()
+    }
+
+    /// Receiver that is notified after every check
+    pub fn subscribe(&self) -> watch::Receiver<Option<HealthStatus>> {
+        self.latest.subscribe()
+    }
+}
+
+async fn probe(timeout_secs: u64) -> HealthStatus {
+    let mut version_cmd = Command::new("python3");
+    version_cmd.arg("--version");
+    let (state, python_version, packages_installed, error) =
+        match run_python(version_cmd, timeout_secs).await {
+            Err(e) => (HealthState::Down, None, false, Some(e)),
+            Ok(output) => {
+                let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
+                if packages_ready(timeout_secs).await {
+                    (HealthState::Up, Some(version), true, None)
+                } else {
+                    (HealthState::Degraded, Some(version), false, None)
+                }
+            }
+        };
+
+    HealthStatus {
+        state,
+        python_version,
+        packages_installed,
+        error,
+        checked_at: now_secs(),
+    }
+}
+
+/// Mock mode never needs python3, so it is always up
+fn mock_status() -> HealthStatus {
+    HealthStatus {
+        state: HealthState::Up,
+        python_version: Some("mock".to_string()),
+        packages_installed: true,
+        error: None,
+        checked_at: now_secs(),
+    }
+}
+
+/// Sleep for `delay`, waking early if the machine was suspended meanwhile.
+///
+/// Tokio's timers follow the monotonic clock, which stops during suspend on
+/// Linux and macOS, so the poller is naturally paused while asleep; the
+/// wall-clock comparison makes it check again as soon as the machine resumes
+/// instead of finishing an interrupted backoff.
+async fn nap(delay: Duration) {
+    let (wall, start) = (SystemTime::now(), Instant::now());
+    while start.elapsed() < delay {
+        tokio::time::sleep(NAP_SLICE.min(delay.saturating_sub(start.elapsed()))).await;
+        if wall.elapsed().unwrap_or_default() > start.elapsed() + SUSPEND_GAP {
+            return;
+        }
+    }
+}
+
+/// Check the environment now, storing the result as the latest status and
+/// emitting `api-status-changed` if the state moved
+async fn check(app: &AppHandle) -> HealthStatus {
+    let timeout_secs = app
+        .state::<Mutex<AppState>>()
+        .lock()
+        .map(|state| state.env_check_timeout_secs)
+        .unwrap_or(crate::DEFAULT_ENV_CHECK_TIMEOUT_SECS);
+    let status = if mock::enabled(app) {
+        mock_status()
+    } else {
+        let started = Instant::now();
+        let status = probe(timeout_secs).await;
+        perf::record(app, Operation::EnvironmentCheck, started);
+        status
+    };
+
+    let previous = app
+        .state::<HealthMonitor>()
+        .latest
+        .send_replace(Some(status.clone()))
+        .map(|s| s.state);
+    if previous != Some(status.state) {
+        let _ = app.emit(
+            "api-status-changed",
+            StatusChange {
+                previous,
+                status: &status,
+            },
+        );
+    }
+    status
+}
+
+/// Background task that checks the environment on an interval, backing off
+/// while it is down, and emits `api-status-changed` on every transition
+pub async fn poll(app: AppHandle) {
+    let monitor = app.state::<HealthMonitor>();
+    let mut delay = monitor.interval();
+
+    loop {
+        let state = check(&app).await.state;
+        delay = if state == HealthState::Down {
+            (delay * 2).min(MAX_BACKOFF.max(monitor.interval()))
+        } else {
+            monitor.interval()
+        };
+        nap(delay).await;
+    }
+}
+
+/// Check whether python3 and the detector's packages are available, the
+/// same way the background poller does, and share the result with it
+#[tauri::command]
+pub async fn check_environment(app: AppHandle) -> HealthStatus {
+    check(&app).await
+}
+
+/// Result of the most recent background environment check, without running
+/// a new one; absent until the first check completes
+#[tauri::command]
+pub fn get_last_health_status(monitor: State<'_, HealthMonitor>) -> Option<HealthStatus> {
+    monitor.latest()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn payload(status: &HealthStatus) -> serde_json::Value {
+        let text = serde_json::to_string(status).unwrap();
+        serde_json::from_str(&text).unwrap()
+    }
+
+    #[test]
+    fn up_payload() {
+        let status = HealthStatus {
+            state: HealthState::Up,
+            python_version: Some("Python 3.11.4".into()),
+            packages_installed: true,
+            error: None,
+            checked_at: 1_700_000_000,
+        };
+        assert_eq!(
+            payload(&status),
+            json!({
+                "state": "up",
+                "python_version": "Python 3.11.4",
+                "packages_installed": true,
+                "checked_at": 1_700_000_000
+            })
+        );
+    }
+
+    #[test]
+    fn degraded_payload() {
+        let status = HealthStatus {
+            state: HealthState::Degraded,
+            python_version: Some("Python 3.8.10".into()),
+            packages_installed: false,
+            error: None,
+            checked_at: 1_700_000_030,
+        };
+        assert_eq!(
+            payload(&status),
+            json!({
+                "state": "degraded",
+                "python_version": "Python 3.8.10",
+                "packages_installed": false,
+                "checked_at": 1_700_000_030
+            })
+        );
+    }
+
+    #[test]
+    fn down_payload() {
+        // App.jsx shows the error screen on `state === 'down'` and reads
+        // `error.message` for the reason
+        let status = HealthStatus {
+            state: HealthState::Down,
+            python_version: None,
+            packages_installed: false,
+            error: Some(AppError::PythonUnavailable(
+                "No such file or directory".into(),
+            )),
+            checked_at: 1_700_000_060,
+        };
+        assert_eq!(
+            payload(&status),
+            json!({
+                "state": "down",
+                "python_version": null,
+                "packages_installed": false,
+                "error": {
+                    "kind": "python_unavailable",
+                    "message": "Failed to execute Python: No such file or directory"
+                },
+                "checked_at": 1_700_000_060
+            })
+        );
+    }
+
+    #[test]
+    fn status_change_carries_both_states() {
+        let status = mock_status();
+        let change = StatusChange {
+            previous: Some(HealthState::Down),
+            status: &status,
+        };
+        let value = serde_json::to_value(&change).unwrap();
+        assert_eq!(value["previous"], "down");
+        assert_eq!(value["status"]["state"], "up");
+        assert_eq!(value["status"]["python_version"], "mock");
+    }
+}
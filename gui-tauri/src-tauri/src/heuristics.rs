@@ -0,0 +1,316 @@
+use crate::error::AppError;
+use crate::links::normalize_input;
+use crate::settings::Settings;
+use crate::AppHandle;
+use crate::ScanResult;
+use serde::{Deserialize, Serialize};
+use tauri::{Manager, State};
+use url::{Host, Url};
+
+/// More labels than this (`a.b.c.example.com` has five) hides the real
+/// domain from anyone reading the start of the URL
+const MAX_HOST_LABELS: usize = 4;
+/// Longer URLs are usually padding meant to push the real host off screen
+const MAX_URL_LENGTH: usize = 120;
+/// Cheap to register and disproportionately used for phishing
+const SUSPICIOUS_TLDS: &[&str] = &[
+    "zip", "mov", "tk", "ml", "ga", "cf", "gq", "xyz", "top", "click", "country", "kim", "work",
+    "support", "rest", "fit", "loan", "cam", "icu",
+];
+/// Link shorteners, which hide the destination until followed
+const SHORTENERS: &[&str] = &[
+    "bit.ly",
+    "tinyurl.com",
+    "t.co",
+    "goo.gl",
+    "ow.ly",
+    "is.gd",
+    "buff.ly",
+    "cutt.ly",
+    "rebrand.ly",
+    "shorturl.at",
+    "tiny.cc",
+    "t.ly",
+    "rb.gy",
+];
+
+/// A cheap check of the URL text alone, run before the detector answers
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Heuristic {
+    IpHost,
+    DeepSubdomains,
+    Punycode,
+    CredentialsInUrl,
+    SuspiciousTld,
+    LongUrl,
+    Shortener,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Low,
+    Medium,
+    High,
+}
+
+impl Severity {
+    fn weight(self) -> u32 {
+        match self {
+            Severity::Low => 10,
+            Severity::Medium => 25,
+            Severity::High => 40,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RedFlag {
+    pub heuristic: Heuristic,
+    pub severity: Severity,
+    pub detail: String,
+}
+
+/// The red flags a URL raises, with a 0-100 local score
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct HeuristicReport {
+    pub flags: Vec<RedFlag>,
+    pub score: u32,
+}
+
+fn flag(heuristic: Heuristic, severity: Severity, detail: String) -> RedFlag {
+    RedFlag {
+        heuristic,
+        severity,
+        detail,
+    }
+}
+
+/// The flag `heuristic` raises for `url`, if any
+fn evaluate(heuristic: Heuristic, raw: &str, url: &Url) -> Option<RedFlag> {
+    let host = url.host_str().unwrap_or_default();
+    match heuristic {
+        Heuristic::IpHost => matches!(url.host(), Some(Host::Ipv4(_) | Host::Ipv6(_))).then(|| {
+            flag(
+                heuristic,
+                Severity::High,
+                format!("the host is the IP address {}", host),
+            )
+        }),
+        Heuristic::DeepSubdomains => {
+            let labels = host.split('.').count();
+            (url.domain().is_some() && labels > MAX_HOST_LABELS).then(|| {
+                flag(
+                    heuristic,
+                    Severity::Medium,
+                    format!("the host has {} levels of subdomain", labels - 2),
+                )
+            })
+        }
+        Heuristic::Punycode => host
+            .split('.')
+            .any(|label| label.starts_with("xn--"))
+            .then(|| {
+                flag(
+                    heuristic,
+                    Severity::High,
+                    "the host uses punycode, which can imitate another name".into(),
+                )
+            }),
+        Heuristic::CredentialsInUrl => (!url.username().is_empty() || url.password().is_some())
+            .then(|| {
+                flag(
+                    heuristic,
+                    Severity::High,
+                    format!("the text before @ hides that the real host is {}", host),
+                )
+            }),
+        Heuristic::SuspiciousTld => {
+            let tld = host.rsplit('.').next().unwrap_or_default();
+            (url.domain().is_some() && SUSPICIOUS_TLDS.contains(&tld)).then(|| {
+                flag(
+                    heuristic,
+                    Severity::Low,
+                    format!(".{} domains are often used for phishing", tld),
+                )
+            })
+        }
+        Heuristic::LongUrl => (raw.len() > MAX_URL_LENGTH).then(|| {
+            flag(
+                heuristic,
+                Severity::Low,
+                format!("the URL is {} characters long", raw.len()),
+            )
+        }),
+        Heuristic::Shortener => {
+            let bare = host.strip_prefix("www.").unwrap_or(host);
+            SHORTENERS.contains(&bare).then(|| {
+                flag(
+                    heuristic,
+                    Severity::Medium,
+                    format!("{} is a link shortener that hides the destination", bare),
+                )
+            })
+        }
+    }
+}
+
+const ALL: [Heuristic; 7] = [
+    Heuristic::IpHost,
+    Heuristic::DeepSubdomains,
+    Heuristic::Punycode,
+    Heuristic::CredentialsInUrl,
+    Heuristic::SuspiciousTld,
+    Heuristic::LongUrl,
+    Heuristic::Shortener,
+];
+
+/// Run every heuristic not in `disabled` against `url`
+pub fn check(url: &str, disabled: &[Heuristic]) -> HeuristicReport {
+    let Ok(parsed) = Url::parse(url) else {
+        return HeuristicReport::default();
+    };
+    let flags: Vec<RedFlag> = ALL
+        .iter()
+        .filter(|heuristic| !disabled.contains(heuristic))
+        .filter_map(|heuristic| evaluate(*heuristic, url, &parsed))
+        .collect();
+    let score = flags
+        .iter()
+        .map(|flag| flag.severity.weight())
+        .sum::<u32>()
+        .min(100);
+    HeuristicReport { flags, score }
+}
+
+/// `check` with the heuristics turned off in the settings left out
+pub fn check_enabled(app: &AppHandle, url: &str) -> HeuristicReport {
+    let disabled = app
+        .try_state::<Settings>()
+        .map(|settings| settings.get().disabled_heuristics)
+        .unwrap_or_default();
+    check(url, &disabled)
+}
+
+/// Attach the local red flags to a verdict
+pub fn annotate(app: &AppHandle, result: &mut ScanResult) {
+    result.red_flags = Some(check_enabled(app, &result.url));
+}
+
+/// The local red flags for `url`, without running the detector
+#[tauri::command]
+pub fn heuristic_check(
+    url: String,
+    settings: State<'_, Settings>,
+) -> Result<HeuristicReport, AppError> {
+    let url = normalize_input(&url)?;
+    Ok(check(&url, &settings.get().disabled_heuristics))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::AppSettings;
+
+    fn raised(url: &str) -> Vec<Heuristic> {
+        check(url, &[])
+            .flags
+            .into_iter()
+            .map(|flag| flag.heuristic)
+            .collect()
+    }
+
+    /// Each rule with a URL that raises it and a near miss that doesn't
+    const CASES: &[(Heuristic, &str, &str)] = &[
+        (
+            Heuristic::IpHost,
+            "http://192.168.4.20/login",
+            "https://example.com/login",
+        ),
+        (
+            Heuristic::DeepSubdomains,
+            "https://secure.login.account.example.com/",
+            "https://login.account.example.com/",
+        ),
+        (
+            Heuristic::Punycode,
+            "https://xn--pypal-4ve.com/",
+            "https://paypal.com/",
+        ),
+        (
+            Heuristic::CredentialsInUrl,
+            "https://paypal.com@evil.example/",
+            "https://evil.example/paypal.com@",
+        ),
+        (
+            Heuristic::SuspiciousTld,
+            "https://account-update.zip/",
+            "https://account-update.com/",
+        ),
+        (
+            Heuristic::Shortener,
+            "https://www.bit.ly/3xYz",
+            "https://bitly.example/3xYz",
+        ),
+    ];
+
+    #[test]
+    fn each_rule_raises_only_on_its_pattern() {
+        for (heuristic, positive, negative) in CASES {
+            assert!(
+                raised(positive).contains(heuristic),
+                "{:?} on {}",
+                heuristic,
+                positive
+            );
+            assert!(
+                !raised(negative).contains(heuristic),
+                "{:?} on {}",
+                heuristic,
+                negative
+            );
+        }
+    }
+
+    #[test]
+    fn long_url() {
+        let short = format!("https://example.com/{}", "a".repeat(MAX_URL_LENGTH - 20));
+        let long = format!("{}a", short);
+        assert_eq!(short.len(), MAX_URL_LENGTH);
+        assert!(!raised(&short).contains(&Heuristic::LongUrl));
+        assert!(raised(&long).contains(&Heuristic::LongUrl));
+    }
+
+    #[test]
+    fn score_sums_severities_and_caps_at_100() {
+        assert_eq!(check("https://example.com/", &[]).score, 0);
+        assert_eq!(check("https://bit.ly/x", &[]).score, 25);
+        let url = format!(
+            "https://user@a.b.c.xn--e1afmkfd.zip/{}",
+            "x".repeat(MAX_URL_LENGTH)
+        );
+        assert_eq!(check(&url, &[]).score, 100);
+    }
+
+    #[test]
+    fn unparseable_input_raises_nothing() {
+        let report = check("not a url", &[]);
+        assert!(report.flags.is_empty());
+        assert_eq!(report.score, 0);
+    }
+
+    #[test]
+    fn disabled_heuristics_suppress_their_rule() {
+        let settings = AppSettings {
+            disabled_heuristics: vec![Heuristic::IpHost],
+            ..AppSettings::default()
+        };
+        let url = "http://192.168.4.20.xyz@192.168.4.20/";
+        let report = check(url, &settings.disabled_heuristics);
+        let flags: Vec<Heuristic> = report.flags.iter().map(|flag| flag.heuristic).collect();
+        assert_eq!(flags, vec![Heuristic::CredentialsInUrl]);
+        assert_eq!(report.score, Severity::High.weight());
+        assert!(raised(url).contains(&Heuristic::IpHost));
+    }
+}
@@ -0,0 +1,1003 @@
+use crate::domain_info::DomainInfo;
+use crate::error::AppError;
+use crate::features::FeatureSet;
+use crate::lifecycle::ScanJob;
+use crate::offline::FALLBACK_CLASSIFICATION;
+use crate::ScanResult;
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, Row, Rows};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::State;
+
+/// File name of the history database inside the app data directory
+pub const DATABASE_FILE: &str = "history.sqlite3";
+
+/// Schema changes applied in order; `PRAGMA user_version` records how many ran
+const MIGRATIONS: &[&str] = &[
+    "CREATE TABLE scans (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        url TEXT NOT NULL,
+        normalized_url TEXT NOT NULL,
+        classification TEXT NOT NULL,
+        confidence REAL NOT NULL,
+        risk_score INTEGER NOT NULL,
+        explanation TEXT NOT NULL,
+        features TEXT NOT NULL,
+        scanned_at INTEGER NOT NULL,
+        source TEXT NOT NULL
+    );
+    CREATE INDEX scans_scanned_at ON scans (scanned_at);
+    CREATE INDEX scans_normalized_url ON scans (normalized_url);",
+    "CREATE INDEX scans_classification ON scans (classification, scanned_at);
+    CREATE INDEX scans_risk_score ON scans (risk_score, scanned_at);",
+    "ALTER TABLE scans ADD COLUMN scan_id TEXT;
+    CREATE INDEX scans_scan_id ON scans (scan_id);",
+    "CREATE TABLE allowlist (
+        pattern TEXT PRIMARY KEY,
+        added_at INTEGER NOT NULL
+    );",
+    "CREATE TABLE blocklist (
+        pattern TEXT PRIMARY KEY,
+        added_at INTEGER NOT NULL
+    );",
+    "ALTER TABLE scans ADD COLUMN note TEXT;
+    CREATE TABLE scan_tags (
+        scan_row INTEGER NOT NULL REFERENCES scans (id) ON DELETE CASCADE,
+        tag TEXT NOT NULL,
+        PRIMARY KEY (scan_row, tag)
+    );
+    CREATE INDEX scan_tags_tag ON scan_tags (tag);",
+    "CREATE VIRTUAL TABLE scans_fts USING fts5(
+        url, explanation, note,
+        content = 'scans', content_rowid = 'id'
+    );
+    INSERT INTO scans_fts (scans_fts) VALUES ('rebuild');
+    CREATE TRIGGER scans_fts_insert AFTER INSERT ON scans BEGIN
+        INSERT INTO scans_fts (rowid, url, explanation, note)
+        VALUES (new.id, new.url, new.explanation, new.note);
+    END;
+    CREATE TRIGGER scans_fts_delete AFTER DELETE ON scans BEGIN
+        INSERT INTO scans_fts (scans_fts, rowid, url, explanation, note)
+        VALUES ('delete', old.id, old.url, old.explanation, old.note);
+    END;
+    CREATE TRIGGER scans_fts_update AFTER UPDATE ON scans BEGIN
+        INSERT INTO scans_fts (scans_fts, rowid, url, explanation, note)
+        VALUES ('delete', old.id, old.url, old.explanation, old.note);
+        INSERT INTO scans_fts (rowid, url, explanation, note)
+        VALUES (new.id, new.url, new.explanation, new.note);
+    END;",
+    "CREATE TABLE offline_scans (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        scan_id TEXT NOT NULL,
+        url TEXT NOT NULL,
+        normalized_url TEXT NOT NULL UNIQUE,
+        source TEXT NOT NULL,
+        force INTEGER NOT NULL,
+        status TEXT NOT NULL DEFAULT 'pending',
+        queued_at INTEGER NOT NULL
+    );",
+    "ALTER TABLE scans ADD COLUMN origin TEXT;",
+    "ALTER TABLE scans ADD COLUMN input TEXT;",
+    "ALTER TABLE scans ADD COLUMN rescan_of INTEGER REFERENCES scans (id) ON DELETE SET NULL;
+    CREATE INDEX scans_rescan_of ON scans (rescan_of);",
+    "CREATE TABLE watchlist (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        url TEXT NOT NULL,
+        normalized_url TEXT NOT NULL UNIQUE,
+        interval_secs INTEGER NOT NULL,
+        added_at INTEGER NOT NULL,
+        next_check_at INTEGER NOT NULL,
+        last_checked_at INTEGER,
+        last_classification TEXT,
+        last_risk_score INTEGER,
+        last_scan_id TEXT
+    );
+    CREATE INDEX watchlist_next_check_at ON watchlist (next_check_at);",
+    // Filled in by `backfill_domains` for rows stored before this
+    "ALTER TABLE scans ADD COLUMN domain TEXT;
+    CREATE INDEX scans_domain ON scans (domain, scanned_at);",
+    "CREATE TABLE domain_info (
+        domain TEXT PRIMARY KEY,
+        info TEXT NOT NULL,
+        fetched_at INTEGER NOT NULL
+    );",
+    // The search index is rebuilt to cover the new column
+    "ALTER TABLE scans ADD COLUMN redirected_from TEXT;
+    DROP TRIGGER scans_fts_insert;
+    DROP TRIGGER scans_fts_delete;
+    DROP TRIGGER scans_fts_update;
+    DROP TABLE scans_fts;
+    CREATE VIRTUAL TABLE scans_fts USING fts5(
+        url, explanation, note, redirected_from,
+        content = 'scans', content_rowid = 'id'
+    );
+    INSERT INTO scans_fts (scans_fts) VALUES ('rebuild');
+    CREATE TRIGGER scans_fts_insert AFTER INSERT ON scans BEGIN
+        INSERT INTO scans_fts (rowid, url, explanation, note, redirected_from)
+        VALUES (new.id, new.url, new.explanation, new.note, new.redirected_from);
+    END;
+    CREATE TRIGGER scans_fts_delete AFTER DELETE ON scans BEGIN
+        INSERT INTO scans_fts (scans_fts, rowid, url, explanation, note, redirected_from)
+        VALUES ('delete', old.id, old.url, old.explanation, old.note, old.redirected_from);
+    END;
+    CREATE TRIGGER scans_fts_update AFTER UPDATE ON scans BEGIN
+        INSERT INTO scans_fts (scans_fts, rowid, url, explanation, note, redirected_from)
+        VALUES ('delete', old.id, old.url, old.explanation, old.note, old.redirected_from);
+        INSERT INTO scans_fts (rowid, url, explanation, note, redirected_from)
+        VALUES (new.id, new.url, new.explanation, new.note, new.redirected_from);
+    END;",
+    "ALTER TABLE scans ADD COLUMN duration_ms INTEGER;",
+    "CREATE TABLE queued_scans (
+        scan_id TEXT PRIMARY KEY,
+        url TEXT NOT NULL,
+        normalized_url TEXT NOT NULL,
+        source TEXT NOT NULL,
+        force INTEGER NOT NULL DEFAULT 0,
+        origin TEXT,
+        input TEXT,
+        status TEXT NOT NULL DEFAULT 'pending',
+        requested_at INTEGER NOT NULL,
+        finished_at INTEGER
+    );
+    CREATE INDEX queued_scans_status ON queued_scans (status, requested_at);",
+];
+
+/// Columns read into a `HistoryEntry`, in `entry_from_row` order
+pub const ENTRY_COLUMNS: &str = "id, url, normalized_url, classification, confidence, risk_score,
+                             explanation, features, scanned_at, source, scan_id, note,
+                             (SELECT group_concat(tag, char(31)) FROM scan_tags
+                              WHERE scan_row = scans.id),
+                             origin, input, rescan_of,
+                             (SELECT info FROM domain_info WHERE domain_info.domain = scans.domain),
+                             redirected_from, duration_ms";
+/// How many columns `ENTRY_COLUMNS` selects; queries that add their own
+/// columns after it find them from this index on
+pub const ENTRY_COLUMN_COUNT: usize = 19;
+
+/// SQL condition matching every classification that counts as phishing
+pub const PHISHING_CONDITION: &str =
+    "classification IN ('phishing', 'ai_generated_phishing', 'phishing_kit')";
+
+/// Largest page `get_scan_history` will return
+const MAX_PAGE_SIZE: usize = 500;
+
+/// A stored scan as returned to the frontend
+#[derive(Serialize, Debug, Clone)]
+pub struct HistoryEntry {
+    pub id: i64,
+    pub url: String,
+    pub normalized_url: String,
+    pub classification: String,
+    pub confidence: f64,
+    pub risk_score: i32,
+    pub explanation: String,
+    pub features: FeatureSet,
+    /// Unix timestamp in seconds
+    pub scanned_at: i64,
+    pub source: String,
+    /// Queue id of the scan that produced this row; absent for rows stored
+    /// before scan ids were recorded
+    pub scan_id: Option<String>,
+    pub note: Option<String>,
+    pub tags: Vec<String>,
+    /// Where the URL was found, e.g. the name of a watched downloaded file
+    pub origin: Option<String>,
+    /// The text the URL was typed or pasted as, when it needed cleaning up
+    /// (e.g. a defanged `hxxps://evil[.]com`)
+    pub input: Option<String>,
+    /// Row of the earlier scan this one re-checked with `rescan_history`
+    pub rescan_of: Option<i64>,
+    /// Registration details of the URL's domain, if enrich_domain has
+    /// looked them up
+    pub domain_info: Option<DomainInfo>,
+    /// The short link or other redirecting URL that led to `url`
+    pub redirected_from: Option<String>,
+    /// How long the scan took; absent for rows stored before it was timed
+    pub duration_ms: Option<i64>,
+}
+
+/// Order of `get_scan_history` results
+#[derive(Deserialize, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum HistorySort {
+    #[default]
+    Newest,
+    Oldest,
+    HighestRisk,
+    LowestRisk,
+}
+
+impl HistorySort {
+    fn order_by(self) -> &'static str {
+        match self {
+            HistorySort::Newest => "scanned_at DESC, id DESC",
+            HistorySort::Oldest => "scanned_at ASC, id ASC",
+            HistorySort::HighestRisk => "risk_score DESC, scanned_at DESC, id DESC",
+            HistorySort::LowestRisk => "risk_score ASC, scanned_at DESC, id DESC",
+        }
+    }
+}
+
+/// Criteria for narrowing the history; every field is optional
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct HistoryFilter {
+    /// "phishing" matches every phishing variant; anything else matches exactly
+    pub classification: Option<String>,
+    /// Inclusive lower bound on `scanned_at`, Unix seconds
+    pub from: Option<i64>,
+    /// Inclusive upper bound on `scanned_at`, Unix seconds
+    pub to: Option<i64>,
+    pub min_risk_score: Option<i32>,
+    /// Case-insensitive substring of the URL or the URL it was redirected from
+    pub url_contains: Option<String>,
+    /// Only scans carrying every one of these tags
+    pub tags: Vec<String>,
+    pub sort: HistorySort,
+}
+
+impl HistoryFilter {
+    /// SQL `WHERE` clause (empty when nothing is filtered) and its bound parameters
+    fn where_clause(&self) -> (String, Vec<Value>) {
+        let mut conditions = Vec::new();
+        let mut values = Vec::new();
+
+        match self.classification.as_deref().map(str::trim) {
+            Some("phishing") => conditions.push(PHISHING_CONDITION),
+            Some(classification) if !classification.is_empty() => {
+                conditions.push("classification = ?");
+                values.push(Value::Text(classification.to_string()));
+            }
+            _ => {}
+        }
+        if let Some(from) = self.from {
+            conditions.push("scanned_at >= ?");
+            values.push(Value::Integer(from));
+        }
+        if let Some(to) = self.to {
+            conditions.push("scanned_at <= ?");
+            values.push(Value::Integer(to));
+        }
+        if let Some(min_risk_score) = self.min_risk_score {
+            conditions.push("risk_score >= ?");
+            values.push(Value::Integer(min_risk_score.into()));
+        }
+        if let Some(needle) = self.url_contains.as_deref().filter(|n| !n.is_empty()) {
+            let escaped = needle
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_");
+            conditions.push("(url LIKE ? ESCAPE '\\' OR redirected_from LIKE ? ESCAPE '\\')");
+            let pattern = format!("%{}%", escaped);
+            values.push(Value::Text(pattern.clone()));
+            values.push(Value::Text(pattern));
+        }
+        for tag in self.tags.iter().filter_map(|t| normalize_tag(t)) {
+            conditions
+                .push("EXISTS (SELECT 1 FROM scan_tags WHERE scan_row = scans.id AND tag = ?)");
+            values.push(Value::Text(tag));
+        }
+
+        if conditions.is_empty() {
+            (String::new(), values)
+        } else {
+            (format!("WHERE {}", conditions.join(" AND ")), values)
+        }
+    }
+
+    /// Query for the `page_size` rows after `offset` that `where_clause`
+    /// (from `Self::where_clause`) keeps, in the filter's sort order
+    fn page_query(&self, where_clause: &str, page_size: usize, offset: usize) -> String {
+        format!(
+            "SELECT {} FROM scans {} ORDER BY {} LIMIT {} OFFSET {}",
+            ENTRY_COLUMNS,
+            where_clause,
+            self.sort.order_by(),
+            page_size,
+            offset
+        )
+    }
+}
+
+/// One page of history plus what the pager needs to render
+#[derive(Serialize, Debug, Clone)]
+pub struct HistoryPage {
+    items: Vec<HistoryEntry>,
+    /// Rows matching the filter across all pages
+    total: usize,
+    /// 1-based page number actually returned
+    page: usize,
+    page_size: usize,
+    total_pages: usize,
+}
+
+/// Clamp a requested 1-based page into range; returns
+/// `(page, page_size, offset, total_pages)`
+fn page_bounds(page: usize, page_size: usize, total: usize) -> (usize, usize, usize, usize) {
+    let page_size = page_size.clamp(1, MAX_PAGE_SIZE);
+    let total_pages = ((total + page_size - 1) / page_size).max(1);
+    let page = page.clamp(1, total_pages);
+    (page, page_size, (page - 1) * page_size, total_pages)
+}
+
+pub fn entry_from_row(row: &Row<'_>) -> rusqlite::Result<HistoryEntry> {
+    let features: String = row.get(7)?;
+    Ok(HistoryEntry {
+        id: row.get(0)?,
+        url: row.get(1)?,
+        normalized_url: row.get(2)?,
+        classification: row.get(3)?,
+        confidence: row.get(4)?,
+        risk_score: row.get(5)?,
+        explanation: row.get(6)?,
+        features: serde_json::from_str(&features).unwrap_or_default(),
+        scanned_at: row.get(8)?,
+        source: row.get(9)?,
+        scan_id: row.get(10)?,
+        note: row.get(11)?,
+        tags: row
+            .get::<_, Option<String>>(12)?
+            .map(|tags| tags.split('\u{1f}').map(str::to_string).collect())
+            .unwrap_or_default(),
+        origin: row.get(13)?,
+        input: row.get(14)?,
+        rescan_of: row.get(15)?,
+        domain_info: row
+            .get::<_, Option<String>>(16)?
+            .and_then(|info| serde_json::from_str(&info).ok()),
+        redirected_from: row.get(17)?,
+        duration_ms: row.get(18)?,
+    })
+}
+
+/// Rows of a `ScanHistory::stream` query, read one at a time
+pub struct HistoryCursor<'a> {
+    rows: Rows<'a>,
+}
+
+impl HistoryCursor<'_> {
+    pub fn next_entry(&mut self) -> Result<Option<HistoryEntry>, AppError> {
+        match self.rows.next()? {
+            Some(row) => Ok(Some(entry_from_row(row)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Local scan history stored in SQLite
+pub struct ScanHistory {
+    conn: Arc<Mutex<Connection>>,
+    /// Held shared by every database task and exclusively by `finish_writes`
+    open: Arc<tokio::sync::RwLock<()>>,
+}
+
+/// Lower-case the scheme and host and drop the fragment and trailing slash,
+/// so trivially different spellings of a URL share one history key
+pub fn normalize_url(raw: &str) -> String {
+    match url::Url::parse(raw.trim()) {
+        Ok(mut parsed) => {
+            parsed.set_fragment(None);
+            parsed.to_string().trim_end_matches('/').to_string()
+        }
+        Err(_) => raw.trim().to_string(),
+    }
+}
+
+/// Registrable domain (eTLD+1) of a URL's host, so `login.evil.co.uk` and
+/// `evil.co.uk` group together. Hosts without one, such as IP addresses and
+/// `localhost`, stand for themselves; anything unparseable is empty.
+pub fn registrable_domain(raw: &str) -> String {
+    let Ok(parsed) = url::Url::parse(raw.trim()) else {
+        return String::new();
+    };
+    match parsed.host() {
+        Some(url::Host::Domain(host)) => {
+            let host = host.trim_end_matches('.').to_ascii_lowercase();
+            psl::domain_str(&host).map_or(host.clone(), str::to_string)
+        }
+        Some(host) => host.to_string(),
+        None => String::new(),
+    }
+}
+
+/// The registrable domain of a host or URL the user typed, e.g.
+/// `evil.co.uk` for `login.evil.co.uk`
+pub fn parse_domain(input: &str) -> Result<String, AppError> {
+    let trimmed = input.trim();
+    let as_url = if trimmed.contains("://") {
+        trimmed.to_string()
+    } else {
+        format!("https://{}", trimmed)
+    };
+    let domain = registrable_domain(&as_url);
+    if domain.is_empty() {
+        return Err(AppError::InvalidInput(format!("not a domain: {}", trimmed)));
+    }
+    Ok(domain)
+}
+
+/// Give rows stored before the `domain` column existed their domain
+fn backfill_domains(conn: &mut Connection) -> Result<(), AppError> {
+    let tx = conn.transaction()?;
+    {
+        let mut select = tx.prepare("SELECT id, url FROM scans WHERE domain IS NULL")?;
+        let mut update = tx.prepare("UPDATE scans SET domain = ?1 WHERE id = ?2")?;
+        let rows = select
+            .query_map([], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        for (id, url) in rows {
+            update.execute(params![registrable_domain(&url), id])?;
+        }
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+/// Trim and lower-case a tag; None if nothing is left
+pub fn normalize_tag(raw: &str) -> Option<String> {
+    let tag = raw
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+    (!tag.is_empty()).then_some(tag)
+}
+
+/// Current time as Unix seconds
+pub fn now_secs() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// Apply pending migrations, first copying an existing database aside as
+/// `<file>.v<version>.bak`. A database from a newer app version is refused
+/// rather than used with a schema this version doesn't know.
+pub fn migrate(conn: &mut Connection, path: &Path) -> Result<(), AppError> {
+    let applied: usize = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    if applied > MIGRATIONS.len() {
+        return Err(AppError::NewerVersion(path.display().to_string()));
+    }
+    if applied > 0 && applied < MIGRATIONS.len() {
+        let mut backup = path.as_os_str().to_owned();
+        backup.push(format!(".v{}.bak", applied));
+        let backup = PathBuf::from(backup);
+        let _ = std::fs::remove_file(&backup);
+        conn.execute("VACUUM INTO ?1", [backup.to_string_lossy()])?;
+    }
+
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(applied) {
+        let tx = conn.transaction()?;
+        tx.execute_batch(migration)?;
+        tx.pragma_update(None, "user_version", index + 1)?;
+        tx.commit()?;
+    }
+    Ok(())
+}
+
+impl ScanHistory {
+    /// Open (creating if needed) the database at `path` and bring its schema up to date
+    pub fn open(path: &Path) -> Result<Self, AppError> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let mut conn = Connection::open(path)?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        // Needed for tags to be removed along with their scan
+        conn.pragma_update(None, "foreign_keys", true)?;
+        migrate(&mut conn, path)?;
+        backfill_domains(&mut conn)?;
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+            open: Arc::default(),
+        })
+    }
+
+    /// Run `work` against the connection on the blocking thread pool
+    pub async fn with_conn<T, F>(&self, work: F) -> Result<T, AppError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Connection) -> Result<T, AppError> + Send + 'static,
+    {
+        let _open = self.open.read().await;
+        let conn = self.conn.clone();
+        tokio::task::spawn_blocking(move || work(&*conn.lock()?))
+            .await
+            .map_err(|e| AppError::Database(format!("history task failed: {}", e)))?
+    }
+
+    /// Wait for the database tasks already running and keep any more from
+    /// starting while the guard is held, so the app can exit without losing
+    /// a write
+    pub async fn finish_writes(&self) -> tokio::sync::OwnedRwLockWriteGuard<()> {
+        self.open.clone().write_owned().await
+    }
+
+    /// Store a completed scan
+    pub async fn record(&self, result: &ScanResult, job: &ScanJob) -> Result<(), AppError> {
+        let result = result.clone();
+        let (scan_id, source) = (job.scan_id.clone(), job.source);
+        let (origin, input) = (job.origin.clone(), job.input.clone());
+        let redirected_from = result
+            .redirects
+            .as_ref()
+            .map(|chain| chain.url.clone())
+            .filter(|from| *from != result.url);
+        self.with_conn(move |conn| {
+            conn.execute(
+                "INSERT INTO scans (url, normalized_url, classification, confidence, risk_score,
+                                    explanation, features, scanned_at, source, scan_id, origin,
+                                    input, domain, redirected_from, duration_ms)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
+                params![
+                    result.url,
+                    normalize_url(&result.url),
+                    result.classification,
+                    result.confidence,
+                    result.risk_score,
+                    result.explanation,
+                    serde_json::to_string(&result.features)?,
+                    now_secs(),
+                    source.as_str(),
+                    scan_id,
+                    origin,
+                    input,
+                    registrable_domain(&result.url),
+                    redirected_from,
+                    result.duration_ms.map(|ms| ms as i64),
+                ],
+            )?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Replace the stand-in verdict recorded for `job` while the detector was
+    /// unavailable with `result`; false if there is no such row to replace
+    pub async fn upgrade(&self, result: &ScanResult, job: &ScanJob) -> Result<bool, AppError> {
+        let result = result.clone();
+        let scan_id = job.scan_id.clone();
+        self.with_conn(move |conn| {
+            let changed = conn.execute(
+                "UPDATE scans SET classification = ?1, confidence = ?2, risk_score = ?3,
+                                  explanation = ?4, features = ?5, scanned_at = ?6,
+                                  duration_ms = ?9
+                 WHERE scan_id = ?7 AND classification = ?8",
+                params![
+                    result.classification,
+                    result.confidence,
+                    result.risk_score,
+                    result.explanation,
+                    serde_json::to_string(&result.features)?,
+                    now_secs(),
+                    scan_id,
+                    FALLBACK_CLASSIFICATION,
+                    result.duration_ms.map(|ms| ms as i64),
+                ],
+            )?;
+            Ok(changed > 0)
+        })
+        .await
+    }
+
+    /// Each URL's newest row, where that row matches `filter`, in the
+    /// filter's order as `(id, url, classification)`
+    pub async fn latest_matching(
+        &self,
+        filter: HistoryFilter,
+    ) -> Result<Vec<(i64, String, String)>, AppError> {
+        self.with_conn(move |conn| {
+            let (where_clause, values) = filter.where_clause();
+            let latest = "id IN (SELECT id FROM (
+                              SELECT id, row_number() OVER (PARTITION BY normalized_url
+                                                            ORDER BY scanned_at DESC, id DESC) AS nth
+                              FROM scans
+                          ) WHERE nth = 1)";
+            let where_clause = if where_clause.is_empty() {
+                format!("WHERE {}", latest)
+            } else {
+                format!("{} AND {}", where_clause, latest)
+            };
+            let mut statement = conn.prepare(&format!(
+                "SELECT id, url, classification FROM scans {} ORDER BY {}",
+                where_clause,
+                filter.sort.order_by()
+            ))?;
+            let rows = statement.query_map(params_from_iter(values.iter()), |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })?;
+            Ok(rows.collect::<Result<Vec<_>, _>>()?)
+        })
+        .await
+    }
+
+    /// Mark the row recorded for scan `scan_id` as a rescan of row `rescan_of`;
+    /// returns the row's id, or None if the scan left no row (e.g. it was
+    /// answered by the blocklist)
+    pub async fn link_rescan(
+        &self,
+        scan_id: &str,
+        rescan_of: i64,
+    ) -> Result<Option<i64>, AppError> {
+        let scan_id = scan_id.to_string();
+        self.with_conn(move |conn| {
+            let id: Option<i64> = conn.query_row(
+                "SELECT max(id) FROM scans WHERE scan_id = ?1",
+                [scan_id],
+                |row| row.get(0),
+            )?;
+            if let Some(id) = id {
+                conn.execute(
+                    "UPDATE scans SET rescan_of = ?1 WHERE id = ?2",
+                    params![rescan_of, id],
+                )?;
+            }
+            Ok(id)
+        })
+        .await
+    }
+
+    /// The stored result of scan `scan_id`, with its time formatted in local time
+    pub async fn find(&self, scan_id: &str) -> Result<Option<(HistoryEntry, String)>, AppError> {
+        let scan_id = scan_id.to_string();
+        self.with_conn(move |conn| {
+            let found = conn
+                .query_row(
+                    &format!(
+                        "SELECT {}, datetime(scanned_at, 'unixepoch', 'localtime')
+                         FROM scans WHERE scan_id = ?1 ORDER BY id DESC LIMIT 1",
+                        ENTRY_COLUMNS
+                    ),
+                    [scan_id],
+                    |row| Ok((entry_from_row(row)?, row.get(ENTRY_COLUMN_COUNT)?)),
+                )
+                .optional()?;
+            Ok(found)
+        })
+        .await
+    }
+
+    /// Delete every stored scan
+    pub async fn clear(&self) -> Result<(), AppError> {
+        self.with_conn(|conn| {
+            conn.execute("DELETE FROM scans", [])?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Most recent scans first
+    pub async fn recent(&self, limit: usize) -> Result<Vec<HistoryEntry>, AppError> {
+        self.with_conn(move |conn| {
+            let mut statement = conn.prepare(&format!(
+                "SELECT {} FROM scans ORDER BY scanned_at DESC, id DESC LIMIT ?1",
+                ENTRY_COLUMNS
+            ))?;
+            let rows = statement.query_map([limit as i64], entry_from_row)?;
+            Ok(rows.collect::<Result<Vec<_>, _>>()?)
+        })
+        .await
+    }
+
+    /// Run `work` on the blocking pool with a cursor over every scan matching
+    /// `filter`, so large exports never hold the whole history in memory
+    pub async fn stream<T, F>(&self, filter: HistoryFilter, work: F) -> Result<T, AppError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut HistoryCursor<'_>) -> Result<T, AppError> + Send + 'static,
+    {
+        self.with_conn(move |conn| {
+            let (where_clause, values) = filter.where_clause();
+            let mut statement = conn.prepare(&format!(
+                "SELECT {} FROM scans {} ORDER BY {}",
+                ENTRY_COLUMNS,
+                where_clause,
+                filter.sort.order_by()
+            ))?;
+            let mut cursor = HistoryCursor {
+                rows: statement.query(params_from_iter(values.iter()))?,
+            };
+            work(&mut cursor)
+        })
+        .await
+    }
+
+    /// One page of scans matching `filter`, in the filter's sort order
+    pub async fn page(
+        &self,
+        page: usize,
+        page_size: usize,
+        filter: HistoryFilter,
+    ) -> Result<HistoryPage, AppError> {
+        self.with_conn(move |conn| {
+            let (where_clause, values) = filter.where_clause();
+
+            let total: i64 = conn.query_row(
+                &format!("SELECT COUNT(*) FROM scans {}", where_clause),
+                params_from_iter(values.iter()),
+                |row| row.get(0),
+            )?;
+            let (page, page_size, offset, total_pages) =
+                page_bounds(page, page_size, total as usize);
+
+            let mut statement =
+                conn.prepare(&filter.page_query(&where_clause, page_size, offset))?;
+            let items = statement
+                .query_map(params_from_iter(values.iter()), entry_from_row)?
+                .collect::<Result<Vec<_>, _>>()?;
+
+            Ok(HistoryPage {
+                items,
+                total: total as usize,
+                page,
+                page_size,
+                total_pages,
+            })
+        })
+        .await
+    }
+}
+
+/// The `limit` most recent scans, newest first
+#[tauri::command]
+pub async fn get_recent_scans(
+    limit: Option<usize>,
+    history: State<'_, ScanHistory>,
+) -> Result<Vec<HistoryEntry>, AppError> {
+    history.recent(limit.unwrap_or(50)).await
+}
+
+/// Page through the history; `page` is 1-based and out-of-range pages are clamped
+#[tauri::command]
+pub async fn get_scan_history(
+    page: Option<usize>,
+    page_size: Option<usize>,
+    filter: Option<HistoryFilter>,
+    history: State<'_, ScanHistory>,
+) -> Result<HistoryPage, AppError> {
+    let filter = filter.unwrap_or_default();
+    history
+        .page(page.unwrap_or(1), page_size.unwrap_or(50), filter)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A history of `count` scans, spread over classifications, risk scores
+    /// and days, kept in memory
+    fn seeded(count: usize) -> ScanHistory {
+        let mut conn = Connection::open_in_memory().unwrap();
+        migrate(&mut conn, Path::new(":memory:")).unwrap();
+        let classifications = ["legitimate", "phishing", "suspicious", "phishing_kit"];
+        let tx = conn.transaction().unwrap();
+        for i in 0..count {
+            let url = format!("https://site{}.example/page", i);
+            tx.execute(
+                "INSERT INTO scans (url, normalized_url, classification, confidence, risk_score,
+                                    explanation, features, scanned_at, source)
+                 VALUES (?1, ?1, ?2, 0.9, ?3, '', '{}', ?4, 'manual')",
+                params![
+                    url,
+                    classifications[i % classifications.len()],
+                    (i * 7 % 101) as i64,
+                    1_700_000_000 + (i as i64 % 365) * 86_400,
+                ],
+            )
+            .unwrap();
+        }
+        tx.commit().unwrap();
+        ScanHistory {
+            conn: Arc::new(Mutex::new(conn)),
+            open: Arc::default(),
+        }
+    }
+
+    #[test]
+    fn page_bounds_clamp_into_range() {
+        assert_eq!(page_bounds(1, 50, 0), (1, 50, 0, 1));
+        assert_eq!(page_bounds(0, 50, 120), (1, 50, 0, 3));
+        assert_eq!(page_bounds(3, 50, 120), (3, 50, 100, 3));
+        assert_eq!(page_bounds(9, 50, 120), (3, 50, 100, 3));
+        assert_eq!(page_bounds(2, 0, 5), (2, 1, 1, 5));
+        assert_eq!(page_bounds(1, 10_000, 1_200), (1, MAX_PAGE_SIZE, 0, 3));
+        assert_eq!(page_bounds(2, 40, 80), (2, 40, 40, 2));
+    }
+
+    #[tokio::test]
+    async fn pages_cover_a_large_history_without_overlap() {
+        let history = seeded(2_500);
+        let first = history
+            .page(1, 200, HistoryFilter::default())
+            .await
+            .unwrap();
+        assert_eq!((first.total, first.total_pages, first.page), (2_500, 13, 1));
+        assert_eq!(first.items.len(), 200);
+
+        let mut seen = std::collections::HashSet::new();
+        let mut previous = i64::MAX;
+        for page in 1..=first.total_pages {
+            let items = history
+                .page(page, 200, HistoryFilter::default())
+                .await
+                .unwrap()
+                .items;
+            for item in items {
+                assert!(item.scanned_at <= previous, "newest first across pages");
+                previous = item.scanned_at;
+                assert!(seen.insert(item.id), "row {} on two pages", item.id);
+            }
+        }
+        assert_eq!(seen.len(), 2_500);
+
+        // The last page holds the remainder, and asking past it returns it again
+        let last = history
+            .page(99, 200, HistoryFilter::default())
+            .await
+            .unwrap();
+        assert_eq!((last.page, last.items.len()), (13, 100));
+
+        let filter = HistoryFilter {
+            classification: Some("phishing".into()),
+            min_risk_score: Some(50),
+            sort: HistorySort::HighestRisk,
+            ..HistoryFilter::default()
+        };
+        let risky = history.page(1, MAX_PAGE_SIZE, filter).await.unwrap();
+        assert!(risky.total > 0 && risky.total < 1_250);
+        assert!(risky
+            .items
+            .iter()
+            .all(|item| item.risk_score >= 50 && item.classification.starts_with("phishing")));
+        assert!(risky
+            .items
+            .windows(2)
+            .all(|pair| pair[0].risk_score >= pair[1].risk_score));
+    }
+
+    /// What SQLite plans to do for the page `filter` asks for
+    fn plan(history: &ScanHistory, filter: &HistoryFilter) -> String {
+        let (where_clause, values) = filter.where_clause();
+        let sql = format!(
+            "EXPLAIN QUERY PLAN {}",
+            filter.page_query(&where_clause, 50, 0)
+        );
+        let conn = history.conn.lock().unwrap();
+        let mut statement = conn.prepare(&sql).unwrap();
+        let details = statement
+            .query_map(params_from_iter(values.iter()), |row| {
+                row.get::<_, String>(3)
+            })
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        details.join("\n")
+    }
+
+    #[test]
+    fn filtered_pages_use_the_history_indexes() {
+        let history = seeded(500);
+        let newest = plan(&history, &HistoryFilter::default());
+        assert!(newest.contains("scans_scanned_at"), "{}", newest);
+
+        let by_classification = plan(
+            &history,
+            &HistoryFilter {
+                classification: Some("suspicious".into()),
+                ..HistoryFilter::default()
+            },
+        );
+        assert!(
+            by_classification.contains("USING INDEX scans_classification (classification=?)"),
+            "{}",
+            by_classification
+        );
+        assert!(
+            !by_classification.contains("TEMP B-TREE"),
+            "{}",
+            by_classification
+        );
+
+        let by_date = plan(
+            &history,
+            &HistoryFilter {
+                from: Some(1_700_000_000),
+                to: Some(1_700_864_000),
+                ..HistoryFilter::default()
+            },
+        );
+        assert!(
+            by_date.contains("USING INDEX scans_scanned_at"),
+            "{}",
+            by_date
+        );
+
+        let riskiest = plan(
+            &history,
+            &HistoryFilter {
+                min_risk_score: Some(80),
+                sort: HistorySort::HighestRisk,
+                ..HistoryFilter::default()
+            },
+        );
+        assert!(
+            riskiest.contains("USING INDEX scans_risk_score"),
+            "{}",
+            riskiest
+        );
+    }
+
+    fn temp_database() -> (PathBuf, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("history-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(DATABASE_FILE);
+        (dir, path)
+    }
+
+    fn user_version(path: &Path) -> usize {
+        Connection::open(path)
+            .unwrap()
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn an_older_database_is_backed_up_before_migrating() {
+        let (dir, path) = temp_database();
+        {
+            // The schema as an app three migrations in left it, with a scan
+            let conn = Connection::open(&path).unwrap();
+            for migration in &MIGRATIONS[..3] {
+                conn.execute_batch(migration).unwrap();
+            }
+            conn.pragma_update(None, "user_version", 3).unwrap();
+            conn.execute(
+                "INSERT INTO scans (url, normalized_url, classification, confidence, risk_score,
+                                    explanation, features, scanned_at, source, scan_id)
+                 VALUES ('https://a.example/', 'https://a.example/', 'legitimate', 0.9, 5,
+                         '', '{}', 1700000000, 'manual', 'kept')",
+                [],
+            )
+            .unwrap();
+        }
+
+        let history = ScanHistory::open(&path).unwrap();
+        drop(history);
+        assert_eq!(user_version(&path), MIGRATIONS.len());
+        let backup = dir.join(format!("{}.v3.bak", DATABASE_FILE));
+        assert_eq!(user_version(&backup), 3);
+        let kept: String = Connection::open(&backup)
+            .unwrap()
+            .query_row("SELECT scan_id FROM scans", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(kept, "kept");
+
+        // Opening it again has nothing to migrate, so makes no backup
+        std::fs::remove_file(&backup).unwrap();
+        drop(ScanHistory::open(&path).unwrap());
+        assert!(!backup.exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn a_newer_database_is_refused() {
+        let (dir, path) = temp_database();
+        Connection::open(&path)
+            .unwrap()
+            .pragma_update(None, "user_version", MIGRATIONS.len() + 1)
+            .unwrap();
+        assert!(matches!(
+            ScanHistory::open(&path),
+            Err(AppError::NewerVersion(_))
+        ));
+        assert_eq!(user_version(&path), MIGRATIONS.len() + 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
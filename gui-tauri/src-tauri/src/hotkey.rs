@@ -0,0 +1,132 @@
+use crate::error::AppError;
+use crate::lifecycle::{ScanJob, ScanSource};
+use crate::links::is_scannable_url;
+use crate::notifications::notify;
+use crate::protection::is_paused;
+use crate::queue::ScanQueue;
+use crate::{AppHandle, AppRuntime};
+use std::str::FromStr;
+use std::sync::Mutex;
+use tauri::plugin::TauriPlugin;
+use tauri::{Manager, State};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+
+/// Shortcut registered at startup unless PHISHING_GUARD_SCAN_SHORTCUT overrides it
+const DEFAULT_SHORTCUT: &str = "CommandOrControl+Shift+S";
+
+/// The accelerator currently bound to "scan the clipboard", if any
+#[derive(Default)]
+pub struct ScanHotkey {
+    current: Mutex<Option<String>>,
+}
+
+/// Global shortcut plugin whose handler scans the clipboard on key press
+pub fn plugin() -> TauriPlugin<AppRuntime> {
+    tauri_plugin_global_shortcut::Builder::new()
+        .with_handler(|app, _shortcut, event| {
+            if event.state() == ShortcutState::Pressed {
+                tauri::async_runtime::spawn(scan_clipboard(app.clone()));
+            }
+        })
+        .build()
+}
+
+fn parse(accelerator: &str) -> Result<Shortcut, AppError> {
+    Shortcut::from_str(accelerator).map_err(|e| {
+        AppError::Shortcut(format!(
+            "\"{}\" is not a valid shortcut: {}",
+            accelerator, e
+        ))
+    })
+}
+
+/// Bind the scan shortcut to `accelerator`, or unbind it when `None`.
+///
+/// The previous binding is only released once the new one is registered, and
+/// restored if registration fails (typically because another application
+/// already owns the combination).
+pub fn bind(app: &AppHandle, accelerator: Option<&str>) -> Result<(), AppError> {
+    let hotkey = app.state::<ScanHotkey>();
+    let mut current = hotkey.current.lock()?;
+    let shortcuts = app.global_shortcut();
+
+    let previous = current.as_deref().map(parse).transpose()?;
+    let next = accelerator.map(parse).transpose()?;
+    if previous == next {
+        return Ok(());
+    }
+
+    if let Some(previous) = previous {
+        let _ = shortcuts.unregister(previous);
+    }
+    if let Some(next) = next {
+        if let Err(e) = shortcuts.register(next) {
+            if let Some(previous) = previous {
+                let _ = shortcuts.register(previous);
+            }
+            return Err(AppError::Shortcut(format!(
+                "{} could not be registered; another application may already be using it ({})",
+                accelerator.unwrap_or_default(),
+                e
+            )));
+        }
+    }
+
+    *current = accelerator.map(str::to_string);
+    Ok(())
+}
+
+/// Register the startup shortcut; a conflict only costs the shortcut, not the app
+pub fn bind_default(app: &AppHandle) {
+    let accelerator = std::env::var("PHISHING_GUARD_SCAN_SHORTCUT")
+        .unwrap_or_else(|_| DEFAULT_SHORTCUT.to_string());
+    if let Err(e) = bind(app, Some(&accelerator)) {
+        tracing::warn!(shortcut = %accelerator, error = %e, "scan shortcut unavailable");
+    }
+}
+
+/// Scan the URL on the clipboard; the verdict arrives as a notification
+async fn scan_clipboard(app: AppHandle) {
+    if is_paused(&app) {
+        return;
+    }
+
+    let text = app.clipboard().read_text().unwrap_or_default();
+    let url = text.trim();
+    if !is_scannable_url(url) {
+        notify(
+            &app,
+            "Nothing to scan",
+            "The clipboard does not contain an http or https link.",
+        );
+        return;
+    }
+
+    // The queue announces the verdict
+    let submitted = match ScanJob::from_input(url, ScanSource::Hotkey) {
+        Ok(job) => app.state::<ScanQueue>().submit(&app, job).await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = submitted {
+        notify(&app, "Link could not be scanned", &e.to_string());
+    }
+}
+
+/// The shortcut that scans the clipboard, if one is bound
+#[tauri::command]
+pub fn get_scan_shortcut(hotkey: State<'_, ScanHotkey>) -> Result<Option<String>, AppError> {
+    Ok(hotkey.current.lock()?.clone())
+}
+
+/// Rebind the clipboard scan shortcut, e.g. "CommandOrControl+Shift+S";
+/// an empty or missing value disables it
+#[tauri::command]
+pub fn set_scan_shortcut(
+    shortcut: Option<String>,
+    app: AppHandle,
+) -> Result<Option<String>, AppError> {
+    let shortcut = shortcut.as_deref().map(str::trim).filter(|s| !s.is_empty());
+    bind(&app, shortcut)?;
+    Ok(shortcut.map(str::to_string))
+}
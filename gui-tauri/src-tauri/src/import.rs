@@ -0,0 +1,203 @@
+use crate::batch::{run_batch, BatchScanItem};
+use crate::error::AppError;
+use crate::lifecycle::ScanSource;
+use crate::links::is_scannable_url;
+use crate::offline::FALLBACK_CLASSIFICATION;
+use crate::AppHandle;
+use serde::Serialize;
+
+/// A line from an imported file that was not scanned
+#[derive(Serialize, Debug, Clone)]
+pub struct SkippedLine {
+    line: usize,
+    content: String,
+    reason: String,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct ImportSummary {
+    parsed: usize,
+    skipped: usize,
+    phishing: usize,
+    legitimate: usize,
+    suspicious: usize,
+    /// Matched the allowlist, so never went to the detector
+    allowlisted: usize,
+    /// Hosts that don't resolve, which were not scanned
+    unresolvable: usize,
+    /// Scored by the local checks alone while the detector was unavailable
+    offline: usize,
+    /// Any other verdict the detector gave
+    other: usize,
+    /// Scans that ended in an error or were cancelled
+    failed: usize,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ImportReport {
+    summary: ImportSummary,
+    skipped: Vec<SkippedLine>,
+    items: Vec<BatchScanItem>,
+}
+
+/// Locate the `url` column if the first line looks like a CSV header
+fn csv_url_column(header: &str) -> Option<usize> {
+    header
+        .split(',')
+        .position(|field| field.trim().trim_matches('"').eq_ignore_ascii_case("url"))
+}
+
+/// Parse newline-delimited URLs or a CSV with a `url` header column.
+///
+/// Returns the valid URLs in file order plus the lines that were rejected.
+pub fn parse_url_list(content: &str) -> (Vec<String>, Vec<SkippedLine>) {
+    let content = content.trim_start_matches('\u{feff}');
+    let mut urls = Vec::new();
+    let mut skipped = Vec::new();
+
+    let mut check = |line: usize, candidate: &str, skipped: &mut Vec<SkippedLine>| {
+        let candidate = candidate.trim();
+        if candidate.is_empty() || candidate.starts_with('#') {
+            return;
+        }
+        if is_scannable_url(candidate) {
+            urls.push(candidate.to_string());
+        } else {
+            skipped.push(SkippedLine {
+                line,
+                content: candidate.to_string(),
+                reason: "not an http(s) URL".to_string(),
+            });
+        }
+    };
+
+    let first_line = content.lines().find(|l| !l.trim().is_empty()).unwrap_or("");
+    match csv_url_column(first_line) {
+        Some(column) => {
+            let mut reader = csv::ReaderBuilder::new()
+                .flexible(true)
+                .trim(csv::Trim::All)
+                .from_reader(content.as_bytes());
+            for record in reader.records() {
+                match record {
+                    Ok(record) => {
+                        let line = record.position().map(|p| p.line() as usize).unwrap_or(0);
+                        check(line, record.get(column).unwrap_or(""), &mut skipped);
+                    }
+                    Err(e) => skipped.push(SkippedLine {
+                        line: e.position().map(|p| p.line() as usize).unwrap_or(0),
+                        content: String::new(),
+                        reason: format!("malformed CSV row: {}", e),
+                    }),
+                }
+            }
+        }
+        None => {
+            for (index, line) in content.lines().enumerate() {
+                check(index + 1, line, &mut skipped);
+            }
+        }
+    }
+
+    (urls, skipped)
+}
+
+/// Summarise batch outcomes by verdict
+pub fn summarize(parsed: usize, skipped: usize, items: &[BatchScanItem]) -> ImportSummary {
+    let mut summary = ImportSummary {
+        parsed,
+        skipped,
+        ..Default::default()
+    };
+    for item in items {
+        let BatchScanItem::Completed { result, .. } = item else {
+            summary.failed += 1;
+            continue;
+        };
+        let count = match result.classification.as_str() {
+            _ if result.is_phishing() => &mut summary.phishing,
+            "legitimate" => &mut summary.legitimate,
+            "suspicious" => &mut summary.suspicious,
+            "allowlisted" => &mut summary.allowlisted,
+            "unresolvable" => &mut summary.unresolvable,
+            FALLBACK_CLASSIFICATION => &mut summary.offline,
+            _ => &mut summary.other,
+        };
+        *count += 1;
+    }
+    summary
+}
+
+/// Read a text or CSV file of URLs and scan every valid entry
+#[tauri::command]
+pub async fn import_and_scan_file(path: String, app: AppHandle) -> Result<ImportReport, AppError> {
+    let bytes = tokio::fs::read(&path).await?;
+    let content = String::from_utf8_lossy(&bytes);
+    let (urls, skipped) = parse_url_list(&content);
+
+    let parsed = urls.len();
+    let items = run_batch(&app, urls, ScanSource::Import).await;
+
+    Ok(ImportReport {
+        summary: summarize(parsed, skipped.len(), &items),
+        skipped,
+        items,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::FeatureSet;
+    use crate::ScanResult;
+
+    fn completed(classification: &str) -> BatchScanItem {
+        let url = format!("https://{}.example/", classification);
+        BatchScanItem::Completed {
+            url: url.clone(),
+            result: Box::new(ScanResult {
+                url,
+                classification: classification.to_string(),
+                confidence: 0.9,
+                risk_score: 50,
+                explanation: String::new(),
+                analysis_mode: None,
+                features: FeatureSet::default(),
+                cached: false,
+                scanned_at: None,
+                red_flags: None,
+                degraded: classification == FALLBACK_CLASSIFICATION,
+                redirects: None,
+                duration_ms: None,
+            }),
+        }
+    }
+
+    #[test]
+    fn only_errors_count_as_failed() {
+        let mut items: Vec<BatchScanItem> = [
+            "phishing",
+            "phishing_kit",
+            "legitimate",
+            "suspicious",
+            "allowlisted",
+            "unresolvable",
+            FALLBACK_CLASSIFICATION,
+            "something_new",
+        ]
+        .into_iter()
+        .map(completed)
+        .collect();
+        items.push(BatchScanItem::Failed {
+            url: "https://timeout.example/".into(),
+            error: AppError::Timeout { seconds: 90 },
+        });
+
+        let summary = summarize(9, 2, &items);
+        assert_eq!((summary.parsed, summary.skipped), (9, 2));
+        assert_eq!((summary.phishing, summary.legitimate), (2, 1));
+        assert_eq!((summary.suspicious, summary.allowlisted), (1, 1));
+        assert_eq!((summary.unresolvable, summary.offline), (1, 1));
+        assert_eq!((summary.other, summary.failed), (1, 1));
+    }
+}
@@ -0,0 +1,70 @@
+use crate::error::AppError;
+use crate::queue::ScanQueue;
+use crate::AppHandle;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+use tauri::{Manager, State};
+use tokio::task::AbortHandle;
+
+/// Batches that are currently running, keyed by batch id
+#[derive(Default)]
+pub struct InFlightScans {
+    handles: Mutex<HashMap<String, AbortHandle>>,
+}
+
+/// Generate a fresh scan id
+pub fn new_scan_id() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+impl InFlightScans {
+    /// Run `work` as a cancellable task registered under `id`.
+    ///
+    /// Aborting the task drops the detector future, which kills the python3
+    /// child and everything it started, and the caller receives
+    /// `AppError::Cancelled`. Queued and running scans only the task was
+    /// waiting for are then cancelled the same way.
+    pub async fn run<T, F>(&self, app: &AppHandle, id: String, work: F) -> Result<T, AppError>
+    where
+        T: Send + 'static,
+        F: Future<Output = Result<T, AppError>> + Send + 'static,
+    {
+        let task = tokio::spawn(work);
+        self.handles.lock()?.insert(id.clone(), task.abort_handle());
+
+        let outcome = task.await;
+        self.handles.lock()?.remove(&id);
+
+        match outcome {
+            Ok(result) => result,
+            Err(e) if e.is_cancelled() => {
+                app.state::<ScanQueue>().drop_abandoned(app)?;
+                Err(AppError::Cancelled)
+            }
+            Err(e) => Err(AppError::State(format!("scan task failed: {}", e))),
+        }
+    }
+
+    /// Abort a running scan; returns false if `id` is unknown or already finished
+    pub fn cancel(&self, id: &str) -> Result<bool, AppError> {
+        match self.handles.lock()?.remove(id) {
+            Some(handle) => {
+                handle.abort();
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+}
+
+/// Cancel a queued or running scan, or a running batch, by id
+#[tauri::command]
+pub fn cancel_scan(
+    scan_id: String,
+    app: AppHandle,
+    queue: State<'_, ScanQueue>,
+    inflight: State<'_, InFlightScans>,
+) -> Result<bool, AppError> {
+    Ok(queue.cancel(&app, &scan_id)? || inflight.cancel(&scan_id)?)
+}
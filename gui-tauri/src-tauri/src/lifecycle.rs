@@ -0,0 +1,385 @@
+use crate::dns;
+use crate::error::AppError;
+use crate::heuristics::{self, HeuristicReport};
+use crate::inflight::new_scan_id;
+use crate::links::normalize_input;
+use crate::logging;
+use crate::mock;
+use crate::offline;
+use crate::perf::{self, Operation};
+use crate::redirects::{self, RedirectChain};
+use crate::settings::Settings;
+use crate::tray;
+use crate::{scan_url_internal, AppHandle, ScanResult};
+use serde::Serialize;
+use std::time::Instant;
+use tauri::{Emitter, Manager};
+
+/// Where a scan request came from
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ScanSource {
+    Manual,
+    Batch,
+    Import,
+    Email,
+    Qr,
+    Drop,
+    Clipboard,
+    Hotkey,
+    #[serde(rename = "deeplink")]
+    DeepLink,
+    Extension,
+    Download,
+    /// Answered from the synced phishing domain feed
+    Feed,
+    /// A history entry checked again with rescan_history
+    Rescan,
+    /// A scheduled check of a watchlist entry
+    Watchlist,
+}
+
+impl ScanSource {
+    /// The same snake_case name the enum serializes to
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ScanSource::Manual => "manual",
+            ScanSource::Batch => "batch",
+            ScanSource::Import => "import",
+            ScanSource::Email => "email",
+            ScanSource::Qr => "qr",
+            ScanSource::Drop => "drop",
+            ScanSource::Clipboard => "clipboard",
+            ScanSource::Hotkey => "hotkey",
+            ScanSource::DeepLink => "deeplink",
+            ScanSource::Extension => "extension",
+            ScanSource::Download => "download",
+            ScanSource::Feed => "feed",
+            ScanSource::Rescan => "rescan",
+            ScanSource::Watchlist => "watchlist",
+        }
+    }
+
+    /// Sources that scan many URLs in one go
+    pub fn is_batch(&self) -> bool {
+        matches!(
+            self,
+            ScanSource::Batch
+                | ScanSource::Import
+                | ScanSource::Email
+                | ScanSource::Drop
+                | ScanSource::Qr
+                | ScanSource::Rescan
+        )
+    }
+
+    /// Inverse of `as_str`
+    pub fn parse(name: &str) -> Option<ScanSource> {
+        [
+            ScanSource::Manual,
+            ScanSource::Batch,
+            ScanSource::Import,
+            ScanSource::Email,
+            ScanSource::Qr,
+            ScanSource::Drop,
+            ScanSource::Clipboard,
+            ScanSource::Hotkey,
+            ScanSource::DeepLink,
+            ScanSource::Extension,
+            ScanSource::Download,
+            ScanSource::Feed,
+            ScanSource::Rescan,
+            ScanSource::Watchlist,
+        ]
+        .into_iter()
+        .find(|source| source.as_str() == name)
+    }
+}
+
+/// A single URL scan with the identity its lifecycle events are keyed by
+#[derive(Debug, Clone)]
+pub struct ScanJob {
+    pub scan_id: String,
+    pub url: String,
+    pub source: ScanSource,
+    pub force: bool,
+    /// Where the URL was found, stored with the history entry
+    pub origin: Option<String>,
+    /// What the user typed or pasted, when it had to be cleaned up into `url`
+    pub input: Option<String>,
+    /// How scan_url got from the entered URL to `url`
+    pub redirects: Option<RedirectChain>,
+}
+
+impl ScanJob {
+    /// A job for `url` exactly as given; entry points use `from_input`
+    fn new(url: String, source: ScanSource) -> Self {
+        Self {
+            scan_id: new_scan_id(),
+            url,
+            source,
+            force: false,
+            origin: None,
+            input: None,
+            redirects: None,
+        }
+    }
+
+    /// A job for the URL in user-entered `input`, keeping the original text
+    /// for display when normalizing changed it
+    pub fn from_input(input: &str, source: ScanSource) -> Result<Self, AppError> {
+        let url = normalize_input(input)?;
+        let typed = input.trim();
+        Ok(Self {
+            input: (typed != url).then(|| typed.to_string()),
+            ..Self::new(url, source)
+        })
+    }
+
+    pub fn with_origin(mut self, origin: Option<String>) -> Self {
+        self.origin = origin;
+        self
+    }
+}
+
+#[derive(Serialize, Clone)]
+struct ScanEvent<'a> {
+    scan_id: &'a str,
+    url: &'a str,
+    source: ScanSource,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<&'a ScanResult>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<&'a AppError>,
+    /// Sent with `scan:queued` so the UI can show them before the verdict
+    #[serde(skip_serializing_if = "Option::is_none")]
+    red_flags: Option<HeuristicReport>,
+}
+
+fn emit(
+    app: &AppHandle,
+    event: &str,
+    job: &ScanJob,
+    outcome: Option<&Result<ScanResult, AppError>>,
+) {
+    let payload = ScanEvent {
+        scan_id: &job.scan_id,
+        url: &job.url,
+        source: job.source,
+        result: outcome.and_then(|o| o.as_ref().ok()),
+        error: outcome.and_then(|o| o.as_ref().err()),
+        red_flags: (event == "scan:queued").then(|| heuristics::check_enabled(app, &job.url)),
+    };
+    let _ = app.emit(event, payload);
+}
+
+/// Announce that a job has been accepted but not started yet
+pub fn emit_queued(app: &AppHandle, job: &ScanJob) {
+    emit(app, "scan:queued", job, None);
+}
+
+/// Announce how a job ended: `scan:completed` or `scan:failed`
+pub fn emit_outcome(app: &AppHandle, job: &ScanJob, outcome: &Result<ScanResult, AppError>) {
+    if matches!(outcome, Ok(result) if result.is_phishing()) {
+        tray::flag_phishing(app);
+    }
+    let event = if outcome.is_ok() {
+        "scan:completed"
+    } else {
+        "scan:failed"
+    };
+    emit(app, event, job, Some(outcome));
+}
+
+/// Announce that the detector's verdict for a job replaces the stand-in
+/// given while it was unavailable
+pub fn emit_upgraded(app: &AppHandle, job: &ScanJob, result: &ScanResult) {
+    emit(app, "scan:upgraded", job, Some(&Ok(result.clone())));
+}
+
+/// Run a job through the detector, emitting `scan:started` and its outcome.
+/// A job the detector is unavailable for gets `offline::fallback` instead.
+pub async fn run_job(
+    app: &AppHandle,
+    job: &ScanJob,
+    project_root: &str,
+    timeout_secs: u64,
+) -> Result<ScanResult, AppError> {
+    emit(app, "scan:started", job, None);
+    let started = Instant::now();
+    let expanded = redirects::expand_shortener(app, job).await;
+    let job = expanded.as_ref().unwrap_or(job);
+    let mock = mock::enabled(app);
+    // A host that doesn't exist has no page for the detector to fetch
+    let unresolvable = if !mock && !job.force && app.state::<Settings>().get().scan.dns_preflight {
+        dns::preflight(app, &job.url).await
+    } else {
+        None
+    };
+    let mut outcome = if mock {
+        mock::scan(&job.url, job.force).await
+    } else if let Some(result) = unresolvable {
+        Ok(result)
+    } else {
+        let detector = Instant::now();
+        let outcome = scan_url_internal(&job.url, project_root, timeout_secs, job.force).await;
+        perf::record(app, Operation::Scan, detector);
+        outcome
+    };
+    if let Ok(result) = &mut outcome {
+        heuristics::annotate(app, result);
+    }
+    let elapsed_ms = started.elapsed().as_millis() as u64;
+    match &outcome {
+        Ok(result) => tracing::info!(
+            scan_id = %job.scan_id,
+            url = %logging::url(&job.url),
+            source = job.source.as_str(),
+            classification = %result.classification,
+            risk_score = result.risk_score,
+            elapsed_ms,
+            "scan completed"
+        ),
+        Err(e) => tracing::warn!(
+            scan_id = %job.scan_id,
+            url = %logging::url(&job.url),
+            source = job.source.as_str(),
+            kind = e.kind(),
+            error = %e,
+            elapsed_ms,
+            "scan failed"
+        ),
+    }
+    // Stand in for the detector with the local checks rather than fail
+    if let Err(e) = &outcome {
+        if e.is_offline() {
+            outcome = Ok(offline::fallback(app, &job.url, e));
+        }
+    }
+    if let Ok(result) = &mut outcome {
+        result.duration_ms = Some(elapsed_ms);
+        result.redirects = job.redirects.clone();
+        if let Some(note) = job
+            .redirects
+            .as_ref()
+            .and_then(|chain| chain.failure_note())
+        {
+            result.explanation = format!("{}\n\n{}", note, result.explanation);
+        }
+    }
+    emit_outcome(app, job, &outcome);
+    outcome
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing;
+    use serde_json::{json, Value};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use tauri::Listener;
+
+    const EVENTS: [&str; 5] = [
+        "scan:queued",
+        "scan:started",
+        "scan:completed",
+        "scan:failed",
+        "scan:upgraded",
+    ];
+
+    /// Every lifecycle event the app emits, in order, as `(event, scan_id)`
+    fn collect(app: &testing::TestApp) -> Arc<Mutex<Vec<(String, String)>>> {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        for event in EVENTS {
+            let seen = seen.clone();
+            app.window.listen_any(event, move |emitted| {
+                let payload: Value = serde_json::from_str(emitted.payload()).unwrap();
+                let scan_id = payload["scan_id"].as_str().unwrap_or_default().to_string();
+                seen.lock().unwrap().push((event.to_string(), scan_id));
+            });
+        }
+        seen
+    }
+
+    /// The events seen for `scan_id`, once `count` of them are in
+    fn events_for(seen: &Mutex<Vec<(String, String)>>, scan_id: &str, count: usize) -> Vec<String> {
+        for _ in 0..250 {
+            let events: Vec<String> = seen
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(_, id)| id == scan_id)
+                .map(|(event, _)| event.clone())
+                .collect();
+            if events.len() >= count {
+                return events;
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        panic!("fewer than {} events for {}", count, scan_id);
+    }
+
+    #[test]
+    fn scans_announce_each_step_in_order() {
+        let app = testing::app(tauri::generate_handler![crate::scan_url]);
+        let seen = collect(&app);
+
+        app.invoke(
+            "scan_url",
+            json!({"url": "https://fine.example/", "scanId": "ok"}),
+        )
+        .unwrap();
+        assert_eq!(
+            events_for(&seen, "ok", 3),
+            ["scan:queued", "scan:started", "scan:completed"]
+        );
+
+        app.invoke(
+            "scan_url",
+            json!({"url": "https://fail-detector.example/", "scanId": "bad"}),
+        )
+        .unwrap_err();
+        assert_eq!(
+            events_for(&seen, "bad", 3),
+            ["scan:queued", "scan:started", "scan:failed"]
+        );
+    }
+
+    #[test]
+    fn a_replayed_verdict_is_announced_after_the_stand_in() {
+        let app = testing::app(tauri::generate_handler![crate::scan_url]);
+        let seen = collect(&app);
+
+        // The detector is unavailable, so the local checks stand in for it
+        let stand_in = app
+            .invoke(
+                "scan_url",
+                json!({"url": "https://fail-offline.example/", "scanId": "held"}),
+            )
+            .unwrap();
+        assert_eq!(stand_in["degraded"], true);
+        assert_eq!(
+            events_for(&seen, "held", 3),
+            ["scan:queued", "scan:started", "scan:completed"]
+        );
+
+        // Its replay, once the detector is back, replaces the stand-in
+        app.invoke(
+            "scan_url",
+            json!({"url": "https://back-online.example/", "scanId": "held", "force": true}),
+        )
+        .unwrap();
+        assert_eq!(
+            events_for(&seen, "held", 7),
+            [
+                "scan:queued",
+                "scan:started",
+                "scan:completed",
+                "scan:queued",
+                "scan:started",
+                "scan:completed",
+                "scan:upgraded"
+            ]
+        );
+    }
+}
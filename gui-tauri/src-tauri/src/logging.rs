@@ -0,0 +1,192 @@
+use crate::error::AppError;
+use crate::settings::{LogLevel, LoggingSettings};
+use crate::AppHandle;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{Manager, State};
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{Builder, Rotation};
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, reload, Registry};
+
+/// Log files are `phishing-guard.<date>.log` in the app log dir
+const LOG_PREFIX: &str = "phishing-guard";
+const LOG_SUFFIX: &str = "log";
+/// Daily files kept before the oldest is deleted
+const MAX_LOG_FILES: usize = 7;
+/// Older files are deleted at startup once all of them add up to more than this
+const MAX_TOTAL_BYTES: u64 = 20 * 1024 * 1024;
+
+/// Mirrors `LoggingSettings::redact_urls` for code that logs URLs
+static REDACT_URLS: AtomicBool = AtomicBool::new(true);
+
+/// Where logs go and the handle that changes their level at runtime
+pub struct Logging {
+    pub dir: PathBuf,
+    level: reload::Handle<LevelFilter, Registry>,
+    /// Flushes buffered lines when the app exits
+    _guard: WorkerGuard,
+}
+
+fn level_filter(level: LogLevel) -> LevelFilter {
+    match level {
+        LogLevel::Error => LevelFilter::ERROR,
+        LogLevel::Warn => LevelFilter::WARN,
+        LogLevel::Info => LevelFilter::INFO,
+        LogLevel::Debug => LevelFilter::DEBUG,
+        LogLevel::Trace => LevelFilter::TRACE,
+    }
+}
+
+/// Log files in `dir`, oldest first (the date in the name sorts them)
+fn log_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|path| {
+                    path.file_name()
+                        .and_then(|name| name.to_str())
+                        .is_some_and(|name| {
+                            name.starts_with(LOG_PREFIX) && name.ends_with(LOG_SUFFIX)
+                        })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort();
+    files
+}
+
+/// Delete the oldest files until the rest fit in `MAX_TOTAL_BYTES`
+fn prune(dir: &Path) {
+    let mut total = 0;
+    for path in log_files(dir).into_iter().rev() {
+        total += std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        if total > MAX_TOTAL_BYTES {
+            let _ = std::fs::remove_file(&path);
+        }
+    }
+}
+
+/// Start writing logs to `dir`, rotating daily
+pub fn init(dir: &Path) -> Result<Logging, AppError> {
+    std::fs::create_dir_all(dir)?;
+    prune(dir);
+    let appender = Builder::new()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(LOG_PREFIX)
+        .filename_suffix(LOG_SUFFIX)
+        .max_log_files(MAX_LOG_FILES)
+        .build(dir)
+        .map_err(|e| AppError::Io(format!("could not open log file: {}", e)))?;
+    let (writer, guard) = tracing_appender::non_blocking(appender);
+
+    let (filter, level) = reload::Layer::new(level_filter(LogLevel::default()));
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer().with_writer(writer).with_ansi(false))
+        .try_init()
+        .map_err(|e| AppError::State(format!("logging already set up: {}", e)))?;
+
+    Ok(Logging {
+        dir: dir.to_path_buf(),
+        level,
+        _guard: guard,
+    })
+}
+
+/// Apply a changed log level and redaction setting
+pub fn apply(app: &AppHandle, settings: &LoggingSettings) {
+    REDACT_URLS.store(settings.redact_urls, Ordering::Relaxed);
+    if let Some(logging) = app.try_state::<Logging>() {
+        let _ = logging.level.reload(level_filter(settings.level));
+    }
+}
+
+/// `url` as it should appear in the log: only scheme and host while
+/// redaction is on
+pub fn url(url: &str) -> String {
+    if !REDACT_URLS.load(Ordering::Relaxed) {
+        return url.to_string();
+    }
+    match url::Url::parse(url) {
+        Ok(parsed) if parsed.host_str().is_some() => format!(
+            "{}://{}/…",
+            parsed.scheme(),
+            parsed.host_str().unwrap_or_default()
+        ),
+        _ => "<redacted>".to_string(),
+    }
+}
+
+/// The last `lines` lines of the newest log file, read synchronously for
+/// places that can't await, like the panic hook
+pub fn tail(dir: &Path, lines: usize) -> Vec<String> {
+    let Some(newest) = log_files(dir).pop() else {
+        return Vec::new();
+    };
+    let contents = std::fs::read_to_string(newest).unwrap_or_default();
+    let all: Vec<&str> = contents.lines().collect();
+    all[all.len().saturating_sub(lines)..]
+        .iter()
+        .map(|line| line.to_string())
+        .collect()
+}
+
+/// Severity of a log line, from the level column after the timestamp
+fn line_level(line: &str) -> Option<LevelFilter> {
+    match line.split_whitespace().nth(1)? {
+        "ERROR" => Some(LevelFilter::ERROR),
+        "WARN" => Some(LevelFilter::WARN),
+        "INFO" => Some(LevelFilter::INFO),
+        "DEBUG" => Some(LevelFilter::DEBUG),
+        "TRACE" => Some(LevelFilter::TRACE),
+        _ => None,
+    }
+}
+
+/// The last `lines` log lines at `level` or more severe, oldest first
+#[tauri::command]
+pub async fn get_recent_logs(
+    lines: usize,
+    level: Option<LogLevel>,
+    logging: State<'_, Logging>,
+) -> Result<Vec<String>, AppError> {
+    let threshold = level_filter(level.unwrap_or(LogLevel::Trace));
+    let mut recent = Vec::new();
+    for path in log_files(&logging.dir).into_iter().rev() {
+        let contents = tokio::fs::read_to_string(&path).await?;
+        let mut matching: Vec<String> = contents
+            .lines()
+            .filter(|line| line_level(line).is_some_and(|l| l <= threshold))
+            .map(str::to_string)
+            .collect();
+        matching.append(&mut recent);
+        recent = matching;
+        if recent.len() >= lines {
+            break;
+        }
+    }
+    let skip = recent.len().saturating_sub(lines);
+    Ok(recent.split_off(skip))
+}
+
+/// Show the log directory in the system file manager
+#[tauri::command]
+pub fn open_log_folder(logging: State<'_, Logging>) -> Result<(), AppError> {
+    #[cfg(target_os = "macos")]
+    let opener = "open";
+    #[cfg(windows)]
+    let opener = "explorer";
+    #[cfg(not(any(target_os = "macos", windows)))]
+    let opener = "xdg-open";
+
+    std::process::Command::new(opener)
+        .arg(&logging.dir)
+        .spawn()
+        .map_err(|e| AppError::Io(format!("could not open {}: {}", logging.dir.display(), e)))?;
+    Ok(())
+}
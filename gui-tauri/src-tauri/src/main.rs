@@ -1,10 +1,101 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod active_blocking;
+mod allowlist;
+mod annotations;
+mod autostart;
+mod batch;
+mod blocklist;
+mod bookmarks;
+mod cache;
+mod cli;
+mod clipboard;
+mod close;
+mod config;
+mod crash;
+mod deep_link;
+mod detector;
+mod diff;
+mod dns;
+mod domain_info;
+mod email;
+mod error;
+mod evidence;
+mod export;
+mod features;
+mod feed;
+mod file_drop;
+mod folder_watch;
+mod governor;
+mod health;
+mod heuristics;
+mod history;
+mod host_rules;
+mod hotkey;
+mod import;
+mod inflight;
+mod lifecycle;
+mod links;
+mod logging;
+mod mini_scanner;
+mod mock;
+mod native_host;
+mod notifications;
+mod offline;
+mod perf;
+mod presence;
+mod protection;
+mod qr;
+mod queue;
+mod quiet_hours;
+mod redirects;
+mod report;
+mod rescan;
+mod result_window;
+mod search;
+mod settings;
+mod stats;
+#[cfg(test)]
+mod testing;
+mod theme;
+mod tray;
+mod updater;
+mod watchlist;
+mod window_state;
+
+use allowlist::Allowlist;
+use blocklist::Blocklist;
+use cache::ResultCache;
+use error::{AppError, ScanError};
+use features::FeatureSet;
+use inflight::{new_scan_id, InFlightScans};
+use lifecycle::{emit_outcome, ScanJob, ScanSource};
+use queue::ScanQueue;
 use serde::{Deserialize, Serialize};
-use std::process::Command;
+use std::process::{Output, Stdio};
 use std::sync::Mutex;
-use tauri::State;
+use std::time::Duration;
+use tauri::{DragDropEvent, Manager, RunEvent, State, WindowEvent};
+
+use tokio::process::Command;
+
+/// Default time allowed for one detect_enhanced.py run; multimodal analysis is slow
+const DEFAULT_SCAN_TIMEOUT_SECS: u64 = 90;
+/// Default time allowed for the python3 environment probes
+const DEFAULT_ENV_CHECK_TIMEOUT_SECS: u64 = 5;
+/// Label of the main window in tauri.conf.json
+const MAIN_WINDOW: &str = "main";
+
+/// The runtime the app runs on; tests drive the app on Tauri's mock runtime
+#[cfg(not(test))]
+pub type AppRuntime = tauri::Wry;
+#[cfg(test)]
+pub type AppRuntime = tauri::test::MockRuntime;
+pub type App<R = AppRuntime> = tauri::App<R>;
+pub type AppHandle<R = AppRuntime> = tauri::AppHandle<R>;
+pub type Window<R = AppRuntime> = tauri::Window<R>;
+pub type WebviewWindow<R = AppRuntime> = tauri::WebviewWindow<R>;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 struct ScanResult {
@@ -13,11 +104,54 @@ struct ScanResult {
     confidence: f64,
     risk_score: i32,
     explanation: String,
+    /// How the detector reached the verdict: "online", "offline" or
+    /// "whitelist"; "mock" marks a made-up verdict from mock mode and
+    /// "heuristic" a stand-in from the local checks alone
+    #[serde(default)]
+    analysis_mode: Option<String>,
+    #[serde(default)]
+    features: FeatureSet,
+    /// Served from the result cache rather than a fresh detector run
+    #[serde(default)]
+    cached: bool,
+    /// When the detector produced this result, Unix seconds
+    #[serde(default)]
+    scanned_at: Option<i64>,
+    /// Red flags spotted in the URL text itself, see `heuristics`
+    #[serde(default)]
+    red_flags: Option<heuristics::HeuristicReport>,
+    /// Not a detector verdict: the detector was unavailable, so this comes
+    /// from the local checks and the URL is held to be scanned properly
+    #[serde(default)]
+    degraded: bool,
+    /// Redirects followed to get to `url`, when scan_url was set to scan
+    /// the landing page
+    #[serde(default)]
+    redirects: Option<redirects::RedirectChain>,
+    /// How long the scan took, in milliseconds
+    #[serde(default)]
+    duration_ms: Option<u64>,
+}
+
+impl ScanResult {
+    fn is_phishing(&self) -> bool {
+        is_phishing_classification(&self.classification)
+    }
+}
+
+/// True for any of the phishing classifications the detector emits
+fn is_phishing_classification(classification: &str) -> bool {
+    matches!(
+        classification,
+        "phishing" | "ai_generated_phishing" | "phishing_kit"
+    )
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 struct AppState {
     project_root: String,
+    scan_timeout_secs: u64,
+    env_check_timeout_secs: u64,
 }
 
 impl AppState {
@@ -30,113 +164,287 @@ impl AppState {
 
         Self {
             project_root: current_dir,
+            scan_timeout_secs: env_timeout(
+                "PHISHING_GUARD_SCAN_TIMEOUT",
+                DEFAULT_SCAN_TIMEOUT_SECS,
+            ),
+            env_check_timeout_secs: env_timeout(
+                "PHISHING_GUARD_ENV_CHECK_TIMEOUT",
+                DEFAULT_ENV_CHECK_TIMEOUT_SECS,
+            ),
+        }
+    }
+
+    /// Take the timeouts from the settings, or the environment where unset
+    fn configure(&mut self, scan: &settings::ScanSettings) {
+        let defaults = AppState::new();
+        self.scan_timeout_secs = scan.timeout_secs.unwrap_or(defaults.scan_timeout_secs);
+        self.env_check_timeout_secs = scan
+            .env_check_timeout_secs
+            .unwrap_or(defaults.env_check_timeout_secs);
+    }
+
+    /// Project root and timeout needed to launch a detector scan
+    fn scan_params(state: &Mutex<AppState>) -> Result<(String, u64), AppError> {
+        let app_state = state.lock()?;
+        Ok((app_state.project_root.clone(), app_state.scan_timeout_secs))
+    }
+}
+
+/// Read a timeout override in seconds from the environment
+fn env_timeout(var: &str, default: u64) -> u64 {
+    std::env::var(var)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|secs| *secs > 0)
+        .unwrap_or(default)
+}
+
+/// A child's process group, killed if dropped before the child exits so the
+/// helpers a detector starts (a browser to scrape with, say) stop with it
+#[cfg(unix)]
+struct ProcessGroup(Option<u32>);
+
+#[cfg(unix)]
+impl Drop for ProcessGroup {
+    fn drop(&mut self) {
+        if let Some(id) = self.0 {
+            // SAFETY: sends a signal and touches no memory
+            unsafe { libc::killpg(id as libc::pid_t, libc::SIGKILL) };
         }
     }
 }
 
+/// Run a python3 invocation, killing the child and everything it started if
+/// it outlives `timeout_secs` or the future is dropped (a cancelled scan)
+async fn run_python(mut command: Command, timeout_secs: u64) -> Result<Output, AppError> {
+    command
+        .kill_on_drop(true)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    #[cfg(unix)]
+    command.process_group(0);
+
+    let program = command.as_std().get_program().to_string_lossy().to_string();
+    let started = std::time::Instant::now();
+    let child = command
+        .spawn()
+        .map_err(|e| AppError::PythonUnavailable(e.to_string()))?;
+    #[cfg(unix)]
+    let mut group = ProcessGroup(child.id());
+    match tokio::time::timeout(Duration::from_secs(timeout_secs), child.wait_with_output()).await {
+        Ok(output) => {
+            #[cfg(unix)]
+            {
+                group.0 = None;
+            }
+            let output = output.map_err(|e| AppError::PythonUnavailable(e.to_string()))?;
+            tracing::debug!(
+                program,
+                status = ?output.status.code(),
+                elapsed_ms = started.elapsed().as_millis() as u64,
+                "python finished"
+            );
+            Ok(output)
+        }
+        Err(_) => Err(AppError::Timeout {
+            seconds: timeout_secs,
+        }),
+    }
+}
+
 /// Internal function to scan a URL
-fn scan_url_internal(url: &str, project_root: &str) -> Result<ScanResult, String> {
+///
+/// `force` runs the full MLLM analysis (`--mllm`) instead of the fast path.
+async fn scan_url_internal(
+    url: &str,
+    project_root: &str,
+    timeout_secs: u64,
+    force: bool,
+) -> Result<ScanResult, AppError> {
     // Call Python script directly
-    let output = Command::new("python3")
-        .arg("detect_enhanced.py")
-        .arg("--json")
-        .arg(url)
-        .current_dir(project_root)
-        .output()
-        .map_err(|e| format!("Failed to execute Python: {}", e))?;
+    let mut command = Command::new("python3");
+    command.arg("detect_enhanced.py").arg("--json");
+    if force {
+        command.arg("--mllm");
+    }
+    command.arg(url).current_dir(project_root);
+    let output = run_python(command, timeout_secs).await?;
 
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("Python error: {}", stderr));
+        return Err(AppError::Detector {
+            code: output.status.code(),
+            detail: stderr.trim().to_string(),
+        });
     }
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
+    detector::parse(&String::from_utf8_lossy(&output.stdout), url)
+}
 
-    // Parse JSON result
-    let result: serde_json::Value =
-        serde_json::from_str(&stdout).map_err(|e| format!("Failed to parse result: {}", e))?;
+/// Scan a URL by calling Python script directly (no server needed)
+///
+/// Pass a `scan_id` to be able to abort the scan with `cancel_scan` or drop it
+/// from the queue with `remove_queued_scan`. A recent result for the same URL
+/// is returned from the cache unless `force` is set. An error carries the scan
+/// id, passed or generated, as its `reference`.
+#[tauri::command]
+async fn scan_url(
+    url: String,
+    force: Option<bool>,
+    scan_id: Option<String>,
+    app: AppHandle,
+    queue: State<'_, ScanQueue>,
+    cache: State<'_, ResultCache>,
+) -> Result<ScanResult, ScanError> {
+    let scan_id = scan_id.unwrap_or_else(new_scan_id);
+    // Refanged and cleaned up first, so the cache sees the URL actually scanned
+    let mut job = ScanJob {
+        scan_id: scan_id.clone(),
+        force: force.unwrap_or(false),
+        ..ScanJob::from_input(&url, ScanSource::Manual).map_err(|e| e.for_scan(&scan_id))?
+    };
+    let config = app.state::<settings::Settings>().get().redirects;
+    if config.enabled && config.scan_landing_page && !mock::enabled(&app) {
+        let chain = redirects::follow(&app, &job.url, config.max_hops).await;
+        if chain.redirected() {
+            job.url = chain.final_url.clone();
+            job.redirects = Some(chain);
+        }
+    }
 
-    Ok(ScanResult {
-        url: result["url"].as_str().unwrap_or(url).to_string(),
-        classification: result["classification"]
-            .as_str()
-            .unwrap_or("unknown")
-            .to_string(),
-        confidence: result["confidence"].as_f64().unwrap_or(0.0),
-        risk_score: result["risk_score"].as_i64().unwrap_or(0) as i32,
-        explanation: result["explanation"].as_str().unwrap_or("").to_string(),
-    })
+    if !job.force {
+        if let Some(mut result) = cache.get(&job.url) {
+            result.redirects = job.redirects.clone();
+            notifications::notify_verdict(&app, &job, &result);
+            emit_outcome(&app, &job, &Ok(result.clone()));
+            return Ok(result);
+        }
+    }
+    queue
+        .submit(&app, job)
+        .await
+        .map_err(|e| e.for_scan(&scan_id))
 }
 
-/// Scan a URL by calling Python script directly (no server needed)
+/// Scan a URL again with the full MLLM analysis
 #[tauri::command]
-fn scan_url(url: String, state: State<'_, Mutex<AppState>>) -> Result<ScanResult, String> {
-    let app_state = state.lock().map_err(|e| e.to_string())?;
-    scan_url_internal(&url, &app_state.project_root)
+async fn rescan_url(
+    url: String,
+    scan_id: Option<String>,
+    app: AppHandle,
+    queue: State<'_, ScanQueue>,
+) -> Result<ScanResult, ScanError> {
+    let scan_id = scan_id.unwrap_or_else(new_scan_id);
+    let job = ScanJob {
+        scan_id: scan_id.clone(),
+        force: true,
+        ..ScanJob::from_input(&url, ScanSource::Manual).map_err(|e| e.for_scan(&scan_id))?
+    };
+    queue
+        .submit(&app, job)
+        .await
+        .map_err(|e| e.for_scan(&scan_id))
 }
 
 /// Batch scan multiple URLs
 #[tauri::command]
-fn scan_batch(
+async fn scan_batch(
     urls: Vec<String>,
-    state: State<'_, Mutex<AppState>>,
-) -> Result<Vec<ScanResult>, String> {
-    let mut results = Vec::new();
-
-    let app_state = state.lock().map_err(|e| e.to_string())?;
-
+    app: AppHandle,
+    queue: State<'_, ScanQueue>,
+) -> Result<Vec<ScanResult>, AppError> {
+    let mut pending = Vec::new();
     for url in urls {
-        match scan_url_internal(&url, &app_state.project_root) {
+        // A malformed line fails on its own instead of failing the batch
+        let outcome = match ScanJob::from_input(&url, ScanSource::Batch) {
+            Ok(job) => Ok(queue.enqueue(&app, job)?),
+            Err(e) => Err(e),
+        };
+        pending.push((url, outcome));
+    }
+
+    let total = pending.len();
+    let mut results = Vec::new();
+    for (url, outcome) in pending {
+        let outcome = match outcome {
+            Ok(outcome) => outcome.await.unwrap_or(Err(AppError::Cancelled)),
+            Err(e) => Err(e),
+        };
+        match outcome {
             Ok(result) => results.push(result),
             Err(e) => {
                 results.push(ScanResult {
-                    url: url,
+                    url,
                     classification: "error".to_string(),
                     confidence: 0.0,
                     risk_score: 0,
-                    explanation: e,
+                    explanation: e.to_string(),
+                    analysis_mode: None,
+                    features: FeatureSet::default(),
+                    cached: false,
+                    scanned_at: None,
+                    red_flags: None,
+                    degraded: false,
+                    redirects: None,
+                    duration_ms: None,
                 });
             }
         }
     }
 
+    let phishing = results.iter().filter(|r| r.is_phishing()).count();
+    notifications::notify_batch_done(&app, ScanSource::Batch, total, phishing);
     Ok(results)
 }
 
-/// Check if Python environment is available
+/// Get the scan and environment check timeouts in seconds
 #[tauri::command]
-fn check_environment() -> Result<serde_json::Value, String> {
-    let output = Command::new("python3")
-        .arg("--version")
-        .output()
-        .map_err(|e| format!("Python not found: {}", e))?;
-
-    let version = String::from_utf8_lossy(&output.stdout);
+fn get_timeouts(state: State<'_, Mutex<AppState>>) -> Result<serde_json::Value, AppError> {
+    let app_state = state.lock()?;
+    Ok(serde_json::json!({
+        "scan_timeout_secs": app_state.scan_timeout_secs,
+        "env_check_timeout_secs": app_state.env_check_timeout_secs,
+    }))
+}
 
-    // Check if required packages are installed
-    let pkg_check = Command::new("python3")
-        .args(&["-c", "import sklearn, colorama; print('OK')"])
-        .output();
+/// Update the scan and/or environment check timeouts in seconds; they are
+/// saved with the rest of the settings
+#[tauri::command]
+fn set_timeouts(
+    scan_timeout_secs: Option<u64>,
+    env_check_timeout_secs: Option<u64>,
+    app: AppHandle,
+) -> Result<(), AppError> {
+    settings::update(&app, |s| {
+        if let Some(secs) = scan_timeout_secs.filter(|secs| *secs > 0) {
+            s.scan.timeout_secs = Some(secs);
+        }
+        if let Some(secs) = env_check_timeout_secs.filter(|secs| *secs > 0) {
+            s.scan.env_check_timeout_secs = Some(secs);
+        }
+    })?;
+    Ok(())
+}
 
-    let packages_ok = match pkg_check {
+/// True when python3 starts and can import the detector's required packages
+async fn packages_ready(timeout_secs: u64) -> bool {
+    let mut pkg_cmd = Command::new("python3");
+    pkg_cmd.args(["-c", "import sklearn, colorama; print('OK')"]);
+    match run_python(pkg_cmd, timeout_secs).await {
         Ok(out) => String::from_utf8_lossy(&out.stdout).contains("OK"),
         Err(_) => false,
-    };
-
-    Ok(serde_json::json!({
-        "python_version": version.trim(),
-        "packages_installed": packages_ok,
-        "status": if packages_ok { "ready" } else { "missing_dependencies" }
-    }))
+    }
 }
 
 /// Get application info
 #[tauri::command]
-fn get_app_info() -> serde_json::Value {
+fn get_app_info(app: AppHandle) -> serde_json::Value {
     serde_json::json!({
         "name": "Phishing Guard",
         "version": "2.0.0",
-        "mode": "standalone",
+        "mode": if mock::enabled(&app) { "mock" } else { "standalone" },
         "python_required": true,
         "features": [
             "Real-time URL scanning",
@@ -148,16 +456,338 @@ fn get_app_info() -> serde_json::Value {
     })
 }
 
+/// Manage the state that needs nothing from disk, i.e. everything but what
+/// `setup` loads, so tests can build the same app on the mock runtime
+fn with_state(
+    builder: tauri::Builder<AppRuntime>,
+    mock_mode: mock::MockMode,
+) -> tauri::Builder<AppRuntime> {
+    builder
+        .manage(Mutex::new(AppState::new()))
+        .manage(mock_mode)
+        .manage(presence::Presence::default())
+        .manage(clipboard::ClipboardWatcher::default())
+        .manage(InFlightScans::default())
+        .manage(ScanQueue::default())
+        .manage(ResultCache::default())
+        .manage(offline::OfflineQueue::default())
+        .manage(health::HealthMonitor::default())
+        .manage(tray::TrayStatus::default())
+        .manage(protection::Protection::default())
+        .manage(hotkey::ScanHotkey::default())
+        .manage(window_state::WindowStateTracker::default())
+        .manage(result_window::ResultWindows::default())
+        .manage(mini_scanner::MiniScanner::default())
+        .manage(notifications::NotificationTargets::default())
+        .manage(governor::NotificationGovernor::default())
+        .manage(quiet_hours::QuietHours::default())
+        .manage(theme::CurrentTheme::default())
+        .manage(folder_watch::FolderWatcher::default())
+        .manage(active_blocking::ActiveBlocking::default())
+        .manage(feed::Feed::default())
+        .manage(updater::Updates::default())
+        .manage(watchlist::Watchlist::default())
+        .manage(domain_info::DomainLookups::default())
+        .manage(dns::Dns::default())
+        .manage(redirects::Redirects::default())
+        .manage(perf::Timings::default())
+}
+
 fn main() {
-    tauri::Builder::default()
+    if let Some(invocation) = cli::parse(std::env::args().skip(1)) {
+        std::process::exit(cli::run(invocation));
+    }
+    // A browser starting the app as its native messaging host gets no windows
+    if let Some(caller) = native_host::caller(std::env::args().skip(1)) {
+        std::process::exit(native_host::run(caller));
+    }
+
+    // The window is created hidden (see tauri.conf.json) and shown in setup
+    // unless the app was launched into the tray
+    let start_minimized = autostart::start_minimized(std::env::args().skip(1));
+    let launch_links = deep_link::links_in(std::env::args().skip(1));
+    let mock_mode = mock::MockMode::from_args(std::env::args().skip(1));
+
+    let builder = tauri::Builder::<AppRuntime>::new()
+        // Must come first so a second launch exits before doing anything
+        .plugin(deep_link::single_instance())
+        .plugin(tauri_plugin_deep_link::init())
         .plugin(tauri_plugin_shell::init())
-        .manage(Mutex::new(AppState::new()))
-        .invoke_handler(tauri::generate_handler![
-            scan_url,
-            scan_batch,
-            check_environment,
-            get_app_info
-        ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .plugin(tauri_plugin_clipboard_manager::init())
+        .plugin(updater::plugin())
+        .plugin(tauri_plugin_notification::init())
+        .plugin(hotkey::plugin())
+        .plugin(autostart::plugin());
+    with_state(builder, mock_mode)
+        .setup(move |app| {
+            let log_dir = match logging::init(&app.path().app_log_dir()?) {
+                Ok(logging) => {
+                    let dir = logging.dir.clone();
+                    app.manage(logging);
+                    Some(dir)
+                }
+                // Nothing to log to yet
+                Err(e) => {
+                    eprintln!("logging disabled: {}", e);
+                    None
+                }
+            };
+            let version = app.package_info().version.to_string();
+            let crashes = crash::install(&app.path().app_data_dir()?, log_dir, version);
+            crash::announce_previous(app.handle(), &crashes);
+            app.manage(crashes);
+            app.manage(settings::Settings::load(&app.path().app_config_dir()?)?);
+            settings::apply(app.handle(), &app.state::<settings::Settings>().get());
+            let database = app.path().app_data_dir()?.join(history::DATABASE_FILE);
+            let history = history::ScanHistory::open(&database)?;
+            let allowlist = tauri::async_runtime::block_on(Allowlist::load(&history))?;
+            let blocklist = tauri::async_runtime::block_on(Blocklist::load(&history))?;
+            app.manage(history);
+            app.manage(allowlist);
+            app.manage(blocklist);
+            tray::build(app)?;
+            if let Some(window) = app.get_webview_window(MAIN_WINDOW) {
+                window_state::restore(&window.as_ref().window());
+            }
+            if !start_minimized {
+                tray::show_main_window(app.handle());
+            }
+            hotkey::bind_default(app.handle());
+            let handle = app.handle();
+            crash::supervise(handle.clone(), "scan queue", queue::drain);
+            crash::supervise(handle.clone(), "scan queue store", queue::write_store);
+            tauri::async_runtime::spawn(queue::resume(handle.clone()));
+            crash::supervise(handle.clone(), "health poller", health::poll);
+            crash::supervise(handle.clone(), "offline replay", offline::watch);
+            crash::supervise(handle.clone(), "quiet hours", quiet_hours::watch);
+            crash::supervise(handle.clone(), "native messaging", native_host::serve);
+            crash::supervise(handle.clone(), "feed sync", feed::watch);
+            crash::supervise(handle.clone(), "update check", updater::watch);
+            crash::supervise(handle.clone(), "watchlist", watchlist::watch);
+            deep_link::listen(handle, launch_links);
+            Ok(())
+        })
+        .on_window_event(|window, event| match event {
+            WindowEvent::DragDrop(DragDropEvent::Drop { paths, .. }) => {
+                tauri::async_runtime::spawn(file_drop::handle_drop(
+                    window.app_handle().clone(),
+                    paths.clone(),
+                ));
+            }
+            WindowEvent::Moved(_) | WindowEvent::Resized(_) if window.label() == MAIN_WINDOW => {
+                window_state::schedule_save(window);
+            }
+            WindowEvent::ThemeChanged(theme) if window.label() == MAIN_WINDOW => {
+                theme::os_theme_changed(window.app_handle(), *theme);
+            }
+            WindowEvent::Focused(true) if window.label() == MAIN_WINDOW => {
+                notifications::main_window_focused(window.app_handle());
+            }
+            WindowEvent::CloseRequested { api, .. } if window.label() == MAIN_WINDOW => {
+                window_state::save(window);
+                close::on_close_requested(window, api);
+            }
+            WindowEvent::Moved(position) if window.label() == mini_scanner::LABEL => {
+                mini_scanner::moved(window, *position);
+            }
+            WindowEvent::Focused(focused) if window.label() == mini_scanner::LABEL => {
+                mini_scanner::focus_changed(window, *focused);
+            }
+            WindowEvent::CloseRequested { .. } if window.label() == mini_scanner::LABEL => {
+                mini_scanner::save_position(window);
+            }
+            WindowEvent::Destroyed if window.label().starts_with(result_window::LABEL_PREFIX) => {
+                window
+                    .state::<result_window::ResultWindows>()
+                    .closed(window.label());
+            }
+            _ => {}
+        })
+        .invoke_handler({
+            let commands: Box<dyn Fn(tauri::ipc::Invoke<AppRuntime>) -> bool + Send + Sync> =
+                Box::new(tauri::generate_handler![
+                    scan_url,
+                    rescan_url,
+                    scan_batch,
+                    batch::scan_urls,
+                    bookmarks::import_bookmarks,
+                    inflight::cancel_scan,
+                    queue::get_queue_status,
+                    queue::get_queue_items,
+                    queue::clear_queue,
+                    queue::set_scan_priority,
+                    queue::remove_queued_scan,
+                    queue::set_max_concurrent_scans,
+                    offline::get_pending_scans,
+                    offline::remove_pending_scan,
+                    history::get_recent_scans,
+                    history::get_scan_history,
+                    rescan::rescan_history,
+                    diff::diff_scans,
+                    watchlist::add_to_watchlist,
+                    watchlist::remove_from_watchlist,
+                    watchlist::get_watchlist,
+                    export::export_history,
+                    report::generate_report,
+                    result_window::open_result_window,
+                    result_window::close_result_windows,
+                    mini_scanner::toggle_mini_scanner,
+                    notifications::show_notification,
+                    notifications::notify_scan_result,
+                    quiet_hours::get_notification_schedule,
+                    settings::get_settings,
+                    config::export_config,
+                    config::import_config,
+                    theme::get_theme,
+                    theme::set_theme,
+                    settings::update_settings,
+                    settings::reset_settings,
+                    quiet_hours::set_notification_schedule,
+                    stats::get_statistics,
+                    stats::get_domain_summary,
+                    stats::get_top_domains,
+                    domain_info::enrich_domain,
+                    dns::check_dns,
+                    redirects::expand_redirects,
+                    evidence::export_evidence_bundle,
+                    perf::get_performance_stats,
+                    perf::reset_performance_stats,
+                    cache::get_cache_stats,
+                    cache::clear_cache,
+                    allowlist::add_to_allowlist,
+                    allowlist::remove_from_allowlist,
+                    allowlist::get_allowlist,
+                    allowlist::export_allowlist,
+                    allowlist::import_allowlist,
+                    blocklist::add_to_blocklist,
+                    blocklist::remove_from_blocklist,
+                    blocklist::get_blocklist,
+                    annotations::set_scan_note,
+                    annotations::add_scan_tag,
+                    annotations::remove_scan_tag,
+                    annotations::get_all_tags,
+                    search::search_history,
+                    import::import_and_scan_file,
+                    email::scan_email_file,
+                    email::scan_email_content,
+                    qr::scan_qr_image,
+                    qr::scan_qr_bytes,
+                    clipboard::start_clipboard_watch,
+                    clipboard::stop_clipboard_watch,
+                    protection::get_protection_status,
+                    protection::set_protection_paused,
+                    hotkey::get_scan_shortcut,
+                    hotkey::set_scan_shortcut,
+                    autostart::get_autostart,
+                    autostart::set_autostart,
+                    close::get_close_behavior,
+                    close::set_close_behavior,
+                    close::resolve_close_request,
+                    health::check_environment,
+                    health::get_last_health_status,
+                    features::get_feature_descriptions,
+                    get_timeouts,
+                    set_timeouts,
+                    logging::get_recent_logs,
+                    logging::open_log_folder,
+                    crash::get_last_crash_report,
+                    native_host::get_native_host_status,
+                    native_host::install_native_host,
+                    native_host::uninstall_native_host,
+                    folder_watch::get_watched_folders,
+                    folder_watch::set_watched_folders,
+                    active_blocking::enable_active_blocking,
+                    active_blocking::get_blocked_hosts,
+                    active_blocking::unblock_host,
+                    feed::get_feed_status,
+                    feed::force_feed_sync,
+                    updater::check_for_updates,
+                    updater::install_update,
+                    presence::verify_user_presence,
+                    presence::get_presence_status,
+                    presence::set_presence_pin,
+                    heuristics::heuristic_check,
+                    get_app_info
+                ]);
+            // Every command passes through here, so it is logged once
+            move |invoke| {
+                tracing::debug!(command = invoke.message.command(), "command invoked");
+                commands(invoke)
+            }
+        })
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            // Quitting from the tray closes the window without a CloseRequested
+            if let RunEvent::ExitRequested { .. } = event {
+                if let Some(window) = app.get_webview_window(MAIN_WINDOW) {
+                    window_state::save(&window.as_ref().window());
+                }
+            }
+        });
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    /// Start `sh` with a background `sleep` and return the run with the
+    /// sleep's pid once it is known
+    async fn run_with_helper(
+        timeout_secs: u64,
+    ) -> (tokio::task::JoinHandle<Result<Output, AppError>>, String) {
+        let pid_file = std::env::temp_dir().join(format!("run-python-{}", new_scan_id()));
+        let mut command = Command::new("sh");
+        command.arg("-c").arg(format!(
+            "sleep 30 & echo $! > {}.tmp && mv {0}.tmp {0}; wait",
+            pid_file.display()
+        ));
+        let run = tokio::spawn(run_python(command, timeout_secs));
+        loop {
+            if let Ok(pid) = std::fs::read_to_string(&pid_file) {
+                let _ = std::fs::remove_file(&pid_file);
+                return (run, pid.trim().to_string());
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    }
+
+    /// Wait up to five seconds for `pid` to exit
+    async fn exits(pid: &str) -> bool {
+        for _ in 0..250 {
+            match std::fs::read_to_string(format!("/proc/{}/stat", pid)) {
+                Err(_) => return true,
+                Ok(stat)
+                    if stat
+                        .rsplit(')')
+                        .next()
+                        .unwrap_or_default()
+                        .trim()
+                        .starts_with('Z') =>
+                {
+                    return true
+                }
+                Ok(_) => tokio::time::sleep(Duration::from_millis(20)).await,
+            }
+        }
+        false
+    }
+
+    #[tokio::test]
+    async fn cancelling_a_run_kills_what_it_started() {
+        let (run, helper) = run_with_helper(60).await;
+        run.abort();
+        assert!(run.await.unwrap_err().is_cancelled());
+        assert!(exits(&helper).await, "helper {} outlived the run", helper);
+    }
+
+    #[tokio::test]
+    async fn a_timed_out_run_kills_what_it_started() {
+        let (run, helper) = run_with_helper(1).await;
+        assert!(matches!(
+            run.await.unwrap(),
+            Err(AppError::Timeout { seconds: 1 })
+        ));
+        assert!(exits(&helper).await, "helper {} outlived the run", helper);
+    }
 }
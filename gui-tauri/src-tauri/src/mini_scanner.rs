@@ -0,0 +1,133 @@
+use crate::error::AppError;
+use crate::settings::{self, Settings};
+use crate::theme;
+use crate::{AppHandle, WebviewWindow, Window};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{Manager, PhysicalPosition, WebviewUrl, WebviewWindowBuilder};
+
+pub const LABEL: &str = "mini-scanner";
+/// Logical size of the widget
+const WIDTH: f64 = 340.0;
+const HEIGHT: f64 = 150.0;
+/// Distance from the screen corner when no position has been saved
+const CORNER_MARGIN: i32 = 16;
+
+/// Where the mini scanner was last moved to, and a counter that cancels
+/// pending idle timers whenever the widget is used again
+#[derive(Default)]
+pub struct MiniScanner {
+    position: Mutex<Option<PhysicalPosition<i32>>>,
+    activity: AtomicU64,
+}
+
+/// Bottom-right of the primary work area, where the tray usually is
+/// (top-right on macOS, where the menu bar is)
+fn corner_position(window: &WebviewWindow) -> Option<PhysicalPosition<i32>> {
+    let monitor = window.primary_monitor().ok().flatten()?;
+    let area = monitor.work_area();
+    let size = window.outer_size().ok()?;
+    let x = area.position.x + area.size.width as i32 - size.width as i32 - CORNER_MARGIN;
+    let y = if cfg!(target_os = "macos") {
+        area.position.y + CORNER_MARGIN
+    } else {
+        area.position.y + area.size.height as i32 - size.height as i32 - CORNER_MARGIN
+    };
+    Some(PhysicalPosition::new(x, y))
+}
+
+fn create(app: &AppHandle) -> Result<(), AppError> {
+    let url = WebviewUrl::App("index.html?view=mini".into());
+    let window = WebviewWindowBuilder::new(app, LABEL, url)
+        .title("Phishing Guard mini scanner")
+        .inner_size(WIDTH, HEIGHT)
+        .resizable(false)
+        .decorations(false)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .visible(false)
+        .theme(theme::native(app.state::<Settings>().get().theme))
+        .build()
+        .map_err(|e| AppError::State(format!("could not open mini scanner: {}", e)))?;
+
+    let saved = app
+        .state::<Settings>()
+        .get()
+        .mini_scanner_position
+        .map(|[x, y]| PhysicalPosition::new(x, y));
+    if let Some(position) = saved.or_else(|| corner_position(&window)) {
+        let _ = window.set_position(position);
+    }
+    let _ = window.show();
+    let _ = window.set_focus();
+    Ok(())
+}
+
+/// Show the mini scanner, or close it if it is already on screen
+pub fn toggle(app: &AppHandle) -> Result<(), AppError> {
+    match app.get_webview_window(LABEL) {
+        Some(window) if window.is_visible().unwrap_or(false) => {
+            let _ = window.close();
+            Ok(())
+        }
+        Some(window) => {
+            let _ = window.show();
+            let _ = window.set_focus();
+            Ok(())
+        }
+        None => create(app),
+    }
+}
+
+/// Remember where the widget was dragged to
+pub fn moved(window: &Window, position: PhysicalPosition<i32>) {
+    if let Ok(mut last) = window.state::<MiniScanner>().position.lock() {
+        *last = Some(position);
+    }
+}
+
+/// Save the last position so the widget reopens where the user left it
+pub fn save_position(window: &Window) {
+    let position = window
+        .state::<MiniScanner>()
+        .position
+        .lock()
+        .ok()
+        .and_then(|p| *p);
+    if let Some(position) = position {
+        let _ = settings::update(window.app_handle(), |s| {
+            s.mini_scanner_position = Some([position.x, position.y])
+        });
+    }
+}
+
+/// Track focus: using the widget cancels the idle timer, and leaving it
+/// starts one that hides it after `mini_scanner_idle_secs` (0 disables it)
+pub fn focus_changed(window: &Window, focused: bool) {
+    let generation = window
+        .state::<MiniScanner>()
+        .activity
+        .fetch_add(1, Ordering::SeqCst)
+        + 1;
+    let idle_secs = window.state::<Settings>().get().mini_scanner_idle_secs;
+    if focused || idle_secs == 0 {
+        return;
+    }
+
+    let window = window.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(Duration::from_secs(idle_secs)).await;
+        let scanner = window.state::<MiniScanner>();
+        if scanner.activity.load(Ordering::SeqCst) == generation {
+            save_position(&window);
+            let _ = window.hide();
+        }
+    });
+}
+
+/// Open or close the always-on-top mini scanner
+#[tauri::command]
+pub async fn toggle_mini_scanner(app: AppHandle) -> Result<(), AppError> {
+    toggle(&app)
+}
@@ -0,0 +1,94 @@
+use crate::error::AppError;
+use crate::features::FeatureSet;
+use crate::history::now_secs;
+use crate::settings::Settings;
+use crate::{env_timeout, AppHandle, ScanResult};
+use std::time::Duration;
+use tauri::Manager;
+use url::Url;
+
+/// Launch argument that answers every scan with a mock verdict
+pub const MOCK_ARG: &str = "--mock-api";
+/// Default delay before a mock verdict, roughly a fast detector run
+const DEFAULT_LATENCY_MS: u64 = 400;
+
+/// Whether the app was launched with `--mock-api` or
+/// PHISHING_GUARD_MOCK_API=1, which wins over the settings toggle
+pub struct MockMode(bool);
+
+impl MockMode {
+    pub fn from_args(mut args: impl Iterator<Item = String>) -> Self {
+        let requested = std::env::var("PHISHING_GUARD_MOCK_API")
+            .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+            .unwrap_or(false);
+        Self(requested || args.any(|arg| arg == MOCK_ARG))
+    }
+}
+
+/// True when scans should skip python3 and return mock verdicts
+pub fn enabled(app: &AppHandle) -> bool {
+    app.try_state::<MockMode>().is_some_and(|mode| mode.0)
+        || app
+            .try_state::<Settings>()
+            .is_some_and(|settings| settings.get().scan.mock_detector)
+}
+
+/// A deterministic verdict for `url` after a simulated delay, for demos and
+/// frontend work without the Python stack.
+///
+/// Any URL containing "phish" is phishing and anything else legitimate. A
+/// host starting with `fail-timeout.`, `fail-detector.` or `fail-offline.`
+/// produces the matching error instead, to exercise the error paths.
+/// PHISHING_GUARD_MOCK_LATENCY_MS sets the delay.
+pub async fn scan(url: &str, force: bool) -> Result<ScanResult, AppError> {
+    let latency = env_timeout("PHISHING_GUARD_MOCK_LATENCY_MS", DEFAULT_LATENCY_MS);
+    // The full analysis is slower for real, so it is here too
+    let latency = if force { latency * 3 } else { latency };
+    tokio::time::sleep(Duration::from_millis(latency)).await;
+
+    let host = Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(str::to_ascii_lowercase))
+        .unwrap_or_default();
+    if host.starts_with("fail-timeout.") {
+        return Err(AppError::Timeout { seconds: 0 });
+    }
+    if host.starts_with("fail-detector.") {
+        return Err(AppError::Detector {
+            code: Some(1),
+            detail: "mock detector failure".into(),
+        });
+    }
+    if host.starts_with("fail-offline.") {
+        return Err(AppError::PythonUnavailable("mock detector offline".into()));
+    }
+
+    let phishing = url.to_ascii_lowercase().contains("phish");
+    let (classification, confidence, risk_score) = if phishing {
+        ("phishing", 0.97, 92)
+    } else {
+        ("legitimate", 0.95, 5)
+    };
+    Ok(ScanResult {
+        url: url.to_string(),
+        classification: classification.to_string(),
+        confidence,
+        risk_score,
+        explanation: format!(
+            "MOCK VERDICT, not a real analysis: the URL {} \"phish\"",
+            if phishing {
+                "contains"
+            } else {
+                "does not contain"
+            }
+        ),
+        analysis_mode: Some("mock".to_string()),
+        features: FeatureSet::default(),
+        cached: false,
+        scanned_at: Some(now_secs()),
+        red_flags: None,
+        degraded: false,
+        redirects: None,
+        duration_ms: None,
+    })
+}
@@ -0,0 +1,543 @@
+use crate::autostart::MINIMIZED_ARG;
+use crate::cache::ResultCache;
+use crate::error::AppError;
+use crate::lifecycle::{ScanJob, ScanSource};
+use crate::links::is_scannable_url;
+use crate::queue::ScanQueue;
+use crate::AppHandle;
+use crate::ScanResult;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, ErrorKind, Read, Write};
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tauri::Manager;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+
+/// Name browsers know the host by, and the manifest's file name
+pub const HOST_NAME: &str = "com.phishingguard.app";
+/// `identifier` in tauri.conf.json, which names the app data dir
+const APP_IDENTIFIER: &str = "com.phishingguard.app";
+/// Written by the running app: the loopback port hosts connect to and the
+/// token they must present
+const ENDPOINT_FILE: &str = "native-host.json";
+const CHROME_ORIGIN_PREFIX: &str = "chrome-extension://";
+/// Browsers refuse replies over 1 MB; no request comes near it
+const MAX_MESSAGE_BYTES: u32 = 1024 * 1024;
+/// A scan that takes longer gets a timeout reply, well before a browser
+/// would give up on the host
+const REPLY_TIMEOUT: Duration = Duration::from_secs(25);
+/// How long to wait for a desktop app the host had to start
+const LAUNCH_TIMEOUT: Duration = Duration::from_secs(15);
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Browser {
+    Chrome,
+    Firefox,
+}
+
+impl Browser {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Browser::Chrome => "chrome",
+            Browser::Firefox => "firefox",
+        }
+    }
+}
+
+/// The extension a browser launched the host for
+#[derive(Debug)]
+pub struct Caller {
+    browser: Browser,
+    id: String,
+}
+
+/// Recognise a launch by a browser: Chrome passes the calling origin
+/// (`chrome-extension://<id>/`), Firefox the manifest path and extension id
+pub fn caller(args: impl Iterator<Item = String>) -> Option<Caller> {
+    let args: Vec<String> = args.collect();
+    if let Some(origin) = args
+        .iter()
+        .find(|arg| arg.starts_with(CHROME_ORIGIN_PREFIX))
+    {
+        return Some(Caller {
+            browser: Browser::Chrome,
+            id: origin[CHROME_ORIGIN_PREFIX.len()..]
+                .trim_end_matches('/')
+                .to_string(),
+        });
+    }
+    match args.as_slice() {
+        [manifest, id, ..] if manifest.ends_with(".json") && !id.starts_with('-') => Some(Caller {
+            browser: Browser::Firefox,
+            id: id.clone(),
+        }),
+        _ => None,
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Endpoint {
+    port: u16,
+    token: String,
+}
+
+/// One scan request from a host to the running app, a line of JSON
+#[derive(Serialize, Deserialize)]
+struct RelayRequest {
+    token: String,
+    url: String,
+}
+
+/// The app data dir without a running app, resolved the way Tauri does
+fn data_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join(APP_IDENTIFIER))
+}
+
+/// Where the host manifest for `browser` goes. Windows finds manifests
+/// through the registry, so there they live in the app data dir.
+fn manifest_path(browser: Browser) -> Option<PathBuf> {
+    #[cfg(windows)]
+    let dir = data_dir()?.join("native-messaging").join(browser.as_str());
+    #[cfg(target_os = "macos")]
+    let dir = dirs::home_dir()?
+        .join("Library/Application Support")
+        .join(match browser {
+            Browser::Chrome => "Google/Chrome/NativeMessagingHosts",
+            Browser::Firefox => "Mozilla/NativeMessagingHosts",
+        });
+    #[cfg(not(any(windows, target_os = "macos")))]
+    let dir = match browser {
+        Browser::Chrome => dirs::config_dir()?.join("google-chrome/NativeMessagingHosts"),
+        Browser::Firefox => dirs::home_dir()?.join(".mozilla/native-messaging-hosts"),
+    };
+    Some(dir.join(format!("{}.json", HOST_NAME)))
+}
+
+#[cfg(windows)]
+fn registry_key(browser: Browser) -> String {
+    let vendor = match browser {
+        Browser::Chrome => r"Google\Chrome",
+        Browser::Firefox => "Mozilla",
+    };
+    format!(
+        r"HKCU\Software\{}\NativeMessagingHosts\{}",
+        vendor, HOST_NAME
+    )
+}
+
+#[cfg(windows)]
+fn reg(args: &[&str]) -> Result<(), AppError> {
+    let status = std::process::Command::new("reg").args(args).status()?;
+    if !status.success() {
+        return Err(AppError::Io(format!("reg {} failed", args[0])));
+    }
+    Ok(())
+}
+
+/// Extension ids as the manifest lists them
+fn manifest_ids(browser: Browser, manifest: &Value) -> Vec<String> {
+    let key = match browser {
+        Browser::Chrome => "allowed_origins",
+        Browser::Firefox => "allowed_extensions",
+    };
+    manifest[key]
+        .as_array()
+        .map(|ids| {
+            ids.iter()
+                .filter_map(Value::as_str)
+                .map(|id| {
+                    id.trim_start_matches(CHROME_ORIGIN_PREFIX)
+                        .trim_end_matches('/')
+                        .to_string()
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn read_manifest(browser: Browser) -> Option<Value> {
+    let contents = std::fs::read(manifest_path(browser)?).ok()?;
+    serde_json::from_slice(&contents).ok()
+}
+
+/// Chrome ids are 32 letters a–p; Firefox ids are `name@domain` or a
+/// braced UUID
+fn valid_id(browser: Browser, id: &str) -> bool {
+    match browser {
+        Browser::Chrome => id.len() == 32 && id.bytes().all(|b| (b'a'..=b'p').contains(&b)),
+        Browser::Firefox => {
+            !id.is_empty()
+                && (id.contains('@') || (id.starts_with('{') && id.ends_with('}')))
+                && !id.contains(|c: char| c.is_whitespace() || c == '"')
+        }
+    }
+}
+
+/// The binary browsers should start; an AppImage's own path moves on every run
+fn host_executable() -> Result<PathBuf, AppError> {
+    match std::env::var_os("APPIMAGE") {
+        Some(path) => Ok(PathBuf::from(path)),
+        None => Ok(std::env::current_exe()?),
+    }
+}
+
+// Host side: runs in a process the browser started, talking to it over
+// stdin/stdout and to the desktop app over loopback
+
+/// Read one length-prefixed message; `None` once the browser closes the pipe
+fn read_message(input: &mut impl Read) -> std::io::Result<Option<Vec<u8>>> {
+    let mut length = [0; 4];
+    match input.read_exact(&mut length) {
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        result => result?,
+    }
+    let length = u32::from_ne_bytes(length);
+    if length > MAX_MESSAGE_BYTES {
+        return Err(std::io::Error::new(
+            ErrorKind::InvalidData,
+            format!("message of {} bytes is too large", length),
+        ));
+    }
+    let mut body = vec![0; length as usize];
+    input.read_exact(&mut body)?;
+    Ok(Some(body))
+}
+
+fn write_message(output: &mut impl Write, message: &Value) -> std::io::Result<()> {
+    let body = serde_json::to_vec(message)?;
+    output.write_all(&(body.len() as u32).to_ne_bytes())?;
+    output.write_all(&body)?;
+    output.flush()
+}
+
+/// Messages an extension can send; an `id` field is echoed in the reply
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Request {
+    Scan { url: String },
+    Ping,
+}
+
+/// Connection to the running desktop app
+struct Relay {
+    reader: BufReader<TcpStream>,
+    token: String,
+}
+
+impl Relay {
+    fn connect() -> Option<Relay> {
+        let contents = std::fs::read(data_dir()?.join(ENDPOINT_FILE)).ok()?;
+        let endpoint: Endpoint = serde_json::from_slice(&contents).ok()?;
+        let stream = TcpStream::connect(("127.0.0.1", endpoint.port)).ok()?;
+        stream.set_read_timeout(Some(REPLY_TIMEOUT)).ok()?;
+        Some(Relay {
+            reader: BufReader::new(stream),
+            token: endpoint.token,
+        })
+    }
+
+    /// Connect, first starting the app in the tray if it isn't running
+    fn connect_or_launch() -> Result<Relay, AppError> {
+        if let Some(relay) = Relay::connect() {
+            return Ok(relay);
+        }
+        // Its output must not end up in the browser's pipe
+        std::process::Command::new(host_executable()?)
+            .arg(MINIMIZED_ARG)
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .spawn()?;
+        let deadline = Instant::now() + LAUNCH_TIMEOUT;
+        while Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(250));
+            if let Some(relay) = Relay::connect() {
+                return Ok(relay);
+            }
+        }
+        Err(AppError::State("the desktop app did not start".into()))
+    }
+
+    /// The app's reply, already in the shape the extension receives
+    fn scan(&mut self, url: &str) -> Result<Value, AppError> {
+        let request = RelayRequest {
+            token: self.token.clone(),
+            url: url.to_string(),
+        };
+        let mut line = serde_json::to_string(&request)?;
+        line.push('\n');
+        self.reader.get_mut().write_all(line.as_bytes())?;
+
+        let mut reply = String::new();
+        match self.reader.read_line(&mut reply) {
+            Ok(0) => Err(AppError::State(
+                "the desktop app closed the connection".into(),
+            )),
+            Ok(_) => Ok(serde_json::from_str(&reply)?),
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                Err(AppError::Timeout {
+                    seconds: REPLY_TIMEOUT.as_secs(),
+                })
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+fn scan_via_app(relay: &mut Option<Relay>, url: &str) -> Result<Value, AppError> {
+    if !is_scannable_url(url) {
+        return Err(AppError::InvalidInput(
+            "only http and https links can be scanned".into(),
+        ));
+    }
+    let connected = match relay {
+        Some(connected) => connected,
+        None => relay.insert(Relay::connect_or_launch()?),
+    };
+    let reply = connected.scan(url);
+    // A reply that timed out may still arrive; start afresh next time
+    if reply.is_err() {
+        *relay = None;
+    }
+    reply
+}
+
+fn answer(relay: &mut Option<Relay>, body: &[u8]) -> Value {
+    let id = serde_json::from_slice::<Value>(body)
+        .ok()
+        .and_then(|message| message.get("id").cloned());
+    let reply = serde_json::from_slice::<Request>(body)
+        .map_err(|e| AppError::InvalidInput(format!("unrecognised message: {}", e)))
+        .and_then(|request| match request {
+            Request::Scan { url } => scan_via_app(relay, &url),
+            Request::Ping => Ok(json!({ "type": "pong" })),
+        });
+    let mut reply = reply.unwrap_or_else(|e| json!({ "type": "error", "error": e }));
+    if let Some(id) = id {
+        reply["id"] = id;
+    }
+    reply
+}
+
+/// Serve the extension until the browser closes the pipe; returns the exit
+/// code. Extensions not listed in the installed manifest are turned away.
+pub fn run(caller: Caller) -> i32 {
+    let allowed = read_manifest(caller.browser)
+        .map(|manifest| manifest_ids(caller.browser, &manifest))
+        .unwrap_or_default();
+    if !allowed.contains(&caller.id) {
+        // stdout belongs to the protocol; browsers log stderr
+        eprintln!("{} is not allowed to use {}", caller.id, HOST_NAME);
+        return 1;
+    }
+
+    let (mut input, mut output) = (std::io::stdin().lock(), std::io::stdout().lock());
+    let mut relay = None;
+    loop {
+        let body = match read_message(&mut input) {
+            Ok(Some(body)) => body,
+            Ok(None) => return 0,
+            Err(e) => {
+                eprintln!("native messaging: {}", e);
+                return 1;
+            }
+        };
+        let reply = answer(&mut relay, &body);
+        if write_message(&mut output, &reply).is_err() {
+            return 1;
+        }
+    }
+}
+
+// App side: the running app answers hosts on a loopback port
+
+/// Cached and instantly decided verdicts come straight back; anything else
+/// goes through the queue like any other scan
+async fn scan(app: &AppHandle, url: String) -> Result<ScanResult, AppError> {
+    if !is_scannable_url(&url) {
+        return Err(AppError::InvalidInput(
+            "only http and https links can be scanned".into(),
+        ));
+    }
+    let job = ScanJob::from_input(&url, ScanSource::Extension)?;
+    if let Some(result) = app.state::<ResultCache>().get(&job.url) {
+        return Ok(result);
+    }
+    app.state::<ScanQueue>().submit(app, job).await
+}
+
+async fn relay(app: AppHandle, stream: tokio::net::TcpStream, token: String) {
+    let (read, mut write) = stream.into_split();
+    let mut lines = tokio::io::BufReader::new(read).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        let Ok(request) = serde_json::from_str::<RelayRequest>(&line) else {
+            return;
+        };
+        if request.token != token {
+            tracing::warn!("native messaging host presented a wrong token");
+            return;
+        }
+        let reply = match scan(&app, request.url).await {
+            Ok(result) => json!({ "type": "result", "result": result }),
+            Err(e) => json!({ "type": "error", "error": e }),
+        };
+        let mut line = reply.to_string();
+        line.push('\n');
+        if write.write_all(line.as_bytes()).await.is_err() {
+            return;
+        }
+    }
+}
+
+fn write_endpoint(dir: &Path, endpoint: &Endpoint) -> Result<(), AppError> {
+    std::fs::create_dir_all(dir)?;
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    // The token is all that keeps other users' processes out
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options
+        .open(dir.join(ENDPOINT_FILE))?
+        .write_all(&serde_json::to_vec(endpoint)?)?;
+    Ok(())
+}
+
+/// Accept hosts on a loopback port, recorded with a fresh token in the app
+/// data dir
+pub async fn serve(app: AppHandle) {
+    let listener = match tokio::net::TcpListener::bind(("127.0.0.1", 0)).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            tracing::warn!(error = %e, "native messaging unavailable");
+            return;
+        }
+    };
+    let endpoint = Endpoint {
+        port: listener.local_addr().map(|a| a.port()).unwrap_or_default(),
+        token: uuid::Uuid::new_v4().to_string(),
+    };
+    let written = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| AppError::State(e.to_string()))
+        .and_then(|dir| write_endpoint(&dir, &endpoint));
+    if let Err(e) = written {
+        tracing::warn!(error = %e, "native messaging unavailable");
+        return;
+    }
+
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                let (app, token) = (app.clone(), endpoint.token.clone());
+                tauri::async_runtime::spawn(relay(app, stream, token));
+            }
+            Err(e) => {
+                tracing::warn!(error = %e, "native messaging accept failed");
+                tokio::time::sleep(Duration::from_secs(1)).await;
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct NativeHostStatus {
+    browser: Browser,
+    installed: bool,
+    manifest: Option<String>,
+    extension_ids: Vec<String>,
+}
+
+#[tauri::command]
+pub fn get_native_host_status(browser: Browser) -> Result<NativeHostStatus, AppError> {
+    let manifest = read_manifest(browser);
+    Ok(NativeHostStatus {
+        browser,
+        installed: manifest.is_some(),
+        manifest: manifest_path(browser).map(|p| p.display().to_string()),
+        extension_ids: manifest
+            .map(|manifest| manifest_ids(browser, &manifest))
+            .unwrap_or_default(),
+    })
+}
+
+/// Register this app as the native messaging host for `browser`, usable
+/// only by the given extensions
+#[tauri::command]
+pub fn install_native_host(
+    browser: Browser,
+    extension_ids: Vec<String>,
+) -> Result<NativeHostStatus, AppError> {
+    let ids: Vec<String> = extension_ids
+        .iter()
+        .map(|id| id.trim().to_string())
+        .collect();
+    if ids.is_empty() {
+        return Err(AppError::InvalidInput(
+            "at least one extension id is needed".into(),
+        ));
+    }
+    if let Some(bad) = ids.iter().find(|id| !valid_id(browser, id)) {
+        return Err(AppError::InvalidInput(format!(
+            "\"{}\" is not a {} extension id",
+            bad,
+            browser.as_str()
+        )));
+    }
+
+    let path = manifest_path(browser)
+        .ok_or_else(|| AppError::State("no home directory to install into".into()))?;
+    let mut manifest = json!({
+        "name": HOST_NAME,
+        "description": "Phishing Guard verdicts for the browser extension",
+        "path": host_executable()?,
+        "type": "stdio",
+    });
+    match browser {
+        Browser::Chrome => {
+            let origins: Vec<String> = ids
+                .iter()
+                .map(|id| format!("{}{}/", CHROME_ORIGIN_PREFIX, id))
+                .collect();
+            manifest["allowed_origins"] = json!(origins);
+        }
+        Browser::Firefox => manifest["allowed_extensions"] = json!(ids),
+    }
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(&path, serde_json::to_vec_pretty(&manifest)?)?;
+    #[cfg(windows)]
+    reg(&[
+        "add",
+        &registry_key(browser),
+        "/ve",
+        "/t",
+        "REG_SZ",
+        "/d",
+        &path.to_string_lossy(),
+        "/f",
+    ])?;
+
+    get_native_host_status(browser)
+}
+
+/// Remove the host registration for `browser`; extensions can no longer reach the app
+#[tauri::command]
+pub fn uninstall_native_host(browser: Browser) -> Result<NativeHostStatus, AppError> {
+    #[cfg(windows)]
+    {
+        // Already gone is fine
+        let _ = reg(&["delete", &registry_key(browser), "/f"]);
+    }
+    if let Some(path) = manifest_path(browser) {
+        match std::fs::remove_file(&path) {
+            Err(e) if e.kind() != ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+    }
+    get_native_host_status(browser)
+}
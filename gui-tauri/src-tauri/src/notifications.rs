@@ -0,0 +1,330 @@
+use crate::error::AppError;
+use crate::governor::{Limits, NotificationGovernor};
+use crate::history::ScanHistory;
+use crate::lifecycle::{ScanJob, ScanSource};
+use crate::links::defang;
+use crate::protection::is_paused;
+use crate::quiet_hours;
+use crate::result_window;
+use crate::settings::{NotificationSettings, Settings};
+use crate::tray::show_main_window;
+use crate::{is_phishing_classification, AppHandle, ScanResult};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{Emitter, Manager, Runtime, State};
+use tauri_plugin_notification::NotificationExt;
+
+/// How prominently a verdict is announced
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tier {
+    /// No notification
+    Quiet,
+    /// An ordinary toast
+    Normal,
+    /// Stays on screen until dismissed, where the platform allows it
+    Urgent,
+}
+
+/// Sources with no window showing the result, so every verdict is announced
+fn announces_every_verdict(source: ScanSource) -> bool {
+    matches!(source, ScanSource::Clipboard | ScanSource::Hotkey)
+}
+
+/// Pick the notification tier for a verdict from its classification and risk score
+pub fn tier(result: &ScanResult, source: ScanSource, settings: &NotificationSettings) -> Tier {
+    if result.is_phishing() || result.risk_score >= settings.phishing_risk_score {
+        Tier::Urgent
+    } else if result.classification == "suspicious"
+        || result.risk_score >= settings.suspicious_risk_score
+        || announces_every_verdict(source)
+        || settings.notify_clean_manual_scans
+    {
+        Tier::Normal
+    } else {
+        Tier::Quiet
+    }
+}
+
+/// Whether the governor lets a notification about `url` through
+fn admitted(app: &AppHandle, url: &str, settings: &NotificationSettings) -> bool {
+    let limits = Limits {
+        per_minute: settings.max_per_minute.max(1),
+        duplicate_window: Duration::from_secs(settings.duplicate_window_secs),
+    };
+    app.state::<NotificationGovernor>().admit(app, url, limits)
+}
+
+/// Scan behind the last notification, on platforms where clicking a
+/// notification only brings the app forward
+#[derive(Default)]
+pub struct NotificationTargets {
+    last: Mutex<Option<String>>,
+}
+
+/// Show a desktop notification, ignoring platforms where it isn't available
+pub fn notify<R: Runtime>(app: &AppHandle<R>, title: &str, body: &str) {
+    let _ = app.notification().builder().title(title).body(body).show();
+}
+
+/// Bring up `scan_id` in its detail window, or in the main window if it has
+/// no saved history entry (e.g. an instant blocklist verdict)
+pub async fn open_scan(app: &AppHandle, scan_id: &str) {
+    if result_window::open(app, scan_id).await.is_err() {
+        show_main_window(app);
+        let _ = app.emit("open-scan", scan_id);
+    }
+}
+
+/// Longest a click on a Linux notification is listened for; urgent ones are
+/// shown for this long rather than until dismissed, so no listener is left
+/// waiting on a notification the server has forgotten or the user ignores
+#[cfg(target_os = "linux")]
+const ACTION_WAIT: Duration = Duration::from_secs(10 * 60);
+
+#[cfg(any(target_os = "linux", windows))]
+fn scan_notification(title: &str, body: &str, urgent: bool) -> notify_rust::Notification {
+    let mut notification = notify_rust::Notification::new();
+    notification
+        .appname("Phishing Guard")
+        .summary(title)
+        .body(body)
+        .action("default", "Open result");
+    if urgent {
+        #[cfg(target_os = "linux")]
+        notification
+            .timeout(notify_rust::Timeout::from(ACTION_WAIT))
+            .urgency(notify_rust::Urgency::Critical);
+        #[cfg(windows)]
+        notification.timeout(notify_rust::Timeout::Never);
+    }
+    notification
+}
+
+/// Show a notification that opens the result for `scan_id` when clicked;
+/// `urgent` ones stay on screen until dismissed, or on Linux for
+/// `ACTION_WAIT`. The click is awaited on the async runtime, so waiting
+/// notifications cost no threads.
+#[cfg(target_os = "linux")]
+pub fn notify_scan(app: &AppHandle, scan_id: &str, title: &str, body: &str, urgent: bool) {
+    let notification = scan_notification(title, body, urgent);
+    let (app, scan_id) = (app.clone(), scan_id.to_string());
+    let (title, body) = (title.to_string(), body.to_string());
+    tauri::async_runtime::spawn(async move {
+        let handle = match notification.show_async().await {
+            Ok(handle) => handle,
+            Err(_) => {
+                notify(&app, &title, &body);
+                return;
+            }
+        };
+        let mut clicked = false;
+        let wait = handle.wait_for_action_async(|response| {
+            clicked = match response {
+                notify_rust::NotificationResponse::Default => true,
+                notify_rust::NotificationResponse::Action(action) => action == "default",
+                _ => false,
+            };
+        });
+        if tokio::time::timeout(ACTION_WAIT, wait).await.is_err() {
+            handle.close_async().await;
+        } else if clicked {
+            open_scan(&app, &scan_id).await;
+        }
+    });
+}
+
+/// Show a notification that opens the result for `scan_id` when clicked;
+/// `urgent` ones stay on screen until dismissed
+#[cfg(windows)]
+pub fn notify_scan(app: &AppHandle, scan_id: &str, title: &str, body: &str, urgent: bool) {
+    match scan_notification(title, body, urgent).show() {
+        Ok(handle) => {
+            let (app, scan_id) = (app.clone(), scan_id.to_string());
+            // Blocks until the toast is clicked, dismissed or expires
+            std::thread::spawn(move || {
+                handle.wait_for_action(|action| {
+                    if action == "default" {
+                        tauri::async_runtime::block_on(open_scan(&app, &scan_id));
+                    }
+                })
+            });
+        }
+        Err(_) => notify(app, title, body),
+    }
+}
+
+/// Show a notification for `scan_id`; the click can't be observed here, so
+/// the result opens the next time the main window gains focus. The platform
+/// decides how long notifications stay, so `urgent` has no effect.
+#[cfg(not(any(target_os = "linux", windows)))]
+pub fn notify_scan(app: &AppHandle, scan_id: &str, title: &str, body: &str, _urgent: bool) {
+    notify(app, title, body);
+    if let Ok(mut last) = app.state::<NotificationTargets>().last.lock() {
+        *last = Some(scan_id.to_string());
+    }
+}
+
+/// Open the result behind a notification the user came back for, if any
+pub fn main_window_focused(app: &AppHandle) {
+    let pending = app
+        .state::<NotificationTargets>()
+        .last
+        .lock()
+        .ok()
+        .and_then(|mut last| last.take());
+    if let Some(scan_id) = pending {
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move { open_scan(&app, &scan_id).await });
+    }
+}
+
+/// Warn about a phishing verdict, showing the URL defanged; with a `scan_id`
+/// clicking the notification opens the result
+pub fn notify_phishing(app: &AppHandle, scan_id: Option<&str>, result: &ScanResult) {
+    if is_paused(app)
+        || quiet_hours::hold(app, true)
+        || !admitted(
+            app,
+            &result.url,
+            &app.state::<Settings>().get().notifications,
+        )
+    {
+        return;
+    }
+    let title = "Phishing detected";
+    let body = format!("{}\n{}", defang(&result.url), result.explanation);
+    match scan_id {
+        Some(scan_id) => notify_scan(app, scan_id, title, &body, true),
+        None => notify(app, title, &body),
+    }
+}
+
+/// Announce a finished scan according to its tier and the notification settings
+pub fn notify_verdict(app: &AppHandle, job: &ScanJob, result: &ScanResult) {
+    // The watchlist only speaks up when an entry turns phishing
+    if is_paused(app) || job.source == ScanSource::Watchlist {
+        return;
+    }
+    let settings = app.state::<Settings>().get().notifications;
+    let mut summary = format!(
+        "{}\nRisk score: {}/100",
+        defang(&result.url),
+        result.risk_score
+    );
+    if let Some(origin) = &job.origin {
+        summary.push_str(&format!("\nFound in {}", origin));
+    }
+    // Never let a stand-in pass for a real verdict
+    if result.degraded {
+        summary.push_str(
+            "\nLocal checks only: the detector is unavailable, so this will be scanned again",
+        );
+    }
+    let title = match job.source {
+        ScanSource::Clipboard => "Copied link",
+        ScanSource::Hotkey => "Scanned link",
+        ScanSource::DeepLink => "Linked URL",
+        ScanSource::Extension => "Browser link",
+        ScanSource::Download => "Downloaded file",
+        _ => "Scan result",
+    };
+
+    let tier = tier(result, job.source, &settings);
+    if tier == Tier::Quiet
+        || (job.source.is_batch() && settings.batch_summary_only)
+        || quiet_hours::hold(app, tier == Tier::Urgent)
+        || !admitted(app, &result.url, &settings)
+    {
+        return;
+    }
+    match tier {
+        Tier::Quiet => {}
+        Tier::Normal if result.degraded => notify_scan(
+            app,
+            &job.scan_id,
+            &format!("{}: offline check only", title),
+            &summary,
+            false,
+        ),
+        Tier::Normal => notify_scan(
+            app,
+            &job.scan_id,
+            &format!("{}: {}", title, result.classification),
+            &summary,
+            false,
+        ),
+        Tier::Urgent => notify_scan(
+            app,
+            &job.scan_id,
+            if result.degraded {
+                "Possible phishing (offline check)"
+            } else {
+                "Phishing detected"
+            },
+            &format!("{}\n{}", summary, result.explanation),
+            true,
+        ),
+    }
+}
+
+/// Announce a finished batch with its phishing and total counts
+pub fn notify_batch_done(app: &AppHandle, source: ScanSource, total: usize, phishing: usize) {
+    if is_paused(app)
+        || total == 0
+        || !app
+            .state::<Settings>()
+            .get()
+            .notifications
+            .batch_summary_only
+        || quiet_hours::hold(app, phishing > 0)
+    {
+        return;
+    }
+    let title = match source {
+        ScanSource::Import => "Import scanned",
+        ScanSource::Email => "Email links scanned",
+        ScanSource::Drop => "Dropped files scanned",
+        ScanSource::Qr => "QR codes scanned",
+        ScanSource::Rescan => "History rescanned",
+        _ => "Batch scan finished",
+    };
+    let links = if total == 1 { "link" } else { "links" };
+    let body = match phishing {
+        0 => format!("{} {} checked, no phishing found", total, links),
+        n => format!("{} of {} {} look like phishing", n, total, links),
+    };
+    notify(app, title, &body);
+}
+
+/// Show a plain notification
+#[tauri::command]
+pub fn show_notification(title: String, body: String, app: AppHandle) {
+    notify(&app, &title, &body);
+}
+
+/// Notify about a saved scan; clicking the notification opens its result
+#[tauri::command]
+pub async fn notify_scan_result(
+    scan_id: String,
+    app: AppHandle,
+    history: State<'_, ScanHistory>,
+) -> Result<(), AppError> {
+    let (entry, _) = history
+        .find(&scan_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(scan_id.clone()))?;
+
+    let phishing = is_phishing_classification(&entry.classification);
+    let title = if phishing {
+        "Phishing detected".to_string()
+    } else {
+        format!("Scan result: {}", entry.classification)
+    };
+    let body = format!(
+        "{}\nRisk score: {}/100",
+        defang(&entry.url),
+        entry.risk_score
+    );
+    notify_scan(&app, &scan_id, &title, &body, phishing);
+    Ok(())
+}
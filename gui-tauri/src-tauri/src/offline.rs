@@ -0,0 +1,357 @@
+use crate::error::AppError;
+use crate::features::FeatureSet;
+use crate::health::{HealthMonitor, HealthState};
+use crate::heuristics;
+use crate::history::{normalize_url, now_secs, ScanHistory};
+use crate::lifecycle::{ScanJob, ScanSource};
+use crate::protection::is_paused;
+use crate::queue::ScanQueue;
+use crate::AppHandle;
+use crate::ScanResult;
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use tauri::{Emitter, Manager, State};
+use tokio::sync::Notify;
+
+/// Wakes the replay task when a scan is held while it is idle
+#[derive(Default)]
+pub struct OfflineQueue {
+    wake: Notify,
+}
+
+/// A scan held back because the detector was unavailable
+#[derive(Serialize, Debug, Clone)]
+pub struct OfflineScan {
+    id: i64,
+    scan_id: String,
+    url: String,
+    source: String,
+    force: bool,
+    /// "pending", or "replaying" while it is back in the scan queue
+    status: String,
+    queued_at: i64,
+}
+
+#[derive(Serialize, Clone)]
+struct QueuedEvent<'a> {
+    scan_id: &'a str,
+    url: &'a str,
+    /// Scans now waiting for the detector, this one included
+    pending: i64,
+}
+
+#[derive(Serialize, Clone)]
+struct FlushedEvent {
+    flushed: usize,
+    /// Scans still waiting after the replay, e.g. because it failed again
+    remaining: i64,
+}
+
+/// Classification of the stand-in verdict given while the detector is down
+pub const FALLBACK_CLASSIFICATION: &str = "offline-heuristic";
+
+/// A stand-in verdict for `url` from the local heuristics, for a scan that
+/// failed with `error` because the detector is unavailable. Blocklisted and
+/// feed-listed URLs are answered before the detector is tried, so only the
+/// heuristics are left to go on.
+pub fn fallback(app: &AppHandle, url: &str, error: &AppError) -> ScanResult {
+    let report = heuristics::check_enabled(app, url);
+    ScanResult {
+        url: url.to_string(),
+        classification: FALLBACK_CLASSIFICATION.to_string(),
+        // No model looked at it, so there is nothing to be confident about
+        confidence: 0.0,
+        risk_score: report.score as i32,
+        explanation: format!(
+            "Full analysis unavailable ({}). This score comes only from local checks \
+             of the URL text, {}; it will be scanned properly once the detector is back.",
+            error,
+            match report.flags.len() {
+                0 => "which found no red flags".to_string(),
+                1 => "which found 1 red flag".to_string(),
+                n => format!("which found {} red flags", n),
+            }
+        ),
+        analysis_mode: Some("heuristic".to_string()),
+        features: FeatureSet::default(),
+        cached: false,
+        scanned_at: Some(now_secs()),
+        red_flags: Some(report),
+        degraded: true,
+        redirects: None,
+        duration_ms: None,
+    }
+}
+
+async fn pending_count(history: &ScanHistory) -> Result<i64, AppError> {
+    history
+        .with_conn(|conn| {
+            Ok(conn.query_row("SELECT count(*) FROM offline_scans", [], |row| row.get(0))?)
+        })
+        .await
+}
+
+/// Hold `job`, or point the URL's existing entry at it; the replay
+/// upgrades the stand-in row of the newest scan held for the URL
+fn hold_row(conn: &Connection, job: &ScanJob, now: i64) -> Result<(), AppError> {
+    conn.execute(
+        "INSERT INTO offline_scans (scan_id, url, normalized_url, source, force, queued_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+         ON CONFLICT (normalized_url) DO UPDATE SET
+             scan_id = excluded.scan_id,
+             status = 'pending',
+             force = max(force, excluded.force)",
+        params![
+            job.scan_id,
+            job.url,
+            normalize_url(&job.url),
+            job.source.as_str(),
+            job.force,
+            now,
+        ],
+    )?;
+    Ok(())
+}
+
+/// Persist a scan that could not reach the detector so it runs once it is back.
+///
+/// A URL is only held once; holding it again (e.g. a replay that failed, or
+/// another scan of it) returns the existing entry to `pending`.
+pub async fn hold(app: &AppHandle, job: &ScanJob) -> Result<(), AppError> {
+    let history = app.state::<ScanHistory>();
+    let held = job.clone();
+    history
+        .with_conn(move |conn| hold_row(conn, &held, now_secs()))
+        .await?;
+
+    let pending = pending_count(&history).await?;
+    let _ = app.emit(
+        "offline-queued",
+        QueuedEvent {
+            scan_id: &job.scan_id,
+            url: &job.url,
+            pending,
+        },
+    );
+    app.state::<OfflineQueue>().wake.notify_one();
+    Ok(())
+}
+
+/// Put every pending scan back through the scan queue, so replays share the
+/// normal concurrency limit, and forget the ones that no longer need retrying
+async fn flush(app: &AppHandle) -> Result<(), AppError> {
+    let history = app.state::<ScanHistory>();
+    let held = history
+        .with_conn(|conn| {
+            let tx = conn.unchecked_transaction()?;
+            let held = {
+                let mut statement = tx.prepare(
+                    "SELECT id, scan_id, url, source, force FROM offline_scans
+                     WHERE status = 'pending' ORDER BY id",
+                )?;
+                let rows = statement.query_map([], |row| {
+                    Ok((
+                        row.get::<_, i64>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, String>(2)?,
+                        row.get::<_, String>(3)?,
+                        row.get::<_, bool>(4)?,
+                    ))
+                })?;
+                rows.collect::<Result<Vec<_>, _>>()?
+            };
+            tx.execute(
+                "UPDATE offline_scans SET status = 'replaying' WHERE status = 'pending'",
+                [],
+            )?;
+            tx.commit()?;
+            Ok(held)
+        })
+        .await?;
+    if held.is_empty() {
+        return Ok(());
+    }
+
+    let queue = app.state::<ScanQueue>();
+    let mut replays = Vec::new();
+    for (id, scan_id, url, source, force) in held {
+        let job = ScanJob {
+            scan_id,
+            url,
+            source: ScanSource::parse(&source).unwrap_or(ScanSource::Manual),
+            force,
+            origin: None,
+            input: None,
+            redirects: None,
+        };
+        replays.push((id, queue.enqueue(app, job)?));
+    }
+
+    let mut flushed = 0;
+    for (id, outcome) in replays {
+        match outcome.await.unwrap_or(Err(AppError::Cancelled)) {
+            // Still no detector, so already held again by the queue
+            Ok(result) if result.degraded => continue,
+            Ok(_) => flushed += 1,
+            Err(_) => {}
+        }
+        history
+            .with_conn(move |conn| {
+                conn.execute(
+                    "DELETE FROM offline_scans WHERE id = ?1 AND status = 'replaying'",
+                    [id],
+                )?;
+                Ok(())
+            })
+            .await?;
+    }
+
+    let remaining = pending_count(&history).await?;
+    let _ = app.emit("offline-flushed", FlushedEvent { flushed, remaining });
+    Ok(())
+}
+
+/// Background task that replays held scans after a health check finds the
+/// detector ready
+pub async fn watch(app: AppHandle) {
+    let history = app.state::<ScanHistory>();
+    // Replays interrupted by a restart go back in line
+    let _ = history
+        .with_conn(|conn| {
+            conn.execute(
+                "UPDATE offline_scans SET status = 'pending' WHERE status = 'replaying'",
+                [],
+            )?;
+            Ok(())
+        })
+        .await;
+
+    let offline = app.state::<OfflineQueue>();
+    let mut health = app.state::<HealthMonitor>().subscribe();
+    loop {
+        if pending_count(&history).await.unwrap_or(0) == 0 {
+            offline.wake.notified().await;
+            continue;
+        }
+
+        // Wait for a fresh check rather than trusting the last one, which may
+        // predate the failure that held these scans
+        if health.changed().await.is_err() {
+            return;
+        }
+        let ready = matches!(&*health.borrow_and_update(), Some(s) if s.state == HealthState::Up);
+        // Replays are automatic scans, so they wait while protection is paused
+        if ready && !is_paused(&app) {
+            let _ = flush(&app).await;
+        }
+    }
+}
+
+/// Scans waiting for the detector to become available, oldest first
+#[tauri::command]
+pub async fn get_pending_scans(
+    history: State<'_, ScanHistory>,
+) -> Result<Vec<OfflineScan>, AppError> {
+    history
+        .with_conn(|conn| {
+            let mut statement = conn.prepare(
+                "SELECT id, scan_id, url, source, force, status, queued_at
+                 FROM offline_scans ORDER BY id",
+            )?;
+            let rows = statement.query_map([], |row| {
+                Ok(OfflineScan {
+                    id: row.get(0)?,
+                    scan_id: row.get(1)?,
+                    url: row.get(2)?,
+                    source: row.get(3)?,
+                    force: row.get(4)?,
+                    status: row.get(5)?,
+                    queued_at: row.get(6)?,
+                })
+            })?;
+            Ok(rows.collect::<Result<Vec<_>, _>>()?)
+        })
+        .await
+}
+
+/// Forget a held scan; returns false if there was none with that id
+#[tauri::command]
+pub async fn remove_pending_scan(
+    id: i64,
+    history: State<'_, ScanHistory>,
+) -> Result<bool, AppError> {
+    history
+        .with_conn(move |conn| {
+            Ok(conn.execute("DELETE FROM offline_scans WHERE id = ?1", [id])? > 0)
+        })
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn verdict(url: &str, classification: &str) -> ScanResult {
+        ScanResult {
+            url: url.to_string(),
+            classification: classification.to_string(),
+            confidence: 0.0,
+            risk_score: 0,
+            explanation: String::new(),
+            analysis_mode: None,
+            features: FeatureSet::default(),
+            cached: false,
+            scanned_at: Some(now_secs()),
+            red_flags: None,
+            degraded: classification == FALLBACK_CLASSIFICATION,
+            redirects: None,
+            duration_ms: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn holding_a_url_again_replays_the_newest_scan() {
+        let dir = std::env::temp_dir().join(format!("offline-test-{}", uuid::Uuid::new_v4()));
+        let history = ScanHistory::open(&dir.join("history.db")).unwrap();
+        let first = ScanJob::from_input("https://held.example/login", ScanSource::Manual).unwrap();
+        let second = ScanJob::from_input("https://held.example/login/", ScanSource::Batch).unwrap();
+        for job in [&first, &second] {
+            let stand_in = verdict(&job.url, FALLBACK_CLASSIFICATION);
+            history.record(&stand_in, job).await.unwrap();
+            let held = job.clone();
+            history
+                .with_conn(move |conn| hold_row(conn, &held, now_secs()))
+                .await
+                .unwrap();
+        }
+
+        let (count, scan_id, status) = history
+            .with_conn(|conn| {
+                Ok(conn.query_row(
+                    "SELECT count(*), scan_id, status FROM offline_scans",
+                    [],
+                    |row| {
+                        Ok((
+                            row.get::<_, i64>(0)?,
+                            row.get::<_, String>(1)?,
+                            row.get::<_, String>(2)?,
+                        ))
+                    },
+                )?)
+            })
+            .await
+            .unwrap();
+        assert_eq!(count, 1);
+        assert_eq!(scan_id, second.scan_id);
+        assert_eq!(status, "pending");
+
+        // The replay runs under the held scan id and finds its stand-in row
+        let replay = ScanJob { scan_id, ..second };
+        let upgraded = history
+            .upgrade(&verdict(&replay.url, "legitimate"), &replay)
+            .await
+            .unwrap();
+        assert!(upgraded);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
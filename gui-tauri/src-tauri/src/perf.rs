@@ -0,0 +1,180 @@
+use crate::error::AppError;
+use crate::AppHandle;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::Instant;
+use tauri::{Manager, State};
+
+/// Percentiles and the maximum are taken over this many recent timings
+const WINDOW: usize = 1000;
+
+/// What was timed
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Operation {
+    /// A detector run, from start to verdict or failure
+    Scan,
+    EnvironmentCheck,
+    /// An uncached DNS lookup
+    Dns,
+    /// Following a URL's redirects
+    Redirects,
+    /// Asking a shortener where a short link points
+    ShortLink,
+    /// An uncached RDAP or WHOIS lookup
+    DomainLookup,
+}
+
+#[derive(Default)]
+struct Samples {
+    count: u64,
+    recent: VecDeque<u64>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct OperationStats {
+    operation: Operation,
+    /// Timings since the last reset
+    count: u64,
+    p50_ms: u64,
+    p95_ms: u64,
+    max_ms: u64,
+}
+
+/// How long the slow parts of the app took, since start or the last reset
+#[derive(Default)]
+pub struct Timings {
+    samples: Mutex<HashMap<Operation, Samples>>,
+}
+
+/// Nearest-rank percentile, `percent` in 0..=100, of ascending `sorted`
+fn percentile(sorted: &[u64], percent: usize) -> u64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let rank = (percent * sorted.len() + 99) / 100;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+impl Timings {
+    pub fn record(&self, operation: Operation, elapsed_ms: u64) {
+        if let Ok(mut samples) = self.samples.lock() {
+            let samples = samples.entry(operation).or_default();
+            samples.count += 1;
+            if samples.recent.len() == WINDOW {
+                samples.recent.pop_front();
+            }
+            samples.recent.push_back(elapsed_ms);
+        }
+    }
+
+    fn stats(&self) -> Result<Vec<OperationStats>, AppError> {
+        let samples = self.samples.lock()?;
+        let mut stats: Vec<OperationStats> = samples
+            .iter()
+            .map(|(operation, samples)| {
+                let mut sorted: Vec<u64> = samples.recent.iter().copied().collect();
+                sorted.sort_unstable();
+                OperationStats {
+                    operation: *operation,
+                    count: samples.count,
+                    p50_ms: percentile(&sorted, 50),
+                    p95_ms: percentile(&sorted, 95),
+                    max_ms: sorted.last().copied().unwrap_or_default(),
+                }
+            })
+            .collect();
+        stats.sort_by_key(|stats| stats.operation);
+        Ok(stats)
+    }
+}
+
+/// Record the time since `started` against `operation`
+pub fn record(app: &AppHandle, operation: Operation, started: Instant) {
+    let elapsed_ms = started.elapsed().as_millis() as u64;
+    app.state::<Timings>().record(operation, elapsed_ms);
+}
+
+/// Count, median, 95th percentile and maximum time of each timed operation;
+/// the percentiles and maximum cover its last 1000 timings
+#[tauri::command]
+pub fn get_performance_stats(timings: State<'_, Timings>) -> Result<Vec<OperationStats>, AppError> {
+    timings.stats()
+}
+
+/// Forget every timing recorded so far
+#[tauri::command]
+pub fn reset_performance_stats(timings: State<'_, Timings>) -> Result<(), AppError> {
+    timings.samples.lock()?.clear();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn only(timings: &Timings) -> OperationStats {
+        let mut stats = timings.stats().unwrap();
+        assert_eq!(stats.len(), 1);
+        stats.remove(0)
+    }
+
+    #[test]
+    fn percentile_of_no_samples_is_zero() {
+        assert_eq!(percentile(&[], 50), 0);
+        assert_eq!(percentile(&[], 95), 0);
+        assert!(Timings::default().stats().unwrap().is_empty());
+    }
+
+    #[test]
+    fn percentile_of_one_sample_is_that_sample() {
+        for percent in [0, 1, 50, 95, 100] {
+            assert_eq!(percentile(&[42], percent), 42);
+        }
+    }
+
+    #[test]
+    fn percentile_uses_nearest_rank() {
+        let even = [10, 20, 30, 40];
+        assert_eq!(percentile(&even, 50), 20);
+        assert_eq!(percentile(&even, 95), 40);
+        assert_eq!(percentile(&even, 0), 10);
+
+        let odd = [10, 20, 30, 40, 50];
+        assert_eq!(percentile(&odd, 50), 30);
+        assert_eq!(percentile(&odd, 95), 50);
+
+        let twenty: Vec<u64> = (1..=20).collect();
+        assert_eq!(percentile(&twenty, 50), 10);
+        assert_eq!(percentile(&twenty, 95), 19);
+        assert_eq!(percentile(&twenty, 100), 20);
+    }
+
+    #[test]
+    fn stats_sort_the_samples() {
+        let timings = Timings::default();
+        for ms in [40, 10, 30, 20, 50] {
+            timings.record(Operation::Dns, ms);
+        }
+        let stats = only(&timings);
+        assert_eq!(stats.operation, Operation::Dns);
+        assert_eq!(stats.count, 5);
+        assert_eq!(stats.p50_ms, 30);
+        assert_eq!(stats.p95_ms, 50);
+        assert_eq!(stats.max_ms, 50);
+    }
+
+    #[test]
+    fn window_evicts_the_oldest_but_keeps_counting() {
+        let timings = Timings::default();
+        timings.record(Operation::Scan, 9000);
+        for _ in 0..WINDOW {
+            timings.record(Operation::Scan, 10);
+        }
+        let stats = only(&timings);
+        assert_eq!(stats.count, WINDOW as u64 + 1);
+        assert_eq!(stats.max_ms, 10);
+        assert_eq!(stats.p95_ms, 10);
+    }
+}
@@ -0,0 +1,314 @@
+use crate::error::AppError;
+use crate::settings::Settings;
+use crate::AppHandle;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{Manager, State};
+
+/// A successful check covers sensitive commands for this long, so the
+/// frontend can verify and then retry the command that asked for it
+const GRANT_LIFETIME: Duration = Duration::from_secs(120);
+/// Longest the OS prompt may stay open
+const PROMPT_TIMEOUT: Duration = Duration::from_secs(180);
+/// Wrong PINs allowed before PIN entry locks for `PIN_LOCKOUT`
+const MAX_PIN_FAILURES: u32 = 5;
+const PIN_LOCKOUT: Duration = Duration::from_secs(300);
+const PIN_LENGTH: std::ops::RangeInclusive<usize> = 4..=32;
+/// Hash rounds, so a copied PIN file can't be brute-forced instantly
+const PIN_ROUNDS: u32 = 100_000;
+/// Salted PIN hash, kept beside the settings rather than in them so it is
+/// never exported or set through update_settings
+const PIN_FILE: &str = "presence-pin.json";
+/// Exit status of the prompt scripts when there is no way to ask; clear of
+/// the statuses pkcheck uses
+const NO_PROMPT_EXIT: i32 = 77;
+
+/// Outcome of verify_user_presence
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Verification {
+    Verified,
+    Failed,
+    /// No OS prompt on this machine, or no PIN configured; see get_presence_status
+    Unsupported,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredPin {
+    salt: String,
+    hash: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct PresenceStatus {
+    require_os_auth: bool,
+    pin_configured: bool,
+    /// Whether a recent verification still covers sensitive commands
+    verified: bool,
+}
+
+#[derive(Default)]
+pub struct Presence {
+    verified_until: Mutex<Option<Instant>>,
+    pin_failures: Mutex<(u32, Option<Instant>)>,
+}
+
+impl Presence {
+    fn grant(&self) {
+        if let Ok(mut until) = self.verified_until.lock() {
+            *until = Some(Instant::now() + GRANT_LIFETIME);
+        }
+    }
+
+    fn granted(&self) -> bool {
+        self.verified_until
+            .lock()
+            .map(|until| until.is_some_and(|until| Instant::now() < until))
+            .unwrap_or(false)
+    }
+}
+
+/// Fail with `VerificationRequired` unless sensitive actions are ungated or
+/// the user verified recently. Called by the commands themselves, so a
+/// webview can't get around it.
+pub fn require(app: &AppHandle, action: &str) -> Result<(), AppError> {
+    if !app.state::<Settings>().get().require_os_auth || app.state::<Presence>().granted() {
+        return Ok(());
+    }
+    tracing::info!(action, "verification required");
+    Err(AppError::VerificationRequired(action.to_string()))
+}
+
+fn pin_path(app: &AppHandle) -> Result<PathBuf, AppError> {
+    app.path()
+        .app_config_dir()
+        .map(|dir| dir.join(PIN_FILE))
+        .map_err(|e| AppError::Io(format!("no app config directory: {}", e)))
+}
+
+fn stored_pin(app: &AppHandle) -> Option<StoredPin> {
+    let contents = std::fs::read(pin_path(app).ok()?).ok()?;
+    serde_json::from_slice(&contents).ok()
+}
+
+fn hash_pin(salt: &str, pin: &str) -> String {
+    let mut digest = Sha256::new()
+        .chain_update(salt.as_bytes())
+        .chain_update(pin.as_bytes())
+        .finalize();
+    for _ in 1..PIN_ROUNDS {
+        digest = Sha256::new()
+            .chain_update(salt.as_bytes())
+            .chain_update(digest)
+            .finalize();
+    }
+    digest.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Compare without stopping at the first difference
+fn same(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |diff, (x, y)| diff | (x ^ y))
+            == 0
+}
+
+/// The hashing runs on a blocking thread with the lock released. Each
+/// attempt counts as a failure from the moment it starts, so PINs tried in
+/// parallel can't get past `MAX_PIN_FAILURES` while they are being hashed.
+async fn check_pin(app: &AppHandle, pin: String) -> Result<Verification, AppError> {
+    let Some(stored) = stored_pin(app) else {
+        return Ok(Verification::Unsupported);
+    };
+    let presence = app.state::<Presence>();
+    {
+        let mut failures = presence.pin_failures.lock()?;
+        if failures
+            .1
+            .is_some_and(|locked| locked.elapsed() < PIN_LOCKOUT)
+            || failures.0 >= MAX_PIN_FAILURES
+        {
+            return Err(AppError::InvalidInput(
+                "too many wrong PINs; try again in a few minutes".into(),
+            ));
+        }
+        failures.0 += 1;
+    }
+
+    let salt = stored.salt;
+    let hash = tauri::async_runtime::spawn_blocking(move || hash_pin(&salt, &pin))
+        .await
+        .map_err(|e| AppError::State(format!("PIN check failed: {}", e)))?;
+
+    let mut failures = presence.pin_failures.lock()?;
+    if same(&hash, &stored.hash) {
+        *failures = (0, None);
+        return Ok(Verification::Verified);
+    }
+    if failures.0 >= MAX_PIN_FAILURES {
+        tracing::warn!("PIN entry locked after repeated failures");
+        *failures = (0, Some(Instant::now()));
+    }
+    Ok(Verification::Failed)
+}
+
+#[cfg(target_os = "macos")]
+fn os_prompt() -> tokio::process::Command {
+    // Touch ID, or the account password where there is none
+    const SCRIPT: &str = "ObjC.import('LocalAuthentication'); ObjC.import('stdlib');\n\
+        var reason = $.NSProcessInfo.processInfo.environment.objectForKey('PHISHGUARD_REASON').js;\n\
+        var context = $.LAContext.alloc.init;\n\
+        if (!context.canEvaluatePolicyError(2, null)) { $.exit(77); }\n\
+        var done = false, verified = false;\n\
+        context.evaluatePolicyLocalizedReasonReply(2, reason, function (ok, error) { verified = ok; done = true; });\n\
+        while (!done) { $.NSRunLoop.currentRunLoop.runUntilDate($.NSDate.dateWithTimeIntervalSinceNow(0.1)); }\n\
+        $.exit(verified ? 0 : 1);";
+    let mut command = tokio::process::Command::new("osascript");
+    command.args(["-l", "JavaScript", "-e", SCRIPT]);
+    command
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn os_prompt() -> tokio::process::Command {
+    // polkit shows the desktop's own authentication dialog for this process
+    let mut command = tokio::process::Command::new("pkcheck");
+    command
+        .args(["--action-id", "org.freedesktop.policykit.exec", "--process"])
+        .arg(std::process::id().to_string())
+        .arg("--allow-user-interaction");
+    command
+}
+
+#[cfg(windows)]
+fn os_prompt() -> tokio::process::Command {
+    // Windows Hello: face, fingerprint or the device PIN
+    const SCRIPT: &str = "Add-Type -AssemblyName System.Runtime.WindowsRuntime\n\
+        $asTask = [System.WindowsRuntimeSystemExtensions].GetMethods() | Where-Object { $_.Name -eq 'AsTask' -and $_.GetParameters().Count -eq 1 -and $_.GetParameters()[0].ParameterType.Name -eq 'IAsyncOperation`1' } | Select-Object -First 1\n\
+        function Await($operation, $type) { $task = $asTask.MakeGenericMethod($type).Invoke($null, @($operation)); $task.Wait() | Out-Null; $task.Result }\n\
+        $verifier = [Windows.Security.Credentials.UI.UserConsentVerifier, Windows.Security.Credentials.UI, ContentType = WindowsRuntime]\n\
+        $availability = Await ($verifier::CheckAvailabilityAsync()) ([Windows.Security.Credentials.UI.UserConsentVerifierAvailability])\n\
+        if ($availability -ne 'Available') { exit 77 }\n\
+        $result = Await ($verifier::RequestVerificationAsync($env:PHISHGUARD_REASON)) ([Windows.Security.Credentials.UI.UserConsentVerificationResult])\n\
+        if ($result -eq 'Verified') { exit 0 } else { exit 1 }";
+    let mut command = tokio::process::Command::new("powershell");
+    command.args(["-NoProfile", "-NonInteractive", "-Command", SCRIPT]);
+    command
+}
+
+/// Ask the OS to confirm the user is present. Exit status 0 is verified and
+/// `NO_PROMPT_EXIT` means the machine has no way to ask.
+async fn ask_os(reason: &str) -> Verification {
+    let mut command = os_prompt();
+    // Passed through the environment so the reason is never parsed as script
+    command.env("PHISHGUARD_REASON", reason).kill_on_drop(true);
+    match tokio::time::timeout(PROMPT_TIMEOUT, command.status()).await {
+        Ok(Ok(status)) => match status.code() {
+            Some(0) => Verification::Verified,
+            Some(NO_PROMPT_EXIT) => Verification::Unsupported,
+            _ => Verification::Failed,
+        },
+        // The helper itself is missing, e.g. no polkit
+        Ok(Err(e)) => {
+            tracing::info!(error = %e, "no OS verification available");
+            Verification::Unsupported
+        }
+        Err(_) => Verification::Failed,
+    }
+}
+
+/// Confirm the user is at the machine with the OS prompt, or with the
+/// in-app PIN when `pin` is given. Success lets gated commands run for the
+/// next two minutes.
+#[tauri::command]
+pub async fn verify_user_presence(
+    reason: String,
+    pin: Option<String>,
+    app: AppHandle,
+) -> Result<Verification, AppError> {
+    let verification = match pin {
+        Some(pin) => check_pin(&app, pin).await?,
+        None => {
+            let reason = reason.trim();
+            let reason = if reason.is_empty() {
+                "Phishing Guard needs to confirm it's you."
+            } else {
+                reason
+            };
+            ask_os(reason).await
+        }
+    };
+    tracing::info!(?verification, "user presence check");
+    if verification == Verification::Verified {
+        app.state::<Presence>().grant();
+    }
+    Ok(verification)
+}
+
+#[tauri::command]
+pub fn get_presence_status(
+    app: AppHandle,
+    settings: State<'_, Settings>,
+    presence: State<'_, Presence>,
+) -> PresenceStatus {
+    PresenceStatus {
+        require_os_auth: settings.get().require_os_auth,
+        pin_configured: stored_pin(&app).is_some(),
+        verified: presence.granted(),
+    }
+}
+
+/// Turning `require_os_auth` on or off needs a fresh verification either
+/// way: off so a webview can't drop the gate, on so the user has shown that
+/// a verification method works before being locked behind it
+pub fn check_gate_change(app: &AppHandle, before: bool, after: bool) -> Result<(), AppError> {
+    if before == after || app.state::<Presence>().granted() {
+        return Ok(());
+    }
+    Err(AppError::VerificationRequired(
+        "change require_os_auth".into(),
+    ))
+}
+
+/// Set the fallback PIN for machines without an OS prompt, or remove it
+/// with `None`. Replacing a PIN, or setting one while sensitive actions are
+/// gated, needs a verification first.
+#[tauri::command]
+pub fn set_presence_pin(pin: Option<String>, app: AppHandle) -> Result<(), AppError> {
+    let gated = app.state::<Settings>().get().require_os_auth || stored_pin(&app).is_some();
+    if gated && !app.state::<Presence>().granted() {
+        return Err(AppError::VerificationRequired("change the PIN".into()));
+    }
+    let path = pin_path(&app)?;
+    let Some(pin) = pin else {
+        return match std::fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        };
+    };
+    if !PIN_LENGTH.contains(&pin.chars().count()) {
+        return Err(AppError::InvalidInput(format!(
+            "the PIN must be {} to {} characters",
+            PIN_LENGTH.start(),
+            PIN_LENGTH.end()
+        )));
+    }
+    let salt = uuid::Uuid::new_v4().simple().to_string();
+    let stored = StoredPin {
+        hash: hash_pin(&salt, &pin),
+        salt,
+    };
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(&path, serde_json::to_vec(&stored)?)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+    }
+    Ok(())
+}
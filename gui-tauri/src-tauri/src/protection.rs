@@ -0,0 +1,143 @@
+use crate::error::AppError;
+use crate::history::now_secs;
+use crate::notifications::notify;
+use crate::tray;
+use crate::AppHandle;
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{Emitter, Manager, Runtime, State};
+
+/// History tag added to scans that ran while protection was paused
+pub const PAUSED_TAG: &str = "scanned-while-paused";
+
+#[derive(Default)]
+struct PauseState {
+    paused: bool,
+    /// Length of a timed pause, for the tray menu's check marks
+    minutes: Option<u64>,
+    /// Unix seconds at which a timed pause ends
+    resumes_at: Option<i64>,
+    /// Bumped on every change so a stale auto-resume timer does nothing
+    generation: u64,
+}
+
+/// Whether background protection (clipboard watching, automatic scans and
+/// phishing notifications) is temporarily switched off
+#[derive(Default)]
+pub struct Protection {
+    state: Mutex<PauseState>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ProtectionStatus {
+    pub paused: bool,
+    /// Minutes the current pause was set for; absent when paused until resumed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub minutes: Option<u64>,
+    /// Unix seconds at which protection resumes by itself
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resumes_at: Option<i64>,
+}
+
+impl Protection {
+    pub fn status(&self) -> ProtectionStatus {
+        match self.state.lock() {
+            Ok(state) => ProtectionStatus {
+                paused: state.paused,
+                minutes: state.minutes,
+                resumes_at: state.resumes_at,
+            },
+            Err(_) => ProtectionStatus {
+                paused: false,
+                minutes: None,
+                resumes_at: None,
+            },
+        }
+    }
+}
+
+/// True while protection is paused; manual scans still run but nothing
+/// happens in the background
+pub fn is_paused<R: Runtime>(app: &AppHandle<R>) -> bool {
+    app.state::<Protection>().status().paused
+}
+
+fn changed(app: &AppHandle) {
+    let status = app.state::<Protection>().status();
+    tray::set_paused(app, status.paused);
+    let _ = app.emit("protection-changed", status);
+}
+
+/// Pause protection for `minutes`, or until `resume` when `None`
+pub fn pause(app: &AppHandle, minutes: Option<u64>) -> Result<(), AppError> {
+    let generation = {
+        let protection = app.state::<Protection>();
+        let mut state = protection.state.lock()?;
+        state.paused = true;
+        state.minutes = minutes;
+        state.resumes_at = minutes.map(|m| now_secs() + m as i64 * 60);
+        state.generation += 1;
+        state.generation
+    };
+    changed(app);
+
+    if let Some(minutes) = minutes {
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            tokio::time::sleep(Duration::from_secs(minutes * 60)).await;
+            let current = app
+                .state::<Protection>()
+                .state
+                .lock()
+                .map(|state| state.generation == generation)
+                .unwrap_or(false);
+            if current && resume(&app).is_ok() {
+                notify(
+                    &app,
+                    "Protection resumed",
+                    "Phishing Guard is watching for phishing links again.",
+                );
+            }
+        });
+    }
+    Ok(())
+}
+
+pub fn resume(app: &AppHandle) -> Result<(), AppError> {
+    {
+        let protection = app.state::<Protection>();
+        let mut state = protection.state.lock()?;
+        *state = PauseState {
+            generation: state.generation + 1,
+            ..PauseState::default()
+        };
+    }
+    changed(app);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_protection_status(protection: State<'_, Protection>) -> ProtectionStatus {
+    protection.status()
+}
+
+/// Pause protection for `minutes` (until resumed when omitted), or resume it
+#[tauri::command]
+pub fn set_protection_paused(
+    paused: bool,
+    minutes: Option<u64>,
+    app: AppHandle,
+) -> Result<ProtectionStatus, AppError> {
+    if paused {
+        if minutes == Some(0) {
+            return Err(AppError::InvalidInput(
+                "pause duration must be at least one minute".to_string(),
+            ));
+        }
+        pause(&app, minutes)?;
+    } else {
+        resume(&app)?;
+    }
+    Ok(app.state::<Protection>().status())
+}
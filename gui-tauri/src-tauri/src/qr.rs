@@ -0,0 +1,133 @@
+use crate::batch::{run_batch, BatchScanItem};
+use crate::error::AppError;
+use crate::file_drop::{read_file, too_large, MAX_FILE_BYTES};
+use crate::lifecycle::ScanSource;
+use crate::links::is_scannable_url;
+use crate::AppHandle;
+use image::{ImageReader, Limits};
+use serde::Serialize;
+use std::io::Cursor;
+
+/// Wider or taller images are refused before decoding; a QR code in a photo
+/// or screenshot reads fine well below this
+const MAX_DIMENSION: u32 = 8192;
+/// Memory the decoder may use for one image
+const MAX_DECODE_BYTES: u64 = 256 * 1024 * 1024;
+
+/// One QR code found in an image, with its scan outcome if it held a URL
+#[derive(Serialize, Debug, Clone)]
+pub struct QrScanEntry {
+    payload: String,
+    result: Option<BatchScanItem>,
+}
+
+/// Decode a PNG or JPEG image, refusing one too large to decode safely
+fn decode_image(bytes: &[u8]) -> Result<image::DynamicImage, AppError> {
+    let mut reader = ImageReader::new(Cursor::new(bytes)).with_guessed_format()?;
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_DIMENSION);
+    limits.max_image_height = Some(MAX_DIMENSION);
+    limits.max_alloc = Some(MAX_DECODE_BYTES);
+    reader.limits(limits);
+    reader.decode().map_err(|e| AppError::Image(e.to_string()))
+}
+
+/// Decode every readable QR code in a PNG or JPEG image
+pub async fn decode_qr_codes(bytes: Vec<u8>) -> Result<Vec<String>, AppError> {
+    if bytes.len() as u64 > MAX_FILE_BYTES {
+        return Err(too_large(bytes.len() as u64));
+    }
+    tokio::task::spawn_blocking(move || {
+        let gray = decode_image(&bytes)?.to_luma8();
+
+        let mut prepared = rqrr::PreparedImage::prepare_from_greyscale(
+            gray.width() as usize,
+            gray.height() as usize,
+            |x, y| gray.get_pixel(x as u32, y as u32)[0],
+        );
+        let payloads: Vec<String> = prepared
+            .detect_grids()
+            .into_iter()
+            .filter_map(|grid| grid.decode().ok())
+            .map(|(_, content)| content)
+            .collect();
+
+        if payloads.is_empty() {
+            return Err(AppError::NoQrCode);
+        }
+        Ok(payloads)
+    })
+    .await
+    .map_err(|e| AppError::Image(format!("QR decoder crashed: {}", e)))?
+}
+
+/// Scan the URL payloads and pair every payload with its outcome
+async fn scan_payloads(app: &AppHandle, payloads: Vec<String>) -> Vec<QrScanEntry> {
+    let urls: Vec<String> = payloads
+        .iter()
+        .map(|p| p.trim().to_string())
+        .filter(|p| is_scannable_url(p))
+        .collect();
+    let mut items = run_batch(app, urls, ScanSource::Qr).await.into_iter();
+
+    payloads
+        .into_iter()
+        .map(|payload| {
+            let result = is_scannable_url(payload.trim())
+                .then(|| items.next())
+                .flatten();
+            QrScanEntry { payload, result }
+        })
+        .collect()
+}
+
+/// Decode QR codes from an image file and scan any URLs they contain
+#[tauri::command]
+pub async fn scan_qr_image(path: String, app: AppHandle) -> Result<Vec<QrScanEntry>, AppError> {
+    let bytes = read_file(path.as_ref()).await?;
+    let payloads = decode_qr_codes(bytes).await?;
+    Ok(scan_payloads(&app, payloads).await)
+}
+
+/// Decode QR codes from raw image bytes (e.g. a webcam capture)
+#[tauri::command]
+pub async fn scan_qr_bytes(bytes: Vec<u8>, app: AppHandle) -> Result<Vec<QrScanEntry>, AppError> {
+    let payloads = decode_qr_codes(bytes).await?;
+    Ok(scan_payloads(&app, payloads).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageFormat, RgbImage};
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        RgbImage::new(width, height)
+            .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+            .unwrap();
+        bytes
+    }
+
+    #[tokio::test]
+    async fn oversized_images_are_refused() {
+        let wide = decode_qr_codes(png(MAX_DIMENSION + 1, 1)).await;
+        assert!(matches!(wide, Err(AppError::Image(_))), "{:?}", wide);
+        let tall = decode_qr_codes(png(1, MAX_DIMENSION + 1)).await;
+        assert!(matches!(tall, Err(AppError::Image(_))), "{:?}", tall);
+
+        let huge = vec![0; MAX_FILE_BYTES as usize + 1];
+        assert!(matches!(
+            decode_qr_codes(huge).await,
+            Err(AppError::InvalidInput(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn images_within_the_limits_are_searched() {
+        let blank = decode_qr_codes(png(64, 64)).await;
+        assert!(matches!(blank, Err(AppError::NoQrCode)), "{:?}", blank);
+        let junk = decode_qr_codes(b"not an image".to_vec()).await;
+        assert!(matches!(junk, Err(AppError::Image(_))), "{:?}", junk);
+    }
+}
@@ -0,0 +1,1626 @@
+use crate::active_blocking;
+use crate::allowlist::Allowlist;
+use crate::annotations::tag_scan;
+use crate::blocklist::Blocklist;
+use crate::cache::ResultCache;
+use crate::error::AppError;
+use crate::feed::{self, Feed};
+use crate::heuristics;
+use crate::history::{normalize_url, now_secs, ScanHistory};
+use crate::lifecycle::{emit_outcome, emit_queued, emit_upgraded, run_job, ScanJob, ScanSource};
+use crate::notifications::{notify, notify_verdict};
+use crate::offline;
+use crate::protection::{is_paused, PAUSED_TAG};
+use crate::settings;
+use crate::tray;
+use crate::{AppHandle, AppState, ScanResult};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Manager, State};
+use tokio::sync::{mpsc, oneshot, Notify};
+use tokio::task::AbortHandle;
+use tracing::Instrument;
+
+/// Default number of detector processes the queue runs at once
+const DEFAULT_MAX_CONCURRENT_SCANS: usize = 3;
+
+/// Finished rows of the persisted queue are forgotten after this long
+const FINISHED_RETENTION_SECS: i64 = 24 * 60 * 60;
+
+/// A low-priority scan waiting this long competes as a normal one, so a long
+/// batch can't hold back scheduled work until it ends
+const AGING_INTERVAL: Duration = Duration::from_secs(60);
+
+type ScanOutcome = Result<ScanResult, AppError>;
+
+/// Which waiting scan gets the next free slot; FIFO within a level
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Priority {
+    /// Scheduled work such as watchlist rechecks
+    Low,
+    /// Batches, imports, rescans and other bulk or background work
+    Normal,
+    /// Scans someone is waiting on right now
+    High,
+}
+
+impl Priority {
+    /// The priority a job gets when it is queued
+    fn of(source: ScanSource) -> Self {
+        match source {
+            ScanSource::Manual
+            | ScanSource::Hotkey
+            | ScanSource::DeepLink
+            | ScanSource::Extension
+            | ScanSource::Clipboard => Priority::High,
+            ScanSource::Watchlist => Priority::Low,
+            _ => Priority::Normal,
+        }
+    }
+
+    /// The priority a scan queued at `self` competes at after `waited`.
+    /// Only low-priority scans age: interactive scans come at human pace, so
+    /// they can't starve normal ones, and a normal scan raised to high would
+    /// put a whole batch back ahead of the next interactive scan.
+    fn aged(self, waited: Duration) -> Self {
+        if self == Priority::Low && waited >= AGING_INTERVAL {
+            Priority::Normal
+        } else {
+            self
+        }
+    }
+}
+
+/// A request for a scan, and where its outcome goes
+struct Waiter {
+    job: ScanJob,
+    reply: oneshot::Sender<ScanOutcome>,
+}
+
+struct PendingScan {
+    job: ScanJob,
+    reply: oneshot::Sender<ScanOutcome>,
+    /// Normalized URL, which later requests for the same page share this scan by
+    key: String,
+    /// Requests sharing this scan, which get its outcome under their own ids
+    followers: Vec<Waiter>,
+    priority: Priority,
+    queued_at: Instant,
+}
+
+struct RunningScan {
+    /// The request the run reports under: the one that started it, or the
+    /// waiter it was handed to when that one left
+    job: ScanJob,
+    key: String,
+    priority: Priority,
+    abort: AbortHandle,
+    /// Requests still waiting on the run, the one that started it included;
+    /// it is aborted once none are left
+    waiters: Vec<Waiter>,
+}
+
+impl RunningScan {
+    /// Report the run under the first request still waiting on it once the
+    /// one it reports under has left, returning that request's job
+    fn hand_over(&mut self) -> Option<ScanJob> {
+        if self
+            .waiters
+            .iter()
+            .any(|w| w.job.scan_id == self.job.scan_id)
+        {
+            return None;
+        }
+        let next = self.waiters.iter().find(|w| !w.reply.is_closed())?;
+        self.job = next.job.clone();
+        Some(self.job.clone())
+    }
+}
+
+struct QueueState {
+    pending: VecDeque<PendingScan>,
+    running: HashMap<String, RunningScan>,
+    max_concurrent: usize,
+}
+
+/// What `QueueState::take_next` found at the front of the line
+enum Next {
+    Start(PendingScan),
+    /// The caller went away before its scan started
+    Dropped {
+        waiter: Waiter,
+        /// A request sharing the scan, which now has its place in line
+        promoted: Option<ScanJob>,
+    },
+}
+
+/// A request taken out of the queue by a cancellation
+struct Cancelled {
+    waiter: Waiter,
+    /// A request that shared the cancelled scan and now has its place in
+    /// line, or its run
+    promoted: Option<ScanJob>,
+    /// Nobody was left waiting on the scan's run, so it was aborted
+    aborted: bool,
+}
+
+/// One scan as reported by `get_queue_status`
+#[derive(Serialize, Debug, Clone)]
+pub struct QueueEntry {
+    scan_id: String,
+    url: String,
+    source: ScanSource,
+    /// As queued or set; a low-priority scan that has waited a while is
+    /// started as if it were normal
+    priority: Priority,
+    /// 1-based place in line; absent for scans that are already running
+    #[serde(skip_serializing_if = "Option::is_none")]
+    position: Option<usize>,
+}
+
+/// Waiting scans at each priority
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct PriorityCounts {
+    high: usize,
+    normal: usize,
+    low: usize,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct QueueStatus {
+    max_concurrent: usize,
+    running: Vec<QueueEntry>,
+    /// In the order they will start
+    pending: Vec<QueueEntry>,
+    /// Number of scans still waiting for a slot
+    length: usize,
+    by_priority: PriorityCounts,
+}
+
+/// Where a scan is in the persisted queue
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ItemStatus {
+    InProgress,
+    Done,
+    Failed,
+    Cancelled,
+}
+
+impl ItemStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            ItemStatus::InProgress => "in_progress",
+            ItemStatus::Done => "done",
+            ItemStatus::Failed => "failed",
+            ItemStatus::Cancelled => "cancelled",
+        }
+    }
+
+    fn of(outcome: &ScanOutcome) -> Self {
+        match outcome {
+            Ok(_) => ItemStatus::Done,
+            Err(AppError::Cancelled) => ItemStatus::Cancelled,
+            Err(_) => ItemStatus::Failed,
+        }
+    }
+}
+
+/// A row of the persisted queue, as reported by `get_queue_items`
+#[derive(Serialize, Debug, Clone)]
+pub struct QueueItem {
+    scan_id: String,
+    url: String,
+    source: String,
+    force: bool,
+    /// "pending", "in_progress", "done", "failed" or "cancelled"
+    status: String,
+    requested_at: i64,
+    finished_at: Option<i64>,
+}
+
+#[derive(Serialize, Clone)]
+struct ResumedEvent {
+    resumed: usize,
+    /// Scans that had already finished and were not run again
+    skipped: usize,
+}
+
+/// A change to the queue table. Changes are applied one at a time in the
+/// order they were made, so a job's status never lands before its row.
+enum StoreOp {
+    Insert {
+        job: ScanJob,
+        requested_at: i64,
+    },
+    Mark {
+        scan_id: String,
+        status: ItemStatus,
+        at: i64,
+    },
+}
+
+fn write(conn: &Connection, op: &StoreOp) -> Result<(), AppError> {
+    match op {
+        // A resumed job keeps its original row
+        StoreOp::Insert { job, requested_at } => conn.execute(
+            "INSERT OR IGNORE INTO queued_scans
+                 (scan_id, url, normalized_url, source, force, origin, input, requested_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                job.scan_id,
+                job.url,
+                normalize_url(&job.url),
+                job.source.as_str(),
+                job.force,
+                job.origin,
+                job.input,
+                requested_at,
+            ],
+        )?,
+        StoreOp::Mark {
+            scan_id,
+            status,
+            at,
+        } => conn.execute(
+            "UPDATE queued_scans SET status = ?1, finished_at = ?2 WHERE scan_id = ?3",
+            params![
+                status.as_str(),
+                (*status != ItemStatus::InProgress).then_some(*at),
+                scan_id
+            ],
+        )?,
+    };
+    Ok(())
+}
+
+/// Keep `job` in the queue table so a restart doesn't lose it. Watchlist
+/// rechecks come round again anyway, so they are not kept.
+fn persist(app: &AppHandle, job: &ScanJob) {
+    if job.source == ScanSource::Watchlist {
+        return;
+    }
+    let op = StoreOp::Insert {
+        job: job.clone(),
+        requested_at: now_secs(),
+    };
+    let _ = app.state::<ScanQueue>().store.send(op);
+}
+
+/// Move `scan_id` to `status` in the queue table, stamping when it finished
+fn mark(app: &AppHandle, scan_id: &str, status: ItemStatus) {
+    let op = StoreOp::Mark {
+        scan_id: scan_id.to_string(),
+        status,
+        at: now_secs(),
+    };
+    let _ = app.state::<ScanQueue>().store.send(op);
+}
+
+/// Scans a restart interrupted, in the order they were asked for, and how
+/// many unfinished rows were skipped. A scan whose result reached the
+/// history before the app went away, or that the offline queue is holding
+/// for replay, is not run again.
+fn take_resumable(conn: &Connection, now: i64) -> Result<(Vec<ScanJob>, usize), AppError> {
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(
+        "DELETE FROM queued_scans WHERE finished_at < ?1",
+        [now - FINISHED_RETENTION_SECS],
+    )?;
+    let skipped = tx.execute(
+        "UPDATE queued_scans SET status = 'done', finished_at = ?1
+         WHERE status IN ('pending', 'in_progress')
+           AND (EXISTS (SELECT 1 FROM scans WHERE scans.scan_id = queued_scans.scan_id)
+                OR EXISTS (SELECT 1 FROM scans
+                           WHERE scans.normalized_url = queued_scans.normalized_url
+                             AND scans.scanned_at >= queued_scans.requested_at)
+                OR EXISTS (SELECT 1 FROM offline_scans
+                           WHERE offline_scans.scan_id = queued_scans.scan_id))",
+        [now],
+    )?;
+    // Scans that were running when the app went away start over
+    tx.execute(
+        "UPDATE queued_scans SET status = 'pending' WHERE status = 'in_progress'",
+        [],
+    )?;
+    let jobs = {
+        let mut statement = tx.prepare(
+            "SELECT scan_id, url, source, force, origin, input FROM queued_scans
+             WHERE status = 'pending' ORDER BY requested_at, rowid",
+        )?;
+        let rows = statement.query_map([], |row| {
+            Ok(ScanJob {
+                scan_id: row.get(0)?,
+                url: row.get(1)?,
+                source: ScanSource::parse(&row.get::<_, String>(2)?).unwrap_or(ScanSource::Manual),
+                force: row.get(3)?,
+                origin: row.get(4)?,
+                input: row.get(5)?,
+                redirects: None,
+            })
+        })?;
+        rows.collect::<Result<Vec<_>, _>>()?
+    };
+    tx.commit()?;
+    Ok((jobs, skipped))
+}
+
+/// Every detector scan goes through this queue, which runs at most
+/// `max_concurrent` of them at a time. Waiting scans start by priority,
+/// oldest first within a level.
+pub struct ScanQueue {
+    state: Mutex<QueueState>,
+    wake: Notify,
+    /// Changes to the queue table, applied in order by `write_store`
+    store: mpsc::UnboundedSender<StoreOp>,
+    store_changes: Mutex<Option<mpsc::UnboundedReceiver<StoreOp>>>,
+}
+
+impl Default for ScanQueue {
+    fn default() -> Self {
+        let (store, store_changes) = mpsc::unbounded_channel();
+        Self {
+            store,
+            store_changes: Mutex::new(Some(store_changes)),
+            state: Mutex::new(QueueState::new(ScanQueue::default_max_concurrent())),
+            wake: Notify::new(),
+        }
+    }
+}
+
+/// What waiting scans are started by: the highest priority after aging,
+/// then the earliest queued
+fn schedule_key(pending: &PendingScan, now: Instant) -> (Reverse<Priority>, Instant) {
+    let waited = now.saturating_duration_since(pending.queued_at);
+    (Reverse(pending.priority.aged(waited)), pending.queued_at)
+}
+
+/// Indices of `pending` in the order they will start
+fn schedule(pending: &VecDeque<PendingScan>, now: Instant) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..pending.len()).collect();
+    order.sort_by_key(|&index| schedule_key(&pending[index], now));
+    order
+}
+
+impl QueueState {
+    fn new(max_concurrent: usize) -> Self {
+        Self {
+            pending: VecDeque::new(),
+            running: HashMap::new(),
+            max_concurrent,
+        }
+    }
+
+    /// Queue `waiter` at `priority`, or, unless it is forced, join it to the
+    /// waiting or running scan of the same URL. Returns true if it joined.
+    fn push(&mut self, waiter: Waiter, priority: Priority, now: Instant) -> bool {
+        let key = normalize_url(&waiter.job.url);
+        let waiter = if waiter.job.force {
+            waiter
+        } else {
+            match self.join(&key, priority, waiter) {
+                Some(waiter) => waiter,
+                None => return true,
+            }
+        };
+        self.pending.push_back(PendingScan {
+            job: waiter.job,
+            reply: waiter.reply,
+            key,
+            followers: Vec::new(),
+            priority,
+            queued_at: now,
+        });
+        false
+    }
+
+    /// Join `waiter` to the waiting or running scan of `key`, raising a
+    /// waiting one to `priority`; gives `waiter` back if there is none
+    fn join(&mut self, key: &str, priority: Priority, waiter: Waiter) -> Option<Waiter> {
+        if let Some(pending) = self.pending.iter_mut().find(|p| p.key == key) {
+            pending.priority = pending.priority.max(priority);
+            pending.followers.push(waiter);
+            return None;
+        }
+        match self.running.values_mut().find(|r| r.key == key) {
+            Some(running) => {
+                running.waiters.push(waiter);
+                None
+            }
+            None => Some(waiter),
+        }
+    }
+
+    /// Take the waiting scan that should start next, if a slot is free
+    fn take_next(&mut self, now: Instant) -> Option<Next> {
+        if self.running.len() >= self.max_concurrent {
+            return None;
+        }
+        let index =
+            (0..self.pending.len()).min_by_key(|&index| schedule_key(&self.pending[index], now))?;
+        let next = self.pending.remove(index)?;
+        if !next.reply.is_closed() {
+            return Some(Next::Start(next));
+        }
+        let (waiter, promoted) = promote(next);
+        let promoted = promoted.map(|promoted| {
+            let job = promoted.job.clone();
+            self.pending.insert(index, promoted);
+            job
+        });
+        Some(Next::Dropped { waiter, promoted })
+    }
+
+    /// Count `scan` as running under the task `abort` stops
+    fn start(&mut self, scan: PendingScan, abort: AbortHandle) {
+        let PendingScan {
+            job,
+            reply,
+            key,
+            followers,
+            priority,
+            ..
+        } = scan;
+        let mut waiters = vec![Waiter {
+            job: job.clone(),
+            reply,
+        }];
+        waiters.extend(followers);
+        self.running.insert(
+            job.scan_id.clone(),
+            RunningScan {
+                job,
+                key,
+                priority,
+                abort,
+                waiters,
+            },
+        );
+    }
+
+    /// The request the run started for `scan_id` now reports under
+    fn owner(&self, scan_id: &str) -> Option<ScanJob> {
+        self.running.get(scan_id).map(|running| running.job.clone())
+    }
+
+    /// Free the slot of the run started for `scan_id`, returning the
+    /// requests still waiting on it
+    fn finish(&mut self, scan_id: &str) -> Vec<Waiter> {
+        self.running
+            .remove(scan_id)
+            .map(|running| running.waiters)
+            .unwrap_or_default()
+    }
+
+    /// Take a request that has not started out of the queue. A scan taken
+    /// out is replaced in line by the first request sharing it.
+    fn remove_waiting(&mut self, scan_id: &str) -> Option<Cancelled> {
+        if let Some(index) = self.pending.iter().position(|p| p.job.scan_id == scan_id) {
+            let (waiter, next) = promote(self.pending.remove(index)?);
+            let promoted = next.map(|next| {
+                let job = next.job.clone();
+                self.pending.insert(index, next);
+                job
+            });
+            return Some(Cancelled {
+                waiter,
+                promoted,
+                aborted: false,
+            });
+        }
+        for pending in &mut self.pending {
+            if let Some(index) = pending
+                .followers
+                .iter()
+                .position(|f| f.job.scan_id == scan_id)
+            {
+                return Some(Cancelled {
+                    waiter: pending.followers.remove(index),
+                    promoted: None,
+                    aborted: false,
+                });
+            }
+        }
+        None
+    }
+
+    /// Stop waiting on a running scan for the request `scan_id`. The run goes
+    /// on for the other requests sharing it, under the first of them if it was
+    /// reporting under `scan_id`, and is aborted once there are none.
+    fn detach_running(&mut self, scan_id: &str) -> Option<Cancelled> {
+        for running in self.running.values_mut() {
+            if let Some(index) = running
+                .waiters
+                .iter()
+                .position(|w| w.job.scan_id == scan_id)
+            {
+                let waiter = running.waiters.remove(index);
+                let aborted = running.waiters.iter().all(|w| w.reply.is_closed());
+                if aborted {
+                    running.abort.abort();
+                }
+                let promoted = if aborted { None } else { running.hand_over() };
+                return Some(Cancelled {
+                    waiter,
+                    promoted,
+                    aborted,
+                });
+            }
+        }
+        None
+    }
+
+    /// Take out the requests nobody is waiting for any more, e.g. those of a
+    /// cancelled batch, so they don't linger until their turn comes. A scan
+    /// whose own caller left but that others share goes on under the first
+    /// of them, whose job is returned; so does a run. Runs nobody waits on
+    /// are aborted.
+    fn take_abandoned(&mut self) -> (Vec<Waiter>, Vec<ScanJob>) {
+        let mut dropped = Vec::new();
+        let mut promoted = Vec::new();
+        for mut pending in std::mem::take(&mut self.pending) {
+            let (closed, open): (Vec<Waiter>, Vec<Waiter>) = std::mem::take(&mut pending.followers)
+                .into_iter()
+                .partition(|f| f.reply.is_closed());
+            dropped.extend(closed);
+            pending.followers = open;
+            if !pending.reply.is_closed() {
+                self.pending.push_back(pending);
+                continue;
+            }
+            let (waiter, next) = promote(pending);
+            dropped.push(waiter);
+            if let Some(next) = next {
+                promoted.push(next.job.clone());
+                self.pending.push_back(next);
+            }
+        }
+
+        for running in self.running.values_mut() {
+            let (closed, open): (Vec<Waiter>, Vec<Waiter>) = std::mem::take(&mut running.waiters)
+                .into_iter()
+                .partition(|w| w.reply.is_closed());
+            if !closed.is_empty() && open.is_empty() {
+                running.abort.abort();
+            }
+            dropped.extend(closed);
+            running.waiters = open;
+            promoted.extend(running.hand_over());
+        }
+        (dropped, promoted)
+    }
+}
+
+/// The first follower of `dropped` as a scan of its own, shared by the
+/// rest and keeping its place in line; used when `dropped` is removed
+/// before it starts
+fn promote(dropped: PendingScan) -> (Waiter, Option<PendingScan>) {
+    let PendingScan {
+        job,
+        reply,
+        key,
+        mut followers,
+        priority,
+        queued_at,
+    } = dropped;
+    let next = (!followers.is_empty()).then(|| {
+        let Waiter { job, reply } = followers.remove(0);
+        PendingScan {
+            job,
+            reply,
+            key,
+            followers,
+            priority,
+            queued_at,
+        }
+    });
+    (Waiter { job, reply }, next)
+}
+
+fn entry(job: &ScanJob, priority: Priority, position: Option<usize>) -> QueueEntry {
+    QueueEntry {
+        scan_id: job.scan_id.clone(),
+        url: job.url.clone(),
+        source: job.source,
+        priority,
+        position,
+    }
+}
+
+impl ScanQueue {
+    /// Concurrency limit when the settings don't set one
+    pub fn default_max_concurrent() -> usize {
+        std::env::var("PHISHING_GUARD_MAX_CONCURRENT_SCANS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|n| *n > 0)
+            .unwrap_or(DEFAULT_MAX_CONCURRENT_SCANS)
+    }
+
+    /// Add a job to the back of the queue; the receiver yields its outcome.
+    /// A job for a URL that is already waiting or running shares that scan
+    /// instead, unless it is forced, so the detector runs and the history
+    /// records it once. Waiting jobs start by priority, which comes from
+    /// where the job came from.
+    pub fn enqueue(
+        &self,
+        app: &AppHandle,
+        job: ScanJob,
+    ) -> Result<oneshot::Receiver<ScanOutcome>, AppError> {
+        let (reply, outcome) = oneshot::channel();
+        emit_queued(app, &job);
+
+        // Blocklisted, allowlisted and feed-listed URLs are answered
+        // immediately and never reach the detector; a local rule wins over
+        // the feed, and a block rule over an allow rule
+        let instant = app
+            .state::<Blocklist>()
+            .verdict(&job.url)
+            .or_else(|| app.state::<Allowlist>().verdict(&job.url))
+            .or_else(|| {
+                let result = app.state::<Feed>().verdict(&job.url)?;
+                feed::record(app, &job, &result);
+                Some(result)
+            });
+        if let Some(mut result) = instant {
+            heuristics::annotate(app, &mut result);
+            notify_verdict(app, &job, &result);
+            let outcome_now = Ok(result);
+            emit_outcome(app, &job, &outcome_now);
+            let _ = reply.send(outcome_now);
+            return Ok(outcome);
+        }
+
+        let priority = Priority::of(job.source);
+        let queued = job.clone();
+        let joined = self
+            .state
+            .lock()?
+            .push(Waiter { job, reply }, priority, Instant::now());
+        if joined {
+            tracing::debug!(scan_id = %queued.scan_id, "sharing a scan already queued");
+        } else {
+            persist(app, &queued);
+        }
+        self.changed(app);
+        Ok(outcome)
+    }
+
+    /// Queue a job and wait for it to finish
+    pub async fn submit(&self, app: &AppHandle, job: ScanJob) -> ScanOutcome {
+        let outcome = self.enqueue(app, job)?;
+        outcome.await.unwrap_or(Err(AppError::Cancelled))
+    }
+
+    pub fn status(&self) -> Result<QueueStatus, AppError> {
+        let state = self.state.lock()?;
+        let mut by_priority = PriorityCounts::default();
+        for pending in &state.pending {
+            match pending.priority {
+                Priority::High => by_priority.high += 1,
+                Priority::Normal => by_priority.normal += 1,
+                Priority::Low => by_priority.low += 1,
+            }
+        }
+        let pending: Vec<QueueEntry> = schedule(&state.pending, Instant::now())
+            .into_iter()
+            .enumerate()
+            .map(|(place, index)| {
+                let pending = &state.pending[index];
+                entry(&pending.job, pending.priority, Some(place + 1))
+            })
+            .collect();
+        Ok(QueueStatus {
+            max_concurrent: state.max_concurrent,
+            running: state
+                .running
+                .values()
+                .map(|r| entry(&r.job, r.priority, None))
+                .collect(),
+            length: pending.len(),
+            pending,
+            by_priority,
+        })
+    }
+
+    /// Drop a scan that has not started yet, or a request sharing one;
+    /// returns false if it is neither. Requests sharing a dropped scan keep
+    /// its place in line.
+    pub fn remove_pending(&self, app: &AppHandle, scan_id: &str) -> Result<bool, AppError> {
+        let removed = self.state.lock()?.remove_waiting(scan_id);
+        Ok(self.cancelled(app, removed))
+    }
+
+    /// Remove a pending scan, or stop waiting on a running one. A run that
+    /// other requests share goes on for them; one nobody else waits on is
+    /// aborted.
+    pub fn cancel(&self, app: &AppHandle, scan_id: &str) -> Result<bool, AppError> {
+        if self.remove_pending(app, scan_id)? {
+            return Ok(true);
+        }
+        let detached = self.state.lock()?.detach_running(scan_id);
+        Ok(self.cancelled(app, detached))
+    }
+
+    /// Tell the caller of a cancelled request, and record it at once so a
+    /// restart doesn't bring it back
+    fn cancelled(&self, app: &AppHandle, cancelled: Option<Cancelled>) -> bool {
+        let Some(Cancelled {
+            waiter,
+            promoted,
+            aborted,
+        }) = cancelled
+        else {
+            return false;
+        };
+        if let Some(job) = promoted {
+            persist(app, &job);
+        }
+        if aborted {
+            tracing::debug!(scan_id = %waiter.job.scan_id, "aborting a scan nobody waits on");
+        }
+        let outcome = Err(AppError::Cancelled);
+        mark(app, &waiter.job.scan_id, ItemStatus::Cancelled);
+        emit_outcome(app, &waiter.job, &outcome);
+        let _ = waiter.reply.send(outcome);
+        self.changed(app);
+        true
+    }
+
+    /// Cancel the scans whose callers have all gone away
+    pub fn drop_abandoned(&self, app: &AppHandle) -> Result<(), AppError> {
+        let (dropped, promoted) = self.state.lock()?.take_abandoned();
+        if dropped.is_empty() {
+            return Ok(());
+        }
+        for job in &promoted {
+            persist(app, job);
+        }
+        for waiter in dropped {
+            mark(app, &waiter.job.scan_id, ItemStatus::Cancelled);
+            emit_outcome(app, &waiter.job, &Err(AppError::Cancelled));
+        }
+        self.changed(app);
+        Ok(())
+    }
+
+    /// Move a scan that has not started yet to `priority`; returns false if
+    /// it is not waiting. Requests sharing another scan go with that scan.
+    pub fn set_priority(
+        &self,
+        app: &AppHandle,
+        scan_id: &str,
+        priority: Priority,
+    ) -> Result<bool, AppError> {
+        let moved = {
+            let mut state = self.state.lock()?;
+            match state.pending.iter_mut().find(|p| p.job.scan_id == scan_id) {
+                Some(pending) => {
+                    pending.priority = priority;
+                    true
+                }
+                None => false,
+            }
+        };
+        if moved {
+            self.changed(app);
+        }
+        Ok(moved)
+    }
+
+    pub fn set_max_concurrent(
+        &self,
+        app: &AppHandle,
+        max_concurrent: usize,
+    ) -> Result<(), AppError> {
+        self.state.lock()?.max_concurrent = max_concurrent.max(1);
+        self.changed(app);
+        Ok(())
+    }
+
+    fn changed(&self, app: &AppHandle) {
+        if let Ok(status) = self.status() {
+            let _ = app.emit("queue-updated", status);
+        }
+        self.wake.notify_one();
+    }
+
+    /// Start pending jobs until every slot is busy
+    fn start_ready(&self, app: &AppHandle) -> Result<(), AppError> {
+        let mut started = false;
+        let mut state = self.state.lock()?;
+
+        while let Some(next) = state.take_next(Instant::now()) {
+            started = true;
+            let scan = match next {
+                Next::Start(scan) => scan,
+                // The caller went away (e.g. its batch was cancelled) before we got to it
+                Next::Dropped { waiter, promoted } => {
+                    mark(app, &waiter.job.scan_id, ItemStatus::Cancelled);
+                    emit_outcome(app, &waiter.job, &Err(AppError::Cancelled));
+                    if let Some(job) = promoted {
+                        persist(app, &job);
+                    }
+                    continue;
+                }
+            };
+
+            let job = scan.job.clone();
+            mark(app, &job.scan_id, ItemStatus::InProgress);
+            let task_app = app.clone();
+            let task_job = job.clone();
+            // Every log line of the scan, down to the detector run, names it
+            let span = tracing::info_span!("scan", scan_id = %job.scan_id);
+            let task = tokio::spawn(
+                async move { run(&task_app, &task_job.scan_id, &task_job).await }.instrument(span),
+            );
+            state.start(scan, task.abort_handle());
+
+            // Watch the job separately so a panic or abort still frees its slot
+            let app = app.clone();
+            tokio::spawn(async move {
+                // A run that finishes has reported its outcome under the id it
+                // started with, and recorded it under the id it was handed to
+                let (outcome, reported) = match task.await {
+                    Ok(outcome) => (outcome, true),
+                    Err(e) if e.is_cancelled() => (Err(AppError::Cancelled), false),
+                    Err(e) => (
+                        Err(AppError::State(format!("scan task failed: {}", e))),
+                        false,
+                    ),
+                };
+
+                let queue = app.state::<ScanQueue>();
+                let (owner, waiters) = match queue.state.lock() {
+                    Ok(mut state) => (state.owner(&job.scan_id), state.finish(&job.scan_id)),
+                    Err(_) => (None, Vec::new()),
+                };
+                let owner = owner.unwrap_or_else(|| job.clone());
+                mark(&app, &owner.scan_id, ItemStatus::of(&outcome));
+                queue.changed(&app);
+                for waiter in waiters {
+                    if !reported || waiter.job.scan_id != job.scan_id {
+                        emit_outcome(&app, &waiter.job, &outcome);
+                    }
+                    let _ = waiter.reply.send(outcome.clone());
+                }
+            });
+        }
+
+        drop(state);
+        if started {
+            if let Ok(status) = self.status() {
+                let _ = app.emit("queue-updated", status);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Run one job with the scan settings in effect when it leaves the queue,
+/// caching a successful result and recording it in the scan history.
+/// Jobs the detector is unavailable for get a stand-in verdict and are held
+/// for replay, whose verdict then replaces the stand-in in the history.
+/// The verdict goes under whichever request the run started for `key` is
+/// reporting under by the time it is in, as its first caller may have left.
+async fn run(app: &AppHandle, key: &str, job: &ScanJob) -> ScanOutcome {
+    let generation = app.state::<ResultCache>().generation();
+    let outcome = match AppState::scan_params(&app.state::<Mutex<AppState>>()) {
+        Ok((project_root, timeout_secs)) => run_job(app, job, &project_root, timeout_secs).await,
+        Err(e) => {
+            let outcome = Err(e);
+            emit_outcome(app, job, &outcome);
+            outcome
+        }
+    };
+
+    let owner = app
+        .state::<ScanQueue>()
+        .state
+        .lock()
+        .ok()
+        .and_then(|state| state.owner(key));
+    let job = owner.as_ref().unwrap_or(job);
+    if let Ok(result) = &outcome {
+        notify_verdict(app, job, result);
+        // A stand-in verdict neither blocks nor outlives the outage
+        if !result.degraded {
+            active_blocking::on_verdict(app, result);
+            app.state::<ResultCache>().insert(result, generation);
+        }
+        // A history write failure should not cost the user their verdict.
+        // A replay replaces the stand-in row of the scan it was held from.
+        let history = app.state::<ScanHistory>();
+        if history.upgrade(result, job).await.unwrap_or(false) {
+            if !result.degraded {
+                emit_upgraded(app, job, result);
+            }
+        } else if history.record(result, job).await.is_ok() && is_paused(app) {
+            let tag = PAUSED_TAG.to_string();
+            let _ = tag_scan(&history, job.scan_id.clone(), tag).await;
+        }
+        tray::refresh_menu(app).await;
+        tray::refresh_stats(app).await;
+        if result.degraded {
+            let _ = offline::hold(app, job).await;
+        }
+    }
+    outcome
+}
+
+/// Worker that applies changes to the queue table in the order they were made
+pub async fn write_store(app: AppHandle) {
+    let Some(mut changes) = app
+        .state::<ScanQueue>()
+        .store_changes
+        .lock()
+        .ok()
+        .and_then(|mut changes| changes.take())
+    else {
+        return;
+    };
+    let history = app.state::<ScanHistory>();
+    while let Some(op) = changes.recv().await {
+        if let Err(e) = history.with_conn(move |conn| write(conn, &op)).await {
+            tracing::warn!(error = %e, "could not update the persisted scan queue");
+        }
+    }
+}
+
+/// Worker that starts queued scans whenever a slot frees up or work arrives
+pub async fn drain(app: AppHandle) {
+    let queue = app.state::<ScanQueue>();
+    loop {
+        let _ = queue.start_ready(&app);
+        queue.wake.notified().await;
+    }
+}
+
+/// Put scans a restart interrupted back in the queue
+pub async fn resume(app: AppHandle) {
+    let found = app
+        .state::<ScanHistory>()
+        .with_conn(|conn| take_resumable(conn, now_secs()))
+        .await;
+    let (jobs, skipped) = match found {
+        Ok(found) => found,
+        Err(e) => {
+            tracing::warn!(error = %e, "could not read the persisted scan queue");
+            return;
+        }
+    };
+    if jobs.is_empty() {
+        return;
+    }
+
+    let queue = app.state::<ScanQueue>();
+    let mut outcomes = Vec::new();
+    for job in jobs {
+        if let Ok(outcome) = queue.enqueue(&app, job) {
+            outcomes.push(outcome);
+        }
+    }
+    let resumed = outcomes.len();
+    tracing::info!(resumed, skipped, "resumed queued scans");
+    let _ = app.emit("queue-resumed", ResumedEvent { resumed, skipped });
+    if !is_paused(&app) {
+        let body = match resumed {
+            1 => "1 scan left over from last time was queued again".to_string(),
+            n => format!("{} scans left over from last time were queued again", n),
+        };
+        notify(&app, "Scans resumed", &body);
+    }
+
+    // Nobody else waits on these, and a dropped receiver reads as cancelled
+    for outcome in outcomes {
+        let _ = outcome.await;
+    }
+}
+
+/// Current queue contents and concurrency limit
+#[tauri::command]
+pub fn get_queue_status(queue: State<'_, ScanQueue>) -> Result<QueueStatus, AppError> {
+    queue.status()
+}
+
+/// Remove a scan that is still waiting in the queue
+#[tauri::command]
+pub fn remove_queued_scan(
+    scan_id: String,
+    app: AppHandle,
+    queue: State<'_, ScanQueue>,
+) -> Result<bool, AppError> {
+    queue.remove_pending(&app, &scan_id)
+}
+
+/// Move a scan still waiting in the queue to a different priority
+#[tauri::command]
+pub fn set_scan_priority(
+    scan_id: String,
+    priority: Priority,
+    app: AppHandle,
+    queue: State<'_, ScanQueue>,
+) -> Result<bool, AppError> {
+    queue.set_priority(&app, &scan_id, priority)
+}
+
+/// Change how many scans may run at once (minimum 1)
+#[tauri::command]
+pub fn set_max_concurrent_scans(
+    max_concurrent: usize,
+    app: AppHandle,
+    queue: State<'_, ScanQueue>,
+) -> Result<QueueStatus, AppError> {
+    // Saved with the settings, which applies it to the queue
+    settings::update(&app, |s| {
+        s.scan.max_concurrent = Some(max_concurrent.max(1))
+    })?;
+    queue.status()
+}
+
+/// Scans in the persisted queue, newest first: those waiting or running, and
+/// those that finished within the last day
+#[tauri::command]
+pub async fn get_queue_items(history: State<'_, ScanHistory>) -> Result<Vec<QueueItem>, AppError> {
+    history
+        .with_conn(|conn| {
+            let mut statement = conn.prepare(
+                "SELECT scan_id, url, source, force, status, requested_at, finished_at
+                 FROM queued_scans ORDER BY requested_at DESC, rowid DESC",
+            )?;
+            let rows = statement.query_map([], |row| {
+                Ok(QueueItem {
+                    scan_id: row.get(0)?,
+                    url: row.get(1)?,
+                    source: row.get(2)?,
+                    force: row.get(3)?,
+                    status: row.get(4)?,
+                    requested_at: row.get(5)?,
+                    finished_at: row.get(6)?,
+                })
+            })?;
+            Ok(rows.collect::<Result<Vec<_>, _>>()?)
+        })
+        .await
+}
+
+/// Cancel every scan still waiting for a slot and forget finished ones.
+/// Running scans carry on. Returns how many waiting scans were cancelled.
+#[tauri::command]
+pub async fn clear_queue(
+    app: AppHandle,
+    queue: State<'_, ScanQueue>,
+    history: State<'_, ScanHistory>,
+) -> Result<usize, AppError> {
+    let waiting: Vec<String> = queue
+        .state
+        .lock()?
+        .pending
+        .iter()
+        .flat_map(|p| std::iter::once(&p.job).chain(p.followers.iter().map(|f| &f.job)))
+        .map(|job| job.scan_id.clone())
+        .collect();
+    let mut cancelled = 0;
+    for scan_id in waiting {
+        if queue.remove_pending(&app, &scan_id)? {
+            cancelled += 1;
+        }
+    }
+    history
+        .with_conn(|conn| {
+            conn.execute(
+                "DELETE FROM queued_scans WHERE status NOT IN ('pending', 'in_progress')",
+                [],
+            )?;
+            Ok(())
+        })
+        .await?;
+    Ok(cancelled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::FeatureSet;
+
+    fn job(url: &str) -> ScanJob {
+        ScanJob::from_input(url, ScanSource::Manual).unwrap()
+    }
+
+    fn temp_history() -> (ScanHistory, std::path::PathBuf) {
+        let dir = std::env::temp_dir().join(format!("queue-test-{}", uuid::Uuid::new_v4()));
+        let history = ScanHistory::open(&dir.join("history.db")).unwrap();
+        (history, dir)
+    }
+
+    fn result(url: &str) -> ScanResult {
+        ScanResult {
+            url: url.to_string(),
+            classification: "legitimate".to_string(),
+            confidence: 0.9,
+            risk_score: 5,
+            explanation: String::new(),
+            analysis_mode: None,
+            features: FeatureSet::default(),
+            cached: false,
+            scanned_at: Some(now_secs()),
+            red_flags: None,
+            degraded: false,
+            redirects: None,
+            duration_ms: None,
+        }
+    }
+
+    fn ids(jobs: &[ScanJob]) -> Vec<&str> {
+        jobs.iter().map(|job| job.scan_id.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn resume_skips_cancelled_and_finished_scans() {
+        let (history, dir) = temp_history();
+        let waiting = job("https://waiting.example/");
+        let running = job("https://running.example/");
+        let cancelled = job("https://cancelled.example/");
+        let recorded = job("https://recorded.example/");
+        history
+            .record(&result(&recorded.url), &recorded)
+            .await
+            .unwrap();
+
+        let expected = vec![waiting.scan_id.clone(), running.scan_id.clone()];
+        let (jobs, skipped) = history
+            .with_conn(move |conn| {
+                let requested_at = now_secs() - 10;
+                for job in [&waiting, &running, &cancelled, &recorded] {
+                    let job = job.clone();
+                    write(conn, &StoreOp::Insert { job, requested_at })?;
+                }
+                let mark = |scan_id: &str, status| StoreOp::Mark {
+                    scan_id: scan_id.to_string(),
+                    status,
+                    at: now_secs(),
+                };
+                write(conn, &mark(&running.scan_id, ItemStatus::InProgress))?;
+                write(conn, &mark(&cancelled.scan_id, ItemStatus::InProgress))?;
+                write(conn, &mark(&cancelled.scan_id, ItemStatus::Cancelled))?;
+                take_resumable(conn, now_secs())
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(ids(&jobs), expected);
+        assert_eq!(skipped, 1);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn resumed_rows_are_not_resumed_twice() {
+        let (history, dir) = temp_history();
+        let waiting = job("https://waiting.example/");
+        let (first, second) = history
+            .with_conn(move |conn| {
+                let requested_at = now_secs();
+                write(
+                    conn,
+                    &StoreOp::Insert {
+                        job: waiting.clone(),
+                        requested_at,
+                    },
+                )?;
+                let (first, _) = take_resumable(conn, now_secs())?;
+                // Queued again on resume, which must keep the original row
+                write(
+                    conn,
+                    &StoreOp::Insert {
+                        job: waiting,
+                        requested_at: requested_at + 5,
+                    },
+                )?;
+                let (second, _) = take_resumable(conn, now_secs())?;
+                Ok((first, second))
+            })
+            .await
+            .unwrap();
+        assert_eq!(first.len(), 1);
+        assert_eq!(ids(&second), ids(&first));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn abandoned_requests_leave_the_queue() {
+        let mut state = QueueState::new(1);
+        let now = Instant::now();
+        let mut receivers = Vec::new();
+        for url in ["https://a.example/", "https://b.example/"] {
+            let (reply, outcome) = oneshot::channel();
+            state.pending.push_back(PendingScan {
+                key: normalize_url(url),
+                job: job(url),
+                reply,
+                followers: Vec::new(),
+                priority: Priority::Normal,
+                queued_at: now,
+            });
+            receivers.push(outcome);
+        }
+        // The caller of the first scan goes away, e.g. its batch is cancelled
+        drop(receivers.remove(0));
+
+        let (dropped, promoted) = state.take_abandoned();
+        assert_eq!(dropped.len(), 1);
+        assert_eq!(dropped[0].job.url, "https://a.example/");
+        assert!(promoted.is_empty());
+        assert_eq!(state.pending.len(), 1);
+        assert_eq!(state.pending[0].job.url, "https://b.example/");
+    }
+
+    fn waiter(job: &ScanJob) -> (Waiter, oneshot::Receiver<ScanOutcome>) {
+        let (reply, outcome) = oneshot::channel();
+        let waiter = Waiter {
+            job: job.clone(),
+            reply,
+        };
+        (waiter, outcome)
+    }
+
+    /// Start every scan the queue lets through as a fake detector run that
+    /// lasts until `release` is notified, returning the runs started
+    fn start_all(
+        state: &mut QueueState,
+        release: &std::sync::Arc<Notify>,
+    ) -> Vec<tokio::task::JoinHandle<()>> {
+        let mut runs = Vec::new();
+        while let Some(next) = state.take_next(Instant::now()) {
+            let Next::Start(scan) = next else {
+                continue;
+            };
+            let release = release.clone();
+            let run = tokio::spawn(async move { release.notified().await });
+            state.start(scan, run.abort_handle());
+            runs.push(run);
+        }
+        runs
+    }
+
+    #[tokio::test]
+    async fn concurrent_requests_for_a_url_share_one_run() {
+        let mut state = QueueState::new(3);
+        let url = "https://shared.example/login";
+        let clipboard = ScanJob::from_input(url, ScanSource::Clipboard).unwrap();
+        let manual = job(url);
+        let (first, first_outcome) = waiter(&clipboard);
+        let (second, second_outcome) = waiter(&manual);
+        assert!(!state.push(first, Priority::High, Instant::now()));
+        assert!(state.push(second, Priority::High, Instant::now()));
+
+        let release = std::sync::Arc::new(Notify::new());
+        let runs = start_all(&mut state, &release);
+        assert_eq!(runs.len(), 1);
+
+        release.notify_one();
+        for run in runs {
+            run.await.unwrap();
+        }
+        for waiter in state.finish(&clipboard.scan_id) {
+            let _ = waiter.reply.send(Ok(result(url)));
+        }
+        assert_eq!(first_outcome.await.unwrap().unwrap().url, url);
+        assert_eq!(second_outcome.await.unwrap().unwrap().url, url);
+        assert!(state.running.is_empty());
+    }
+
+    #[tokio::test]
+    async fn cancelling_one_request_keeps_a_shared_run_going() {
+        let mut state = QueueState::new(3);
+        let url = "https://shared.example/";
+        let clipboard = ScanJob::from_input(url, ScanSource::Clipboard).unwrap();
+        let manual = job(url);
+        let (first, first_outcome) = waiter(&clipboard);
+        let (second, second_outcome) = waiter(&manual);
+        state.push(first, Priority::High, Instant::now());
+        state.push(second, Priority::High, Instant::now());
+        let release = std::sync::Arc::new(Notify::new());
+        let mut runs = start_all(&mut state, &release);
+
+        // The caller that started the run cancels
+        let cancelled = state.detach_running(&clipboard.scan_id).unwrap();
+        assert!(!cancelled.aborted);
+        let _ = cancelled.waiter.reply.send(Err(AppError::Cancelled));
+
+        release.notify_one();
+        assert!(runs.remove(0).await.is_ok(), "the run must not be aborted");
+        for waiter in state.finish(&clipboard.scan_id) {
+            let _ = waiter.reply.send(Ok(result(url)));
+        }
+        assert!(matches!(first_outcome.await, Ok(Err(AppError::Cancelled))));
+        assert_eq!(second_outcome.await.unwrap().unwrap().url, url);
+    }
+
+    #[tokio::test]
+    async fn a_shared_run_reports_under_a_request_still_waiting() {
+        let mut state = QueueState::new(3);
+        let url = "https://shared.example/";
+        let leader = job(url);
+        let follower = ScanJob::from_input(url, ScanSource::Clipboard).unwrap();
+        let (first, _first_outcome) = waiter(&leader);
+        let (second, _second_outcome) = waiter(&follower);
+        state.push(first, Priority::High, Instant::now());
+        state.push(second, Priority::High, Instant::now());
+        let release = std::sync::Arc::new(Notify::new());
+        let _runs = start_all(&mut state, &release);
+        assert_eq!(
+            state.owner(&leader.scan_id).unwrap().scan_id,
+            leader.scan_id
+        );
+
+        let cancelled = state.detach_running(&leader.scan_id).unwrap();
+        let promoted = cancelled.promoted.unwrap();
+        assert_eq!(promoted.scan_id, follower.scan_id);
+        // The run keeps its slot under the id it started with
+        assert_eq!(
+            state.owner(&leader.scan_id).unwrap().scan_id,
+            follower.scan_id
+        );
+        let waiters: Vec<ScanJob> = state
+            .finish(&leader.scan_id)
+            .into_iter()
+            .map(|w| w.job)
+            .collect();
+        assert_eq!(ids(&waiters), [follower.scan_id.as_str()]);
+    }
+
+    #[tokio::test]
+    async fn an_abandoned_run_is_handed_to_a_request_still_waiting() {
+        let mut state = QueueState::new(3);
+        let url = "https://shared.example/";
+        let leader = job(url);
+        let follower = ScanJob::from_input(url, ScanSource::Clipboard).unwrap();
+        let (first, first_outcome) = waiter(&leader);
+        let (second, _second_outcome) = waiter(&follower);
+        state.push(first, Priority::High, Instant::now());
+        state.push(second, Priority::High, Instant::now());
+        let release = std::sync::Arc::new(Notify::new());
+        let _runs = start_all(&mut state, &release);
+
+        drop(first_outcome);
+        let (dropped, promoted) = state.take_abandoned();
+        assert_eq!(dropped.len(), 1);
+        assert_eq!(dropped[0].job.scan_id, leader.scan_id);
+        assert_eq!(ids(&promoted), [follower.scan_id.as_str()]);
+        assert_eq!(
+            state.owner(&leader.scan_id).unwrap().scan_id,
+            follower.scan_id
+        );
+    }
+
+    #[tokio::test]
+    async fn last_request_leaving_aborts_the_run() {
+        let mut state = QueueState::new(3);
+        let url = "https://shared.example/";
+        let (first, _first_outcome) = waiter(&job(url));
+        let second_job = ScanJob::from_input(url, ScanSource::Clipboard).unwrap();
+        let (second, _second_outcome) = waiter(&second_job);
+        let first_id = first.job.scan_id.clone();
+        state.push(first, Priority::High, Instant::now());
+        state.push(second, Priority::High, Instant::now());
+        let release = std::sync::Arc::new(Notify::new());
+        let mut runs = start_all(&mut state, &release);
+
+        assert!(!state.detach_running(&first_id).unwrap().aborted);
+        assert!(state.detach_running(&second_job.scan_id).unwrap().aborted);
+        assert!(runs.remove(0).await.unwrap_err().is_cancelled());
+        assert!(state.finish(&first_id).is_empty());
+    }
+
+    #[tokio::test]
+    async fn cancelling_a_waiting_scan_hands_it_to_a_sharer() {
+        let mut state = QueueState::new(1);
+        let url = "https://shared.example/";
+        let (first, _first_outcome) = waiter(&job(url));
+        let second_job = ScanJob::from_input(url, ScanSource::Clipboard).unwrap();
+        let (second, second_outcome) = waiter(&second_job);
+        let first_id = first.job.scan_id.clone();
+        state.push(first, Priority::High, Instant::now());
+        state.push(second, Priority::High, Instant::now());
+
+        let cancelled = state.remove_waiting(&first_id).unwrap();
+        assert_eq!(
+            cancelled.promoted.map(|job| job.scan_id),
+            Some(second_job.scan_id.clone())
+        );
+        let release = std::sync::Arc::new(Notify::new());
+        let runs = start_all(&mut state, &release);
+        assert_eq!(runs.len(), 1);
+        assert!(state.running.contains_key(&second_job.scan_id));
+        drop(second_outcome);
+    }
+
+    #[test]
+    fn forced_scans_are_not_shared() {
+        let mut state = QueueState::new(3);
+        let url = "https://shared.example/";
+        let (first, _first_outcome) = waiter(&job(url));
+        let forced = ScanJob {
+            force: true,
+            ..job(url)
+        };
+        let (second, _second_outcome) = waiter(&forced);
+        assert!(!state.push(first, Priority::High, Instant::now()));
+        assert!(!state.push(second, Priority::High, Instant::now()));
+        assert_eq!(state.pending.len(), 2);
+    }
+
+    /// The URLs of every waiting scan, in the order `take_next` starts them
+    fn start_order(state: &mut QueueState, now: Instant) -> Vec<String> {
+        let mut order = Vec::new();
+        while let Some(next) = state.take_next(now) {
+            if let Next::Start(scan) = next {
+                order.push(scan.job.url);
+            }
+        }
+        order
+    }
+
+    fn queue_at(
+        state: &mut QueueState,
+        url: &str,
+        priority: Priority,
+        at: Instant,
+    ) -> oneshot::Receiver<ScanOutcome> {
+        let (waiter, outcome) = waiter(&job(url));
+        state.push(waiter, priority, at);
+        outcome
+    }
+
+    #[test]
+    fn higher_priority_starts_first() {
+        let mut state = QueueState::new(usize::MAX);
+        let now = Instant::now();
+        let _low = queue_at(&mut state, "https://low.example/", Priority::Low, now);
+        let _normal = queue_at(&mut state, "https://normal.example/", Priority::Normal, now);
+        let _high = queue_at(&mut state, "https://high.example/", Priority::High, now);
+        assert_eq!(
+            start_order(&mut state, now),
+            [
+                "https://high.example/",
+                "https://normal.example/",
+                "https://low.example/"
+            ]
+        );
+    }
+
+    #[test]
+    fn same_priority_starts_in_queue_order() {
+        let mut state = QueueState::new(usize::MAX);
+        let start = Instant::now();
+        let mut receivers = Vec::new();
+        for (n, url) in [
+            "https://1.example/",
+            "https://2.example/",
+            "https://3.example/",
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let at = start + Duration::from_millis(n as u64);
+            receivers.push(queue_at(&mut state, url, Priority::Normal, at));
+        }
+        assert_eq!(
+            start_order(&mut state, start + Duration::from_millis(10)),
+            [
+                "https://1.example/",
+                "https://2.example/",
+                "https://3.example/"
+            ]
+        );
+    }
+
+    #[test]
+    fn a_low_priority_scan_ages_into_normal() {
+        let mut state = QueueState::new(usize::MAX);
+        let start = Instant::now();
+        let _watch = queue_at(&mut state, "https://watch.example/", Priority::Low, start);
+        let later = start + Duration::from_secs(1);
+        let _batch = queue_at(
+            &mut state,
+            "https://batch.example/",
+            Priority::Normal,
+            later,
+        );
+
+        // Before the aging interval a normal scan queued later still wins
+        assert_eq!(
+            Priority::Low.aged(AGING_INTERVAL - Duration::from_secs(1)),
+            Priority::Low
+        );
+        let aged = start + AGING_INTERVAL;
+        assert_eq!(state.pending.len(), 2);
+        assert_eq!(
+            start_order(&mut state, aged),
+            ["https://watch.example/", "https://batch.example/"]
+        );
+    }
+
+    #[test]
+    fn a_long_batch_does_not_starve_low_priority_work() {
+        let mut state = QueueState::new(1);
+        let start = Instant::now();
+        let _watch = queue_at(&mut state, "https://watch.example/", Priority::Low, start);
+        let mut batch = Vec::new();
+        for n in 0..100 {
+            let url = format!("https://batch{}.example/", n);
+            batch.push(queue_at(&mut state, &url, Priority::Normal, start));
+        }
+
+        // One slot, each batch scan taking a second: the watchlist scan gets
+        // its turn once it has waited the aging interval
+        let mut started = 0;
+        let mut now = start;
+        loop {
+            let Some(Next::Start(scan)) = state.take_next(now) else {
+                panic!("the queue ran dry before the watchlist scan started");
+            };
+            if scan.job.url == "https://watch.example/" {
+                break;
+            }
+            started += 1;
+            now += Duration::from_secs(1);
+        }
+        assert_eq!(started as u64, AGING_INTERVAL.as_secs());
+    }
+
+    #[test]
+    fn normal_and_high_scans_do_not_age() {
+        let long_wait = AGING_INTERVAL * 100;
+        assert_eq!(Priority::Normal.aged(long_wait), Priority::Normal);
+        assert_eq!(Priority::High.aged(long_wait), Priority::High);
+        assert_eq!(Priority::Low.aged(long_wait), Priority::Normal);
+    }
+
+    #[test]
+    fn an_interactive_scan_skips_a_waiting_batch() {
+        let mut state = QueueState::new(usize::MAX);
+        let start = Instant::now();
+        let mut receivers = Vec::new();
+        for n in 0..5 {
+            let url = format!("https://batch{}.example/", n);
+            receivers.push(queue_at(&mut state, &url, Priority::Normal, start));
+        }
+        // Even after the batch has waited a long time
+        let later = start + AGING_INTERVAL * 10;
+        let _typed = queue_at(&mut state, "https://typed.example/", Priority::High, later);
+        assert_eq!(start_order(&mut state, later)[0], "https://typed.example/");
+    }
+
+    #[test]
+    fn joining_a_waiting_scan_raises_its_priority() {
+        let mut state = QueueState::new(usize::MAX);
+        let now = Instant::now();
+        let _first = queue_at(&mut state, "https://first.example/", Priority::Normal, now);
+        let _shared = queue_at(&mut state, "https://shared.example/", Priority::Low, now);
+        let _joined = queue_at(&mut state, "https://shared.example/", Priority::High, now);
+        assert_eq!(state.pending.len(), 2);
+        assert_eq!(start_order(&mut state, now)[0], "https://shared.example/");
+    }
+
+    #[test]
+    fn a_full_queue_starts_nothing() {
+        let mut state = QueueState::new(1);
+        let now = Instant::now();
+        let _a = queue_at(&mut state, "https://a.example/", Priority::Normal, now);
+        let _b = queue_at(&mut state, "https://b.example/", Priority::High, now);
+        let Some(Next::Start(scan)) = state.take_next(now) else {
+            panic!("a free slot should start a scan");
+        };
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let task = rt.spawn(std::future::pending::<()>());
+        state.start(scan, task.abort_handle());
+        assert!(state.take_next(now).is_none());
+    }
+}
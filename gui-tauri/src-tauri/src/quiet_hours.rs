@@ -0,0 +1,176 @@
+use crate::error::AppError;
+use crate::history::ScanHistory;
+use crate::notifications::notify;
+use crate::settings::{self, minutes_of_day, QuietHoursSchedule, Settings};
+use crate::tray;
+use crate::AppHandle;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{Manager, State};
+use tokio::sync::Notify;
+
+/// How often the schedule is checked against the clock
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Default)]
+struct QuietState {
+    active: bool,
+    /// Notifications held back since quiet hours began
+    held: usize,
+    /// How many of those were phishing alerts
+    held_phishing: usize,
+}
+
+/// Whether the quiet-hours schedule is in effect, and what it has held back
+#[derive(Default)]
+pub struct QuietHours {
+    state: Mutex<QuietState>,
+    /// Re-checks the schedule right away after it is changed
+    wake: Notify,
+}
+
+impl QuietHours {
+    pub fn is_active(&self) -> bool {
+        self.state.lock().map(|s| s.active).unwrap_or(false)
+    }
+
+    /// Check the schedule again now, e.g. after it was edited
+    pub fn reschedule(&self) {
+        self.wake.notify_one();
+    }
+}
+
+/// Whether `schedule` covers local `weekday` (0 = Sunday) at `minute` after
+/// midnight. A window that crosses midnight belongs to the day it starts on,
+/// so e.g. Friday 22:00-07:00 also covers early Saturday morning.
+fn covers(schedule: &QuietHoursSchedule, weekday: u8, minute: u32) -> bool {
+    let (Some(start), Some(end)) = (
+        minutes_of_day(&schedule.start),
+        minutes_of_day(&schedule.end),
+    ) else {
+        return false;
+    };
+    if !schedule.enabled {
+        return false;
+    }
+    let on = |day: u8| schedule.days.contains(&day);
+    let yesterday = (weekday + 6) % 7;
+    match start.cmp(&end) {
+        std::cmp::Ordering::Less => on(weekday) && (start..end).contains(&minute),
+        std::cmp::Ordering::Greater => {
+            (on(weekday) && minute >= start) || (on(yesterday) && minute < end)
+        }
+        // Equal start and end means the whole day
+        std::cmp::Ordering::Equal => on(weekday),
+    }
+}
+
+/// Local weekday (0 = Sunday) and minute of the day, from SQLite like the
+/// rest of the app's local-time handling
+async fn local_now(history: &ScanHistory) -> Result<(u8, u32), AppError> {
+    history
+        .with_conn(|conn| {
+            Ok(conn.query_row(
+                "SELECT CAST(strftime('%w', 'now', 'localtime') AS INTEGER),
+                        CAST(strftime('%H', 'now', 'localtime') AS INTEGER) * 60
+                            + CAST(strftime('%M', 'now', 'localtime') AS INTEGER)",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )?)
+        })
+        .await
+}
+
+/// Whether a notification should be held back for quiet hours; held ones are
+/// counted for the digest shown when quiet hours end. Phishing alerts get
+/// through unless the schedule says to suppress them too.
+pub fn hold(app: &AppHandle, phishing: bool) -> bool {
+    let suppress_phishing = app
+        .state::<Settings>()
+        .get()
+        .notifications
+        .quiet_hours
+        .suppress_phishing;
+    let quiet = app.state::<QuietHours>();
+    let Ok(mut state) = quiet.state.lock() else {
+        return false;
+    };
+    if !state.active || (phishing && !suppress_phishing) {
+        return false;
+    }
+    state.held += 1;
+    if phishing {
+        state.held_phishing += 1;
+    }
+    true
+}
+
+fn digest(app: &AppHandle, held: usize, held_phishing: usize) {
+    if held == 0 {
+        return;
+    }
+    let noun = if held == 1 {
+        "notification"
+    } else {
+        "notifications"
+    };
+    let body = match held_phishing {
+        0 => format!("{} {} held, none about phishing", held, noun),
+        n => format!("{} {} held, {} about phishing", held, noun, n),
+    };
+    notify(app, "Quiet hours ended", &body);
+}
+
+/// Background task that follows the schedule, updating the tray and showing
+/// the digest when quiet hours end
+pub async fn watch(app: AppHandle) {
+    let quiet = app.state::<QuietHours>();
+    loop {
+        let schedule = app.state::<Settings>().get().notifications.quiet_hours;
+        if let Ok((weekday, minute)) = local_now(&app.state::<ScanHistory>()).await {
+            let active = covers(&schedule, weekday, minute);
+            let ended = match quiet.state.lock() {
+                Ok(mut state) if state.active != active => {
+                    state.active = active;
+                    let held = (state.held, state.held_phishing);
+                    if !active {
+                        state.held = 0;
+                        state.held_phishing = 0;
+                    }
+                    Some(held)
+                }
+                _ => None,
+            };
+            if let Some((held, held_phishing)) = ended {
+                tray::set_quiet(&app, active);
+                if !active {
+                    digest(&app, held, held_phishing);
+                }
+            }
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(CHECK_INTERVAL) => {}
+            _ = quiet.wake.notified() => {}
+        }
+    }
+}
+
+#[tauri::command]
+pub fn get_notification_schedule(settings: State<'_, Settings>) -> QuietHoursSchedule {
+    settings.get().notifications.quiet_hours
+}
+
+/// Replace the quiet-hours schedule; times are "HH:MM" in local time and
+/// days are numbered from 0 = Sunday
+#[tauri::command]
+pub fn set_notification_schedule(
+    schedule: QuietHoursSchedule,
+    app: AppHandle,
+) -> Result<QuietHoursSchedule, AppError> {
+    let mut schedule = schedule;
+    schedule.days.sort_unstable();
+    schedule.days.dedup();
+    let saved = settings::update(&app, |s| s.notifications.quiet_hours = schedule)?;
+    Ok(saved.notifications.quiet_hours)
+}
@@ -0,0 +1,352 @@
+use crate::error::AppError;
+use crate::history::normalize_url;
+use crate::lifecycle::ScanJob;
+use crate::links::normalize_input;
+use crate::mock;
+use crate::perf::{self, Operation};
+use crate::settings::Settings;
+use crate::AppHandle;
+use reqwest::header::{CONTENT_TYPE, LOCATION};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+use tauri::{Manager, State};
+use url::Url;
+
+/// Each hop gets this long, body included
+const HOP_TIMEOUT: Duration = Duration::from_secs(8);
+/// Only this much of an HTML page is read, to look for a meta refresh
+const MAX_BODY_BYTES: usize = 16 * 1024;
+
+/// Why a chain ended before reaching a page that doesn't redirect
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChainStop {
+    /// A hop led back to a URL already visited
+    Loop,
+    /// Still redirecting after `redirects.max_hops` hops
+    TooManyRedirects,
+    /// A hop led to a non-http(s) URL, which is not fetched
+    UnsupportedScheme,
+    /// A request failed or a hop's target was not a URL
+    Failed,
+}
+
+/// One request in a redirect chain
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RedirectHop {
+    url: String,
+    host: Option<String>,
+    /// Absent when the request failed
+    status: Option<u16>,
+    /// Where this hop leads, resolved against `url`
+    location: Option<String>,
+    /// `location` came from a meta refresh tag, not a Location header
+    meta_refresh: bool,
+    error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RedirectChain {
+    pub url: String,
+    /// Where the chain ends up: the last page fetched, or for a short link
+    /// its destination, which is not fetched. The landing page unless
+    /// `stopped` says otherwise.
+    pub final_url: String,
+    hops: Vec<RedirectHop>,
+    stopped: Option<ChainStop>,
+}
+
+impl RedirectChain {
+    /// True if the first URL led anywhere else
+    pub fn redirected(&self) -> bool {
+        self.hops.len() > 1 || self.stopped.is_some()
+    }
+
+    /// Said of a result when the chain failed on its first URL, which was
+    /// then scanned as it is
+    pub fn failure_note(&self) -> Option<String> {
+        if self.stopped != Some(ChainStop::Failed) || self.final_url != self.url {
+            return None;
+        }
+        let error = self.hops.first().and_then(|hop| hop.error.as_deref());
+        Some(format!(
+            "Could not follow where {} leads ({}), so it was scanned as it is.",
+            self.url,
+            error.unwrap_or("no answer")
+        ))
+    }
+}
+
+impl RedirectHop {
+    fn new(url: &Url) -> Self {
+        Self {
+            url: url.to_string(),
+            host: url.host_str().map(str::to_string),
+            status: None,
+            location: None,
+            meta_refresh: false,
+            error: None,
+        }
+    }
+}
+
+/// True if `host` is one of `shorteners` or a subdomain of one
+fn is_shortener(host: &str, shorteners: &[String]) -> bool {
+    let host = host.trim_start_matches("www.");
+    shorteners.iter().any(|shortener| {
+        host == shortener
+            || host
+                .strip_suffix(shortener.as_str())
+                .is_some_and(|rest| rest.ends_with('.'))
+    })
+}
+
+/// HTTP client that never follows redirects on its own
+pub struct Redirects {
+    client: reqwest::Client,
+}
+
+impl Default for Redirects {
+    fn default() -> Self {
+        let client = reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .timeout(HOP_TIMEOUT)
+            .user_agent(concat!("PhishingGuard/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .unwrap_or_default();
+        Self { client }
+    }
+}
+
+/// Target of a `<meta http-equiv="refresh" content="0; url=...">` tag
+fn meta_refresh(html: &str) -> Option<String> {
+    // Same byte offsets as `html`, since only ASCII changes case
+    let lower = html.to_ascii_lowercase();
+    let mut from = 0;
+    while let Some(start) = lower[from..].find("<meta").map(|at| from + at) {
+        let end = lower[start..]
+            .find('>')
+            .map_or(lower.len(), |at| start + at);
+        from = end;
+        let tag = &lower[start..end];
+        if !tag.contains("http-equiv") || !tag.contains("refresh") {
+            continue;
+        }
+        let Some(at) = tag.find("content=") else {
+            continue;
+        };
+        let raw = &html[start + at + "content=".len()..end];
+        let content = match raw.chars().next() {
+            Some(quote @ ('"' | '\'')) => raw[1..].split(quote).next().unwrap_or_default(),
+            _ => raw.split_whitespace().next().unwrap_or_default(),
+        };
+        // "5; url=https://..." with optional quotes around the URL
+        let Some(at) = content.to_ascii_lowercase().find("url=") else {
+            continue;
+        };
+        let target = content[at + "url=".len()..]
+            .trim()
+            .trim_matches(|c| c == '\'' || c == '"');
+        if !target.is_empty() {
+            return Some(target.to_string());
+        }
+    }
+    None
+}
+
+impl Redirects {
+    /// Status and redirect target of one GET. Only the start of an HTML
+    /// page is read, and nothing on it is run.
+    async fn fetch(&self, url: &Url) -> Result<(u16, Option<String>, bool), reqwest::Error> {
+        let mut response = self.client.get(url.clone()).send().await?;
+        let status = response.status();
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        if status.is_redirection() {
+            return Ok((status.as_u16(), header(LOCATION), false));
+        }
+        let html = header(CONTENT_TYPE).is_some_and(|kind| kind.contains("html"));
+        if !status.is_success() || !html {
+            return Ok((status.as_u16(), None, false));
+        }
+        let mut body = Vec::new();
+        while body.len() < MAX_BODY_BYTES {
+            match response.chunk().await? {
+                Some(chunk) => body.extend_from_slice(&chunk),
+                None => break,
+            }
+        }
+        body.truncate(MAX_BODY_BYTES);
+        let target = meta_refresh(&String::from_utf8_lossy(&body));
+        let found = target.is_some();
+        Ok((status.as_u16(), target, found))
+    }
+
+    /// Follow `url` through up to `max_hops` redirects, including meta
+    /// refreshes. Failures end the chain instead of failing the call.
+    pub async fn expand(&self, url: &str, max_hops: usize) -> RedirectChain {
+        let mut chain = RedirectChain {
+            url: url.to_string(),
+            final_url: url.to_string(),
+            hops: Vec::new(),
+            stopped: None,
+        };
+        let mut seen = HashSet::new();
+        let mut next = Url::parse(url).map_err(|e| e.to_string());
+        loop {
+            let current = match next {
+                Ok(current) => current,
+                Err(e) => {
+                    if let Some(hop) = chain.hops.last_mut() {
+                        hop.error = Some(format!("not a URL: {}", e));
+                    }
+                    chain.stopped = Some(ChainStop::Failed);
+                    break;
+                }
+            };
+            if !matches!(current.scheme(), "http" | "https") {
+                chain.stopped = Some(ChainStop::UnsupportedScheme);
+                break;
+            }
+            if !seen.insert(normalize_url(current.as_str())) {
+                chain.stopped = Some(ChainStop::Loop);
+                break;
+            }
+            if chain.hops.len() > max_hops {
+                chain.stopped = Some(ChainStop::TooManyRedirects);
+                break;
+            }
+
+            let mut hop = RedirectHop::new(&current);
+            chain.final_url = hop.url.clone();
+            let target = match self.fetch(&current).await {
+                Ok((status, location, meta_refresh)) => {
+                    hop.status = Some(status);
+                    hop.meta_refresh = meta_refresh;
+                    location
+                }
+                Err(e) => {
+                    hop.error = Some(e.without_url().to_string());
+                    chain.stopped = Some(ChainStop::Failed);
+                    chain.hops.push(hop);
+                    break;
+                }
+            };
+            let Some(target) = target else {
+                chain.hops.push(hop);
+                break;
+            };
+            next = current.join(&target).map_err(|e| e.to_string());
+            hop.location = Some(next.as_ref().map_or(target, |url| url.to_string()));
+            chain.hops.push(hop);
+        }
+        chain
+    }
+
+    /// Where a link on one of `shorteners` points, from a single request to
+    /// the shortener; the destination itself is not fetched. None for other
+    /// links.
+    pub async fn expand_short_link(
+        &self,
+        url: &str,
+        shorteners: &[String],
+    ) -> Option<RedirectChain> {
+        let short = Url::parse(url).ok()?;
+        if !is_shortener(&short.host_str()?.to_ascii_lowercase(), shorteners) {
+            return None;
+        }
+        let mut chain = RedirectChain {
+            url: url.to_string(),
+            final_url: url.to_string(),
+            hops: Vec::new(),
+            stopped: Some(ChainStop::Failed),
+        };
+        let mut hop = RedirectHop::new(&short);
+        match self.fetch(&short).await {
+            Ok((status, Some(target), meta_refresh)) => {
+                hop.status = Some(status);
+                hop.meta_refresh = meta_refresh;
+                match short.join(&target) {
+                    Ok(destination) => {
+                        hop.location = Some(destination.to_string());
+                        if matches!(destination.scheme(), "http" | "https") {
+                            chain.final_url = destination.to_string();
+                            chain.stopped = None;
+                        } else {
+                            chain.stopped = Some(ChainStop::UnsupportedScheme);
+                        }
+                    }
+                    Err(e) => {
+                        hop.location = Some(target);
+                        hop.error = Some(format!("not a URL: {}", e));
+                    }
+                }
+            }
+            Ok((status, None, _)) => {
+                hop.status = Some(status);
+                hop.error = Some(format!(
+                    "the short link answered {} without redirecting",
+                    status
+                ));
+            }
+            Err(e) => hop.error = Some(e.without_url().to_string()),
+        }
+        chain.hops.push(hop);
+        Some(chain)
+    }
+}
+
+/// `job` pointed at the destination of its short link, if its URL is on a
+/// known shortener. A dead short link keeps the job's URL, so the short
+/// link itself is scanned and the result says why.
+pub async fn expand_shortener(app: &AppHandle, job: &ScanJob) -> Option<ScanJob> {
+    let config = app.state::<Settings>().get().redirects;
+    if !config.enabled || !config.expand_shorteners || job.redirects.is_some() || mock::enabled(app)
+    {
+        return None;
+    }
+    let started = Instant::now();
+    let chain = app
+        .state::<Redirects>()
+        .expand_short_link(&job.url, &config.shorteners)
+        .await?;
+    perf::record(app, Operation::ShortLink, started);
+    Some(ScanJob {
+        url: chain.final_url.clone(),
+        redirects: Some(chain),
+        ..job.clone()
+    })
+}
+
+/// `Redirects::expand`, timed
+pub async fn follow(app: &AppHandle, url: &str, max_hops: usize) -> RedirectChain {
+    let started = Instant::now();
+    let chain = app.state::<Redirects>().expand(url, max_hops).await;
+    perf::record(app, Operation::Redirects, started);
+    chain
+}
+
+/// Follow the redirects of `url` without running anything it serves, and
+/// report each hop and where it ends up. Requests come from this machine,
+/// so the `redirects.enabled` setting can turn this off.
+#[tauri::command]
+pub async fn expand_redirects(
+    url: String,
+    app: AppHandle,
+    settings: State<'_, Settings>,
+) -> Result<RedirectChain, AppError> {
+    let config = settings.get().redirects;
+    if !config.enabled {
+        return Err(AppError::InvalidInput(
+            "redirect expansion is turned off in the settings".into(),
+        ));
+    }
+    let url = normalize_input(&url)?;
+    Ok(follow(&app, &url, config.max_hops).await)
+}
@@ -0,0 +1,219 @@
+use crate::error::AppError;
+use crate::features::feature_label;
+use crate::history::{HistoryEntry, ScanHistory};
+use crate::links::defang;
+use crate::AppHandle;
+use printpdf::{IndirectFontRef, Mm, PdfDocument, PdfDocumentReference, PdfLayerReference};
+use std::fs::File;
+use std::io::{BufWriter, Cursor};
+use tauri::State;
+
+/// Monospaced so wrapping by character count matches the rendered width,
+/// and embedded so non-ASCII URLs render without any system fonts
+const FONT_REGULAR: &[u8] = include_bytes!("../fonts/DejaVuSansMono.ttf");
+const FONT_BOLD: &[u8] = include_bytes!("../fonts/DejaVuSansMono-Bold.ttf");
+
+const PAGE_WIDTH: f32 = 210.0;
+const PAGE_HEIGHT: f32 = 297.0;
+const MARGIN: f32 = 20.0;
+const BODY_SIZE: f32 = 10.0;
+const HEADING_SIZE: f32 = 12.0;
+const TITLE_SIZE: f32 = 16.0;
+/// DejaVu Sans Mono advance width as a fraction of the font size
+const GLYPH_WIDTH_EM: f32 = 0.602;
+const PT_TO_MM: f32 = 0.3528;
+
+/// Split `text` into lines of at most `width` characters, breaking at
+/// whitespace where possible and hard-breaking longer words such as URLs
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            let mut word: Vec<char> = word.chars().collect();
+            let line_len = line.chars().count();
+            if line_len > 0 && line_len + 1 + word.len() <= width {
+                line.push(' ');
+                line.extend(word.iter());
+                continue;
+            }
+            if line_len > 0 {
+                lines.push(std::mem::take(&mut line));
+            }
+            while word.len() > width {
+                lines.push(word.drain(..width).collect());
+            }
+            line = word.into_iter().collect();
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+/// Writes lines top to bottom, starting a new page when the current one fills
+struct ReportWriter {
+    doc: PdfDocumentReference,
+    layer: PdfLayerReference,
+    regular: IndirectFontRef,
+    bold: IndirectFontRef,
+    y: f32,
+    pages: usize,
+}
+
+impl ReportWriter {
+    fn new(title: &str) -> Result<Self, AppError> {
+        let (doc, page, layer) = PdfDocument::new(title, Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Report");
+        let regular = doc
+            .add_external_font(Cursor::new(FONT_REGULAR))
+            .map_err(|e| AppError::Report(e.to_string()))?;
+        let bold = doc
+            .add_external_font(Cursor::new(FONT_BOLD))
+            .map_err(|e| AppError::Report(e.to_string()))?;
+        let layer = doc.get_page(page).get_layer(layer);
+        Ok(Self {
+            doc,
+            layer,
+            regular,
+            bold,
+            y: PAGE_HEIGHT - MARGIN,
+            pages: 1,
+        })
+    }
+
+    fn line_height(size: f32) -> f32 {
+        size * PT_TO_MM * 1.4
+    }
+
+    /// Characters that fit across the page at `size`
+    fn columns(size: f32) -> usize {
+        ((PAGE_WIDTH - 2.0 * MARGIN) / (size * GLYPH_WIDTH_EM * PT_TO_MM)) as usize
+    }
+
+    fn line(&mut self, text: &str, size: f32, bold: bool) {
+        let height = Self::line_height(size);
+        if self.y - height < MARGIN {
+            let (page, layer) = self.doc.add_page(
+                Mm(PAGE_WIDTH),
+                Mm(PAGE_HEIGHT),
+                format!("Page {}", self.pages + 1),
+            );
+            self.layer = self.doc.get_page(page).get_layer(layer);
+            self.y = PAGE_HEIGHT - MARGIN;
+            self.pages += 1;
+        }
+        self.y -= height;
+        let font = if bold { &self.bold } else { &self.regular };
+        self.layer
+            .use_text(text, size, Mm(MARGIN), Mm(self.y), font);
+    }
+
+    fn paragraph(&mut self, text: &str, size: f32, bold: bool) {
+        for line in wrap(text, Self::columns(size)) {
+            self.line(&line, size, bold);
+        }
+    }
+
+    fn heading(&mut self, text: &str) {
+        self.y -= Self::line_height(BODY_SIZE) / 2.0;
+        self.line(text, HEADING_SIZE, true);
+    }
+
+    fn save(self, path: &str) -> Result<usize, AppError> {
+        let file = File::create(path)?;
+        self.doc
+            .save(&mut BufWriter::new(file))
+            .map_err(|e| AppError::Report(e.to_string()))?;
+        Ok(self.pages)
+    }
+}
+
+/// Feature rows as `label: value`, skipping features the detector left out
+fn feature_lines(entry: &HistoryEntry) -> Vec<String> {
+    let Ok(serde_json::Value::Object(features)) = serde_json::to_value(&entry.features) else {
+        return Vec::new();
+    };
+    features
+        .iter()
+        .filter(|(_, value)| !value.is_null())
+        .map(|(key, value)| {
+            let value = match value {
+                serde_json::Value::Bool(true) => "yes".to_string(),
+                serde_json::Value::Bool(false) => "no".to_string(),
+                serde_json::Value::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            format!("{}: {}", feature_label(key).unwrap_or(key), value)
+        })
+        .collect()
+}
+
+fn render(
+    entry: &HistoryEntry,
+    scanned_at: &str,
+    app_version: &str,
+    path: &str,
+) -> Result<usize, AppError> {
+    let mut report = ReportWriter::new("Phishing Guard incident report")?;
+
+    report.line("Phishing Guard incident report", TITLE_SIZE, true);
+    report.paragraph(&format!("Scanned {}", scanned_at), BODY_SIZE, false);
+
+    report.heading("URL");
+    report.paragraph(&defang(&entry.url), BODY_SIZE, false);
+
+    report.heading("Verdict");
+    report.line(&entry.classification, BODY_SIZE, true);
+    report.line(
+        &format!("Confidence: {:.1}%", entry.confidence * 100.0),
+        BODY_SIZE,
+        false,
+    );
+    report.line(
+        &format!("Risk score: {}/100", entry.risk_score),
+        BODY_SIZE,
+        false,
+    );
+
+    report.heading("Explanation");
+    report.paragraph(&entry.explanation, BODY_SIZE, false);
+
+    let features = feature_lines(entry);
+    if !features.is_empty() {
+        report.heading("Features");
+        for feature in features {
+            report.paragraph(&feature, BODY_SIZE, false);
+        }
+    }
+
+    report.heading("Generated by");
+    report.paragraph(
+        &format!(
+            "Phishing Guard {} with the local detect_enhanced.py detector (scan id {})",
+            app_version,
+            entry.scan_id.as_deref().unwrap_or("unknown")
+        ),
+        BODY_SIZE,
+        false,
+    );
+
+    report.save(path)
+}
+
+/// Render a stored scan as a PDF incident report at `path`, returning the page count
+#[tauri::command]
+pub async fn generate_report(
+    scan_id: String,
+    path: String,
+    app: AppHandle,
+    history: State<'_, ScanHistory>,
+) -> Result<usize, AppError> {
+    let (entry, scanned_at) = history
+        .find(&scan_id)
+        .await?
+        .ok_or(AppError::NotFound(scan_id))?;
+    let app_version = app.package_info().version.to_string();
+
+    tokio::task::spawn_blocking(move || render(&entry, &scanned_at, &app_version, &path))
+        .await
+        .map_err(|e| AppError::Report(e.to_string()))?
+}
@@ -0,0 +1,135 @@
+use crate::allowlist::Allowlist;
+use crate::error::AppError;
+use crate::history::{HistoryFilter, ScanHistory};
+use crate::inflight::{new_scan_id, InFlightScans};
+use crate::lifecycle::{ScanJob, ScanSource};
+use crate::notifications::notify_batch_done;
+use crate::queue::ScanQueue;
+use crate::AppHandle;
+use serde::Serialize;
+use tauri::{Emitter, Manager, State};
+use tokio::task::JoinSet;
+
+/// A URL whose verdict is different now
+#[derive(Serialize, Debug, Clone)]
+pub struct VerdictChange {
+    url: String,
+    /// History row of the earlier scan
+    rescan_of: i64,
+    /// History row of the new scan, if it was recorded
+    id: Option<i64>,
+    before: String,
+    after: String,
+}
+
+/// Outcome of rescan_history, also sent as `rescan-done`
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct RescanSummary {
+    /// URLs sent back through the detector
+    total: usize,
+    /// URLs left alone because the allowlist now covers them
+    skipped: usize,
+    failed: usize,
+    /// URLs waiting for the detector to come back; their new entries are
+    /// updated and linked once it does
+    held: usize,
+    changed: Vec<VerdictChange>,
+}
+
+#[derive(Serialize, Clone)]
+struct RescanProgress {
+    completed: usize,
+    total: usize,
+}
+
+async fn rescan(app: AppHandle, filter: HistoryFilter) -> Result<RescanSummary, AppError> {
+    let history = app.state::<ScanHistory>();
+    let candidates = history.latest_matching(filter).await?;
+    let allowlist = app.state::<Allowlist>();
+    let queue = app.state::<ScanQueue>();
+
+    let mut summary = RescanSummary::default();
+    let mut tasks = JoinSet::new();
+    for (rescan_of, url, before) in candidates {
+        if allowlist.verdict(&url).is_some() {
+            summary.skipped += 1;
+            continue;
+        }
+        // Entries from before input normalization may no longer pass it
+        let Ok(job) = ScanJob::from_input(&url, ScanSource::Rescan) else {
+            summary.failed += 1;
+            continue;
+        };
+        let job = ScanJob { force: true, ..job };
+        let scan_id = job.scan_id.clone();
+        // Queued like any other scan, so the concurrency limit applies
+        let outcome = queue.enqueue(&app, job)?;
+        tasks.spawn(async move {
+            let outcome = outcome.await.unwrap_or(Err(AppError::Cancelled));
+            (scan_id, rescan_of, url, before, outcome)
+        });
+    }
+
+    summary.total = tasks.len();
+    let mut phishing = 0;
+    while let Some(joined) = tasks.join_next().await {
+        let Ok((scan_id, rescan_of, url, before, outcome)) = joined else {
+            summary.failed += 1;
+            continue;
+        };
+        match outcome {
+            Ok(result) => {
+                if result.is_phishing() {
+                    phishing += 1;
+                }
+                let id = history.link_rescan(&scan_id, rescan_of).await?;
+                if result.degraded {
+                    summary.held += 1;
+                } else if result.classification != before {
+                    summary.changed.push(VerdictChange {
+                        url,
+                        rescan_of,
+                        id,
+                        before,
+                        after: result.classification,
+                    });
+                }
+            }
+            Err(_) => summary.failed += 1,
+        }
+        let _ = app.emit(
+            "rescan-progress",
+            RescanProgress {
+                completed: summary.total - tasks.len(),
+                total: summary.total,
+            },
+        );
+    }
+
+    notify_batch_done(&app, ScanSource::Rescan, summary.total, phishing);
+    let _ = app.emit("rescan-done", &summary);
+    Ok(summary)
+}
+
+/// Scan every URL whose latest entry matches `filter` again with the full
+/// analysis, linking each new entry to the one it re-checks. URLs the
+/// allowlist now covers are skipped.
+///
+/// Pass a `batch_id` to be able to stop it with `cancel_scan`; rescans that
+/// have not started yet are dropped from the queue.
+#[tauri::command]
+pub async fn rescan_history(
+    filter: Option<HistoryFilter>,
+    batch_id: Option<String>,
+    app: AppHandle,
+    inflight: State<'_, InFlightScans>,
+) -> Result<RescanSummary, AppError> {
+    let filter = filter.unwrap_or_default();
+    inflight
+        .run(
+            &app,
+            batch_id.unwrap_or_else(new_scan_id),
+            rescan(app.clone(), filter),
+        )
+        .await
+}
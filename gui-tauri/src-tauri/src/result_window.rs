@@ -0,0 +1,90 @@
+use crate::error::AppError;
+use crate::history::ScanHistory;
+use crate::settings::Settings;
+use crate::theme;
+use crate::AppHandle;
+use std::collections::HashSet;
+use std::sync::Mutex;
+use tauri::{Manager, State, WebviewUrl, WebviewWindowBuilder};
+
+/// Detail windows are labeled this prefix followed by the scan id
+pub const LABEL_PREFIX: &str = "result-";
+
+/// Labels of the scan detail windows currently open
+#[derive(Default)]
+pub struct ResultWindows {
+    open: Mutex<HashSet<String>>,
+}
+
+impl ResultWindows {
+    /// Forget a detail window once it has been destroyed
+    pub fn closed(&self, label: &str) {
+        if let Ok(mut open) = self.open.lock() {
+            open.remove(label);
+        }
+    }
+}
+
+/// Show the detail window for `scan_id`, focusing it if it is already open.
+///
+/// The frontend reads the scan id from the `result` query parameter.
+pub async fn open(app: &AppHandle, scan_id: &str) -> Result<(), AppError> {
+    // Labels only allow a limited alphabet; scan ids are UUIDs in practice
+    if scan_id.is_empty()
+        || !scan_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(AppError::InvalidInput(format!(
+            "\"{}\" is not a valid scan id",
+            scan_id
+        )));
+    }
+
+    let label = format!("{}{}", LABEL_PREFIX, scan_id);
+    if let Some(window) = app.get_webview_window(&label) {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+        return Ok(());
+    }
+
+    let (entry, _) = app
+        .state::<ScanHistory>()
+        .find(scan_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(scan_id.to_string()))?;
+
+    let url = WebviewUrl::App(format!("index.html?result={}", scan_id).into());
+    WebviewWindowBuilder::new(app, &label, url)
+        .title(format!("Scan result - {}", entry.url))
+        .inner_size(640.0, 720.0)
+        .resizable(true)
+        .theme(theme::native(app.state::<Settings>().get().theme))
+        .build()
+        .map_err(|e| AppError::State(format!("could not open result window: {}", e)))?;
+
+    app.state::<ResultWindows>().open.lock()?.insert(label);
+    Ok(())
+}
+
+/// Open a scan result in its own window
+#[tauri::command]
+pub async fn open_result_window(scan_id: String, app: AppHandle) -> Result<(), AppError> {
+    open(&app, &scan_id).await
+}
+
+/// Close every scan detail window
+#[tauri::command]
+pub fn close_result_windows(
+    app: AppHandle,
+    windows: State<'_, ResultWindows>,
+) -> Result<(), AppError> {
+    let labels: Vec<String> = windows.open.lock()?.drain().collect();
+    for label in labels {
+        if let Some(window) = app.get_webview_window(&label) {
+            let _ = window.close();
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,195 @@
+use crate::error::AppError;
+use crate::history::{
+    entry_from_row, HistoryEntry, ScanHistory, ENTRY_COLUMNS, ENTRY_COLUMN_COUNT,
+};
+use rusqlite::Connection;
+use serde::Serialize;
+use tauri::State;
+
+/// Marks placed around matched terms in `SearchHit::snippet`
+const MATCH_START: &str = "[[";
+const MATCH_END: &str = "]]";
+
+#[derive(Serialize, Debug, Clone)]
+pub struct SearchHit {
+    entry: HistoryEntry,
+    /// Excerpt of the best-matching column with matches wrapped in `[[` `]]`
+    snippet: String,
+    /// bm25 score; lower is more relevant
+    rank: f64,
+}
+
+/// Turn free text into an FTS5 query that cannot contain syntax errors.
+///
+/// Text in double quotes stays together as a phrase, every other word is its
+/// own term, and each is quoted so operators like `AND`, `*` or `:` are
+/// matched literally. Terms are implicitly ANDed.
+fn fts_query(input: &str) -> String {
+    let mut terms = Vec::new();
+    let mut current = String::new();
+    let mut in_phrase = false;
+
+    let mut flush = |current: &mut String| {
+        let term = current.trim();
+        if !term.is_empty() {
+            terms.push(format!("\"{}\"", term.replace('"', "\"\"")));
+        }
+        current.clear();
+    };
+
+    for c in input.chars() {
+        match c {
+            '"' => {
+                flush(&mut current);
+                in_phrase = !in_phrase;
+            }
+            c if c.is_whitespace() && !in_phrase => flush(&mut current),
+            c => current.push(c),
+        }
+    }
+    flush(&mut current);
+
+    terms.join(" ")
+}
+
+/// Ranked full-text search over URLs, the URLs they were redirected from,
+/// explanations and notes
+#[tauri::command]
+pub async fn search_history(
+    query: String,
+    limit: Option<usize>,
+    history: State<'_, ScanHistory>,
+) -> Result<Vec<SearchHit>, AppError> {
+    let fts = fts_query(&query);
+    if fts.is_empty() {
+        return Ok(Vec::new());
+    }
+    let limit = limit.unwrap_or(50).clamp(1, 500) as i64;
+
+    history
+        .with_conn(move |conn| search(conn, &fts, limit))
+        .await
+}
+
+/// The `limit` best matches of the FTS5 query `fts`, most relevant first
+fn search(conn: &Connection, fts: &str, limit: i64) -> Result<Vec<SearchHit>, AppError> {
+    let mut statement = conn.prepare(&format!(
+        "SELECT {}, hits.snippet, hits.rank
+         FROM scans JOIN (
+             SELECT rowid,
+                    snippet(scans_fts, -1, '{}', '{}', '…', 12) AS snippet,
+                    bm25(scans_fts) AS rank
+             FROM scans_fts WHERE scans_fts MATCH ?1
+             ORDER BY rank LIMIT ?2
+         ) AS hits ON hits.rowid = scans.id
+         ORDER BY hits.rank",
+        ENTRY_COLUMNS, MATCH_START, MATCH_END
+    ))?;
+    let hits = statement.query_map(rusqlite::params![fts, limit], |row| {
+        Ok(SearchHit {
+            entry: entry_from_row(row)?,
+            snippet: row.get(ENTRY_COLUMN_COUNT)?,
+            rank: row.get(ENTRY_COLUMN_COUNT + 1)?,
+        })
+    })?;
+    Ok(hits.collect::<Result<Vec<_>, _>>()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::migrate;
+    use rusqlite::params;
+    use std::path::Path;
+
+    const FILLER: [&str; 8] = [
+        "login", "page", "asks", "for", "banking", "details", "bright", "logo",
+    ];
+
+    /// An explanation of twenty words holding "credential harvesting"
+    /// `phrases` times and, if `scattered`, both words apart as well
+    fn explanation(seed: usize, phrases: usize, scattered: bool) -> String {
+        let mut words: Vec<&str> = (0..20)
+            .map(|i| FILLER[(seed + i * 3) % FILLER.len()])
+            .collect();
+        for n in 0..phrases {
+            words[n * 5] = "credential";
+            words[n * 5 + 1] = "harvesting";
+        }
+        if scattered {
+            words[2] = "credential";
+            words[17] = "harvesting";
+        }
+        words.join(" ")
+    }
+
+    /// Seed an in-memory history; returns the rows holding the phrase twice
+    /// and those holding it once
+    fn seeded(conn: &mut Connection) -> (Vec<i64>, Vec<i64>) {
+        migrate(conn, Path::new(":memory:")).unwrap();
+        let (mut twice, mut once) = (Vec::new(), Vec::new());
+        let tx = conn.transaction().unwrap();
+        for i in 0..400 {
+            let (phrases, scattered) = match i % 40 {
+                0 => (2, false),
+                1 | 2 => (1, false),
+                3..=6 => (0, true),
+                _ => (0, false),
+            };
+            tx.execute(
+                "INSERT INTO scans (url, normalized_url, classification, confidence, risk_score,
+                                    explanation, features, scanned_at, source)
+                 VALUES (?1, ?1, 'phishing', 0.9, 80, ?2, '{}', ?3, 'manual')",
+                params![
+                    format!("https://shop{}.example/account", i),
+                    explanation(i, phrases, scattered),
+                    1_700_000_000 + i as i64,
+                ],
+            )
+            .unwrap();
+            match phrases {
+                2 => twice.push(tx.last_insert_rowid()),
+                1 => once.push(tx.last_insert_rowid()),
+                _ => {}
+            }
+        }
+        tx.commit().unwrap();
+        (twice, once)
+    }
+
+    #[test]
+    fn phrase_matches_come_back_most_relevant_first() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        let (twice, once) = seeded(&mut conn);
+
+        let hits = search(&conn, &fts_query("\"credential harvesting\""), 500).unwrap();
+        let ids: Vec<i64> = hits.iter().map(|hit| hit.entry.id).collect();
+        // Both words apart is not the phrase
+        assert_eq!(hits.len(), twice.len() + once.len());
+        assert!(hits.windows(2).all(|pair| pair[0].rank <= pair[1].rank));
+        let (top, rest) = ids.split_at(twice.len());
+        assert!(top.iter().all(|id| twice.contains(id)), "{:?}", ids);
+        assert!(rest.iter().all(|id| once.contains(id)), "{:?}", ids);
+        assert!(hits
+            .iter()
+            .all(|hit| hit.snippet.contains("[[credential harvesting]]")));
+
+        // As separate terms the scattered rows match too, after the phrases
+        let terms = search(&conn, &fts_query("credential harvesting"), 500).unwrap();
+        assert_eq!(terms.len(), hits.len() + 40);
+        assert!(terms[..twice.len()]
+            .iter()
+            .all(|hit| twice.contains(&hit.entry.id)));
+
+        let limited = search(&conn, &fts_query("\"credential harvesting\""), 5).unwrap();
+        assert_eq!(limited.len(), 5);
+        assert!(limited.iter().all(|hit| twice.contains(&hit.entry.id)));
+    }
+
+    #[test]
+    fn query_syntax_is_taken_literally() {
+        assert_eq!(fts_query("paypal AND login*"), r#""paypal" "AND" "login*""#);
+        assert_eq!(fts_query(r#"a "b c" d"#), r#""a" "b c" "d""#);
+        assert_eq!(fts_query("  "), "");
+    }
+}
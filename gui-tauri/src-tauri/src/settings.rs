@@ -0,0 +1,848 @@
+use crate::error::AppError;
+use crate::feed;
+use crate::folder_watch;
+use crate::health::HealthMonitor;
+use crate::heuristics::Heuristic;
+use crate::logging;
+use crate::presence;
+use crate::queue::ScanQueue;
+use crate::quiet_hours::QuietHours;
+use crate::theme;
+use crate::AppHandle;
+use crate::AppState;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{Emitter, Manager, State};
+
+/// Preferences file in the app config dir
+pub const SETTINGS_FILE: &str = "settings.json";
+/// Layout of the settings file this version writes
+pub const SETTINGS_VERSION: u32 = 1;
+
+/// Upgrades applied in order to older settings files: entry N turns a
+/// version N file into version N + 1. Files from before versioning count as
+/// version 0.
+const SETTINGS_MIGRATIONS: &[fn(&mut Map<String, Value>)] = &[
+    // Unversioned files already have the version 1 layout
+    |_| {},
+];
+
+const SCAN_TIMEOUT_RANGE: RangeInclusive<u64> = 1..=600;
+const ENV_CHECK_TIMEOUT_RANGE: RangeInclusive<u64> = 1..=120;
+const MAX_CONCURRENT_RANGE: RangeInclusive<usize> = 1..=16;
+const HEALTH_INTERVAL_RANGE: RangeInclusive<u64> = 5..=3600;
+const FEED_INTERVAL_RANGE: RangeInclusive<u64> = 300..=86400;
+const MAX_HOPS_RANGE: RangeInclusive<usize> = 1..=20;
+const DEFAULT_SHORTENERS: &[&str] = &[
+    "bit.ly",
+    "bitly.com",
+    "t.co",
+    "tinyurl.com",
+    "goo.gl",
+    "ow.ly",
+    "is.gd",
+    "v.gd",
+    "buff.ly",
+    "rebrand.ly",
+    "cutt.ly",
+    "shorturl.at",
+    "t.ly",
+    "rb.gy",
+    "tiny.cc",
+    "bit.do",
+    "lnkd.in",
+    "s.id",
+    "shorte.st",
+    "adf.ly",
+    "tr.im",
+    "qrco.de",
+    "surl.li",
+];
+const MAX_WATCHED_FOLDERS: usize = 16;
+
+/// What the main window's close button does
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CloseBehavior {
+    /// Hide the window and keep protecting from the tray
+    Tray,
+    /// Exit the app
+    #[default]
+    Quit,
+    /// Let the frontend ask; see `resolve_close_request`
+    Ask,
+}
+
+/// Light or dark windows
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ThemePreference {
+    Light,
+    Dark,
+    /// Follow the OS, including switches while the app runs
+    #[default]
+    System,
+}
+
+/// Least severe log lines written
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Error,
+    Warn,
+    #[default]
+    Info,
+    Debug,
+    Trace,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct LoggingSettings {
+    pub level: LogLevel,
+    /// Log only the scheme and host of scanned URLs
+    pub redact_urls: bool,
+}
+
+impl Default for LoggingSettings {
+    fn default() -> Self {
+        Self {
+            level: LogLevel::default(),
+            redact_urls: true,
+        }
+    }
+}
+
+/// Blocking phishing hosts through the system hosts file
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct ActiveBlockingSettings {
+    pub enabled: bool,
+    /// Phishing verdicts at or above this risk score get their host blocked
+    pub min_risk_score: i32,
+}
+
+impl Default for ActiveBlockingSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min_risk_score: 85,
+        }
+    }
+}
+
+/// The server-published phishing domain feed
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct FeedSettings {
+    /// Where the feed is published; `None` leaves syncing off
+    pub url: Option<String>,
+    pub interval_secs: u64,
+}
+
+impl Default for FeedSettings {
+    fn default() -> Self {
+        Self {
+            url: None,
+            interval_secs: 3600,
+        }
+    }
+}
+
+impl FeedSettings {
+    fn validate(&self) -> Result<(), AppError> {
+        check_range(
+            "feed.interval_secs",
+            Some(self.interval_secs),
+            FEED_INTERVAL_RANGE,
+        )?;
+        // The feed decides verdicts, so it must not be swappable in transit
+        match &self.url {
+            Some(url) if url::Url::parse(url).map_or(true, |u| u.scheme() != "https") => Err(
+                AppError::InvalidInput("feed.url must be an https URL".into()),
+            ),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Following a URL's redirects from this machine
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct RedirectSettings {
+    /// Off for when fetching attacker URLs from this IP is unwanted
+    pub enabled: bool,
+    /// Have scan_url scan where a URL's redirects end up instead of the URL
+    pub scan_landing_page: bool,
+    pub max_hops: usize,
+    /// Scan where links on `shorteners` point instead of the short link
+    pub expand_shorteners: bool,
+    /// Hosts of link shorteners, subdomains included
+    pub shorteners: Vec<String>,
+}
+
+impl Default for RedirectSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            scan_landing_page: false,
+            max_hops: 10,
+            expand_shorteners: true,
+            shorteners: DEFAULT_SHORTENERS.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+}
+
+impl RedirectSettings {
+    fn validate(&self) -> Result<(), AppError> {
+        check_range("redirects.max_hops", Some(self.max_hops), MAX_HOPS_RANGE)?;
+        // Bare lowercase host names, since that is how hosts are compared
+        match self.shorteners.iter().find(|host| {
+            host.is_empty()
+                || !host.contains('.')
+                || host
+                    .chars()
+                    .any(|c| !(c.is_ascii_lowercase() || c.is_ascii_digit() || "-.".contains(c)))
+        }) {
+            Some(host) => Err(AppError::InvalidInput(format!(
+                "redirects.shorteners entry {:?} is not a lowercase host name",
+                host
+            ))),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Minutes after local midnight for an "HH:MM" time
+pub fn minutes_of_day(time: &str) -> Option<u32> {
+    let (hours, minutes) = time.split_once(':')?;
+    let (hours, minutes): (u32, u32) = (hours.parse().ok()?, minutes.parse().ok()?);
+    (hours < 24 && minutes < 60).then_some(hours * 60 + minutes)
+}
+
+fn check_range<T: PartialOrd + std::fmt::Display>(
+    name: &str,
+    value: Option<T>,
+    range: RangeInclusive<T>,
+) -> Result<(), AppError> {
+    match value {
+        Some(value) if !range.contains(&value) => Err(AppError::InvalidInput(format!(
+            "{} must be between {} and {}",
+            name,
+            range.start(),
+            range.end()
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Detector limits; unset values fall back to the environment variables and
+/// built-in defaults
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct ScanSettings {
+    pub timeout_secs: Option<u64>,
+    pub env_check_timeout_secs: Option<u64>,
+    pub max_concurrent: Option<usize>,
+    /// Seconds between background environment checks
+    pub health_interval_secs: Option<u64>,
+    /// Answer scans with mock verdicts instead of running the detector
+    pub mock_detector: bool,
+    /// Look up each host before scanning and skip ones that don't exist,
+    /// unless the scan is forced
+    pub dns_preflight: bool,
+}
+
+impl ScanSettings {
+    fn validate(&self) -> Result<(), AppError> {
+        check_range("scan.timeout_secs", self.timeout_secs, SCAN_TIMEOUT_RANGE)?;
+        check_range(
+            "scan.env_check_timeout_secs",
+            self.env_check_timeout_secs,
+            ENV_CHECK_TIMEOUT_RANGE,
+        )?;
+        check_range(
+            "scan.max_concurrent",
+            self.max_concurrent,
+            MAX_CONCURRENT_RANGE,
+        )?;
+        check_range(
+            "scan.health_interval_secs",
+            self.health_interval_secs,
+            HEALTH_INTERVAL_RANGE,
+        )
+    }
+}
+
+/// Local times during which notifications are held back
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct QuietHoursSchedule {
+    pub enabled: bool,
+    /// "HH:MM" local time
+    pub start: String,
+    /// "HH:MM" local time; before `start` when the window crosses midnight
+    pub end: String,
+    /// Days the window starts on, 0 = Sunday
+    pub days: Vec<u8>,
+    /// Hold back phishing alerts as well
+    pub suppress_phishing: bool,
+}
+
+impl QuietHoursSchedule {
+    fn validate(&self) -> Result<(), AppError> {
+        for time in [&self.start, &self.end] {
+            if minutes_of_day(time).is_none() {
+                return Err(AppError::InvalidInput(format!(
+                    "invalid time {:?}, expected HH:MM",
+                    time
+                )));
+            }
+        }
+        match self.days.iter().find(|day| **day > 6) {
+            Some(day) => Err(AppError::InvalidInput(format!(
+                "invalid day {}, expected 0 (Sunday) to 6 (Saturday)",
+                day
+            ))),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Default for QuietHoursSchedule {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            start: "22:00".to_string(),
+            end: "07:00".to_string(),
+            days: (0..7).collect(),
+            suppress_phishing: false,
+        }
+    }
+}
+
+/// When scan verdicts are announced
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct NotificationSettings {
+    /// Risk score from which a result is announced as suspicious
+    pub suspicious_risk_score: i32,
+    /// Risk score from which a result gets the urgent phishing notification
+    pub phishing_risk_score: i32,
+    /// Also announce clean results of scans started from the app's own windows
+    pub notify_clean_manual_scans: bool,
+    /// Toasts per minute before the rest are collapsed into a summary
+    pub max_per_minute: usize,
+    /// Repeated notifications for the same URL within this many seconds are dropped
+    pub duplicate_window_secs: u64,
+    /// Announce batches, imports and drops with one summary instead of per result
+    pub batch_summary_only: bool,
+    pub quiet_hours: QuietHoursSchedule,
+}
+
+impl NotificationSettings {
+    fn validate(&self) -> Result<(), AppError> {
+        check_range(
+            "notifications.suspicious_risk_score",
+            Some(self.suspicious_risk_score),
+            0..=100,
+        )?;
+        check_range(
+            "notifications.phishing_risk_score",
+            Some(self.phishing_risk_score),
+            0..=100,
+        )?;
+        if self.suspicious_risk_score > self.phishing_risk_score {
+            return Err(AppError::InvalidInput(
+                "notifications.suspicious_risk_score must not exceed phishing_risk_score"
+                    .to_string(),
+            ));
+        }
+        check_range(
+            "notifications.max_per_minute",
+            Some(self.max_per_minute),
+            1..=60,
+        )?;
+        self.quiet_hours.validate()
+    }
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        Self {
+            suspicious_risk_score: 40,
+            phishing_risk_score: 70,
+            notify_clean_manual_scans: false,
+            max_per_minute: 6,
+            duplicate_window_secs: 60,
+            batch_summary_only: true,
+            quiet_hours: QuietHoursSchedule::default(),
+        }
+    }
+}
+
+/// User preferences that survive restarts
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct AppSettings {
+    /// Always `SETTINGS_VERSION` once loaded; see `SETTINGS_MIGRATIONS`
+    pub schema_version: u32,
+    pub close_behavior: CloseBehavior,
+    pub theme: ThemePreference,
+    /// Physical screen position the mini scanner was last left at
+    pub mini_scanner_position: Option<[i32; 2]>,
+    /// Hide the mini scanner after it has been unfocused this long; 0 never hides it
+    pub mini_scanner_idle_secs: u64,
+    pub notifications: NotificationSettings,
+    pub scan: ScanSettings,
+    pub logging: LoggingSettings,
+    /// Folders whose new .html, .eml and .txt files are scanned; empty
+    /// leaves the watcher off
+    pub watched_folders: Vec<PathBuf>,
+    pub active_blocking: ActiveBlockingSettings,
+    pub feed: FeedSettings,
+    /// Sensitive commands need verify_user_presence first; see `presence`
+    pub require_os_auth: bool,
+    /// Local red-flag checks left out of every scan
+    pub disabled_heuristics: Vec<Heuristic>,
+    /// Let enrich_domain ask RDAP and WHOIS servers about a domain; off for
+    /// networks that don't allow that traffic
+    pub domain_lookups: bool,
+    pub redirects: RedirectSettings,
+}
+
+impl AppSettings {
+    pub fn validate(&self) -> Result<(), AppError> {
+        self.notifications.validate()?;
+        self.scan.validate()?;
+        self.feed.validate()?;
+        self.redirects.validate()?;
+        check_range(
+            "active_blocking.min_risk_score",
+            Some(self.active_blocking.min_risk_score),
+            0..=100,
+        )?;
+        if self.watched_folders.len() > MAX_WATCHED_FOLDERS {
+            return Err(AppError::InvalidInput(format!(
+                "at most {} folders can be watched",
+                MAX_WATCHED_FOLDERS
+            )));
+        }
+        match self.watched_folders.iter().find(|p| !p.is_absolute()) {
+            Some(folder) => Err(AppError::InvalidInput(format!(
+                "watched folder {} is not an absolute path",
+                folder.display()
+            ))),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            schema_version: SETTINGS_VERSION,
+            close_behavior: CloseBehavior::default(),
+            theme: ThemePreference::default(),
+            mini_scanner_position: None,
+            mini_scanner_idle_secs: 120,
+            notifications: NotificationSettings::default(),
+            scan: ScanSettings::default(),
+            logging: LoggingSettings::default(),
+            watched_folders: Vec::new(),
+            active_blocking: ActiveBlockingSettings::default(),
+            feed: FeedSettings::default(),
+            require_os_auth: false,
+            disabled_heuristics: Vec::new(),
+            domain_lookups: true,
+            redirects: RedirectSettings::default(),
+        }
+    }
+}
+
+/// Overlay `changes` onto `target`, recursing into objects so a partial
+/// update only touches the fields it names
+fn merge(target: &mut Value, changes: Value, path: &str) -> Result<(), AppError> {
+    match (target, changes) {
+        (Value::Object(target), Value::Object(changes)) => {
+            for (key, value) in changes {
+                let name = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                let field = target
+                    .get_mut(&key)
+                    .ok_or_else(|| AppError::InvalidInput(format!("unknown setting {}", name)))?;
+                merge(field, value, &name)?;
+            }
+            Ok(())
+        }
+        (target, changes) => {
+            *target = changes;
+            Ok(())
+        }
+    }
+}
+
+/// `path` with `suffix` appended to the file name
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// Bring serialized settings up to `SETTINGS_VERSION`, returning the
+/// version they had; `source` names them in the error for a newer version
+pub fn upgrade(value: &mut Value, source: &str) -> Result<u64, AppError> {
+    let Value::Object(fields) = value else {
+        return Err(AppError::Parse(format!("{} is not an object", source)));
+    };
+    let version = fields
+        .get("schema_version")
+        .and_then(Value::as_u64)
+        .unwrap_or(0);
+    if version > SETTINGS_VERSION as u64 {
+        return Err(AppError::NewerVersion(source.to_string()));
+    }
+
+    for migration in &SETTINGS_MIGRATIONS[version as usize..] {
+        migration(fields);
+    }
+    fields.insert("schema_version".to_string(), SETTINGS_VERSION.into());
+    Ok(version)
+}
+
+/// `base` with the fields named in `changes` replaced, validated. Active
+/// blocking is turned on and off only by enable_active_blocking, which
+/// keeps the hosts file in step and asks the user, so `changes` can't.
+pub fn overlay(base: &AppSettings, changes: Value) -> Result<AppSettings, AppError> {
+    let mut value = serde_json::to_value(base)?;
+    merge(&mut value, changes, "")?;
+    let next: AppSettings =
+        serde_json::from_value(value).map_err(|e| AppError::InvalidInput(e.to_string()))?;
+    if next.active_blocking.enabled != base.active_blocking.enabled {
+        return Err(AppError::InvalidInput(
+            "active_blocking.enabled is changed with enable_active_blocking".into(),
+        ));
+    }
+    next.validate()?;
+    Ok(next)
+}
+
+/// Read and upgrade a settings file, keeping a copy of an older one as
+/// `settings.json.v<version>.bak`. A corrupt or invalid one is moved aside
+/// to `.bak` and `None` returned; one from a newer version is an error, so
+/// it is never overwritten with defaults, and so is failing to make the copy.
+fn read_file(path: &Path) -> Result<Option<AppSettings>, AppError> {
+    let Ok(contents) = std::fs::read_to_string(path) else {
+        return Ok(None);
+    };
+    let parsed = serde_json::from_str::<Value>(&contents)
+        .map_err(AppError::from)
+        .and_then(|mut value| {
+            let version = upgrade(&mut value, &path.display().to_string())?;
+            let settings = serde_json::from_value::<AppSettings>(value)?;
+            settings.validate()?;
+            Ok((settings, version))
+        });
+
+    match parsed {
+        Ok((settings, version)) => {
+            if version < SETTINGS_VERSION as u64 {
+                std::fs::copy(path, sibling(path, &format!(".v{}.bak", version)))?;
+                write_file(path, &settings)?;
+            }
+            Ok(Some(settings))
+        }
+        Err(e @ AppError::NewerVersion(_)) => Err(e),
+        Err(e) => {
+            tracing::warn!(path = %path.display(), error = %e, "ignoring unreadable settings");
+            let _ = std::fs::rename(path, sibling(path, ".bak"));
+            Ok(None)
+        }
+    }
+}
+
+/// Write through a temporary file so a crash never leaves half a file behind
+fn write_file(path: &Path, settings: &AppSettings) -> Result<(), AppError> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let temp = sibling(path, ".tmp");
+    std::fs::write(&temp, serde_json::to_string_pretty(settings)?)?;
+    std::fs::rename(&temp, path)?;
+    Ok(())
+}
+
+/// The loaded settings and where they are saved
+pub struct Settings {
+    path: PathBuf,
+    current: Mutex<AppSettings>,
+}
+
+impl Settings {
+    /// Read settings from `dir`, using defaults if the file is missing or
+    /// unreadable; fails only for a file from a newer app version
+    pub fn load(dir: &Path) -> Result<Self, AppError> {
+        let path = dir.join(SETTINGS_FILE);
+        let current = read_file(&path)?.unwrap_or_default();
+        Ok(Self {
+            path,
+            current: Mutex::new(current),
+        })
+    }
+
+    pub fn get(&self) -> AppSettings {
+        self.current
+            .lock()
+            .map(|settings| settings.clone())
+            .unwrap_or_default()
+    }
+
+    /// Validate the changed settings and write them to disk, returning the new
+    /// values; nothing changes if `change` fails or the result is invalid
+    fn commit(
+        &self,
+        change: impl FnOnce(&mut AppSettings) -> Result<(), AppError>,
+    ) -> Result<AppSettings, AppError> {
+        let mut current = self.current.lock()?;
+        let mut next = current.clone();
+        change(&mut next)?;
+        next.schema_version = SETTINGS_VERSION;
+        next.validate()?;
+
+        write_file(&self.path, &next)?;
+        *current = next.clone();
+        Ok(next)
+    }
+}
+
+/// Push settings that running components cache into them
+pub fn apply(app: &AppHandle, settings: &AppSettings) {
+    logging::apply(app, &settings.logging);
+    if let Ok(mut state) = app.state::<Mutex<AppState>>().lock() {
+        state.configure(&settings.scan);
+    }
+    let max_concurrent = settings
+        .scan
+        .max_concurrent
+        .unwrap_or_else(ScanQueue::default_max_concurrent);
+    let _ = app
+        .state::<ScanQueue>()
+        .set_max_concurrent(app, max_concurrent);
+    app.state::<HealthMonitor>()
+        .set_interval(settings.scan.health_interval_secs);
+    app.state::<QuietHours>().reschedule();
+    theme::apply(app, settings.theme);
+    folder_watch::apply(app, &settings.watched_folders);
+    feed::apply(app, &settings.feed);
+}
+
+fn saved(app: &AppHandle, saved: AppSettings) -> AppSettings {
+    apply(app, &saved);
+    let _ = app.emit("settings-changed", &saved);
+    saved
+}
+
+/// Change the settings, save them, and apply them to the running app
+pub fn update(
+    app: &AppHandle,
+    change: impl FnOnce(&mut AppSettings),
+) -> Result<AppSettings, AppError> {
+    let next = app.state::<Settings>().commit(|s| {
+        change(s);
+        Ok(())
+    })?;
+    Ok(saved(app, next))
+}
+
+#[tauri::command]
+pub fn get_settings(settings: State<'_, Settings>) -> AppSettings {
+    settings.get()
+}
+
+/// Change only the settings named in `changes`, e.g. `{"scan": {"timeout_secs": 60}}`;
+/// `null` clears an optional value
+#[tauri::command]
+pub fn update_settings(
+    changes: Value,
+    app: AppHandle,
+    settings: State<'_, Settings>,
+) -> Result<AppSettings, AppError> {
+    let next = settings.commit(|s| {
+        let gated = s.require_os_auth;
+        *s = overlay(s, changes)?;
+        presence::check_gate_change(&app, gated, s.require_os_auth)
+    })?;
+    Ok(saved(&app, next))
+}
+
+/// Restore every setting to its default, except whether active blocking is
+/// on; enable_active_blocking turns it off along with the hosts it blocked
+#[tauri::command]
+pub fn reset_settings(
+    app: AppHandle,
+    settings: State<'_, Settings>,
+) -> Result<AppSettings, AppError> {
+    let next = settings.commit(|s| {
+        presence::check_gate_change(&app, s.require_os_auth, false)?;
+        let blocking = s.active_blocking.enabled;
+        *s = AppSettings::default();
+        s.active_blocking.enabled = blocking;
+        Ok(())
+    })?;
+    Ok(saved(&app, next))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn overlay_refuses_to_switch_active_blocking() {
+        let base = AppSettings::default();
+        let error = overlay(&base, json!({"active_blocking": {"enabled": true}})).unwrap_err();
+        assert!(matches!(error, AppError::InvalidInput(_)), "{:?}", error);
+
+        let on = AppSettings {
+            active_blocking: ActiveBlockingSettings {
+                enabled: true,
+                ..ActiveBlockingSettings::default()
+            },
+            ..AppSettings::default()
+        };
+        assert!(overlay(&on, json!({"active_blocking": {"enabled": false}})).is_err());
+
+        // Restating the current value and the other fields are fine
+        let next = overlay(
+            &on,
+            json!({"active_blocking": {"enabled": true, "min_risk_score": 60}}),
+        )
+        .unwrap();
+        assert!(next.active_blocking.enabled);
+        assert_eq!(next.active_blocking.min_risk_score, 60);
+    }
+
+    /// A settings file from before `schema_version` was written
+    const V0_FIXTURE: &str = r#"{
+        "close_behavior": "tray",
+        "theme": "dark",
+        "scan": {"timeout_secs": 120, "max_concurrent": 2}
+    }"#;
+
+    /// A settings file from a version of the app newer than this one
+    fn newer_fixture() -> String {
+        json!({
+            "schema_version": SETTINGS_VERSION + 1,
+            "theme": "light",
+            "setting_from_the_future": true
+        })
+        .to_string()
+    }
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("settings-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn every_version_has_a_migration() {
+        assert_eq!(SETTINGS_MIGRATIONS.len(), SETTINGS_VERSION as usize);
+    }
+
+    #[test]
+    fn upgrade_stamps_the_current_version() {
+        let mut value: Value = serde_json::from_str(V0_FIXTURE).unwrap();
+        assert_eq!(upgrade(&mut value, "fixture").unwrap(), 0);
+        assert_eq!(value["schema_version"], SETTINGS_VERSION);
+        assert_eq!(value["theme"], "dark");
+
+        let mut current = serde_json::to_value(AppSettings::default()).unwrap();
+        assert_eq!(
+            upgrade(&mut current, "current").unwrap(),
+            SETTINGS_VERSION as u64
+        );
+
+        let mut newer: Value = serde_json::from_str(&newer_fixture()).unwrap();
+        let error = upgrade(&mut newer, "newer").unwrap_err();
+        assert!(matches!(&error, AppError::NewerVersion(source) if source == "newer"));
+        assert!(upgrade(&mut json!([1, 2]), "list").is_err());
+    }
+
+    #[test]
+    fn reading_an_old_file_keeps_a_backup() {
+        let dir = temp_dir();
+        let path = dir.join(SETTINGS_FILE);
+        std::fs::write(&path, V0_FIXTURE).unwrap();
+
+        let settings = read_file(&path).unwrap().unwrap();
+        assert_eq!(settings.close_behavior, CloseBehavior::Tray);
+        assert_eq!(settings.theme, ThemePreference::Dark);
+        assert_eq!(settings.scan.timeout_secs, Some(120));
+        let backup = dir.join(format!("{}.v0.bak", SETTINGS_FILE));
+        assert_eq!(std::fs::read_to_string(&backup).unwrap(), V0_FIXTURE);
+        // The file itself now has the current layout
+        let rewritten: Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(rewritten["schema_version"], SETTINGS_VERSION);
+
+        // A current file is read as it is, with no new backup
+        std::fs::remove_file(&backup).unwrap();
+        assert!(read_file(&path).unwrap().is_some());
+        assert!(!backup.exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn a_newer_file_is_refused_and_left_alone() {
+        let dir = temp_dir();
+        let path = dir.join(SETTINGS_FILE);
+        std::fs::write(&path, newer_fixture()).unwrap();
+
+        assert!(matches!(read_file(&path), Err(AppError::NewerVersion(_))));
+        assert!(matches!(
+            Settings::load(&dir),
+            Err(AppError::NewerVersion(_))
+        ));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), newer_fixture());
+        assert_eq!(
+            std::fs::read_dir(&dir).unwrap().count(),
+            1,
+            "no backup is made"
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn a_corrupt_file_is_moved_aside() {
+        let dir = temp_dir();
+        let path = dir.join(SETTINGS_FILE);
+        std::fs::write(&path, "{ not json").unwrap();
+
+        assert!(read_file(&path).unwrap().is_none());
+        assert!(!path.exists());
+        let aside = dir.join(format!("{}.bak", SETTINGS_FILE));
+        assert_eq!(std::fs::read_to_string(aside).unwrap(), "{ not json");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn a_failed_backup_is_an_error_not_a_corrupt_file() {
+        let dir = temp_dir();
+        let path = dir.join(SETTINGS_FILE);
+        std::fs::write(&path, V0_FIXTURE).unwrap();
+        // Nothing can be copied over a directory
+        std::fs::create_dir(dir.join(format!("{}.v0.bak", SETTINGS_FILE))).unwrap();
+
+        assert!(matches!(read_file(&path), Err(AppError::Io(_))));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), V0_FIXTURE);
+        assert!(!dir.join(format!("{}.bak", SETTINGS_FILE)).exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,433 @@
+use crate::error::AppError;
+use crate::history::{
+    entry_from_row, parse_domain, HistoryEntry, ScanHistory, ENTRY_COLUMNS, PHISHING_CONDITION,
+};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+/// Window of history the dashboard summarises
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum StatsRange {
+    Today,
+    #[default]
+    Week,
+    Month,
+    Year,
+    All,
+}
+
+impl StatsRange {
+    /// Days before today included in the window, or None for all history
+    fn days_back(self) -> Option<u32> {
+        match self {
+            StatsRange::Today => Some(0),
+            StatsRange::Week => Some(6),
+            StatsRange::Month => Some(29),
+            StatsRange::Year => Some(364),
+            StatsRange::All => None,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ClassificationCount {
+    classification: String,
+    count: u64,
+}
+
+/// Scans on one local calendar day
+#[derive(Serialize, Debug, Clone)]
+pub struct DayCount {
+    /// YYYY-MM-DD in the user's local timezone
+    day: String,
+    scans: u64,
+    phishing: u64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct DomainCount {
+    domain: String,
+    count: u64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct Statistics {
+    range: StatsRange,
+    /// Start of the window as a Unix timestamp (local midnight)
+    since: i64,
+    total: u64,
+    phishing: u64,
+    /// Share of scans classified as phishing; 0 when there are none
+    phishing_ratio: f64,
+    by_classification: Vec<ClassificationCount>,
+    /// One entry per day in the window, including days without scans
+    per_day: Vec<DayCount>,
+    top_flagged_domains: Vec<DomainCount>,
+    average_confidence: Option<f64>,
+    median_confidence: Option<f64>,
+    average_risk_score: Option<f64>,
+}
+
+/// Most scans `get_domain_summary` lists; the counts cover all of them
+const DOMAIN_SCAN_LIMIT: usize = 500;
+/// Most domains `get_top_domains` returns
+const MAX_TOP_DOMAINS: usize = 100;
+
+/// Order of `get_top_domains` results, highest first
+#[derive(Deserialize, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum DomainOrder {
+    #[default]
+    Scans,
+    Phishing,
+    AverageRiskScore,
+    MaxRiskScore,
+    LastSeen,
+}
+
+impl DomainOrder {
+    fn order_by(self) -> &'static str {
+        match self {
+            DomainOrder::Scans => "scans DESC, domain",
+            DomainOrder::Phishing => "phishing DESC, scans DESC, domain",
+            DomainOrder::AverageRiskScore => "average_risk_score DESC, scans DESC, domain",
+            DomainOrder::MaxRiskScore => "max_risk_score DESC, scans DESC, domain",
+            DomainOrder::LastSeen => "last_seen DESC, domain",
+        }
+    }
+}
+
+/// Scans of one registrable domain within a window
+#[derive(Serialize, Debug, Clone)]
+pub struct DomainStats {
+    domain: String,
+    scans: u64,
+    phishing: u64,
+    average_risk_score: f64,
+    max_risk_score: i32,
+    /// Unix timestamps of the first and last scan in the window
+    first_seen: i64,
+    last_seen: i64,
+}
+
+/// Risk scores of a domain's scans on one local calendar day
+#[derive(Serialize, Debug, Clone)]
+pub struct DayRisk {
+    /// YYYY-MM-DD in the user's local timezone
+    day: String,
+    scans: u64,
+    average_risk_score: f64,
+    max_risk_score: i32,
+}
+
+/// Everything the history knows about one registrable domain
+#[derive(Serialize, Debug, Clone)]
+pub struct DomainSummary {
+    /// eTLD+1, e.g. `evil.co.uk` for `login.evil.co.uk`
+    domain: String,
+    total: u64,
+    first_seen: Option<i64>,
+    last_seen: Option<i64>,
+    by_classification: Vec<ClassificationCount>,
+    /// One entry per day the domain was scanned, oldest first
+    risk_trend: Vec<DayRisk>,
+    /// Newest first, at most `DOMAIN_SCAN_LIMIT` of them
+    scans: Vec<HistoryEntry>,
+}
+
+/// Scans since local midnight, for the tray tooltip
+#[derive(Debug, Clone, Copy)]
+pub struct TodayCounts {
+    pub scans: u64,
+    pub phishing: u64,
+    /// Time left until the counts reset at the next local midnight
+    pub secs_until_midnight: u64,
+}
+
+pub async fn today_counts(history: &ScanHistory) -> Result<TodayCounts, AppError> {
+    history
+        .with_conn(|conn| {
+            let query = format!(
+                "SELECT COUNT(*), COALESCE(SUM(CASE WHEN {} THEN 1 ELSE 0 END), 0),
+                        CAST(strftime('%s', date('now', 'localtime', '+1 day'), 'utc') AS INTEGER)
+                            - CAST(strftime('%s', 'now') AS INTEGER)
+                 FROM scans
+                 WHERE scanned_at >= CAST(strftime('%s', date('now', 'localtime'), 'utc') AS INTEGER)",
+                PHISHING_CONDITION
+            );
+            Ok(conn.query_row(&query, [], |row| {
+                Ok(TodayCounts {
+                    scans: row.get(0)?,
+                    phishing: row.get(1)?,
+                    secs_until_midnight: row.get::<_, i64>(2)?.max(1) as u64,
+                })
+            })?)
+        })
+        .await
+}
+
+/// Host part of `normalized_url` (which the history stores as `scheme://host/...`)
+const HOST_SQL: &str = "substr(
+        substr(normalized_url, instr(normalized_url, '://') + 3),
+        1,
+        instr(substr(normalized_url, instr(normalized_url, '://') + 3) || '/', '/') - 1
+    )";
+
+/// Local date the window starts on
+fn first_day_sql(range: StatsRange) -> String {
+    match range.days_back() {
+        Some(days) => format!("date('now', 'localtime', '-{} days')", days),
+        None => "COALESCE((SELECT date(MIN(scanned_at), 'unixepoch', 'localtime') FROM scans),
+                          date('now', 'localtime'))"
+            .to_string(),
+    }
+}
+
+fn compute(conn: &Connection, range: StatsRange) -> rusqlite::Result<Statistics> {
+    let first_day = first_day_sql(range);
+    let since: i64 = conn.query_row(
+        &format!(
+            "SELECT CAST(strftime('%s', {}, 'utc') AS INTEGER)",
+            first_day
+        ),
+        [],
+        |row| row.get(0),
+    )?;
+
+    let (total, phishing, average_confidence, average_risk_score): (
+        u64,
+        u64,
+        Option<f64>,
+        Option<f64>,
+    ) = conn.query_row(
+        &format!(
+            "SELECT COUNT(*), COALESCE(SUM(CASE WHEN {} THEN 1 ELSE 0 END), 0),
+                        AVG(confidence), AVG(risk_score)
+                 FROM scans WHERE scanned_at >= ?1",
+            PHISHING_CONDITION
+        ),
+        [since],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+    )?;
+
+    // Middle row, or the mean of the two middle rows for an even count
+    let median_confidence: Option<f64> = conn.query_row(
+        "SELECT AVG(confidence) FROM (
+             SELECT confidence FROM scans WHERE scanned_at >= ?1
+             ORDER BY confidence
+             LIMIT 2 - ?2 % 2 OFFSET (?2 - 1) / 2
+         )",
+        rusqlite::params![since, total as i64],
+        |row| row.get(0),
+    )?;
+
+    let mut statement = conn.prepare(
+        "SELECT classification, COUNT(*) FROM scans WHERE scanned_at >= ?1
+         GROUP BY classification ORDER BY COUNT(*) DESC",
+    )?;
+    let by_classification = statement
+        .query_map([since], |row| {
+            Ok(ClassificationCount {
+                classification: row.get(0)?,
+                count: row.get(1)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut statement = conn.prepare(&format!(
+        "WITH RECURSIVE days(day) AS (
+             SELECT {first_day}
+             UNION ALL
+             SELECT date(day, '+1 day') FROM days WHERE day < date('now', 'localtime')
+         ),
+         counts AS (
+             SELECT date(scanned_at, 'unixepoch', 'localtime') AS day,
+                    COUNT(*) AS scans,
+                    SUM(CASE WHEN {phishing} THEN 1 ELSE 0 END) AS phishing
+             FROM scans WHERE scanned_at >= ?1
+             GROUP BY 1
+         )
+         SELECT days.day, COALESCE(counts.scans, 0), COALESCE(counts.phishing, 0)
+         FROM days LEFT JOIN counts USING (day)
+         ORDER BY days.day",
+        first_day = first_day,
+        phishing = PHISHING_CONDITION
+    ))?;
+    let per_day = statement
+        .query_map([since], |row| {
+            Ok(DayCount {
+                day: row.get(0)?,
+                scans: row.get(1)?,
+                phishing: row.get(2)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut statement = conn.prepare(&format!(
+        "SELECT {} AS domain, COUNT(*) FROM scans
+         WHERE scanned_at >= ?1 AND {}
+         GROUP BY domain ORDER BY COUNT(*) DESC, domain LIMIT 10",
+        HOST_SQL, PHISHING_CONDITION
+    ))?;
+    let top_flagged_domains = statement
+        .query_map([since], |row| {
+            Ok(DomainCount {
+                domain: row.get(0)?,
+                count: row.get(1)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Statistics {
+        range,
+        since,
+        total,
+        phishing,
+        phishing_ratio: if total == 0 {
+            0.0
+        } else {
+            phishing as f64 / total as f64
+        },
+        by_classification,
+        per_day,
+        top_flagged_domains,
+        average_confidence,
+        median_confidence,
+        average_risk_score,
+    })
+}
+
+/// Aggregate counts and trends over the local history for a dashboard
+#[tauri::command]
+pub async fn get_statistics(
+    range: Option<StatsRange>,
+    history: State<'_, ScanHistory>,
+) -> Result<Statistics, AppError> {
+    let range = range.unwrap_or_default();
+    history
+        .with_conn(move |conn| Ok(compute(conn, range)?))
+        .await
+}
+
+fn summarize_domain(conn: &Connection, domain: String) -> rusqlite::Result<DomainSummary> {
+    let (total, first_seen, last_seen) = conn.query_row(
+        "SELECT COUNT(*), MIN(scanned_at), MAX(scanned_at) FROM scans WHERE domain = ?1",
+        [&domain],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    )?;
+
+    let mut statement = conn.prepare(
+        "SELECT classification, COUNT(*) FROM scans WHERE domain = ?1
+         GROUP BY classification ORDER BY COUNT(*) DESC",
+    )?;
+    let by_classification = statement
+        .query_map([&domain], |row| {
+            Ok(ClassificationCount {
+                classification: row.get(0)?,
+                count: row.get(1)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut statement = conn.prepare(
+        "SELECT date(scanned_at, 'unixepoch', 'localtime') AS day, COUNT(*),
+                AVG(risk_score), MAX(risk_score)
+         FROM scans WHERE domain = ?1
+         GROUP BY day ORDER BY day",
+    )?;
+    let risk_trend = statement
+        .query_map([&domain], |row| {
+            Ok(DayRisk {
+                day: row.get(0)?,
+                scans: row.get(1)?,
+                average_risk_score: row.get(2)?,
+                max_risk_score: row.get(3)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut statement = conn.prepare(&format!(
+        "SELECT {} FROM scans WHERE domain = ?1
+         ORDER BY scanned_at DESC, id DESC LIMIT ?2",
+        ENTRY_COLUMNS
+    ))?;
+    let scans = statement
+        .query_map(params![domain, DOMAIN_SCAN_LIMIT as i64], entry_from_row)?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(DomainSummary {
+        domain,
+        total,
+        first_seen,
+        last_seen,
+        by_classification,
+        risk_trend,
+        scans,
+    })
+}
+
+/// The scans of a registrable domain with their verdicts and risk trend.
+/// `domain` may be any host or URL on it; `login.evil.co.uk` is summarised
+/// as `evil.co.uk`.
+#[tauri::command]
+pub async fn get_domain_summary(
+    domain: String,
+    history: State<'_, ScanHistory>,
+) -> Result<DomainSummary, AppError> {
+    let domain = parse_domain(&domain)?;
+    history
+        .with_conn(move |conn| Ok(summarize_domain(conn, domain)?))
+        .await
+}
+
+/// Registrable domains scanned in `range`, busiest first unless `order_by`
+/// says otherwise
+#[tauri::command]
+pub async fn get_top_domains(
+    range: Option<StatsRange>,
+    order_by: Option<DomainOrder>,
+    limit: Option<usize>,
+    history: State<'_, ScanHistory>,
+) -> Result<Vec<DomainStats>, AppError> {
+    let (range, order_by) = (range.unwrap_or_default(), order_by.unwrap_or_default());
+    let limit = limit.unwrap_or(20).clamp(1, MAX_TOP_DOMAINS);
+    history
+        .with_conn(move |conn| {
+            let since: i64 = conn.query_row(
+                &format!(
+                    "SELECT CAST(strftime('%s', {}, 'utc') AS INTEGER)",
+                    first_day_sql(range)
+                ),
+                [],
+                |row| row.get(0),
+            )?;
+            let mut statement = conn.prepare(&format!(
+                "SELECT domain, COUNT(*) AS scans,
+                        SUM(CASE WHEN {} THEN 1 ELSE 0 END) AS phishing,
+                        AVG(risk_score) AS average_risk_score,
+                        MAX(risk_score) AS max_risk_score,
+                        MIN(scanned_at), MAX(scanned_at) AS last_seen
+                 FROM scans WHERE scanned_at >= ?1 AND domain != ''
+                 GROUP BY domain ORDER BY {} LIMIT ?2",
+                PHISHING_CONDITION,
+                order_by.order_by()
+            ))?;
+            let domains = statement
+                .query_map(params![since, limit as i64], |row| {
+                    Ok(DomainStats {
+                        domain: row.get(0)?,
+                        scans: row.get(1)?,
+                        phishing: row.get(2)?,
+                        average_risk_score: row.get(3)?,
+                        max_risk_score: row.get(4)?,
+                        first_seen: row.get(5)?,
+                        last_seen: row.get(6)?,
+                    })
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(domains)
+        })
+        .await
+}
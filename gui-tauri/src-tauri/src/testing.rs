@@ -0,0 +1,85 @@
+//! The app on Tauri's mock runtime, for tests that go through commands and
+//! events rather than calling into a module directly
+
+use crate::allowlist::Allowlist;
+use crate::blocklist::Blocklist;
+use crate::history::ScanHistory;
+use crate::mock::{MockMode, MOCK_ARG};
+use crate::settings::Settings;
+use crate::{queue, with_state, App, WebviewWindow};
+use std::path::PathBuf;
+use tauri::ipc::{CallbackFn, Invoke, InvokeBody};
+use tauri::test::{get_ipc_response, mock_builder, mock_context, noop_assets, INVOKE_KEY};
+use tauri::webview::InvokeRequest;
+use tauri::{Manager, WebviewWindowBuilder};
+
+/// A mock-mode app with its data in a directory of its own, removed again
+/// when it is dropped
+pub struct TestApp {
+    /// Kept for as long as its window is in use
+    _app: App,
+    pub window: WebviewWindow,
+    dir: PathBuf,
+}
+
+impl Drop for TestApp {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Build the app the way `main` does, answering scans with mock verdicts
+/// and serving the commands `handler` takes
+pub fn app<F>(handler: F) -> TestApp
+where
+    F: Fn(Invoke<crate::AppRuntime>) -> bool + Send + Sync + 'static,
+{
+    let dir = std::env::temp_dir().join(format!("app-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let mock_mode = MockMode::from_args(std::iter::once(MOCK_ARG.to_string()));
+    let app = with_state(mock_builder(), mock_mode)
+        .invoke_handler(handler)
+        .build(mock_context(noop_assets()))
+        .unwrap();
+
+    // What `setup` loads from disk
+    app.manage(Settings::load(&dir).unwrap());
+    let history = ScanHistory::open(&dir.join("history.db")).unwrap();
+    let allowlist = tauri::async_runtime::block_on(Allowlist::load(&history)).unwrap();
+    let blocklist = tauri::async_runtime::block_on(Blocklist::load(&history)).unwrap();
+    app.manage(history);
+    app.manage(allowlist);
+    app.manage(blocklist);
+    tauri::async_runtime::spawn(queue::drain(app.handle().clone()));
+    tauri::async_runtime::spawn(queue::write_store(app.handle().clone()));
+
+    let window = WebviewWindowBuilder::new(&app, "main", Default::default())
+        .build()
+        .unwrap();
+    TestApp {
+        _app: app,
+        window,
+        dir,
+    }
+}
+
+impl TestApp {
+    /// Call `command` the way the frontend does, returning what it answers
+    /// or the error it fails with
+    pub fn invoke(
+        &self,
+        command: &str,
+        args: serde_json::Value,
+    ) -> Result<serde_json::Value, serde_json::Value> {
+        let request = InvokeRequest {
+            cmd: command.into(),
+            callback: CallbackFn(0),
+            error: CallbackFn(1),
+            url: "tauri://localhost".parse().unwrap(),
+            body: InvokeBody::Json(args),
+            headers: Default::default(),
+            invoke_key: INVOKE_KEY.to_string(),
+        };
+        get_ipc_response(&self.window, request).map(|body| body.deserialize().unwrap())
+    }
+}
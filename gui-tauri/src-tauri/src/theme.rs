@@ -0,0 +1,86 @@
+use crate::error::AppError;
+use crate::settings::{self, Settings, ThemePreference};
+use crate::tray;
+use crate::AppHandle;
+use crate::MAIN_WINDOW;
+use serde::Serialize;
+use std::sync::Mutex;
+use tauri::{Emitter, Manager, State, Theme};
+
+/// The theme last announced, so unchanged settings don't re-emit it
+#[derive(Default)]
+pub struct CurrentTheme {
+    last: Mutex<Option<ThemeStatus>>,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ThemeStatus {
+    preference: ThemePreference,
+    /// What windows are actually drawn in: the preference, or the OS theme
+    /// when following the system
+    dark: bool,
+}
+
+/// Theme to force on native windows; `None` follows the OS
+pub fn native(preference: ThemePreference) -> Option<Theme> {
+    match preference {
+        ThemePreference::Light => Some(Theme::Light),
+        ThemePreference::Dark => Some(Theme::Dark),
+        ThemePreference::System => None,
+    }
+}
+
+/// Theme the main window is drawn in, which tracks the OS while it follows it
+fn resolved(app: &AppHandle) -> Theme {
+    app.get_webview_window(MAIN_WINDOW)
+        .and_then(|window| window.theme().ok())
+        .unwrap_or(Theme::Light)
+}
+
+fn announce(app: &AppHandle, preference: ThemePreference, theme: Theme) {
+    let status = ThemeStatus {
+        preference,
+        dark: theme == Theme::Dark,
+    };
+    let changed = match app.state::<CurrentTheme>().last.lock() {
+        Ok(mut last) => last.replace(status) != Some(status),
+        Err(_) => true,
+    };
+    if changed {
+        tray::set_dark(app, status.dark);
+        let _ = app.emit("theme-changed", status);
+    }
+}
+
+/// Apply the theme preference to every native window
+pub fn apply(app: &AppHandle, preference: ThemePreference) {
+    app.set_theme(native(preference));
+    // The forced theme is known up front; the OS one is read back
+    let theme = native(preference).unwrap_or_else(|| resolved(app));
+    announce(app, preference, theme);
+}
+
+/// The OS switched between light and dark while windows follow it
+pub fn os_theme_changed(app: &AppHandle, theme: Theme) {
+    let preference = app.state::<Settings>().get().theme;
+    if preference == ThemePreference::System {
+        announce(app, preference, theme);
+    }
+}
+
+#[tauri::command]
+pub fn get_theme(app: AppHandle, settings: State<'_, Settings>) -> ThemeStatus {
+    let preference = settings.get().theme;
+    let theme = native(preference).unwrap_or_else(|| resolved(&app));
+    ThemeStatus {
+        preference,
+        dark: theme == Theme::Dark,
+    }
+}
+
+/// Save the theme preference; applying it emits `theme-changed`
+#[tauri::command]
+pub fn set_theme(theme: ThemePreference, app: AppHandle) -> Result<ThemeStatus, AppError> {
+    settings::update(&app, |s| s.theme = theme)?;
+    Ok(get_theme(app.clone(), app.state::<Settings>()))
+}
@@ -0,0 +1,433 @@
+use crate::clipboard::{self, ClipboardWatcher};
+use crate::close;
+use crate::crash;
+use crate::health::{HealthMonitor, HealthState};
+use crate::history::{HistoryEntry, ScanHistory};
+use crate::links::defang;
+use crate::mini_scanner;
+use crate::notifications::open_scan;
+use crate::protection::{self, Protection};
+use crate::quiet_hours::QuietHours;
+use crate::stats::{today_counts, TodayCounts};
+use crate::{App, AppHandle, AppRuntime};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::image::Image;
+use tauri::menu::{CheckMenuItem, Menu, MenuEvent, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
+use tauri::Manager;
+
+pub const TRAY_ID: &str = "main";
+/// How long the tray shows the alert badge after a phishing verdict
+const ALERT_DURATION: Duration = Duration::from_secs(30);
+/// Entries in the "Recent scans" submenu
+const RECENT_LIMIT: usize = 5;
+/// Longest URL label shown in the submenu, in characters
+const MAX_LABEL_CHARS: usize = 48;
+/// Menu item ids for recent scans are this prefix followed by the scan id
+const RECENT_PREFIX: &str = "recent:";
+/// Timed pause lengths offered in the tray, in minutes
+const PAUSE_OPTIONS: [(u64, &str); 2] = [(15, "For 15 minutes"), (60, "For 1 hour")];
+
+/// Tray icon pixel size; macOS menu bars are drawn at 2x on Retina displays
+#[cfg(target_os = "macos")]
+const ICON_SIZE: u32 = 44;
+#[cfg(not(target_os = "macos"))]
+const ICON_SIZE: u32 = 32;
+
+const GREEN: [u8; 3] = [0x2e, 0x9d, 0x5b];
+const GREY: [u8; 3] = [0x8a, 0x8f, 0x98];
+const LIGHT_GREY: [u8; 3] = [0xc4, 0xc8, 0xce];
+const RED: [u8; 3] = [0xd6, 0x33, 0x2f];
+
+/// What the tray icon is currently telling the user
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Indicator {
+    Protected,
+    Unavailable,
+    Alert,
+}
+
+/// Inputs the tray icon is derived from
+#[derive(Default)]
+pub struct TrayStatus {
+    state: Mutex<TrayInputs>,
+}
+
+#[derive(Default)]
+struct TrayInputs {
+    detector: Option<HealthState>,
+    alert_until: Option<Instant>,
+    paused: bool,
+    quiet: bool,
+    /// The desktop is in dark mode, so the greyed-out icon is drawn lighter
+    dark: bool,
+    /// Scans and phishing verdicts since local midnight
+    today: Option<(u64, u64)>,
+}
+
+impl TrayInputs {
+    fn indicator(&self) -> Indicator {
+        if self.alert_until.is_some_and(|until| Instant::now() < until) {
+            Indicator::Alert
+        } else if self.paused
+            || matches!(
+                self.detector,
+                Some(HealthState::Down | HealthState::Degraded)
+            )
+        {
+            Indicator::Unavailable
+        } else {
+            Indicator::Protected
+        }
+    }
+
+    fn tooltip(&self) -> String {
+        let mut parts = Vec::new();
+        if self.indicator() == Indicator::Alert {
+            parts.push("phishing detected".to_string());
+        }
+        if let Some((scans, phishing)) = self.today {
+            parts.push(format!("{} scans today, {} phishing", scans, phishing));
+        }
+        parts.push(
+            match self.detector {
+                Some(HealthState::Up) => "detector: ready",
+                Some(HealthState::Degraded) => "detector: missing packages",
+                Some(HealthState::Down) => "detector: unavailable",
+                None => "detector: starting",
+            }
+            .to_string(),
+        );
+        if self.paused {
+            parts.push("protection paused".to_string());
+        }
+        if self.quiet {
+            parts.push("quiet hours".to_string());
+        }
+        format!("Phishing Guard - {}", parts.join(", "))
+    }
+}
+
+/// Anti-aliased disc icon, with a red badge in the top-right corner for alerts
+fn render(indicator: Indicator, dark: bool) -> Image<'static> {
+    let size = ICON_SIZE as f32;
+    let (fill, badge) = match indicator {
+        Indicator::Protected => (GREEN, false),
+        Indicator::Unavailable if dark => (LIGHT_GREY, false),
+        Indicator::Unavailable => (GREY, false),
+        Indicator::Alert => (GREEN, true),
+    };
+
+    // Coverage of pixel (x, y) by a circle, feathered over one pixel
+    let coverage = |x: f32, y: f32, cx: f32, cy: f32, r: f32| {
+        let d = ((x - cx).powi(2) + (y - cy).powi(2)).sqrt();
+        (r - d + 0.5).clamp(0.0, 1.0)
+    };
+
+    let mut rgba = Vec::with_capacity((ICON_SIZE * ICON_SIZE * 4) as usize);
+    for y in 0..ICON_SIZE {
+        for x in 0..ICON_SIZE {
+            let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+            let mut color = fill;
+            let mut alpha = coverage(px, py, size / 2.0, size / 2.0, size / 2.0 - 1.0);
+            if badge {
+                let b = coverage(px, py, size * 0.75, size * 0.25, size * 0.25);
+                for (c, r) in color.iter_mut().zip(RED) {
+                    *c = (*c as f32 * (1.0 - b) + r as f32 * b) as u8;
+                }
+                alpha = alpha.max(b);
+            }
+            rgba.extend_from_slice(&color);
+            rgba.push((alpha * 255.0) as u8);
+        }
+    }
+    Image::new_owned(rgba, ICON_SIZE, ICON_SIZE)
+}
+
+/// Redraw the tray icon and tooltip from the current inputs.
+///
+/// Icon swapping is unreliable on some Linux desktops, so failures are only logged.
+pub fn refresh(app: &AppHandle) {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+    let (indicator, dark, tooltip) = match app.state::<TrayStatus>().state.lock() {
+        Ok(inputs) => (inputs.indicator(), inputs.dark, inputs.tooltip()),
+        Err(_) => return,
+    };
+    if let Err(e) = tray.set_icon(Some(render(indicator, dark))) {
+        tracing::warn!(error = %e, "failed to update tray icon");
+    }
+    // Not every platform shows tooltips; there is nothing useful to report
+    let _ = tray.set_tooltip(Some(tooltip));
+}
+
+/// Show the alert badge for a while after a phishing verdict
+pub fn flag_phishing(app: &AppHandle) {
+    if let Ok(mut inputs) = app.state::<TrayStatus>().state.lock() {
+        inputs.alert_until = Some(Instant::now() + ALERT_DURATION);
+    }
+    refresh(app);
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(ALERT_DURATION).await;
+        refresh(&app);
+    });
+}
+
+/// Recount today's scans for the tooltip
+pub async fn refresh_stats(app: &AppHandle) -> Option<TodayCounts> {
+    let counts = today_counts(&app.state::<ScanHistory>()).await.ok()?;
+    if let Ok(mut inputs) = app.state::<TrayStatus>().state.lock() {
+        inputs.today = Some((counts.scans, counts.phishing));
+    }
+    refresh(app);
+    Some(counts)
+}
+
+/// Reset the tooltip's daily counts at every local midnight
+async fn track_today(app: AppHandle) {
+    loop {
+        let wait = refresh_stats(&app)
+            .await
+            .map_or(60, |counts| counts.secs_until_midnight + 1);
+        tokio::time::sleep(Duration::from_secs(wait)).await;
+    }
+}
+
+/// Grey out the icon while protection is paused and update the pause menu
+pub fn set_paused(app: &AppHandle, paused: bool) {
+    if let Ok(mut inputs) = app.state::<TrayStatus>().state.lock() {
+        inputs.paused = paused;
+    }
+    refresh(app);
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move { refresh_menu(&app).await });
+}
+
+/// Redraw the icon for a light or dark desktop
+pub fn set_dark(app: &AppHandle, dark: bool) {
+    if let Ok(mut inputs) = app.state::<TrayStatus>().state.lock() {
+        inputs.dark = dark;
+    }
+    refresh(app);
+}
+
+/// Note quiet hours in the tooltip and check the menu item while they last
+pub fn set_quiet(app: &AppHandle, quiet: bool) {
+    if let Ok(mut inputs) = app.state::<TrayStatus>().state.lock() {
+        inputs.quiet = quiet;
+    }
+    refresh(app);
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move { refresh_menu(&app).await });
+}
+
+/// Keep the tray in step with the background health checks
+async fn follow_health(app: AppHandle) {
+    let mut health = app.state::<HealthMonitor>().subscribe();
+    while health.changed().await.is_ok() {
+        let state = health.borrow_and_update().as_ref().map(|s| s.state);
+        if let Ok(mut inputs) = app.state::<TrayStatus>().state.lock() {
+            inputs.detector = state;
+        }
+        refresh(&app);
+    }
+}
+
+pub fn show_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window(crate::MAIN_WINDOW) {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+/// Submenu label for a saved scan: verdict mark plus the defanged, truncated URL
+fn recent_label(entry: &HistoryEntry) -> String {
+    let mark = match entry.classification.as_str() {
+        "legitimate" | "allowlisted" => '✓',
+        _ => '⚠',
+    };
+    let url = defang(&entry.url);
+    let url = if url.chars().count() > MAX_LABEL_CHARS {
+        let cut: String = url.chars().take(MAX_LABEL_CHARS - 1).collect();
+        format!("{}…", cut)
+    } else {
+        url
+    };
+    format!("{} {}", mark, url)
+}
+
+/// Open, a "Recent scans" submenu with the latest verdicts, and Quit
+fn menu(app: &AppHandle, recent: &[HistoryEntry]) -> tauri::Result<Menu<AppRuntime>> {
+    let recent_menu = Submenu::with_id(app, "recent", "Recent scans", true)?;
+    if recent.is_empty() {
+        recent_menu.append(&MenuItem::with_id(
+            app,
+            "recent-empty",
+            "No scans yet",
+            false,
+            None::<&str>,
+        )?)?;
+    }
+    for entry in recent {
+        // Entries saved before scan ids were recorded cannot be opened
+        let id = entry.scan_id.as_deref().unwrap_or_default();
+        recent_menu.append(&MenuItem::with_id(
+            app,
+            format!("{}{}", RECENT_PREFIX, id),
+            recent_label(entry),
+            !id.is_empty(),
+            None::<&str>,
+        )?)?;
+    }
+
+    let protection = app.state::<Protection>().status();
+    let pause_menu = Submenu::with_id(app, "pause", "Pause protection", true)?;
+    for (minutes, label) in PAUSE_OPTIONS {
+        pause_menu.append(&CheckMenuItem::with_id(
+            app,
+            format!("pause:{}", minutes),
+            label,
+            true,
+            protection.paused && protection.minutes == Some(minutes),
+            None::<&str>,
+        )?)?;
+    }
+    pause_menu.append(&CheckMenuItem::with_id(
+        app,
+        "pause:indefinite",
+        "Until resumed",
+        true,
+        protection.paused && protection.minutes.is_none(),
+        None::<&str>,
+    )?)?;
+    pause_menu.append(&PredefinedMenuItem::separator(app)?)?;
+    pause_menu.append(&MenuItem::with_id(
+        app,
+        "resume",
+        "Resume protection",
+        protection.paused,
+        None::<&str>,
+    )?)?;
+
+    // Only reflects the schedule, which is edited in the settings
+    let quiet = CheckMenuItem::with_id(
+        app,
+        "quiet-hours",
+        "Quiet hours",
+        false,
+        app.state::<QuietHours>().is_active(),
+        None::<&str>,
+    )?;
+
+    let clipboard_watch = CheckMenuItem::with_id(
+        app,
+        "clipboard-watch",
+        "Watch clipboard",
+        true,
+        app.state::<ClipboardWatcher>().is_running(),
+        None::<&str>,
+    )?;
+
+    Menu::with_items(
+        app,
+        &[
+            &MenuItem::with_id(app, "open", "Open Phishing Guard", true, None::<&str>)?,
+            &MenuItem::with_id(app, "mini-scanner", "Mini scanner", true, None::<&str>)?,
+            &recent_menu,
+            &pause_menu,
+            &quiet,
+            &clipboard_watch,
+            &PredefinedMenuItem::separator(app)?,
+            &MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?,
+        ],
+    )
+}
+
+/// Rebuild the tray menu so "Recent scans" shows the latest history
+pub async fn refresh_menu(app: &AppHandle) {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+    let recent = app
+        .state::<ScanHistory>()
+        .recent(RECENT_LIMIT)
+        .await
+        .unwrap_or_default();
+    let result = menu(app, &recent).and_then(|menu| tray.set_menu(Some(menu)));
+    if let Err(e) = result {
+        tracing::warn!(error = %e, "failed to update tray menu");
+    }
+}
+
+fn on_menu_event(app: &AppHandle, event: MenuEvent) {
+    match event.id.as_ref() {
+        "open" => show_main_window(app),
+        "quit" => close::quit(app),
+        "mini-scanner" => {
+            let _ = mini_scanner::toggle(app);
+        }
+        "clipboard-watch" => {
+            if let Err(e) = clipboard::toggle(app) {
+                tracing::warn!(error = %e, "could not toggle the clipboard watcher");
+            }
+        }
+        "resume" => {
+            let _ = protection::resume(app);
+        }
+        "pause:indefinite" => {
+            let _ = protection::pause(app, None);
+        }
+        id => {
+            if let Some(minutes) = id.strip_prefix("pause:").and_then(|m| m.parse().ok()) {
+                let _ = protection::pause(app, Some(minutes));
+            } else if let Some(scan_id) = id.strip_prefix(RECENT_PREFIX) {
+                let (app, scan_id) = (app.clone(), scan_id.to_string());
+                tauri::async_runtime::spawn(async move { open_scan(&app, &scan_id).await });
+            }
+        }
+    }
+}
+
+/// Create the tray icon and its menu
+pub fn build(app: &App) -> tauri::Result<()> {
+    let menu = menu(app.handle(), &[])?;
+    // Inputs may already be set, e.g. the theme applied with the settings
+    let (icon, tooltip) = match app.state::<TrayStatus>().state.lock() {
+        Ok(inputs) => (render(inputs.indicator(), inputs.dark), inputs.tooltip()),
+        Err(_) => {
+            let inputs = TrayInputs::default();
+            (render(inputs.indicator(), inputs.dark), inputs.tooltip())
+        }
+    };
+
+    TrayIconBuilder::with_id(TRAY_ID)
+        .icon(icon)
+        .tooltip(tooltip)
+        .menu(&menu)
+        .show_menu_on_left_click(false)
+        .on_menu_event(on_menu_event)
+        .on_tray_icon_event(|tray, event| {
+            if let TrayIconEvent::Click {
+                button: MouseButton::Left,
+                button_state: MouseButtonState::Up,
+                ..
+            } = event
+            {
+                show_main_window(tray.app_handle());
+            }
+        })
+        .build(app)?;
+
+    crash::supervise(app.handle().clone(), "tray health", follow_health);
+    crash::supervise(app.handle().clone(), "tray stats", track_today);
+    let handle = app.handle().clone();
+    tauri::async_runtime::spawn(async move { refresh_menu(&handle).await });
+    Ok(())
+}
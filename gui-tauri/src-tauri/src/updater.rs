@@ -0,0 +1,217 @@
+use crate::error::AppError;
+use crate::history::ScanHistory;
+use crate::notifications::notify;
+use crate::{AppHandle, AppRuntime};
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::plugin::TauriPlugin;
+use tauri::{Emitter, Manager, State};
+use tauri_plugin_updater::{Update, UpdaterExt};
+
+/// How often the background check asks the update endpoint
+const CHECK_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+/// Let startup settle before the first check
+const FIRST_CHECK_DELAY: Duration = Duration::from_secs(30);
+/// Release builds are signed with the key this matches; `tauri.conf.json`
+/// leaves the key empty so development builds never install anything
+const RELEASE_PUBKEY: Option<&str> = option_env!("PHISHING_GUARD_UPDATER_PUBKEY");
+
+/// The updater plugin, using the release signing key when this build has one
+pub fn plugin() -> TauriPlugin<AppRuntime, tauri_plugin_updater::Config> {
+    let builder = tauri_plugin_updater::Builder::new();
+    match RELEASE_PUBKEY {
+        Some(pubkey) => builder.pubkey(pubkey).build(),
+        None => builder.build(),
+    }
+}
+
+/// The update found by the last check, kept so installing it doesn't ask
+/// the endpoint again
+#[derive(Default)]
+pub struct Updates {
+    available: Mutex<Option<Update>>,
+    /// Version the user was last notified about, so the daily check doesn't
+    /// repeat itself
+    notified: Mutex<Option<String>>,
+}
+
+/// Result of check_for_updates
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum UpdateCheck {
+    /// This copy can't update itself; `reason` says why
+    Unavailable {
+        reason: String,
+    },
+    UpToDate {
+        current_version: String,
+    },
+    Available {
+        current_version: String,
+        version: String,
+        notes: Option<String>,
+        /// As published by the endpoint, e.g. `2024-05-01T12:00:00Z`
+        published: Option<String>,
+    },
+}
+
+#[derive(Serialize, Debug, Clone)]
+struct UpdateProgress {
+    version: String,
+    downloaded: u64,
+    total: Option<u64>,
+}
+
+/// Why this copy can't update itself, if it can't
+fn unavailable_reason(app: &AppHandle) -> Option<String> {
+    let configured_key = app
+        .config()
+        .plugins
+        .0
+        .get("updater")
+        .and_then(|updater| updater.get("pubkey"))
+        .and_then(|pubkey| pubkey.as_str())
+        .is_some_and(|pubkey| !pubkey.trim().is_empty());
+    if tauri::is_dev() || (RELEASE_PUBKEY.is_none() && !configured_key) {
+        return Some("this build is not signed for updates".into());
+    }
+    // Only the AppImage can replace itself; distribution packages are
+    // updated by the package manager
+    #[cfg(target_os = "linux")]
+    if std::env::var_os("APPIMAGE").is_none() {
+        return Some("installed by a package manager; update it from there".into());
+    }
+    // Portable copies are shipped with a `portable` file next to the executable
+    let portable = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.join("portable").exists()))
+        .unwrap_or(false);
+    portable.then(|| "portable copies are updated by replacing them".into())
+}
+
+fn failed(e: tauri_plugin_updater::Error) -> AppError {
+    AppError::Update(e.to_string())
+}
+
+async fn check(app: &AppHandle) -> Result<UpdateCheck, AppError> {
+    if let Some(reason) = unavailable_reason(app) {
+        return Ok(UpdateCheck::Unavailable { reason });
+    }
+    let current_version = app.package_info().version.to_string();
+    let update = app
+        .updater()
+        .map_err(failed)?
+        .check()
+        .await
+        .map_err(failed)?;
+    let check = match &update {
+        Some(update) => UpdateCheck::Available {
+            current_version,
+            version: update.version.clone(),
+            notes: update.body.clone(),
+            published: update
+                .raw_json
+                .get("pub_date")
+                .and_then(|date| date.as_str())
+                .map(str::to_string),
+        },
+        None => UpdateCheck::UpToDate { current_version },
+    };
+    *app.state::<Updates>().available.lock()? = update;
+    Ok(check)
+}
+
+/// Check at startup and then daily, notifying once per new version
+pub async fn watch(app: AppHandle) {
+    tokio::time::sleep(FIRST_CHECK_DELAY).await;
+    loop {
+        match check(&app).await {
+            Ok(UpdateCheck::Available { version, .. }) => {
+                let updates = app.state::<Updates>();
+                let first = updates
+                    .notified
+                    .lock()
+                    .map(|mut notified| notified.replace(version.clone()) != Some(version.clone()))
+                    .unwrap_or(false);
+                if first {
+                    tracing::info!(%version, "update available");
+                    let _ = app.emit("update-available", &version);
+                    notify(
+                        &app,
+                        "Update available",
+                        &format!(
+                            "Phishing Guard {} is ready to install from Settings",
+                            version
+                        ),
+                    );
+                }
+            }
+            Ok(UpdateCheck::Unavailable { reason }) => {
+                tracing::debug!(%reason, "updates unavailable");
+                return;
+            }
+            Ok(UpdateCheck::UpToDate { .. }) => {}
+            Err(e) => tracing::warn!(error = %e, "update check failed"),
+        }
+        tokio::time::sleep(CHECK_INTERVAL).await;
+    }
+}
+
+#[tauri::command]
+pub async fn check_for_updates(app: AppHandle) -> Result<UpdateCheck, AppError> {
+    check(&app).await
+}
+
+/// Download, verify and install the update found by the last check, then
+/// restart into it. Emits `update-progress` while downloading.
+#[tauri::command]
+pub async fn install_update(app: AppHandle, updates: State<'_, Updates>) -> Result<(), AppError> {
+    if let Some(reason) = unavailable_reason(&app) {
+        return Err(AppError::Update(reason));
+    }
+    let pending = updates.available.lock()?.clone();
+    let update = match pending {
+        Some(update) => update,
+        None => match check(&app).await? {
+            UpdateCheck::Available { .. } => updates
+                .available
+                .lock()?
+                .clone()
+                .ok_or_else(|| AppError::Update("the update is no longer available".into()))?,
+            _ => return Err(AppError::Update("there is no update to install".into())),
+        },
+    };
+
+    let version = update.version.clone();
+    let mut downloaded = 0u64;
+    let bytes = update
+        .download(
+            |chunk, total| {
+                downloaded += chunk as u64;
+                let _ = app.emit(
+                    "update-progress",
+                    UpdateProgress {
+                        version: version.clone(),
+                        downloaded,
+                        total,
+                    },
+                );
+            },
+            || {
+                let _ = app.emit("update-downloaded", &version);
+            },
+        )
+        .await
+        .map_err(failed)?;
+
+    // Installing exits the process on Windows, so history has to be settled
+    // first; the guard stays held until the restart
+    let _closed = match app.try_state::<ScanHistory>() {
+        Some(history) => Some(history.finish_writes().await),
+        None => None,
+    };
+    tracing::info!(%version, "installing update");
+    update.install(bytes).map_err(failed)?;
+    app.restart()
+}
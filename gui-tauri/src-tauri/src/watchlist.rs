@@ -0,0 +1,474 @@
+use crate::error::AppError;
+use crate::history::{normalize_url, now_secs, ScanHistory};
+use crate::lifecycle::{ScanJob, ScanSource};
+use crate::links::normalize_input;
+use crate::logging;
+use crate::notifications::notify_phishing;
+use crate::protection::is_paused;
+use crate::queue::ScanQueue;
+use crate::{is_phishing_classification, AppHandle, ScanResult};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use std::ops::RangeInclusive;
+use std::time::Duration;
+use tauri::{Emitter, Manager, State};
+use tokio::sync::Notify;
+
+/// Allowed check intervals, in seconds: a quarter hour to a month
+const INTERVAL_RANGE: RangeInclusive<u64> = 900..=2_592_000;
+const DEFAULT_INTERVAL_SECS: u64 = 24 * 60 * 60;
+/// Checks missed while the app was closed are spread over this many seconds
+/// instead of all running at startup
+const CATCH_UP_SPREAD_SECS: i64 = 600;
+/// How soon a check that got no detector verdict is tried again
+const RETRY_SECS: i64 = 15 * 60;
+/// Longest the scheduler sleeps before looking again, so a suspended
+/// machine doesn't oversleep a due check by much
+const MAX_SLEEP: Duration = Duration::from_secs(300);
+
+const COLUMNS: &str = "id, url, interval_secs, added_at, next_check_at, last_checked_at,
+                       last_classification, last_risk_score, last_scan_id";
+
+/// Wakes the scheduler when an entry is added or changed
+#[derive(Default)]
+pub struct Watchlist {
+    wake: Notify,
+}
+
+impl Watchlist {
+    /// Have the scheduler look at the entries again
+    pub fn changed(&self) {
+        self.wake.notify_one();
+    }
+}
+
+/// A watched URL as exported in a configuration bundle
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct WatchedUrl {
+    pub url: String,
+    pub interval_secs: u64,
+}
+
+impl WatchedUrl {
+    /// `url` cleaned up the way a scan would see it, with `interval_secs`
+    /// (a day by default) checked against the allowed range
+    pub fn new(url: &str, interval_secs: Option<u64>) -> Result<Self, AppError> {
+        let interval_secs = interval_secs.unwrap_or(DEFAULT_INTERVAL_SECS);
+        if !INTERVAL_RANGE.contains(&interval_secs) {
+            return Err(AppError::InvalidInput(format!(
+                "interval_secs must be between {} and {}",
+                INTERVAL_RANGE.start(),
+                INTERVAL_RANGE.end()
+            )));
+        }
+        Ok(Self {
+            url: normalize_input(url)?,
+            interval_secs,
+        })
+    }
+}
+
+/// A URL checked again on a schedule
+#[derive(Serialize, Debug, Clone)]
+pub struct WatchEntry {
+    id: i64,
+    url: String,
+    interval_secs: i64,
+    added_at: i64,
+    /// Unix seconds; includes a little jitter so entries added together
+    /// don't stay in step
+    next_check_at: i64,
+    last_checked_at: Option<i64>,
+    last_classification: Option<String>,
+    last_risk_score: Option<i32>,
+    /// Scan behind the last verdict, to open it from history
+    last_scan_id: Option<String>,
+}
+
+fn entry_from_row(row: &Row<'_>) -> rusqlite::Result<WatchEntry> {
+    Ok(WatchEntry {
+        id: row.get(0)?,
+        url: row.get(1)?,
+        interval_secs: row.get(2)?,
+        added_at: row.get(3)?,
+        next_check_at: row.get(4)?,
+        last_checked_at: row.get(5)?,
+        last_classification: row.get(6)?,
+        last_risk_score: row.get(7)?,
+        last_scan_id: row.get(8)?,
+    })
+}
+
+async fn find(history: &ScanHistory, id: i64) -> Result<Option<WatchEntry>, AppError> {
+    history
+        .with_conn(move |conn| {
+            Ok(conn
+                .query_row(
+                    &format!("SELECT {} FROM watchlist WHERE id = ?1", COLUMNS),
+                    [id],
+                    entry_from_row,
+                )
+                .optional()?)
+        })
+        .await
+}
+
+/// Entries due now, soonest first
+async fn due(history: &ScanHistory) -> Result<Vec<WatchEntry>, AppError> {
+    history
+        .with_conn(|conn| {
+            let mut statement = conn.prepare(&format!(
+                "SELECT {} FROM watchlist WHERE next_check_at <= ?1 ORDER BY next_check_at",
+                COLUMNS
+            ))?;
+            let rows = statement.query_map([now_secs()], entry_from_row)?;
+            Ok(rows.collect::<Result<Vec<_>, _>>()?)
+        })
+        .await
+}
+
+async fn next_due_at(history: &ScanHistory) -> Result<Option<i64>, AppError> {
+    history
+        .with_conn(|conn| {
+            Ok(
+                conn.query_row("SELECT min(next_check_at) FROM watchlist", [], |row| {
+                    row.get(0)
+                })?,
+            )
+        })
+        .await
+}
+
+/// Try `id` again in `delay` seconds without touching its last verdict
+async fn postpone(history: &ScanHistory, id: i64, delay: i64) -> Result<(), AppError> {
+    history
+        .with_conn(move |conn| {
+            conn.execute(
+                "UPDATE watchlist SET next_check_at = ?1 WHERE id = ?2",
+                params![now_secs() + delay, id],
+            )?;
+            Ok(())
+        })
+        .await
+}
+
+/// Store the verdict for `id` and schedule its next check one interval out,
+/// plus up to a tenth of the interval of jitter
+async fn store_verdict(
+    history: &ScanHistory,
+    id: i64,
+    scan_id: String,
+    result: &ScanResult,
+) -> Result<(), AppError> {
+    let (classification, risk_score) = (result.classification.clone(), result.risk_score);
+    history
+        .with_conn(move |conn| {
+            let now = now_secs();
+            conn.execute(
+                "UPDATE watchlist SET
+                     last_checked_at = ?1, last_classification = ?2, last_risk_score = ?3,
+                     last_scan_id = ?4,
+                     next_check_at = ?1 + interval_secs + abs(random() % (interval_secs / 10 + 1))
+                 WHERE id = ?5",
+                params![now, classification, risk_score, scan_id, id],
+            )?;
+            Ok(())
+        })
+        .await
+}
+
+/// Scan one due entry, warning as soon as it turns phishing
+async fn check(app: &AppHandle, entry: WatchEntry) -> Result<(), AppError> {
+    let history = app.state::<ScanHistory>();
+    let job = ScanJob::from_input(&entry.url, ScanSource::Watchlist)?;
+    let scan_id = job.scan_id.clone();
+    let outcome = app
+        .state::<ScanQueue>()
+        .submit(app, job)
+        .await
+        .and_then(|result| {
+            // A stand-in says nothing about whether the site has changed
+            if result.degraded {
+                Err(AppError::PythonUnavailable("detector unavailable".into()))
+            } else {
+                Ok(result)
+            }
+        });
+    let result = match outcome {
+        Ok(result) => result,
+        Err(e) => {
+            tracing::debug!(id = entry.id, error = %e, "watchlist check failed");
+            return postpone(&history, entry.id, RETRY_SECS).await;
+        }
+    };
+
+    let was_phishing = entry
+        .last_classification
+        .as_deref()
+        .is_some_and(is_phishing_classification);
+    if result.is_phishing() && !was_phishing {
+        tracing::warn!(id = entry.id, url = %logging::url(&entry.url), "watched URL turned phishing");
+        notify_phishing(app, Some(&scan_id), &result);
+    }
+    store_verdict(&history, entry.id, scan_id, &result).await?;
+    if let Some(entry) = find(&history, entry.id).await? {
+        let _ = app.emit("watchlist-checked", entry);
+    }
+    Ok(())
+}
+
+/// Background task that checks each entry when it falls due. Checks run one
+/// at a time through the scan queue, and wait while protection is paused.
+pub async fn watch(app: AppHandle) {
+    let history = app.state::<ScanHistory>();
+    // Checks missed while the app was closed run once, spread out a little
+    let _ = history
+        .with_conn(|conn| {
+            let now = now_secs();
+            conn.execute(
+                "UPDATE watchlist SET next_check_at = ?1 + abs(random() % ?2)
+                 WHERE next_check_at < ?1",
+                params![now, CATCH_UP_SPREAD_SECS],
+            )?;
+            Ok(())
+        })
+        .await;
+
+    let watchlist = app.state::<Watchlist>();
+    loop {
+        let Some(due_at) = next_due_at(&history).await.ok().flatten() else {
+            watchlist.wake.notified().await;
+            continue;
+        };
+        let wait = Duration::from_secs((due_at - now_secs()).max(0) as u64);
+        if !wait.is_zero() {
+            tokio::select! {
+                _ = tokio::time::sleep(wait.min(MAX_SLEEP)) => {}
+                _ = watchlist.wake.notified() => {}
+            }
+            continue;
+        }
+
+        for entry in due(&history).await.unwrap_or_default() {
+            let outcome = if is_paused(&app) {
+                postpone(&history, entry.id, RETRY_SECS).await
+            } else {
+                check(&app, entry).await
+            };
+            if let Err(e) = outcome {
+                tracing::warn!(error = %e, "watchlist check could not be stored");
+                // Don't spin on an entry that can't be rescheduled
+                tokio::time::sleep(MAX_SLEEP).await;
+            }
+        }
+    }
+}
+
+/// Add `watched`, or change its interval if the URL is already watched
+fn upsert(conn: &Connection, watched: &WatchedUrl) -> Result<i64, AppError> {
+    Ok(conn.query_row(
+        "INSERT INTO watchlist (url, normalized_url, interval_secs, added_at, next_check_at)
+         VALUES (?1, ?2, ?3, ?4, ?4)
+         ON CONFLICT (normalized_url) DO UPDATE SET
+             interval_secs = excluded.interval_secs,
+             next_check_at = coalesce(last_checked_at + excluded.interval_secs, next_check_at)
+         RETURNING id",
+        params![
+            watched.url,
+            normalize_url(&watched.url),
+            watched.interval_secs as i64,
+            now_secs()
+        ],
+        |row| row.get(0),
+    )?)
+}
+
+/// Every watched URL with its interval, in the order they were added
+pub async fn entries(history: &ScanHistory) -> Result<Vec<WatchedUrl>, AppError> {
+    history
+        .with_conn(|conn| {
+            let mut statement =
+                conn.prepare("SELECT url, interval_secs FROM watchlist ORDER BY id")?;
+            let rows = statement.query_map([], |row| {
+                Ok(WatchedUrl {
+                    url: row.get(0)?,
+                    interval_secs: row.get::<_, i64>(1)? as u64,
+                })
+            })?;
+            Ok(rows.collect::<Result<Vec<_>, _>>()?)
+        })
+        .await
+}
+
+/// Watch each of `watched`, keeping the URLs already watched
+pub async fn add_all(history: &ScanHistory, watched: &[WatchedUrl]) -> Result<(), AppError> {
+    let watched = watched.to_vec();
+    history
+        .with_conn(move |conn| {
+            let tx = conn.unchecked_transaction()?;
+            for entry in &watched {
+                upsert(&tx, entry)?;
+            }
+            tx.commit()?;
+            Ok(())
+        })
+        .await
+}
+
+/// Watch exactly `watched`; entries kept keep their last verdict
+pub async fn replace(history: &ScanHistory, watched: &[WatchedUrl]) -> Result<(), AppError> {
+    let watched = watched.to_vec();
+    history
+        .with_conn(move |conn| {
+            let tx = conn.unchecked_transaction()?;
+            let kept: Vec<String> = watched.iter().map(|w| normalize_url(&w.url)).collect();
+            tx.execute(
+                "DELETE FROM watchlist WHERE normalized_url NOT IN (SELECT value FROM json_each(?1))",
+                [serde_json::to_string(&kept)?],
+            )?;
+            for entry in &watched {
+                upsert(&tx, entry)?;
+            }
+            tx.commit()?;
+            Ok(())
+        })
+        .await
+}
+
+/// Watch `url`, scanning it every `interval_secs` (a day by default) and
+/// warning when it turns phishing. The first check runs right away. Adding
+/// a URL that is already watched changes its interval.
+#[tauri::command]
+pub async fn add_to_watchlist(
+    url: String,
+    interval_secs: Option<u64>,
+    history: State<'_, ScanHistory>,
+    watchlist: State<'_, Watchlist>,
+) -> Result<WatchEntry, AppError> {
+    let watched = WatchedUrl::new(&url, interval_secs)?;
+    let id = history
+        .with_conn(move |conn| upsert(conn, &watched))
+        .await?;
+    watchlist.changed();
+    find(&history, id)
+        .await?
+        .ok_or_else(|| AppError::State("watchlist entry vanished".into()))
+}
+
+/// Stop watching an entry; returns false if there was none with that id
+#[tauri::command]
+pub async fn remove_from_watchlist(
+    id: i64,
+    history: State<'_, ScanHistory>,
+) -> Result<bool, AppError> {
+    history
+        .with_conn(move |conn| Ok(conn.execute("DELETE FROM watchlist WHERE id = ?1", [id])? > 0))
+        .await
+}
+
+/// Every watched URL with its last verdict and next check, soonest first
+#[tauri::command]
+pub async fn get_watchlist(history: State<'_, ScanHistory>) -> Result<Vec<WatchEntry>, AppError> {
+    history
+        .with_conn(|conn| {
+            let mut statement = conn.prepare(&format!(
+                "SELECT {} FROM watchlist ORDER BY next_check_at",
+                COLUMNS
+            ))?;
+            let rows = statement.query_map([], entry_from_row)?;
+            Ok(rows.collect::<Result<Vec<_>, _>>()?)
+        })
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn watched(url: &str, interval_secs: u64) -> WatchedUrl {
+        WatchedUrl::new(url, Some(interval_secs)).unwrap()
+    }
+
+    #[test]
+    fn new_checks_the_url_and_interval() {
+        assert_eq!(
+            WatchedUrl::new("hxxps://example[.]com/", None).unwrap(),
+            WatchedUrl {
+                url: "https://example.com/".into(),
+                interval_secs: DEFAULT_INTERVAL_SECS
+            }
+        );
+        assert!(WatchedUrl::new("https://example.com/", Some(60)).is_err());
+        assert!(WatchedUrl::new("not a url", None).is_err());
+    }
+
+    #[tokio::test]
+    async fn merge_and_replace() {
+        let dir = std::env::temp_dir().join(format!("watchlist-test-{}", uuid::Uuid::new_v4()));
+        let history = ScanHistory::open(&dir.join("history.db")).unwrap();
+        add_all(
+            &history,
+            &[
+                watched("https://a.example/", 3600),
+                watched("https://b.example/", 3600),
+            ],
+        )
+        .await
+        .unwrap();
+        history
+            .with_conn(|conn| {
+                conn.execute(
+                    "UPDATE watchlist SET last_classification = 'legitimate'
+                     WHERE url = 'https://a.example/'",
+                    [],
+                )?;
+                Ok(())
+            })
+            .await
+            .unwrap();
+
+        // Merging changes the interval of a URL already watched
+        add_all(&history, &[watched("https://a.example", 7200)])
+            .await
+            .unwrap();
+        assert_eq!(
+            entries(&history).await.unwrap(),
+            vec![
+                watched("https://a.example/", 7200),
+                watched("https://b.example/", 3600)
+            ]
+        );
+
+        replace(
+            &history,
+            &[
+                watched("https://a.example/", 7200),
+                watched("https://c.example/", 900),
+            ],
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            entries(&history).await.unwrap(),
+            vec![
+                watched("https://a.example/", 7200),
+                watched("https://c.example/", 900)
+            ]
+        );
+        let kept: Option<String> = history
+            .with_conn(|conn| {
+                Ok(conn.query_row(
+                    "SELECT last_classification FROM watchlist WHERE url = 'https://a.example/'",
+                    [],
+                    |row| row.get(0),
+                )?)
+            })
+            .await
+            .unwrap();
+        assert_eq!(kept.as_deref(), Some("legitimate"));
+
+        replace(&history, &[]).await.unwrap();
+        assert!(entries(&history).await.unwrap().is_empty());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
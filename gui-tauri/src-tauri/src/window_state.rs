@@ -0,0 +1,151 @@
+use crate::Window;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tauri::{Manager, Monitor, PhysicalPosition, PhysicalSize};
+
+/// Saved geometry of the main window, in the app config dir
+const STATE_FILE: &str = "window-state.json";
+/// Moves and resizes arrive in bursts; save once they have settled
+const SAVE_DEBOUNCE: Duration = Duration::from_millis(500);
+/// How much of a restored window must overlap a monitor to count as visible
+const MIN_VISIBLE: i32 = 64;
+
+/// Outer position and inner size in physical pixels
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct WindowGeometry {
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    maximized: bool,
+    /// Name of the monitor the window was on
+    #[serde(default)]
+    monitor: Option<String>,
+}
+
+/// Counts window moves so only the last of a burst is written
+#[derive(Default)]
+pub struct WindowStateTracker {
+    generation: AtomicU64,
+}
+
+fn state_path(window: &Window) -> Option<PathBuf> {
+    Some(window.path().app_config_dir().ok()?.join(STATE_FILE))
+}
+
+fn load(window: &Window) -> Option<WindowGeometry> {
+    let contents = std::fs::read_to_string(state_path(window)?).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+/// Current geometry, or `None` while minimized (positions are meaningless then).
+/// A maximized window keeps its saved normal size so un-maximizing restores it.
+fn capture(window: &Window) -> Option<WindowGeometry> {
+    if window.is_minimized().unwrap_or(false) {
+        return None;
+    }
+    let maximized = window.is_maximized().unwrap_or(false);
+    if maximized {
+        if let Some(saved) = load(window) {
+            return Some(WindowGeometry {
+                maximized: true,
+                ..saved
+            });
+        }
+    }
+
+    let position = window.outer_position().ok()?;
+    let size = window.inner_size().ok()?;
+    Some(WindowGeometry {
+        x: position.x,
+        y: position.y,
+        width: size.width,
+        height: size.height,
+        maximized,
+        monitor: window
+            .current_monitor()
+            .ok()
+            .flatten()
+            .and_then(|m| m.name().cloned()),
+    })
+}
+
+/// Write the window's geometry now
+pub fn save(window: &Window) {
+    let (Some(path), Some(geometry)) = (state_path(window), capture(window)) else {
+        return;
+    };
+    if let Some(dir) = path.parent() {
+        let _ = std::fs::create_dir_all(dir);
+    }
+    if let Ok(json) = serde_json::to_string_pretty(&geometry) {
+        let _ = std::fs::write(path, json);
+    }
+}
+
+/// Save after the window has stopped moving or resizing for a moment
+pub fn schedule_save(window: &Window) {
+    let tracker = window.state::<WindowStateTracker>();
+    let generation = tracker.generation.fetch_add(1, Ordering::SeqCst) + 1;
+
+    let window = window.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(SAVE_DEBOUNCE).await;
+        let tracker = window.state::<WindowStateTracker>();
+        if tracker.generation.load(Ordering::SeqCst) == generation {
+            save(&window);
+        }
+    });
+}
+
+fn overlap(start: i32, len: u32, other_start: i32, other_len: u32) -> i32 {
+    let end = start.saturating_add(len as i32);
+    let other_end = other_start.saturating_add(other_len as i32);
+    end.min(other_end) - start.max(other_start)
+}
+
+fn visible_on(geometry: &WindowGeometry, monitor: &Monitor) -> bool {
+    let (origin, size) = (monitor.position(), monitor.size());
+    overlap(geometry.x, geometry.width, origin.x, size.width) >= MIN_VISIBLE
+        && overlap(geometry.y, geometry.height, origin.y, size.height) >= MIN_VISIBLE
+}
+
+/// Move a window that would be off-screen (e.g. its monitor was unplugged)
+/// onto the primary display, shrinking it to fit if needed
+fn clamp_to_screen(window: &Window, geometry: &mut WindowGeometry) {
+    let monitors = window.available_monitors().unwrap_or_default();
+    if monitors.is_empty() || monitors.iter().any(|m| visible_on(geometry, m)) {
+        return;
+    }
+    let Some(target) = window
+        .primary_monitor()
+        .ok()
+        .flatten()
+        .or_else(|| monitors.into_iter().next())
+    else {
+        return;
+    };
+
+    let (origin, size) = (target.position(), target.size());
+    geometry.width = geometry.width.min(size.width);
+    geometry.height = geometry.height.min(size.height);
+    geometry.x = origin.x + ((size.width - geometry.width) / 2) as i32;
+    geometry.y = origin.y + ((size.height - geometry.height) / 2) as i32;
+    geometry.monitor = target.name().cloned();
+}
+
+/// Apply the saved geometry; call before the window is first shown
+pub fn restore(window: &Window) {
+    let Some(mut geometry) = load(window) else {
+        return;
+    };
+    clamp_to_screen(window, &mut geometry);
+
+    let _ = window.set_size(PhysicalSize::new(geometry.width, geometry.height));
+    let _ = window.set_position(PhysicalPosition::new(geometry.x, geometry.y));
+    if geometry.maximized {
+        let _ = window.maximize();
+    }
+}
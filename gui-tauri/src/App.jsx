@@ -22,7 +22,7 @@ function App() {
       const status = await invoke('check_environment');
       setEnvStatus(status);
     } catch (e) {
-      setEnvStatus({ status: 'error', error: e.toString() });
+      setEnvStatus({ state: 'down', error: e.message ?? e.toString() });
     }
   };
 
@@ -37,7 +37,8 @@ function App() {
       const scanResult = await invoke('scan_url', { url });
       setResult(scanResult);
     } catch (e) {
-      setError(e.toString());
+      const message = e.message ?? e.toString();
+      setError(e.reference ? `${message} (reference: ${e.reference})` : message);
     } finally {
       setIsScanning(false);
     }
@@ -54,7 +55,7 @@ function App() {
       const results = await invoke('scan_batch', { urls });
       setBatchResults(results);
     } catch (e) {
-      setError(e.toString());
+      setError(e.message ?? e.toString());
     } finally {
       setIsScanning(false);
     }
@@ -84,7 +85,7 @@ function App() {
     return <div className="loading">Checking environment...</div>;
   }
 
-  if (envStatus.status === 'error') {
+  if (envStatus.state === 'down') {
     return (
       <div className="error-screen">
         <AlertTriangle className="error-icon" />